@@ -0,0 +1,75 @@
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use push2::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH, FRAME_BYTES, encode_frame};
+use push2::{MidiParser, MockBackend, PadCoord, Push2};
+use std::hint::black_box;
+
+/// Presses and releases every pad, as raw MIDI.
+fn pad_messages() -> Vec<Vec<u8>> {
+    (36..100u8)
+        .flat_map(|note| [vec![0x90, note, 100], vec![0x80, note, 0]])
+        .collect()
+}
+
+fn bench_encode_frame(c: &mut Criterion) {
+    let frame: Vec<u16> = (0..DISPLAY_WIDTH * DISPLAY_HEIGHT)
+        .map(|i| i as u16)
+        .collect();
+    let mut buffer = vec![0u8; FRAME_BYTES];
+    let mut group = c.benchmark_group("display");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("encode_frame", |b| {
+        b.iter(|| encode_frame(black_box(&frame), &mut buffer))
+    });
+    group.finish();
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let bytes: Vec<u8> = pad_messages().concat();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(128));
+    group.bench_function("midi_parser", |b| {
+        let mut parser = MidiParser::new();
+        b.iter(|| {
+            parser.feed(black_box(&bytes));
+            while let Some(message) = parser.next_message() {
+                black_box(message);
+            }
+        })
+    });
+    group.bench_function("poll_event", |b| {
+        let (backend, input) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        let messages = pad_messages();
+        b.iter(|| {
+            for message in &messages {
+                input.send(message.clone()).unwrap();
+            }
+            while let Some(event) = push2.poll_event() {
+                black_box(event);
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_lights(c: &mut Criterion) {
+    let (backend, _input) = MockBackend::new();
+    let mut push2 = Push2::with_backend(backend).unwrap();
+    let mut group = c.benchmark_group("lights");
+    group.throughput(Throughput::Elements(64));
+    group.bench_function("set_pad_color", |b| {
+        let mut color = 0u8;
+        b.iter(|| {
+            color = (color + 1) % 128;
+            for y in 0..8 {
+                for x in 0..8 {
+                    push2.set_pad_color(PadCoord { x, y }, color).unwrap();
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode_frame, bench_parse, bench_lights);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+use push2::{
+    AppHost, ControlName, FontSize, Push2, Push2Colors, Push2Error, Push2Event, PushApp, TextStyle,
+};
+
+use embedded_graphics::{pixelcolor::Bgr565, prelude::*, text::Text};
+use log::info;
+use std::error;
+
+/// Lights the pads while they are held.
+struct Notes;
+
+impl PushApp for Notes {
+    fn name(&self) -> &str {
+        "Notes"
+    }
+
+    fn on_event(&mut self, push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+        match *event {
+            Push2Event::PadPressed { coord, .. } => push2.set_pad_color(coord, Push2Colors::GREEN),
+            Push2Event::PadReleased { coord, .. } => push2.set_pad_color(coord, 0),
+            _ => Ok(()),
+        }
+    }
+
+    fn draw(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        draw_title(push2, "Notes: play the pads");
+        Ok(())
+    }
+}
+
+/// Toggles pads on and off. The host puts them back after a switch to Notes.
+struct Session;
+
+impl PushApp for Session {
+    fn name(&self) -> &str {
+        "Session"
+    }
+
+    fn on_event(&mut self, push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+        if let Push2Event::PadPressed { coord, .. } = *event {
+            let lit = push2.state.pads[coord.y as usize][coord.x as usize].color != 0;
+            let color = if lit { 0 } else { Push2Colors::BLUE_SKY };
+            push2.set_pad_color(coord, color)?;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        draw_title(push2, "Session: toggle pads");
+        Ok(())
+    }
+}
+
+fn draw_title(push2: &mut Push2, title: &str) {
+    let _ = push2.display.clear(Bgr565::BLACK);
+    let style = TextStyle::new(FontSize::Large).with_color(Bgr565::WHITE);
+    let _ = Text::new(title, Point::new(20, 80), style).draw(&mut push2.display);
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    env_logger::init();
+    let mut host = AppHost::new(Push2::new()?);
+    host.add_mode(ControlName::Note, Notes);
+    host.add_mode(ControlName::Session, Session);
+    info!("Switch modes with Note and Session.");
+    host.run()?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use push2::{FontSize, MirrorWindow, Push2, Push2Colors, Push2Event, TextStyle};
+
+use embedded_graphics::{pixelcolor::Bgr565, prelude::*, text::Text};
+use log::info;
+use std::error;
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    env_logger::init();
+    let mut push2 = Push2::new()?;
+    let mut mirror = MirrorWindow::open()?;
+    info!("Press pads; close the window to quit.");
+
+    let mut last = String::from("Press a pad");
+    while mirror.is_open() {
+        while let Some(event) = push2.poll_event() {
+            match event {
+                Push2Event::PadPressed { coord, velocity } => {
+                    push2.set_pad_color(coord, Push2Colors::GREEN_PALE)?;
+                    last = format!("Pad ({}, {}) velocity {}", coord.x, coord.y, velocity);
+                }
+                Push2Event::PadReleased { coord, .. } => push2.set_pad_color(coord, 0)?,
+                _ => {}
+            }
+        }
+        push2.display.clear(Bgr565::BLACK)?;
+        Text::new(
+            &last,
+            Point::new(20, 80),
+            TextStyle::new(FontSize::Large).with_color(Bgr565::WHITE),
+        )
+        .draw(&mut push2.display)?;
+        push2.display.flush()?;
+        // Paced by the window at 60 frames per second.
+        mirror.update(&push2)?;
+    }
+    Ok(())
+}
@@ -1,23 +1,30 @@
 use embedded_graphics::{pixelcolor::Bgr565, prelude::*};
 use log::debug;
 use push2::{GuiApi, Push2, Push2Event, button_map::EncoderName};
-use std::{error::Error, thread, time};
+use std::error::Error;
 fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
     // --- 1. Initialize Push 2 ---
     debug!("Connecting to Ableton Push 2...");
     let mut push2 = Push2::new()?;
+    push2.display.set_target_fps(60);
     debug!("Connection established.");
-    // --- 2. State for our 8 track encoders ---
-    let mut track_encoder_values: [i32; 8] = [64; 8];
+    // --- 2. Seed the state of our 8 track encoders ---
+    for name in EncoderName::TRACKS {
+        push2.state.set_encoder_default(name, 64);
+        push2.state.reset_encoder(name);
+    }
+    // Pick up where the last run left the knobs.
+    push2.persist_session("draw_encoders")?;
     // --- 3. Initial Draw ---
     push2.display.clear(Bgr565::BLACK)?;
-    for i in 0..8u8 {
+    for (i, name) in EncoderName::TRACKS.into_iter().enumerate() {
+        let i = i as u8;
         // Draw the empty outline
         push2.display.draw_encoder_outline(i, Bgr565::WHITE)?;
         push2
             .display
-            .draw_encoder_bar(i, track_encoder_values[i as usize], Bgr565::GREEN)?; // ‼️ This now passes an i32
+            .draw_encoder_bar(i, push2.state.encoder_value(name), Bgr565::GREEN)?;
     }
     push2.display.flush()?;
     // --- 4. Main Loop ---
@@ -25,33 +32,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     loop {
         let mut needs_redraw = false;
         // --- 4a. Poll for events ---
+        // The state accumulates and clamps the encoder values for us.
         while let Some(event) = push2.poll_event() {
-            if let Push2Event::EncoderTwisted { name, raw_delta } = event {
-                let index = match name {
-                    EncoderName::Track1 => Some(0),
-                    EncoderName::Track2 => Some(1),
-                    EncoderName::Track3 => Some(2),
-                    EncoderName::Track4 => Some(3),
-                    EncoderName::Track5 => Some(4),
-                    EncoderName::Track6 => Some(5),
-                    EncoderName::Track7 => Some(6),
-                    EncoderName::Track8 => Some(7),
-                    _ => None, // Ignore other encoders (Tempo, Swing, Master)
-                };
-                if let Some(idx) = index {
-                    let delta = if raw_delta > 64 {
-                        -((128 - raw_delta) as i32)
-                    } else {
-                        raw_delta as i32
-                    };
-                    let current_value = track_encoder_values[idx as usize];
-                    let new_value = (current_value.saturating_add(delta)).clamp(0, 127);
-                    if track_encoder_values[idx as usize] != new_value {
-                        track_encoder_values[idx as usize] = new_value;
-                        needs_redraw = true;
-                        debug!("Encoder {} ({:?}) updated to: {}", idx, name, new_value);
-                    }
-                }
+            if let Push2Event::EncoderTwisted { name, .. } = event
+                && let Some(idx) = name.track_index()
+            {
+                needs_redraw = true;
+                debug!(
+                    "Encoder {} ({:?}) updated to: {}",
+                    idx,
+                    name,
+                    push2.state.encoder_value(name)
+                );
             }
         }
         // --- 4b. Render if needed ---
@@ -59,21 +51,19 @@ fn main() -> Result<(), Box<dyn Error>> {
             // Clear the display
             push2.display.clear(Bgr565::BLACK)?;
             // Redraw all 8 bars and outlines
-            for i in 0..8u8 {
+            for (i, name) in EncoderName::TRACKS.into_iter().enumerate() {
+                let i = i as u8;
                 // Draw the outline
                 push2.display.draw_encoder_outline(i, Bgr565::WHITE)?;
                 // Draw the filled bar
                 push2.display.draw_encoder_bar(
                     i,
-                    track_encoder_values[i as usize],
+                    push2.state.encoder_value(name),
                     Bgr565::GREEN,
                 )?;
             }
             // Flush the frame buffer to the screen
         }
         push2.display.flush()?;
-        // --- 4c. Sleep ---
-        // Don't spin the CPU
-        thread::sleep(time::Duration::from_millis(16));
     }
 }
@@ -1,7 +1,7 @@
 use embedded_graphics::{pixelcolor::Bgr565, prelude::*};
 use log::{debug, info};
 use push2::{GuiApi, Push2, Push2Colors, Push2Event, gui};
-use std::{error::Error, path::PathBuf, thread, time};
+use std::{error::Error, path::PathBuf};
 
 // --- Color Configuration ---
 const BACKGROUND_COLOR: Bgr565 = Bgr565::BLACK;
@@ -25,6 +25,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     // --- 1. Initialize Push 2 ---
     info!("Connecting to Ableton Push 2...");
     let mut push2 = Push2::new()?;
+    push2.display.set_target_fps(60);
+    // Light pads while they are held.
+    push2.press_highlight = Some(Push2Colors::GREEN_PALE);
     let display_size = push2.display.size();
     let image_width = display_size.width;
 
@@ -48,17 +51,14 @@ fn main() -> Result<(), Box<dyn Error>> {
             match event {
                 Push2Event::PadPressed { coord, .. } => {
                     debug!("Pad ({}, {}) PRESSED", coord.x, coord.y);
-                    push2.set_pad_color(coord, Push2Colors::GREEN_PALE)?;
                 }
-                Push2Event::PadReleased { coord } => {
+                Push2Event::PadReleased { coord, .. } => {
                     debug!("Pad ({}, {}) RELEASED", coord.x, coord.y);
-                    push2.set_pad_color(coord, 0)?;
                 }
                 _ => {} // Ignore other events
             }
         }
 
         push2.display.flush()?;
-        thread::sleep(time::Duration::from_millis(16));
     }
 }
@@ -0,0 +1,31 @@
+use log::info;
+use push2::{EguiDisplay, Push2};
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let mut push2 = Push2::new()?;
+    push2.display.set_target_fps(60);
+    let mut ui = EguiDisplay::new();
+
+    let mut volume = 0.8f32;
+    let mut swing = 0.0f32;
+    let mut metronome = false;
+
+    info!("Turn the first track encoder to move between widgets and the second to adjust them.");
+    info!("Select toggles the checkbox. Press Ctrl-C to quit.");
+    loop {
+        while let Some(event) = push2.poll_event() {
+            ui.handle_event(&event);
+        }
+        ui.run(&mut push2.display, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.heading("Settings");
+                ui.add(egui::Slider::new(&mut volume, 0.0..=1.0).text("Volume"));
+                ui.add(egui::Slider::new(&mut swing, 0.0..=0.5).text("Swing"));
+                ui.checkbox(&mut metronome, "Metronome");
+            });
+        })?;
+        push2.display.flush()?;
+    }
+}
@@ -0,0 +1,44 @@
+use push2::{Push2, Push2Colors, Push2Event, Push2Virtual};
+
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_10X20},
+    pixelcolor::Bgr565,
+    prelude::*,
+    text::Text,
+};
+use log::info;
+use std::{error, thread, time};
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    env_logger::init();
+    // Open http://127.0.0.1:8080/ in a browser to play the device.
+    let mut push2 = Push2::with_backend(Push2Virtual::default())?;
+
+    let text_style = MonoTextStyle::new(&FONT_10X20, Bgr565::WHITE);
+    push2.display.clear(Bgr565::BLACK)?;
+    Text::new("Hello from the emulator", Point::new(20, 80), text_style)
+        .draw(&mut push2.display)?;
+    push2.display.flush()?;
+
+    loop {
+        while let Some(event) = push2.poll_event() {
+            info!("{:?}", event);
+            match event {
+                Push2Event::PadPressed { coord, .. } => {
+                    push2.set_pad_color(coord, Push2Colors::GREEN_PALE)?;
+                }
+                Push2Event::PadReleased { coord, .. } => {
+                    push2.set_pad_color(coord, Push2Colors::BLACK)?;
+                }
+                Push2Event::ButtonPressed { name, .. } => {
+                    push2.set_button_light(name, Push2Colors::WHITE_BRIGHT)?;
+                }
+                Push2Event::ButtonReleased { name } => {
+                    push2.set_button_light(name, Push2Colors::BLACK)?;
+                }
+                _ => {}
+            }
+        }
+        thread::sleep(time::Duration::from_millis(10));
+    }
+}
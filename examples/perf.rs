@@ -0,0 +1,102 @@
+//! Measures the display, LED and event paths against the mock backend.
+//!
+//! cargo run --release --example perf [seconds per test]
+
+use embedded_graphics::{pixelcolor::Bgr565, prelude::*, primitives::*};
+use push2::display::{FRAME_BYTES, encode_frame};
+use push2::{FrameSink, MockBackend, PadCoord, Push2, Push2DisplayError};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use std::{env, error};
+
+/// Encodes every frame like the USB display does, without sending it.
+struct EncodingSink {
+    buffer: Vec<u8>,
+}
+
+impl FrameSink for EncodingSink {
+    fn write_frame(&mut self, frame: &[u16]) -> Result<(), Push2DisplayError> {
+        encode_frame(frame, &mut self.buffer);
+        Ok(())
+    }
+}
+
+/// Runs `step` repeatedly for `duration`. Returns the number of runs per second.
+fn rate(duration: Duration, mut step: impl FnMut() -> Result<u64, Box<dyn error::Error>>) -> f64 {
+    let start = Instant::now();
+    let mut count = 0;
+    while start.elapsed() < duration {
+        match step() {
+            Ok(n) => count += n,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                break;
+            }
+        }
+    }
+    count as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let seconds: f64 = env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(2.0);
+    let duration = Duration::from_secs_f64(seconds);
+
+    let (backend, input) = MockBackend::new();
+    let sent = backend.midi_out.sent.clone();
+    let mut push2 = Push2::with_backend(backend)?;
+    push2.display = push2::Push2Display::with_sink(Box::new(EncodingSink {
+        buffer: vec![0; FRAME_BYTES],
+    }));
+
+    // --- Display: draw a moving bar and flush ---
+    let mut x = 0;
+    let fps = rate(duration, || {
+        push2.display.clear(Bgr565::BLACK)?;
+        Rectangle::new(Point::new(x, 0), Size::new(40, 160))
+            .into_styled(PrimitiveStyle::with_fill(Bgr565::CYAN))
+            .draw(&mut push2.display)?;
+        push2.display.flush()?;
+        x = (x + 8) % 960;
+        Ok(1)
+    });
+    println!("Display:    {:>12.1} frames/s", fps);
+
+    // --- LEDs: recolor every pad ---
+    let before = sent.load(Ordering::Relaxed);
+    let mut color = 0;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        color = (color + 1) % 128;
+        for y in 0..8 {
+            for x in 0..8 {
+                push2.set_pad_color(PadCoord { x, y }, color)?;
+            }
+        }
+    }
+    let messages = sent.load(Ordering::Relaxed) - before;
+    println!(
+        "LEDs:       {:>12.1} messages/s",
+        messages as f64 / start.elapsed().as_secs_f64()
+    );
+
+    // --- Events: parse pad presses and releases ---
+    let batch: Vec<Vec<u8>> = (36..100u8)
+        .flat_map(|note| [vec![0x90, note, 100], vec![0x80, note, 0]])
+        .collect();
+    let events = rate(duration, || {
+        for message in &batch {
+            input.send(message.clone())?;
+        }
+        let mut count = 0;
+        while push2.poll_event().is_some() {
+            count += 1;
+        }
+        Ok(count)
+    });
+    println!("Events:     {:>12.1} events/s", events);
+
+    Ok(())
+}
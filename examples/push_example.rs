@@ -1,14 +1,13 @@
-use push2::{Push2, Push2Colors, Push2Event};
+use push2::{FontSize, Push2, Push2Colors, Push2Event, TextStyle};
 
 use embedded_graphics::{
-    mono_font::{MonoTextStyle, ascii::FONT_10X20},
     pixelcolor::Bgr565,
     prelude::*,
     primitives::{PrimitiveStyle, Rectangle},
     text::Text,
 };
 use log::{debug, info, trace};
-use std::{error, thread, time};
+use std::error;
 
 const PAD_COLOR_ON: u8 = Push2Colors::GREEN_PALE;
 const BUTTON_LIGHT_ON: u8 = Push2Colors::GREEN_PALE;
@@ -18,9 +17,11 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     // --- Config Loading ---
 
     let mut push2 = Push2::new()?;
+    // flush() waits for the next frame, so the loop needs no sleep.
+    push2.display.set_target_fps(60);
 
     // --- Display Setup (Application Logic) ---
-    let text_style = MonoTextStyle::new(&FONT_10X20, Bgr565::WHITE);
+    let text_style = TextStyle::new(FontSize::Large);
     let mut position = Point::new(0, 70);
     let mut step = 4;
 
@@ -36,7 +37,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     debug!("--- Pad ({}, {}) PRESSED ---", coord.x, coord.y);
                     push2.set_pad_color(coord, PAD_COLOR_ON)?;
                 }
-                Push2Event::PadReleased { coord } => {
+                Push2Event::PadReleased { coord, .. } => {
                     debug!("--- Pad ({}, {}) RELEASED ---", coord.x, coord.y);
                     push2.set_pad_color(coord, 0)?;
                 }
@@ -58,6 +59,23 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 Push2Event::SliderMoved { value } => {
                     debug!("--- Slider MOVED, value {} ---", value);
                 }
+                Push2Event::PadAftertouch { coord, pressure } => {
+                    trace!("Pad ({}, {}) pressure {}", coord.x, coord.y, pressure);
+                }
+                Push2Event::ChannelPressure { value } => {
+                    trace!("Channel pressure {}", value);
+                }
+                Push2Event::PadChord { coords } => {
+                    debug!("--- Chord of {} pads ---", coords.len());
+                }
+                Push2Event::IdleEntered => debug!("--- Idle ---"),
+                Push2Event::IdleExited => debug!("--- Awake ---"),
+                Push2Event::UnknownControl { address, value } => {
+                    debug!("--- Unknown control CC {}, value {} ---", address, value);
+                }
+                Push2Event::ConfigReloaded { file } => {
+                    debug!("--- Reloaded {:?} ---", file.path());
+                }
             }
         }
 
@@ -73,10 +91,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             step *= -1;
         }
 
-        Text::new("Hello!", position, text_style).draw(&mut push2.display)?;
+        Text::new("Hello!", position, text_style.clone()).draw(&mut push2.display)?;
         push2.display.flush()?;
-
-        thread::sleep(time::Duration::from_millis(1000 / 60));
     }
 }
-
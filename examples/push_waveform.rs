@@ -9,7 +9,7 @@ use embedded_graphics::{
 };
 use log::{debug, info, trace};
 use std::path::PathBuf;
-use std::{error, fs, thread, time};
+use std::{error, fs};
 
 pub fn get_audio_storage_path() -> std::io::Result<PathBuf> {
     match dirs::audio_dir() {
@@ -30,6 +30,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     // --- Config Loading ---
 
     let mut push2 = Push2::new()?;
+    push2.display.set_target_fps(60);
 
     let audio_storage_path = get_audio_storage_path()?;
     let bmp_path = audio_storage_path.join("waveform.bmp");
@@ -60,7 +61,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     debug!("--- Pad ({}, {}) PRESSED ---", coord.x, coord.y);
                     push2.set_pad_color(coord, PAD_COLOR_ON)?;
                 }
-                Push2Event::PadReleased { coord } => {
+                Push2Event::PadReleased { coord, .. } => {
                     debug!("--- Pad ({}, {}) RELEASED ---", coord.x, coord.y);
                     push2.set_pad_color(coord, 0)?;
                 }
@@ -81,6 +82,23 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 Push2Event::SliderMoved { value } => {
                     debug!("--- Slider MOVED, value {} ---", value);
                 }
+                Push2Event::PadAftertouch { coord, pressure } => {
+                    trace!("Pad ({}, {}) pressure {}", coord.x, coord.y, pressure);
+                }
+                Push2Event::ChannelPressure { value } => {
+                    trace!("Channel pressure {}", value);
+                }
+                Push2Event::PadChord { coords } => {
+                    debug!("--- Chord of {} pads ---", coords.len());
+                }
+                Push2Event::IdleEntered => debug!("--- Idle ---"),
+                Push2Event::IdleExited => debug!("--- Awake ---"),
+                Push2Event::UnknownControl { address, value } => {
+                    debug!("--- Unknown control CC {}, value {} ---", address, value);
+                }
+                Push2Event::ConfigReloaded { file } => {
+                    debug!("--- Reloaded {:?} ---", file.path());
+                }
             }
         }
 
@@ -101,7 +119,5 @@ fn main() -> Result<(), Box<dyn error::Error>> {
 
         Text::new("Hello!", position, text_style).draw(&mut push2.display)?;
         push2.display.flush()?;
-
-        thread::sleep(time::Duration::from_millis(1000 / 60));
     }
 }
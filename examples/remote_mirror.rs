@@ -0,0 +1,35 @@
+use push2::{FrameEncoder, Push2, RemoteServer};
+
+use log::info;
+use std::{error, thread, time};
+
+/// Mirror frames are sent at most this often.
+const FRAME_INTERVAL: time::Duration = time::Duration::from_millis(33);
+/// Keeps the mirror usable over Wi-Fi; the raw display stream is about 7.3 MB/s.
+const MAX_BANDWIDTH: usize = 1_000_000;
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    env_logger::init();
+    let mut push2 = Push2::new()?;
+    let encoder = FrameEncoder::new().with_max_bandwidth(MAX_BANDWIDTH);
+    push2.remote = Some(RemoteServer::bind("0.0.0.0:9001")?.with_frame_encoder(encoder));
+    info!("Connect a WebSocket client to ws://<host>:9001");
+
+    let mut last_frame = time::Instant::now();
+    loop {
+        // Events are mirrored to the clients by poll_event.
+        while let Some(event) = push2.poll_event() {
+            info!("{:?}", event);
+        }
+        // Commands from the clients draw on the display and set the LEDs.
+        push2.process_remote_commands()?;
+
+        if last_frame.elapsed() >= FRAME_INTERVAL {
+            if let Some(remote) = push2.remote.as_ref() {
+                remote.broadcast_compressed_frame(&push2.display);
+            }
+            last_frame = time::Instant::now();
+        }
+        thread::sleep(time::Duration::from_millis(10));
+    }
+}
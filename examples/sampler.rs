@@ -0,0 +1,36 @@
+use push2::Push2;
+use push2::apps::sampler::{CommandBackend, Sampler};
+
+use log::{debug, info};
+use std::{error, thread, time};
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    env_logger::init();
+
+    let mut push2 = Push2::new()?;
+    let recordings_dir = Sampler::default_recordings_dir()?;
+    let assignments = recordings_dir.join("pads.ron");
+
+    let mut sampler = Sampler::new(CommandBackend::default(), &recordings_dir);
+    if assignments.exists() {
+        sampler.load(&assignments)?;
+    }
+
+    info!("Press Record, then hold a pad to record into it. Clip toggles edit mode.");
+
+    // --- Main Loop ---
+    loop {
+        while let Some(event) = push2.poll_event() {
+            debug!("Received event: {:?}", event);
+            if sampler.handle_event(&event)? {
+                sampler.save(&assignments)?;
+            }
+        }
+
+        if sampler.render(&mut push2)? {
+            push2.display.flush()?;
+        }
+
+        thread::sleep(time::Duration::from_millis(1000 / 60));
+    }
+}
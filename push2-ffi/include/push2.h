@@ -0,0 +1,150 @@
+#ifndef PUSH2_H
+#define PUSH2_H
+
+/* Generated by cbindgen from push2-ffi/src/lib.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The kind of a `Push2CEvent`.
+ */
+typedef enum Push2EventKind {
+  PUSH2_EVENT_KIND_NONE,
+  PUSH2_EVENT_KIND_PAD_PRESSED,
+  PUSH2_EVENT_KIND_PAD_RELEASED,
+  PUSH2_EVENT_KIND_BUTTON_PRESSED,
+  PUSH2_EVENT_KIND_BUTTON_RELEASED,
+  PUSH2_EVENT_KIND_ENCODER_TWISTED,
+  PUSH2_EVENT_KIND_SLIDER_MOVED,
+  PUSH2_EVENT_KIND_PAD_AFTERTOUCH,
+  PUSH2_EVENT_KIND_CHANNEL_PRESSURE,
+  /**
+   * An event without a C representation, such as a pad chord.
+   */
+  PUSH2_EVENT_KIND_OTHER,
+  /**
+   * A control change from an address with no known button or encoder.
+   */
+  PUSH2_EVENT_KIND_UNKNOWN_CONTROL,
+} Push2EventKind;
+
+typedef struct Push2 Push2;
+
+/**
+ * An event in a flat C layout. Which fields are set depends on `kind`.
+ */
+typedef struct Push2CEvent {
+  Push2EventKind kind;
+  /**
+   * The pad column (0-7, left to right) of pad events.
+   */
+  uint8_t x;
+  /**
+   * The pad row (0-7, top to bottom) of pad events.
+   */
+  uint8_t y;
+  /**
+   * The MIDI CC number of a button, encoder or unknown control.
+   */
+  uint8_t control;
+  /**
+   * The velocity, pressure or channel pressure.
+   */
+  uint8_t value;
+  /**
+   * The signed turn of an encoder.
+   */
+  int32_t delta;
+  /**
+   * The position of the touch slider (0-16383).
+   */
+  uint16_t slider;
+} Push2CEvent;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Connects to the Push 2. Returns NULL on failure.
+ * The device must be released with `push2_free`.
+ */
+struct Push2 *push2_connect(void);
+
+/**
+ * Disconnects and frees a device returned by `push2_connect`.
+ *
+ * # Safety
+ * `push2` must come from `push2_connect` and not be used afterwards.
+ */
+void push2_free(struct Push2 *push2);
+
+/**
+ * The message of the last failure on this thread, or NULL.
+ * The string stays valid until the next failure on this thread.
+ */
+const char *push2_last_error(void);
+
+/**
+ * Fills `event` with the next event. Returns false if there is none.
+ *
+ * # Safety
+ * `push2` must be a live device and `event` point to a writable `Push2CEvent`.
+ */
+bool push2_poll_event(struct Push2 *push2, struct Push2CEvent *event);
+
+/**
+ * Sets the palette color (0-127) of the pad at column `x` and row `y`.
+ *
+ * # Safety
+ * `push2` must be a live device.
+ */
+int32_t push2_set_pad_color(struct Push2 *push2, uint8_t x, uint8_t y, uint8_t color);
+
+/**
+ * Lights the button with MIDI CC number `control`.
+ *
+ * # Safety
+ * `push2` must be a live device.
+ */
+int32_t push2_set_button_light(struct Push2 *push2, uint8_t control, uint8_t light);
+
+/**
+ * Fills the frame buffer with a color.
+ *
+ * # Safety
+ * `push2` must be a live device.
+ */
+int32_t push2_clear(struct Push2 *push2, uint8_t r, uint8_t g, uint8_t b);
+
+/**
+ * Draws UTF-8 text into the frame buffer, with its baseline at `y`.
+ *
+ * # Safety
+ * `push2` must be a live device and `text` a NUL-terminated string.
+ */
+int32_t push2_draw_text(struct Push2 *push2,
+                        int32_t x,
+                        int32_t y,
+                        const char *text,
+                        uint8_t r,
+                        uint8_t g,
+                        uint8_t b);
+
+/**
+ * Sends the frame buffer to the display.
+ *
+ * # Safety
+ * `push2` must be a live device.
+ */
+int32_t push2_flush(struct Push2 *push2);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PUSH2_H */
@@ -0,0 +1,276 @@
+//! A C interface to the push2 crate, for hosts such as C++ apps or Max externals.
+//!
+//! Functions returning `int32_t` return 0 on success and -1 on failure, with
+//! the reason available from `push2_last_error`. The header is generated
+//! with cbindgen; see `cbindgen.toml`.
+
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::{Bgr565, Rgb888},
+    prelude::*,
+    text::Text,
+};
+use push2::{PadCoord, Push2, Push2Event};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string()).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Turns a result into the 0 / -1 status returned to C.
+fn status<E: ToString>(result: Result<(), E>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            set_last_error(e);
+            -1
+        }
+    }
+}
+
+/// The kind of a `Push2CEvent`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Push2EventKind {
+    None,
+    PadPressed,
+    PadReleased,
+    ButtonPressed,
+    ButtonReleased,
+    EncoderTwisted,
+    SliderMoved,
+    PadAftertouch,
+    ChannelPressure,
+    /// An event without a C representation, such as a pad chord.
+    Other,
+    /// A control change from an address with no known button or encoder.
+    UnknownControl,
+}
+
+/// An event in a flat C layout. Which fields are set depends on `kind`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Push2CEvent {
+    pub kind: Push2EventKind,
+    /// The pad column (0-7, left to right) of pad events.
+    pub x: u8,
+    /// The pad row (0-7, top to bottom) of pad events.
+    pub y: u8,
+    /// The MIDI CC number of a button, encoder or unknown control.
+    pub control: u8,
+    /// The velocity, pressure or channel pressure.
+    pub value: u8,
+    /// The signed turn of an encoder.
+    pub delta: i32,
+    /// The position of the touch slider (0-16383).
+    pub slider: u16,
+}
+
+impl Push2CEvent {
+    const EMPTY: Self = Self {
+        kind: Push2EventKind::None,
+        x: 0,
+        y: 0,
+        control: 0,
+        value: 0,
+        delta: 0,
+        slider: 0,
+    };
+
+    fn from_event(push2: &Push2, event: &Push2Event) -> Self {
+        let mut c = Self::EMPTY;
+        let pad = |c: &mut Self, coord: PadCoord, value: u8| {
+            c.x = coord.x;
+            c.y = coord.y;
+            c.value = value;
+        };
+        match *event {
+            Push2Event::PadPressed { coord, velocity } => {
+                c.kind = Push2EventKind::PadPressed;
+                pad(&mut c, coord, velocity);
+            }
+            Push2Event::PadReleased {
+                coord,
+                release_velocity,
+            } => {
+                c.kind = Push2EventKind::PadReleased;
+                pad(&mut c, coord, release_velocity);
+            }
+            Push2Event::PadAftertouch { coord, pressure } => {
+                c.kind = Push2EventKind::PadAftertouch;
+                pad(&mut c, coord, pressure);
+            }
+            Push2Event::ButtonPressed { name, velocity } => {
+                c.kind = Push2EventKind::ButtonPressed;
+                c.control = push2.button_map.get_control_address(name).unwrap_or(0);
+                c.value = velocity;
+            }
+            Push2Event::ButtonReleased { name } => {
+                c.kind = Push2EventKind::ButtonReleased;
+                c.control = push2.button_map.get_control_address(name).unwrap_or(0);
+            }
+            Push2Event::EncoderTwisted { name, raw_delta } => {
+                c.kind = Push2EventKind::EncoderTwisted;
+                c.control = push2.button_map.get_encoder_address(name).unwrap_or(0);
+                c.delta = push2::button_map::decode_delta(raw_delta);
+            }
+            Push2Event::SliderMoved { value } => {
+                c.kind = Push2EventKind::SliderMoved;
+                c.slider = value;
+            }
+            Push2Event::ChannelPressure { value } => {
+                c.kind = Push2EventKind::ChannelPressure;
+                c.value = value;
+            }
+            Push2Event::UnknownControl { address, value } => {
+                c.kind = Push2EventKind::UnknownControl;
+                c.control = address;
+                c.value = value;
+            }
+            _ => c.kind = Push2EventKind::Other,
+        }
+        c
+    }
+}
+
+/// Connects to the Push 2. Returns NULL on failure.
+/// The device must be released with `push2_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn push2_connect() -> *mut Push2 {
+    match Push2::new() {
+        Ok(push2) => Box::into_raw(Box::new(push2)),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Disconnects and frees a device returned by `push2_connect`.
+///
+/// # Safety
+/// `push2` must come from `push2_connect` and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_free(push2: *mut Push2) {
+    if !push2.is_null() {
+        drop(unsafe { Box::from_raw(push2) });
+    }
+}
+
+/// The message of the last failure on this thread, or NULL.
+/// The string stays valid until the next failure on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn push2_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Fills `event` with the next event. Returns false if there is none.
+///
+/// # Safety
+/// `push2` must be a live device and `event` point to a writable `Push2CEvent`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_poll_event(push2: *mut Push2, event: *mut Push2CEvent) -> bool {
+    let (Some(push2), Some(event)) = (unsafe { push2.as_mut() }, unsafe { event.as_mut() }) else {
+        return false;
+    };
+    match push2.poll_event() {
+        Some(e) => {
+            *event = Push2CEvent::from_event(push2, &e);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Sets the palette color (0-127) of the pad at column `x` and row `y`.
+///
+/// # Safety
+/// `push2` must be a live device.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_set_pad_color(push2: *mut Push2, x: u8, y: u8, color: u8) -> i32 {
+    let Some(push2) = (unsafe { push2.as_mut() }) else {
+        return status(Err("Null device"));
+    };
+    if x > 7 || y > 7 {
+        return status(Err(format!("No pad at ({}, {})", x, y)));
+    }
+    status(push2.set_pad_color(PadCoord { x, y }, color))
+}
+
+/// Lights the button with MIDI CC number `control`.
+///
+/// # Safety
+/// `push2` must be a live device.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_set_button_light(push2: *mut Push2, control: u8, light: u8) -> i32 {
+    let Some(push2) = (unsafe { push2.as_mut() }) else {
+        return status(Err("Null device"));
+    };
+    match push2.button_map.get_control(control) {
+        Some(name) => status(push2.set_button_light(name, light)),
+        None => status(Err(format!("No button with CC {}", control))),
+    }
+}
+
+/// Fills the frame buffer with a color.
+///
+/// # Safety
+/// `push2` must be a live device.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_clear(push2: *mut Push2, r: u8, g: u8, b: u8) -> i32 {
+    let Some(push2) = (unsafe { push2.as_mut() }) else {
+        return status(Err("Null device"));
+    };
+    push2
+        .display
+        .clear(Bgr565::from(Rgb888::new(r, g, b)))
+        .unwrap(); // Infallible
+    0
+}
+
+/// Draws UTF-8 text into the frame buffer, with its baseline at `y`.
+///
+/// # Safety
+/// `push2` must be a live device and `text` a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_draw_text(
+    push2: *mut Push2,
+    x: i32,
+    y: i32,
+    text: *const c_char,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> i32 {
+    let Some(push2) = (unsafe { push2.as_mut() }) else {
+        return status(Err("Null device"));
+    };
+    if text.is_null() {
+        return status(Err("Null text"));
+    }
+    let text = unsafe { CStr::from_ptr(text) }.to_string_lossy();
+    let style = MonoTextStyle::new(&FONT_6X10, Bgr565::from(Rgb888::new(r, g, b)));
+    Text::new(&text, Point::new(x, y), style)
+        .draw(&mut push2.display)
+        .unwrap(); // Infallible
+    0
+}
+
+/// Sends the frame buffer to the display.
+///
+/// # Safety
+/// `push2` must be a live device.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn push2_flush(push2: *mut Push2) -> i32 {
+    let Some(push2) = (unsafe { push2.as_mut() }) else {
+        return status(Err("Null device"));
+    };
+    status(push2.display.flush().map(|_| ()))
+}
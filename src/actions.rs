@@ -0,0 +1,175 @@
+use crate::app_config::config_dir;
+use crate::button_map::decode_delta;
+use crate::{ControlName, LearnedControl, Push2, Push2Event, Push2State};
+use log::info;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ActionMapError {
+    #[error("Failed to parse action mapping file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read action mapping file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// How a control has to be used to trigger an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Gesture {
+    #[default]
+    Press,
+    Release,
+    /// An encoder turned clockwise.
+    TurnRight,
+    /// An encoder turned counter-clockwise.
+    TurnLeft,
+}
+
+/// Binds an app command to a control, a gesture and held modifier buttons.
+///
+/// In RON: `(action: next_page, control: Button(PageRight), modifiers: [Shift])`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionBinding<A> {
+    pub action: A,
+    pub control: LearnedControl,
+    #[serde(default)]
+    pub gesture: Gesture,
+    /// Buttons that must be held for the binding to apply.
+    #[serde(default)]
+    pub modifiers: Vec<ControlName>,
+}
+
+impl<A> ActionBinding<A> {
+    pub fn new(action: A, control: LearnedControl) -> Self {
+        Self {
+            action,
+            control,
+            gesture: Gesture::Press,
+            modifiers: Vec::new(),
+        }
+    }
+
+    pub fn with_gesture(mut self, gesture: Gesture) -> Self {
+        self.gesture = gesture;
+        self
+    }
+
+    pub fn with_modifier(mut self, modifier: ControlName) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+
+    fn matches(&self, event: &Push2Event, state: &Push2State) -> bool {
+        let used = match (event, self.control) {
+            (Push2Event::PadPressed { coord, .. }, LearnedControl::Pad(pad)) => {
+                *coord == pad && self.gesture == Gesture::Press
+            }
+            (Push2Event::PadReleased { coord, .. }, LearnedControl::Pad(pad)) => {
+                *coord == pad && self.gesture == Gesture::Release
+            }
+            (Push2Event::ButtonPressed { name, .. }, LearnedControl::Button(button)) => {
+                *name == button && self.gesture == Gesture::Press
+            }
+            (Push2Event::ButtonReleased { name }, LearnedControl::Button(button)) => {
+                *name == button && self.gesture == Gesture::Release
+            }
+            (Push2Event::EncoderTwisted { name, raw_delta }, LearnedControl::Encoder(encoder)) => {
+                let gesture = if decode_delta(*raw_delta) > 0 {
+                    Gesture::TurnRight
+                } else {
+                    Gesture::TurnLeft
+                };
+                *name == encoder && self.gesture == gesture
+            }
+            _ => false,
+        };
+        used && self.modifiers.iter().all(|m| state.is_button_held(*m))
+    }
+}
+
+/// Produced by `ActionMap::poll_action`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionEvent<A> {
+    /// An event triggered a bound command.
+    Action { action: A, event: Push2Event },
+    /// An event no binding applied to.
+    Unbound(Push2Event),
+}
+
+/// Maps events to app-defined commands through declarative bindings.
+///
+/// The app declares its commands as an enum deriving `Deserialize`, and the
+/// bindings come from code or a RON file the user can edit, so app logic
+/// never refers to physical controls. When several bindings match, the one
+/// with the most modifiers wins, so Shift+PageRight can differ from PageRight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap<A> {
+    pub bindings: Vec<ActionBinding<A>>,
+}
+
+impl<A> Default for ActionMap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl<A: Clone> ActionMap<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_binding(mut self, binding: ActionBinding<A>) -> Self {
+        self.bindings.push(binding);
+        self
+    }
+
+    /// The command an event triggers, if any.
+    pub fn resolve(&self, event: &Push2Event, state: &Push2State) -> Option<&A> {
+        self.bindings
+            .iter()
+            .filter(|b| b.matches(event, state))
+            .max_by_key(|b| b.modifiers.len())
+            .map(|b| &b.action)
+    }
+
+    /// The next event from the device, with the command it triggers.
+    pub fn poll_action(&self, push2: &mut Push2) -> Option<ActionEvent<A>> {
+        let event = push2.poll_event()?;
+        Some(match self.resolve(&event, &push2.state) {
+            Some(action) => ActionEvent::Action {
+                action: action.clone(),
+                event,
+            },
+            None => ActionEvent::Unbound(event),
+        })
+    }
+}
+
+impl<A: Clone + DeserializeOwned> ActionMap<A> {
+    /// Loads the bindings from a RON list of `ActionBinding`s.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ActionMapError> {
+        let ron = fs::read_to_string(path)?;
+        let bindings = ron::from_str(&ron).map_err(Box::new)?;
+        Ok(Self { bindings })
+    }
+
+    /// Loads "actions/<app_name>.ron" from the push2 config directory if the
+    /// user wrote one, and uses `defaults` otherwise.
+    pub fn in_config_dir(app_name: &str, defaults: Self) -> Result<Self, ActionMapError> {
+        let Some(mut path) = config_dir() else {
+            return Ok(defaults);
+        };
+        path.push("actions");
+        path.push(format!("{app_name}.ron"));
+        if !path.exists() {
+            return Ok(defaults);
+        }
+        info!("Loading action bindings from: {:?}", path);
+        Self::load(path)
+    }
+}
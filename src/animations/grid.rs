@@ -0,0 +1,184 @@
+//! 8x8 pad animations for feedback flourishes and idle screens.
+
+use crate::{LightTarget, PadCoord, Push2, Push2Colors, Push2Error};
+use std::time::{Duration, Instant};
+
+/// One color per pad, `None` leaving the pad to the layers below.
+pub type GridFrame = [[Option<u8>; 8]; 8];
+
+/// A canned pad animation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridAnimation {
+    /// A ring spreading out from a pad, then fading.
+    Ripple { origin: PadCoord, color: u8 },
+    /// A lit row moving from the top to the bottom, over and over.
+    RowSweep { color: u8 },
+    /// Diagonal bands of every hue, scrolling.
+    Rainbow,
+    /// One meter per pad column, green to red from the bottom; levels are 0.0-1.0.
+    VuMeters { levels: [f32; 8] },
+}
+
+impl GridAnimation {
+    /// How long the animation lasts, or `None` if it runs until stopped.
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            GridAnimation::Ripple { .. } => Some(Duration::from_millis(800)),
+            _ => None,
+        }
+    }
+
+    /// The pads of the animation `elapsed` after it started.
+    pub fn frame(&self, elapsed: Duration) -> GridFrame {
+        let mut frame = [[None; 8]; 8];
+        let secs = elapsed.as_secs_f32();
+        for (y, row) in frame.iter_mut().enumerate() {
+            for (x, pad) in row.iter_mut().enumerate() {
+                *pad = self.pad(x as f32, y as f32, secs);
+            }
+        }
+        frame
+    }
+
+    fn pad(&self, x: f32, y: f32, secs: f32) -> Option<u8> {
+        match self {
+            GridAnimation::Ripple { origin, color } => {
+                let distance = (x - origin.x as f32).hypot(y - origin.y as f32);
+                // The ring moves 12 pads per second.
+                let radius = secs * 12.0;
+                if (distance - radius).abs() >= 0.75 {
+                    return None;
+                }
+                let (h, s, v) = Push2Colors::palette_hsv(*color);
+                let fade = (1.0 - secs / 0.8).max(0.0);
+                Some(Push2Colors::hsv_to_nearest_index(h, s, v * fade))
+            }
+            GridAnimation::RowSweep { color } => {
+                // One row every 60 ms.
+                let row = (secs / 0.06) as u32 % 8;
+                (row == y as u32).then_some(*color)
+            }
+            GridAnimation::Rainbow => {
+                let hue = (x + y) * 22.5 + secs * 120.0;
+                Some(Push2Colors::hsv_to_nearest_index(hue, 1.0, 1.0))
+            }
+            GridAnimation::VuMeters { levels } => {
+                let height = 8.0 - y;
+                if levels[x as usize] * 8.0 < height - 0.5 {
+                    return Some(Push2Colors::BLACK);
+                }
+                Some(match height as u8 {
+                    8 => Push2Colors::RED,
+                    6..=7 => Push2Colors::YELLOW,
+                    _ => Push2Colors::GREEN,
+                })
+            }
+        }
+    }
+}
+
+/// Identifies a running animation, to stop or update it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnimationHandle(u64);
+
+struct Running {
+    handle: AnimationHandle,
+    animation: GridAnimation,
+    started: Instant,
+}
+
+/// Plays `GridAnimation`s on the pads, later ones on top of earlier ones.
+///
+/// The pad colors from before the first animation are restored when the
+/// last one stops or ends. Call `update` from the main loop.
+#[derive(Default)]
+pub struct GridAnimator {
+    running: Vec<Running>,
+    next_handle: u64,
+    /// The pad colors to restore once nothing is running.
+    base: Option<[[u8; 8]; 8]>,
+}
+
+impl GridAnimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&mut self, animation: GridAnimation) -> AnimationHandle {
+        let handle = AnimationHandle(self.next_handle);
+        self.next_handle += 1;
+        self.running.push(Running {
+            handle,
+            animation,
+            started: Instant::now(),
+        });
+        handle
+    }
+
+    /// Stops an animation. The pads are redrawn by the next `update`.
+    pub fn stop(&mut self, handle: AnimationHandle) {
+        self.running.retain(|r| r.handle != handle);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.running.clear();
+    }
+
+    pub fn is_running(&self, handle: AnimationHandle) -> bool {
+        self.running.iter().any(|r| r.handle == handle)
+    }
+
+    /// Changes the levels of a `VuMeters` animation.
+    pub fn set_levels(&mut self, handle: AnimationHandle, new_levels: [f32; 8]) {
+        for running in &mut self.running {
+            if running.handle == handle
+                && let GridAnimation::VuMeters { levels } = &mut running.animation
+            {
+                *levels = new_levels;
+            }
+        }
+    }
+
+    /// Draws the running animations, sending the pads that changed.
+    pub fn update(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let now = Instant::now();
+        self.running.retain(|r| {
+            r.animation
+                .duration()
+                .is_none_or(|d| now.duration_since(r.started) < d)
+        });
+        let base = match (self.running.is_empty(), self.base) {
+            (true, None) => return Ok(()),
+            (true, Some(base)) => {
+                self.base = None;
+                base
+            }
+            (false, _) => *self
+                .base
+                .get_or_insert_with(|| push2.state.pads.map(|row| row.map(|pad| pad.color))),
+        };
+
+        let mut lights = Vec::new();
+        for (y, base_row) in base.iter().enumerate() {
+            for (x, &base_color) in base_row.iter().enumerate() {
+                let color = self
+                    .running
+                    .iter()
+                    .rev()
+                    .find_map(|r| {
+                        let secs = (now - r.started).as_secs_f32();
+                        r.animation.pad(x as f32, y as f32, secs)
+                    })
+                    .unwrap_or(base_color);
+                if push2.state.pads[y][x].color != color {
+                    let coord = PadCoord {
+                        x: x as u8,
+                        y: y as u8,
+                    };
+                    lights.push((LightTarget::Pad(coord), color));
+                }
+            }
+        }
+        push2.set_lights(lights).into_result()
+    }
+}
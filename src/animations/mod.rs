@@ -0,0 +1,3 @@
+//! Canned light animations.
+
+pub mod grid;
@@ -1,6 +1,10 @@
+use crate::GridOrigin;
+use crate::color_correction::ColorCorrection;
 use log::{info, warn};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,44 +15,402 @@ pub enum ConfigError {
     ConfigDirError(std::io::Error),
     #[error("Could not read or write config file: {0}")]
     ConfigFileError(std::io::Error),
+    #[error("Failed to serialize config: {0}")]
+    SerializeError(#[from] ron::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+    #[error("Missing value for argument {0}")]
+    MissingArgument(String),
 }
 
-#[derive(Deserialize)]
+/// The directory holding the push2 config files: `PUSH2_CONFIG_DIR` if set,
+/// otherwise "push2" in the user's config directory.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("PUSH2_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::config_dir().map(|dir| dir.join("push2"))
+}
+
+/// A file in the config directory, reported by `Push2Event::ConfigReloaded`
+/// after it changed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConfigFile {
+    /// "app_config.ron". A new port or route reconnects the MIDI ports.
+    AppConfig,
+    /// "button_map.ron".
+    ButtonMap,
+    /// "themes/<name>.ron", for `Theme::named`.
+    Theme(String),
+    /// "actions/<app_name>.ron", for `ActionMap::in_config_dir`.
+    Actions(String),
+}
+
+impl ConfigFile {
+    /// The config file at `path`, relative to the config directory.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if path.extension()? != "ron" {
+            return None;
+        }
+        let stem = path.file_stem()?.to_str()?.to_string();
+        let mut parents = path.parent()?.components();
+        let folder = parents.next().map(|c| c.as_os_str().to_str());
+        if parents.next().is_some() {
+            return None;
+        }
+        match (folder, stem.as_str()) {
+            (None, "app_config") => Some(ConfigFile::AppConfig),
+            (None, "button_map") => Some(ConfigFile::ButtonMap),
+            (Some(Some("themes")), _) => Some(ConfigFile::Theme(stem)),
+            (Some(Some("actions")), _) => Some(ConfigFile::Actions(stem)),
+            _ => None,
+        }
+    }
+
+    /// The file's path relative to the config directory.
+    pub fn path(&self) -> PathBuf {
+        match self {
+            ConfigFile::AppConfig => PathBuf::from("app_config.ron"),
+            ConfigFile::ButtonMap => PathBuf::from("button_map.ron"),
+            ConfigFile::Theme(name) => Path::new("themes").join(format!("{name}.ron")),
+            ConfigFile::Actions(name) => Path::new("actions").join(format!("{name}.ron")),
+        }
+    }
+}
+
+/// Settings that take precedence over the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    pub midi_input_port: Option<String>,
+    pub midi_output_port: Option<String>,
+    pub config_dir: Option<PathBuf>,
+    pub headless: bool,
+}
+
+impl ConfigOverrides {
+    /// Reads `PUSH2_MIDI_INPUT`, `PUSH2_MIDI_OUTPUT`, `PUSH2_CONFIG_DIR` and
+    /// `PUSH2_HEADLESS` (any value but "0" or "false").
+    pub fn from_env() -> Self {
+        Self {
+            midi_input_port: env::var("PUSH2_MIDI_INPUT").ok(),
+            midi_output_port: env::var("PUSH2_MIDI_OUTPUT").ok(),
+            config_dir: env::var_os("PUSH2_CONFIG_DIR").map(PathBuf::from),
+            headless: env::var("PUSH2_HEADLESS").is_ok_and(|v| v != "0" && v != "false"),
+        }
+    }
+
+    /// Applies `--midi-input <port>`, `--midi-output <port>`, `--config-dir <dir>`
+    /// and `--headless` on top of these overrides. Other arguments are skipped.
+    pub fn with_args<I>(mut self, args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| ConfigError::MissingArgument(arg.clone()))
+            };
+            match arg.as_str() {
+                "--midi-input" => self.midi_input_port = Some(value()?),
+                "--midi-output" => self.midi_output_port = Some(value()?),
+                "--config-dir" => self.config_dir = Some(PathBuf::from(value()?)),
+                "--headless" => self.headless = true,
+                _ => {}
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppConfig {
     pub midi_input_port: String,
     pub midi_output_port: String,
+    /// Extra forwarding rules applied to incoming MIDI
+    #[serde(default)]
+    pub routes: Vec<RouteRule>,
+    /// The file the config was loaded from, and is saved to
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// When true, no files are written and no port is picked interactively
+    #[serde(default)]
+    pub headless: bool,
+    /// What to do when a configured port is not found
+    #[serde(default)]
+    pub port_selection: PortSelectionPolicy,
+    /// The MIDI backend the ports belong to. Connecting fails if the crate was
+    /// built with another one, rather than opening ports from the wrong system.
+    #[serde(default)]
+    pub midi_backend: Option<MidiBackend>,
+    /// Gamma and contrast correction for this install's display panel
+    #[serde(default)]
+    pub color_correction: Option<ColorCorrection>,
+    /// Which pad is (0, 0) for `Push2::pad_at` and `Push2::grid_position`
+    #[serde(default)]
+    pub grid_origin: GridOrigin,
+}
+
+/// The MIDI system the ports are opened on, chosen when the crate is built.
+///
+/// On Linux and macOS the `jack` feature switches from the native backend to JACK,
+/// e.g. to share the device with a JACK-based audio setup.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiBackend {
+    /// ALSA sequencer ports, the default on Linux.
+    Alsa,
+    Jack,
+    CoreMidi,
+    WindowsMidi,
+    WebMidi,
+}
+
+impl MidiBackend {
+    /// The backend this build of the crate uses.
+    pub const fn current() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            MidiBackend::WebMidi
+        } else if cfg!(target_os = "windows") {
+            MidiBackend::WindowsMidi
+        } else if cfg!(feature = "jack") {
+            MidiBackend::Jack
+        } else if cfg!(target_os = "linux") {
+            MidiBackend::Alsa
+        } else {
+            MidiBackend::CoreMidi
+        }
+    }
+
+    /// A regular expression matching the name this backend gives the Push 2 live port.
+    ///
+    /// ALSA names it "Ableton Push 2:Ableton Push 2 Live Port 32:0", where the
+    /// client number changes between boots. Under JACK the ports come from a
+    /// bridge, e.g. "a2j:Ableton Push 2 [32] (capture): Ableton Push 2 Live Port"
+    /// with a2jmidid, or "Midi-Bridge:Ableton Push 2 1:(capture_0) Ableton Push 2 Live Port"
+    /// with PipeWire.
+    pub fn push2_port_pattern(&self) -> &'static str {
+        match self {
+            MidiBackend::Alsa => r"^Ableton Push 2:Ableton Push 2 Live Port \d+:\d+$",
+            MidiBackend::Jack => r"Ableton Push 2.*Live Port$",
+            MidiBackend::CoreMidi => r"^Ableton Push 2 Live Port$",
+            MidiBackend::WindowsMidi => r"^Ableton Push 2( \d+)?$",
+            MidiBackend::WebMidi => r"Ableton Push 2",
+        }
+    }
+}
+
+/// What `MidiHandler` does when a configured port is not found.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum PortSelectionPolicy {
+    /// Fail: only the configured port is used.
+    ConfiguredOnly,
+    /// Use the first port whose name matches this regular expression.
+    FirstMatching(String),
+    /// Look for the Push 2 by its port name, then use the only available port,
+    /// or ask on stdin which one to use.
+    #[default]
+    Interactive,
+    /// Look for the Push 2 by its port name, then use the only available port,
+    /// or fail if there are several.
+    Fail,
+}
+
+/// One end of a MIDI route.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RouteEndpoint {
+    /// The Push 2 itself: as a source, its input port; as a destination, the event stream.
+    Push2,
+    /// Any other MIDI port, matched by a substring of its name.
+    Port(String),
+}
+
+/// Selects which MIDI messages a route forwards.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFilter {
+    NoteOn,
+    NoteOff,
+    PolyPressure,
+    ControlChange,
+    /// A single control change number
+    Control(u8),
+    ProgramChange,
+    ChannelPressure,
+    PitchBend,
+    SysEx,
+    /// Clock, start, stop and other realtime messages
+    Realtime,
+    /// An exact status byte (including the channel)
+    Status(u8),
+}
+
+impl MessageFilter {
+    pub fn matches(&self, message: &[u8]) -> bool {
+        let Some(&status) = message.first() else {
+            return false;
+        };
+        let kind = if status < 0xF0 { status & 0xF0 } else { status };
+        let data2 = message.get(2).copied().unwrap_or(0);
+        match self {
+            MessageFilter::NoteOn => kind == 0x90 && data2 > 0,
+            MessageFilter::NoteOff => kind == 0x80 || (kind == 0x90 && data2 == 0),
+            MessageFilter::PolyPressure => kind == 0xA0,
+            MessageFilter::ControlChange => kind == 0xB0,
+            MessageFilter::Control(number) => kind == 0xB0 && message.get(1) == Some(number),
+            MessageFilter::ProgramChange => kind == 0xC0,
+            MessageFilter::ChannelPressure => kind == 0xD0,
+            MessageFilter::PitchBend => kind == 0xE0,
+            MessageFilter::SysEx => status == 0xF0,
+            MessageFilter::Realtime => status >= 0xF8,
+            MessageFilter::Status(s) => status == *s,
+        }
+    }
+}
+
+/// Forwards messages from `source` to `destination`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RouteRule {
+    pub source: RouteEndpoint,
+    pub destination: RouteEndpoint,
+    /// Only messages matching one of these filters are forwarded. Empty forwards everything.
+    #[serde(default)]
+    pub filter: Vec<MessageFilter>,
+}
+
+impl RouteRule {
+    pub fn matches(&self, message: &[u8]) -> bool {
+        self.filter.is_empty() || self.filter.iter().any(|f| f.matches(message))
+    }
 }
 
 impl AppConfig {
+    /// Loads the config file, with the environment overrides applied.
     pub fn new() -> Result<Self, ConfigError> {
-        if let Some(mut config_path) = dirs::config_dir() {
-            config_path.push("push2");
-            fs::create_dir_all(&config_path).map_err(ConfigError::ConfigDirError)?;
+        Self::with_overrides(ConfigOverrides::from_env())
+    }
 
-            config_path.push("app_config.ron");
+    /// Like `new`, with the command line arguments applied on top of the environment.
+    pub fn from_args() -> Result<Self, ConfigError> {
+        Self::with_overrides(ConfigOverrides::from_env().with_args(env::args().skip(1))?)
+    }
 
-            match fs::read_to_string(&config_path) {
-                Ok(config_string) => {
-                    info!("Loading config from: {:?}", config_path);
-                    let config: AppConfig = ron::from_str(&config_string).map_err(Box::new)?;
-                    return Ok(config);
-                }
-                Err(_) => {
-                    info!("No config file found. Writing default to {:?}", config_path);
-                    let default_config_string = include_str!("../config/app_config.ron");
-                    fs::write(&config_path, default_config_string)
-                        .map_err(ConfigError::ConfigFileError)?;
-
-                    let config: AppConfig =
-                        ron::from_str(default_config_string).map_err(Box::new)?;
-                    return Ok(config);
+    pub fn with_overrides(overrides: ConfigOverrides) -> Result<Self, ConfigError> {
+        let dir = overrides.config_dir.clone().or_else(config_dir);
+        let mut config = Self::load(dir, overrides.headless)?;
+        if let Some(port) = overrides.midi_input_port {
+            config.midi_input_port = port;
+        }
+        if let Some(port) = overrides.midi_output_port {
+            config.midi_output_port = port;
+        }
+        config.headless |= overrides.headless;
+        Ok(config)
+    }
+
+    /// Reads "app_config.ron" from `dir`, writing the default there first if
+    /// it is missing (unless headless).
+    fn load(dir: Option<PathBuf>, headless: bool) -> Result<Self, ConfigError> {
+        let default_config_string = include_str!("../config/app_config.ron");
+        let Some(mut config_path) = dir else {
+            warn!("Could not find config directory. Falling back to embedded config.");
+            let config: AppConfig = ron::from_str(default_config_string).map_err(Box::new)?;
+            return Ok(config);
+        };
+        config_path.push("app_config.ron");
+
+        match fs::read_to_string(&config_path) {
+            Ok(config_string) => {
+                info!("Loading config from: {:?}", config_path);
+                let mut config: AppConfig = ron::from_str(&config_string).map_err(Box::new)?;
+                config.path = Some(config_path);
+                Ok(config)
+            }
+            Err(_) if headless => {
+                info!("No config file found. Using the embedded config.");
+                let config: AppConfig = ron::from_str(default_config_string).map_err(Box::new)?;
+                Ok(config)
+            }
+            Err(_) => {
+                info!("No config file found. Writing default to {:?}", config_path);
+                if let Some(dir) = config_path.parent() {
+                    fs::create_dir_all(dir).map_err(ConfigError::ConfigDirError)?;
                 }
+                fs::write(&config_path, default_config_string)
+                    .map_err(ConfigError::ConfigFileError)?;
+
+                let mut config: AppConfig =
+                    ron::from_str(default_config_string).map_err(Box::new)?;
+                config.path = Some(config_path);
+                Ok(config)
             }
         }
+    }
 
-        warn!("Could not find config directory. Falling back to embedded config.");
-        let config_string = include_str!("../config/app_config.ron");
-        let config: AppConfig = ron::from_str(config_string).map_err(Box::new)?;
+    /// Reads a config file, e.g. again after it changed.
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        let config_string = fs::read_to_string(path).map_err(ConfigError::ConfigFileError)?;
+        let mut config: AppConfig = ron::from_str(&config_string).map_err(Box::new)?;
+        config.path = Some(path.to_path_buf());
         Ok(config)
     }
-}
\ No newline at end of file
+
+    /// Whether connecting with `other` would open other MIDI ports or routes.
+    pub fn midi_differs(&self, other: &AppConfig) -> bool {
+        self.midi_input_port != other.midi_input_port
+            || self.midi_output_port != other.midi_output_port
+            || self.routes != other.routes
+            || self.port_selection != other.port_selection
+            || self.midi_backend != other.midi_backend
+    }
+
+    pub fn with_port_selection(mut self, policy: PortSelectionPolicy) -> Self {
+        self.port_selection = policy;
+        self
+    }
+
+    pub fn with_grid_origin(mut self, origin: GridOrigin) -> Self {
+        self.grid_origin = origin;
+        self
+    }
+
+    /// The port selection policy in effect: `Interactive` becomes `Fail` when headless.
+    pub fn effective_port_selection(&self) -> PortSelectionPolicy {
+        match &self.port_selection {
+            PortSelectionPolicy::Interactive if self.headless => PortSelectionPolicy::Fail,
+            policy => policy.clone(),
+        }
+    }
+
+    /// Sets the MIDI ports, e.g. to the ones picked when the configured ports were missing.
+    /// Returns whether anything changed.
+    pub fn set_ports(&mut self, input: &str, output: &str) -> bool {
+        let changed = self.midi_input_port != input || self.midi_output_port != output;
+        self.midi_input_port = input.to_string();
+        self.midi_output_port = output.to_string();
+        changed
+    }
+
+    /// Writes the config back to the file it was loaded from, or to
+    /// "app_config.ron" in the config directory.
+    /// Comments in the file are not kept.
+    pub fn save(&mut self) -> Result<(), ConfigError> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => config_dir()
+                .ok_or(ConfigError::NoConfigDir)?
+                .join("app_config.ron"),
+        };
+        self.save_to(&path)?;
+        self.path = Some(path);
+        Ok(())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), ConfigError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(ConfigError::ConfigDirError)?;
+        }
+        let config_string = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, config_string).map_err(ConfigError::ConfigFileError)?;
+        info!("Saved config to: {:?}", path);
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+//! Runs several apps on one Push 2, switched with the mode buttons like the
+//! stock Note, Session, Device, Mix and Browse modes.
+
+use crate::{ControlName, LedScene, Push2, Push2Colors, Push2Error, Push2Event};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_10X20},
+    pixelcolor::Bgr565,
+    prelude::*,
+    text::{Alignment, Text},
+};
+
+/// The buttons under the display that pick an app on the launcher screen.
+const LAUNCHER_BUTTONS: [ControlName; 8] = [
+    ControlName::UpperRow1,
+    ControlName::UpperRow2,
+    ControlName::UpperRow3,
+    ControlName::UpperRow4,
+    ControlName::UpperRow5,
+    ControlName::UpperRow6,
+    ControlName::UpperRow7,
+    ControlName::UpperRow8,
+];
+
+/// A mode of an `AppHost`, shown while its button is selected.
+///
+/// An app owns the pads, the display and every button light but the mode
+/// buttons while it is active. The host keeps its lights when it is switched
+/// away and puts them back when it returns, so an app only has to light what
+/// changes.
+pub trait PushApp {
+    fn name(&self) -> &str;
+
+    /// Called when the app becomes active, after its lights are restored.
+    fn on_enter(&mut self, _push2: &mut Push2) -> Result<(), Push2Error> {
+        Ok(())
+    }
+
+    /// Called before another app becomes active.
+    fn on_exit(&mut self, _push2: &mut Push2) -> Result<(), Push2Error> {
+        Ok(())
+    }
+
+    /// Handles an event from the device. Mode button presses are not passed on.
+    fn on_event(&mut self, push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error>;
+
+    /// Draws the next frame into `push2.display`. The host flushes it.
+    fn draw(&mut self, push2: &mut Push2) -> Result<(), Push2Error>;
+
+    /// When true, `AppHost::run` returns.
+    fn should_quit(&self) -> bool {
+        false
+    }
+}
+
+struct HostedApp {
+    app: Box<dyn PushApp>,
+    /// The mode button selecting the app, if any.
+    button: Option<ControlName>,
+    /// The app's lights, kept while another app is active.
+    scene: Option<LedScene>,
+}
+
+/// Owns the `Push2`, switches between apps with their mode buttons and runs
+/// the main loop:
+///
+/// ```ignore
+/// let mut host = AppHost::new(Push2::new()?);
+/// host.add_mode(ControlName::Note, MyNoteApp::new());
+/// host.add_mode(ControlName::Mix, MyMixer::new());
+/// host.run()
+/// ```
+pub struct AppHost {
+    pub push2: Push2,
+    apps: Vec<HostedApp>,
+    active: Option<usize>,
+    /// The button that opens the launcher screen, if there is one.
+    launcher_button: Option<ControlName>,
+    /// Whether the first app or the launcher was shown.
+    started: bool,
+}
+
+impl AppHost {
+    /// Flushes at 60 frames per second unless the display already has a target.
+    pub fn new(mut push2: Push2) -> Self {
+        if push2.display.target_fps().is_none() {
+            push2.display.set_target_fps(60);
+        }
+        Self {
+            push2,
+            apps: Vec::new(),
+            active: None,
+            launcher_button: None,
+            started: false,
+        }
+    }
+
+    /// Starts on a launcher screen listing the first 8 apps above the buttons
+    /// that open them. `button` goes back to it from any app.
+    pub fn with_launcher(mut self, button: ControlName) -> Self {
+        self.launcher_button = Some(button);
+        self
+    }
+
+    /// Adds an app without a mode button, for `switch_to`. Returns its index.
+    pub fn add(&mut self, app: impl PushApp + 'static) -> usize {
+        self.push(Box::new(app), None)
+    }
+
+    /// Adds an app selected by a mode button, such as `ControlName::Session`.
+    /// Returns its index.
+    pub fn add_mode(&mut self, button: ControlName, app: impl PushApp + 'static) -> usize {
+        self.push(Box::new(app), Some(button))
+    }
+
+    /// Adds an app created at runtime, e.g. by an `AppRegistry`.
+    pub fn add_boxed(&mut self, app: Box<dyn PushApp>) -> usize {
+        self.push(app, None)
+    }
+
+    fn push(&mut self, app: Box<dyn PushApp>, button: Option<ControlName>) -> usize {
+        self.apps.push(HostedApp {
+            app,
+            button,
+            scene: None,
+        });
+        self.apps.len() - 1
+    }
+
+    /// The index of the active app.
+    pub fn active(&self) -> Option<usize> {
+        self.active
+    }
+
+    /// The names of the apps, by index.
+    pub fn app_names(&self) -> impl Iterator<Item = &str> {
+        self.apps.iter().map(|hosted| hosted.app.name())
+    }
+
+    /// Makes another app active. The display is cleared for it and its
+    /// lights from the last time it was active are restored.
+    pub fn switch_to(&mut self, index: usize) -> Result<(), Push2Error> {
+        if index >= self.apps.len() || self.active == Some(index) {
+            return Ok(());
+        }
+        self.leave_active()?;
+        self.started = true;
+        self.active = Some(index);
+        let scene = self.apps[index]
+            .scene
+            .take()
+            .unwrap_or_else(|| LedScene::new(self.apps[index].app.name()));
+        self.push2.clear_buttons().into_result()?;
+        self.push2.apply_scene(&scene)?;
+        self.light_mode_buttons()?;
+        // Drawing on the display cannot fail.
+        let _ = self.push2.display.clear(Bgr565::BLACK);
+        self.apps[index].app.on_enter(&mut self.push2)
+    }
+
+    /// Shows the launcher screen, if the host has one.
+    pub fn show_launcher(&mut self) -> Result<(), Push2Error> {
+        if self.launcher_button.is_none() {
+            return Ok(());
+        }
+        self.leave_active()?;
+        self.started = true;
+        self.active = None;
+        self.push2.clear_all().into_result()?;
+        for button in LAUNCHER_BUTTONS.iter().take(self.apps.len()) {
+            self.push2
+                .set_button_light(*button, Push2Colors::WHITE_BRIGHT)?;
+        }
+        self.light_mode_buttons()?;
+        self.draw_launcher();
+        Ok(())
+    }
+
+    fn draw_launcher(&mut self) {
+        // Drawing on the display cannot fail.
+        let _ = self.push2.display.clear(Bgr565::BLACK);
+        let style = MonoTextStyle::new(&FONT_10X20, Bgr565::WHITE);
+        let column = (crate::display::DISPLAY_WIDTH / LAUNCHER_BUTTONS.len()) as i32;
+        for (index, hosted) in self.apps.iter().take(LAUNCHER_BUTTONS.len()).enumerate() {
+            let center = Point::new(column * index as i32 + column / 2, 20);
+            let _ = Text::with_alignment(hosted.app.name(), center, style, Alignment::Center)
+                .draw(&mut self.push2.display);
+        }
+    }
+
+    /// Lets the active app clean up and keeps its lights for its return.
+    fn leave_active(&mut self) -> Result<(), Push2Error> {
+        if let Some(previous) = self.active {
+            let hosted = &mut self.apps[previous];
+            hosted.app.on_exit(&mut self.push2)?;
+            hosted.scene = Some(LedScene::capture(hosted.app.name(), &self.push2.state));
+        }
+        Ok(())
+    }
+
+    fn light_mode_buttons(&mut self) -> Result<(), Push2Error> {
+        if let Some(button) = self.launcher_button {
+            let light = if self.active.is_none() {
+                Push2Colors::WHITE_BRIGHT
+            } else {
+                Push2Colors::WHITE_LOW
+            };
+            self.push2.set_button_light(button, light)?;
+        }
+        for (index, hosted) in self.apps.iter().enumerate() {
+            if let Some(button) = hosted.button {
+                let light = if self.active == Some(index) {
+                    Push2Colors::WHITE_BRIGHT
+                } else {
+                    Push2Colors::WHITE_LOW
+                };
+                self.push2.set_button_light(button, light)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the pending events, then draws and flushes the active app's
+    /// frame, waiting for the display's target frame rate.
+    pub fn step(&mut self) -> Result<(), Push2Error> {
+        if !self.started {
+            match self.launcher_button {
+                Some(_) => self.show_launcher()?,
+                None if !self.apps.is_empty() => self.switch_to(0)?,
+                None => {}
+            }
+        }
+        while let Some(event) = self.push2.poll_event() {
+            if let Push2Event::ButtonPressed { name, .. } = event {
+                if Some(name) == self.launcher_button {
+                    self.show_launcher()?;
+                    continue;
+                }
+                if let Some(index) = self.apps.iter().position(|h| h.button == Some(name)) {
+                    self.switch_to(index)?;
+                    continue;
+                }
+                if self.active.is_none()
+                    && let Some(index) = LAUNCHER_BUTTONS.iter().position(|b| *b == name)
+                {
+                    self.switch_to(index)?;
+                    continue;
+                }
+            }
+            if let Push2Event::ButtonReleased { name } = event
+                && (Some(name) == self.launcher_button
+                    || self.apps.iter().any(|h| h.button == Some(name)))
+            {
+                continue;
+            }
+            if let Some(index) = self.active {
+                self.apps[index].app.on_event(&mut self.push2, &event)?;
+            }
+        }
+        if let Some(index) = self.active {
+            self.apps[index].app.draw(&mut self.push2)?;
+        }
+        self.push2.display.flush()?;
+        Ok(())
+    }
+
+    /// Runs the main loop, starting with the first app or the launcher,
+    /// until the active app asks to quit.
+    pub fn run(&mut self) -> Result<(), Push2Error> {
+        loop {
+            self.step()?;
+            if let Some(index) = self.active
+                && self.apps[index].app.should_quit()
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockBackend, PadCoord};
+
+    struct Lights;
+
+    impl PushApp for Lights {
+        fn name(&self) -> &str {
+            "Lights"
+        }
+
+        fn on_event(&mut self, push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+            if let Push2Event::PadPressed { coord, .. } = *event {
+                push2.set_pad_color(coord, Push2Colors::RED)?;
+            }
+            Ok(())
+        }
+
+        fn draw(&mut self, _push2: &mut Push2) -> Result<(), Push2Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn mode_buttons_switch_apps_and_keep_their_lights() {
+        let (backend, tx) = MockBackend::new();
+        let mut host = AppHost::new(Push2::with_backend(backend).unwrap());
+        host.push2.display.set_target_fps(0);
+        host.add_mode(ControlName::Note, Lights);
+        host.add_mode(ControlName::Session, Lights);
+        let note = host
+            .push2
+            .button_map
+            .get_control_address(ControlName::Note)
+            .unwrap();
+        let session = host
+            .push2
+            .button_map
+            .get_control_address(ControlName::Session)
+            .unwrap();
+
+        // The top-left pad.
+        tx.send(vec![0x90, 92, 100]).unwrap();
+        host.step().unwrap();
+        assert_eq!(host.active(), Some(0));
+        assert_eq!(host.push2.state.pads[0][0].color, Push2Colors::RED);
+
+        tx.send(vec![0xB0, session, 127]).unwrap();
+        host.step().unwrap();
+        assert_eq!(host.active(), Some(1));
+        assert_eq!(host.push2.state.pads[0][0].color, 0);
+
+        tx.send(vec![0xB0, note, 127]).unwrap();
+        host.step().unwrap();
+        assert_eq!(host.active(), Some(0));
+        let pad = PadCoord::new(0, 0);
+        assert_eq!(
+            host.push2.state.pads[pad.y as usize][pad.x as usize].color,
+            Push2Colors::RED
+        );
+        assert_eq!(
+            host.push2.state.buttons[&ControlName::Note].light,
+            Push2Colors::WHITE_BRIGHT
+        );
+    }
+}
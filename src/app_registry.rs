@@ -0,0 +1,115 @@
+//! A registry of apps by name, for hosts that pick their apps at startup or
+//! from the launcher, such as the push2-host binary.
+
+use crate::apps::sampler::{CommandBackend, Sampler};
+use crate::{AppHost, ClipLauncher, PushApp, StepSequencer};
+
+/// Creates a fresh instance of an app.
+pub type AppFactory = fn() -> Box<dyn PushApp>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AppEntry {
+    pub name: &'static str,
+    pub create: AppFactory,
+}
+
+/// Apps registered by name, usually built with `app_registry!`:
+///
+/// ```ignore
+/// let registry = push2::app_registry! {
+///     "Drums" => my_drums::DrumMachine::new(),
+///     "Looper" => my_looper::LooperApp::default(),
+/// };
+/// let mut host = AppHost::new(Push2::new()?).with_launcher(ControlName::Setup);
+/// registry.install(&mut host);
+/// host.run()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AppRegistry {
+    entries: Vec<AppEntry>,
+}
+
+impl AppRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The apps that come with the crate: a step sequencer, a clip launcher
+    /// and a sampler.
+    pub fn builtin() -> Self {
+        crate::app_registry! {
+            "Sequencer" => StepSequencer::new(36, 32),
+            "Clips" => ClipLauncher::new(8),
+            "Sampler" => Sampler::new(
+                CommandBackend::default(),
+                Sampler::default_recordings_dir().unwrap_or_else(|_| std::env::temp_dir()),
+            ),
+        }
+    }
+
+    /// Adds an app, replacing one registered under the same name.
+    pub fn register(&mut self, name: &'static str, create: AppFactory) {
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.push(AppEntry { name, create });
+    }
+
+    /// Adds the apps of another registry.
+    pub fn extend(&mut self, other: AppRegistry) {
+        for entry in other.entries {
+            self.register(entry.name, entry.create);
+        }
+    }
+
+    /// The registered apps, in the order they were added.
+    pub fn entries(&self) -> &[AppEntry] {
+        &self.entries
+    }
+
+    /// Creates the app registered under `name`.
+    pub fn create(&self, name: &str) -> Option<Box<dyn PushApp>> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| (entry.create)())
+    }
+
+    /// Adds an instance of every app to a host, in order.
+    pub fn install(&self, host: &mut AppHost) {
+        for entry in &self.entries {
+            host.add_boxed((entry.create)());
+        }
+    }
+}
+
+/// Builds an `AppRegistry` from `name => constructor` pairs. Each constructor
+/// is run every time the app is created.
+#[macro_export]
+macro_rules! app_registry {
+    ($($name:literal => $create:expr),* $(,)?) => {{
+        let mut registry = $crate::AppRegistry::new();
+        $(
+            registry.register($name, || -> ::std::boxed::Box<dyn $crate::PushApp> {
+                ::std::boxed::Box::new($create)
+            });
+        )*
+        registry
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_apps_by_name() {
+        let mut registry = app_registry! {
+            "Clips" => ClipLauncher::new(4),
+            "Sequencer" => StepSequencer::new(36, 16),
+        };
+        registry.register("Clips", || Box::new(ClipLauncher::new(8)));
+        let names: Vec<_> = registry.entries().iter().map(|e| e.name).collect();
+        assert_eq!(names, ["Sequencer", "Clips"]);
+        assert_eq!(registry.create("Sequencer").unwrap().name(), "Sequencer");
+        assert!(registry.create("Missing").is_none());
+    }
+}
@@ -0,0 +1,3 @@
+//! Ready-made apps built from the components of this crate.
+
+pub mod sampler;
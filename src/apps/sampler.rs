@@ -0,0 +1,534 @@
+use crate::button_map::decode_delta;
+use crate::formatters;
+use crate::gui::{ENCODER_LABEL_Y_POS, ENCODER_VALUE_Y_POS, GuiApi};
+use crate::{
+    ControlName, EncoderName, HighlightLayer, PadCoord, Push2, Push2Colors, Push2Error, Push2Event,
+    PushApp,
+};
+use embedded_graphics::pixelcolor::{Bgr565, RgbColor};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SamplerError {
+    #[error("Audio backend error: {0}")]
+    Backend(String),
+    #[error("Could not read or write sampler file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize pad assignments: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to parse pad assignments: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+}
+
+/// The sample assigned to a pad, with its playback settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PadSample {
+    pub path: PathBuf,
+    /// The playback volume (0.0-1.0).
+    pub volume: f32,
+    /// The pitch shift in semitones (-24 to 24).
+    pub pitch: f32,
+}
+
+impl PadSample {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            volume: 1.0,
+            pitch: 0.0,
+        }
+    }
+
+    /// The playback speed giving the pitch shift (1.0 = unchanged).
+    pub fn speed(&self) -> f32 {
+        2f32.powf(self.pitch / 12.0)
+    }
+}
+
+/// Assigns a sample to a pad, or clears the pad, as an undoable command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssignSample {
+    pub coord: PadCoord,
+    pub sample: Option<PadSample>,
+    previous: Option<PadSample>,
+}
+
+impl AssignSample {
+    pub fn new(coord: PadCoord, sample: PadSample) -> Self {
+        Self {
+            coord,
+            sample: Some(sample),
+            previous: None,
+        }
+    }
+
+    /// Removes the sample of a pad.
+    pub fn clear(coord: PadCoord) -> Self {
+        Self {
+            coord,
+            sample: None,
+            previous: None,
+        }
+    }
+}
+
+impl crate::undo::Command<Sampler> for AssignSample {
+    fn name(&self) -> &str {
+        if self.sample.is_some() {
+            "Assign sample"
+        } else {
+            "Clear pad"
+        }
+    }
+
+    fn apply(&mut self, sampler: &mut Sampler) {
+        self.previous = match &self.sample {
+            Some(sample) => sampler.samples.insert(self.coord, sample.clone()),
+            None => sampler.samples.remove(&self.coord),
+        };
+        sampler.display_dirty = true;
+    }
+
+    fn revert(&mut self, sampler: &mut Sampler) {
+        match self.previous.take() {
+            Some(sample) => sampler.samples.insert(self.coord, sample),
+            None => sampler.samples.remove(&self.coord),
+        };
+        sampler.display_dirty = true;
+    }
+}
+
+/// Plays and records the audio of a `Sampler`.
+///
+/// Each pad is its own voice, so a backend can stop one pad without
+/// stopping the others.
+pub trait SamplerBackend: Send {
+    fn play(&mut self, voice: PadCoord, sample: &PadSample) -> Result<(), SamplerError>;
+
+    fn stop(&mut self, voice: PadCoord) -> Result<(), SamplerError>;
+
+    /// Starts recording the input into a new file at `path`.
+    fn start_recording(&mut self, path: &Path) -> Result<(), SamplerError>;
+
+    fn stop_recording(&mut self) -> Result<(), SamplerError>;
+}
+
+/// A backend running PipeWire's `pw-play` and `pw-record` commands.
+///
+/// It needs nothing but a PipeWire install, but cannot shift the pitch.
+pub struct CommandBackend {
+    pub play_command: String,
+    pub record_command: String,
+    voices: HashMap<PadCoord, Child>,
+    recording: Option<Child>,
+}
+
+impl Default for CommandBackend {
+    fn default() -> Self {
+        Self::new("pw-play", "pw-record")
+    }
+}
+
+impl CommandBackend {
+    pub fn new(play_command: &str, record_command: &str) -> Self {
+        Self {
+            play_command: play_command.to_string(),
+            record_command: record_command.to_string(),
+            voices: HashMap::new(),
+            recording: None,
+        }
+    }
+
+    /// Interrupts a command so it can finish the file it writes, then reaps it.
+    fn interrupt(mut child: Child) -> Result<(), SamplerError> {
+        let status = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()?;
+        if !status.success() {
+            child.kill()?;
+        }
+        child.wait()?;
+        Ok(())
+    }
+}
+
+impl SamplerBackend for CommandBackend {
+    fn play(&mut self, voice: PadCoord, sample: &PadSample) -> Result<(), SamplerError> {
+        self.stop(voice)?;
+        let child = Command::new(&self.play_command)
+            .arg("--volume")
+            .arg(sample.volume.clamp(0.0, 1.0).to_string())
+            .arg(&sample.path)
+            .spawn()?;
+        self.voices.insert(voice, child);
+        Ok(())
+    }
+
+    fn stop(&mut self, voice: PadCoord) -> Result<(), SamplerError> {
+        if let Some(mut child) = self.voices.remove(&voice) {
+            // The voice may have finished on its own already.
+            let _ = child.kill();
+            child.wait()?;
+        }
+        Ok(())
+    }
+
+    fn start_recording(&mut self, path: &Path) -> Result<(), SamplerError> {
+        self.stop_recording()?;
+        self.recording = Some(Command::new(&self.record_command).arg(path).spawn()?);
+        Ok(())
+    }
+
+    fn stop_recording(&mut self) -> Result<(), SamplerError> {
+        match self.recording.take() {
+            Some(child) => Self::interrupt(child),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where the record state machine is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordState {
+    #[default]
+    Idle,
+    /// Record was pressed; the next pad pressed records into that pad.
+    Armed,
+    /// Recording into a pad until it is released.
+    Recording { coord: PadCoord },
+}
+
+/// The pad colors and button lights of the sampler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerColors {
+    pub empty: u8,
+    pub loaded: u8,
+    /// Shown over `loaded` while the pad is held.
+    pub playing: u8,
+    pub recording: u8,
+    /// The pad being edited in edit mode.
+    pub selected: u8,
+    pub button_on: u8,
+    pub button_off: u8,
+}
+
+impl Default for SamplerColors {
+    fn default() -> Self {
+        Self {
+            empty: Push2Colors::BLACK,
+            loaded: Push2Colors::GREEN_PALE,
+            playing: Push2Colors::GREEN_BRIGHT,
+            recording: Push2Colors::RED_BRIGHT,
+            selected: Push2Colors::WHITE_BRIGHT,
+            button_on: Push2Colors::RED_BRIGHT,
+            button_off: Push2Colors::WHITE_LOW,
+        }
+    }
+}
+
+/// A pad sampler: each pad plays the audio file assigned to it.
+///
+/// Press Record, then hold a pad to record a new sample into it. The edit
+/// button (Clip by default) switches to edit mode, where pressing a pad
+/// selects it, the first two track encoders set its volume and pitch, and
+/// Delete removes its sample.
+pub struct Sampler {
+    backend: Box<dyn SamplerBackend>,
+    samples: HashMap<PadCoord, PadSample>,
+    /// The directory new recordings are written to.
+    pub recordings_dir: PathBuf,
+    /// Stops a sample when its pad is released, instead of playing it to the end.
+    pub stop_on_release: bool,
+    pub edit_button: ControlName,
+    pub colors: SamplerColors,
+    record_state: RecordState,
+    editing: bool,
+    selected: Option<PadCoord>,
+    display_dirty: bool,
+}
+
+impl Sampler {
+    /// Creates a sampler with no samples, recording into `recordings_dir`.
+    pub fn new(backend: impl SamplerBackend + 'static, recordings_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            backend: Box::new(backend),
+            samples: HashMap::new(),
+            recordings_dir: recordings_dir.into(),
+            stop_on_release: false,
+            edit_button: ControlName::Clip,
+            colors: SamplerColors::default(),
+            record_state: RecordState::Idle,
+            editing: false,
+            selected: None,
+            display_dirty: true,
+        }
+    }
+
+    /// The default recordings directory, "soundboard-recordings" in the user's audio directory.
+    pub fn default_recordings_dir() -> Result<PathBuf, SamplerError> {
+        let mut path = dirs::audio_dir()
+            .ok_or_else(|| std::io::Error::other("Could not find audio directory"))?;
+        path.push("soundboard-recordings");
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    pub fn sample(&self, coord: PadCoord) -> Option<&PadSample> {
+        self.samples.get(&coord)
+    }
+
+    pub fn assign(&mut self, coord: PadCoord, sample: PadSample) {
+        self.samples.insert(coord, sample);
+        self.display_dirty = true;
+    }
+
+    pub fn unassign(&mut self, coord: PadCoord) -> Option<PadSample> {
+        self.display_dirty = true;
+        self.samples.remove(&coord)
+    }
+
+    pub fn record_state(&self) -> RecordState {
+        self.record_state
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    /// Saves the pad assignments as RON.
+    pub fn save(&self, path: &Path) -> Result<(), SamplerError> {
+        let ron = ron::ser::to_string_pretty(&self.samples, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+
+    /// Replaces the pad assignments with the ones saved by `save`.
+    pub fn load(&mut self, path: &Path) -> Result<(), SamplerError> {
+        let ron = fs::read_to_string(path)?;
+        self.samples = ron::from_str(&ron).map_err(Box::new)?;
+        self.display_dirty = true;
+        Ok(())
+    }
+
+    /// Handles the pads, Record, Delete, the edit button and the edit encoders.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Result<bool, SamplerError> {
+        match *event {
+            Push2Event::PadPressed { coord, .. } => self.pad_pressed(coord)?,
+            Push2Event::PadReleased { coord, .. } => self.pad_released(coord)?,
+            Push2Event::ButtonPressed {
+                name: ControlName::Record,
+                ..
+            } => {
+                self.record_state = match self.record_state {
+                    RecordState::Idle => RecordState::Armed,
+                    RecordState::Armed => RecordState::Idle,
+                    RecordState::Recording { coord } => {
+                        self.finish_recording(coord)?;
+                        RecordState::Idle
+                    }
+                };
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::Delete,
+                ..
+            } if self.editing => {
+                if let Some(coord) = self.selected {
+                    self.unassign(coord);
+                }
+            }
+            Push2Event::ButtonPressed { name, .. } if name == self.edit_button => {
+                self.editing = !self.editing;
+                self.display_dirty = true;
+            }
+            Push2Event::EncoderTwisted { name, raw_delta } if self.editing => {
+                let Some(sample) = self.selected.and_then(|c| self.samples.get_mut(&c)) else {
+                    return Ok(false);
+                };
+                let delta = decode_delta(raw_delta) as f32;
+                match name {
+                    EncoderName::Track1 => {
+                        sample.volume = (sample.volume + delta * 0.01).clamp(0.0, 1.0)
+                    }
+                    EncoderName::Track2 => {
+                        sample.pitch = (sample.pitch + delta * 0.1).clamp(-24.0, 24.0)
+                    }
+                    _ => return Ok(false),
+                }
+                self.display_dirty = true;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn pad_pressed(&mut self, coord: PadCoord) -> Result<(), SamplerError> {
+        if self.record_state == RecordState::Armed {
+            let path = self.recording_path(coord)?;
+            self.backend.start_recording(&path)?;
+            self.record_state = RecordState::Recording { coord };
+            self.samples.insert(coord, PadSample::new(path));
+            return Ok(());
+        }
+        if self.editing {
+            self.selected = Some(coord);
+            self.display_dirty = true;
+        }
+        if let Some(sample) = self.samples.get(&coord) {
+            self.backend.play(coord, sample)?;
+        }
+        Ok(())
+    }
+
+    fn pad_released(&mut self, coord: PadCoord) -> Result<(), SamplerError> {
+        if self.record_state == (RecordState::Recording { coord }) {
+            self.finish_recording(coord)?;
+            self.record_state = RecordState::Idle;
+        } else if self.stop_on_release {
+            self.backend.stop(coord)?;
+        }
+        Ok(())
+    }
+
+    fn finish_recording(&mut self, coord: PadCoord) -> Result<(), SamplerError> {
+        self.backend.stop_recording()?;
+        let missing = self.samples.get(&coord).is_some_and(|s| !s.path.exists());
+        if missing {
+            warn!(
+                "Recording for pad ({}, {}) produced no file",
+                coord.x, coord.y
+            );
+            self.samples.remove(&coord);
+        }
+        self.display_dirty = true;
+        Ok(())
+    }
+
+    /// A new file name for a recording into a pad.
+    fn recording_path(&self, coord: PadCoord) -> Result<PathBuf, SamplerError> {
+        fs::create_dir_all(&self.recordings_dir)?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Ok(self
+            .recordings_dir
+            .join(format!("pad_{}_{}_{}.wav", coord.x, coord.y, stamp)))
+    }
+
+    /// Colors the pads and lights the buttons, if they changed, and draws the
+    /// edited sample. Returns true if the display was drawn to and needs a flush.
+    pub fn render(&mut self, push2: &mut Push2) -> Result<bool, Push2Error> {
+        for y in 0..8u8 {
+            for x in 0..8u8 {
+                let coord = PadCoord { x, y };
+                let recording = self.record_state == (RecordState::Recording { coord });
+                let selected = self.editing && self.selected == Some(coord);
+                let loaded = self.samples.contains_key(&coord);
+                let color = if recording {
+                    self.colors.recording
+                } else if selected {
+                    self.colors.selected
+                } else if !loaded {
+                    self.colors.empty
+                } else {
+                    self.colors.loaded
+                };
+                if push2.state.pads[y as usize][x as usize].color != color {
+                    push2.set_pad_color(coord, color)?;
+                }
+                // Held samples are shown playing over their color until released.
+                let playing = (loaded && !recording && !selected && push2.state.is_pad_held(coord))
+                    .then_some(self.colors.playing);
+                if push2.state.pads[y as usize][x as usize].highlight_of(HighlightLayer::App)
+                    != playing
+                {
+                    match playing {
+                        Some(color) => push2.highlight_pad(coord, HighlightLayer::App, color)?,
+                        None => push2.clear_highlight(coord, HighlightLayer::App)?,
+                    }
+                }
+            }
+        }
+        let record = self.record_state != RecordState::Idle;
+        for (name, on) in [
+            (ControlName::Record, record),
+            (self.edit_button, self.editing),
+        ] {
+            let light = if on {
+                self.colors.button_on
+            } else {
+                self.colors.button_off
+            };
+            if push2.state.buttons.get(&name).map(|b| b.light) != Some(light) {
+                push2.set_button_light(name, light)?;
+            }
+        }
+        if !self.display_dirty {
+            return Ok(false);
+        }
+        self.draw_edit_slots(push2)?;
+        self.display_dirty = false;
+        Ok(true)
+    }
+
+    /// Draws the volume and pitch of the selected pad above the first two encoders.
+    fn draw_edit_slots(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let display = &mut push2.display;
+        for slot in 0..2 {
+            display.clear_encoder_area(slot, Bgr565::BLACK)?;
+        }
+        let sample = self.selected.and_then(|c| self.samples.get(&c));
+        let Some(sample) = sample.filter(|_| self.editing) else {
+            return Ok(());
+        };
+        let slots = [
+            ("Volume", sample.volume, formatters::percent(sample.volume)),
+            (
+                "Pitch",
+                (sample.pitch + 24.0) / 48.0,
+                formatters::semitones(sample.pitch),
+            ),
+        ];
+        for (slot, (name, normalized, value)) in slots.iter().enumerate() {
+            let slot = slot as u8;
+            display.draw_encoder_outline(slot, Bgr565::WHITE)?;
+            display.draw_encoder_bar(slot, (normalized * 127.0).round() as i32, Bgr565::GREEN)?;
+            display.draw_encoder_label(slot, ENCODER_LABEL_Y_POS, name, Bgr565::WHITE)?;
+            display.draw_encoder_label(slot, ENCODER_VALUE_Y_POS, value, Bgr565::CYAN)?;
+        }
+        Ok(())
+    }
+}
+
+/// Backend errors are logged, so one failed sample does not stop the host.
+impl PushApp for Sampler {
+    fn name(&self) -> &str {
+        "Sampler"
+    }
+
+    fn on_enter(&mut self, _push2: &mut Push2) -> Result<(), Push2Error> {
+        // The host cleared the display.
+        self.display_dirty = true;
+        Ok(())
+    }
+
+    fn on_event(&mut self, _push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+        if let Err(e) = self.handle_event(event) {
+            warn!("Sampler: {}", e);
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        self.render(push2)?;
+        Ok(())
+    }
+}
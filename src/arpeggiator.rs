@@ -0,0 +1,277 @@
+use crate::clock::{ClockTick, beat_button_ticks};
+use crate::note_output::NoteEvent;
+use crate::{
+    ControlName, NoteLayout, PadCoord, Push2, Push2Colors, Push2Error, Push2Event, RadioGroup,
+};
+
+/// The order the held notes are played in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArpMode {
+    Up,
+    Down,
+    UpDown,
+    Random,
+}
+
+impl ArpMode {
+    /// The next mode, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            ArpMode::Up => ArpMode::Down,
+            ArpMode::Down => ArpMode::UpDown,
+            ArpMode::UpDown => ArpMode::Random,
+            ArpMode::Random => ArpMode::Up,
+        }
+    }
+}
+
+/// The button lights used by the arpeggiator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpColors {
+    pub on: u8,
+    pub off: u8,
+}
+
+impl Default for ArpColors {
+    fn default() -> Self {
+        Self {
+            on: Push2Colors::WHITE_BRIGHT,
+            off: Push2Colors::WHITE_LOW,
+        }
+    }
+}
+
+/// Plays the held pads one after another, in time with a `Clock`.
+///
+/// The toggle button (Repeat by default) turns the arpeggiator on and off,
+/// Accent plays every note at `accent_velocity`, and the Beat1_* buttons
+/// pick the rate. Notes come from the pads through a `NoteLayout`; while
+/// the arpeggiator is on, set `NoteOutput::play_pads` to false so the pads
+/// are not also played directly.
+pub struct Arpeggiator {
+    pub mode: ArpMode,
+    /// The number of octaves (1-4) the held notes are repeated over.
+    pub octaves: u8,
+    /// The length of each note, as a fraction (0.0-1.0) of the step.
+    pub gate: f32,
+    pub toggle_button: ControlName,
+    pub accent_velocity: u8,
+    /// The Beat1_* buttons choosing the rate.
+    pub rate: RadioGroup,
+    pub colors: ArpColors,
+    enabled: bool,
+    accent: bool,
+    /// The held pads with their note and velocity, in press order.
+    held: Vec<(PadCoord, u8, u8)>,
+    step: usize,
+    /// The note that is sounding and the tick it stops at.
+    playing: Option<(u8, u64)>,
+    rng_state: u32,
+}
+
+impl Default for Arpeggiator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Arpeggiator {
+    /// Creates a disabled arpeggiator playing sixteenth notes upwards over one octave.
+    pub fn new() -> Self {
+        let colors = ArpColors::default();
+        Self {
+            mode: ArpMode::Up,
+            octaves: 1,
+            gate: 0.5,
+            toggle_button: ControlName::Repeat,
+            accent_velocity: 127,
+            rate: RadioGroup::beat_buttons(colors.on, colors.off).with_selected(Some(4)),
+            colors,
+            enabled: false,
+            accent: false,
+            held: Vec::new(),
+            step: 0,
+            playing: None,
+            rng_state: 0x2545_F491,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns the arpeggiator on or off. The sounding note is released on the next tick.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.step = 0;
+    }
+
+    pub fn is_accent(&self) -> bool {
+        self.accent
+    }
+
+    pub fn set_accent(&mut self, accent: bool) {
+        self.accent = accent;
+    }
+
+    /// The notes of the held pads, in press order.
+    pub fn held_notes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.held.iter().map(|(_, note, _)| *note)
+    }
+
+    /// Handles the toggle, Accent and Beat1_* buttons, and collects the held
+    /// pads through `layout`. Returns true if the event was consumed; pad
+    /// events are only consumed while enabled.
+    pub fn handle_event(
+        &mut self,
+        push2: &mut Push2,
+        layout: &NoteLayout,
+        event: &Push2Event,
+    ) -> Result<bool, Push2Error> {
+        match *event {
+            Push2Event::ButtonPressed { name, .. } if name == self.toggle_button => {
+                self.set_enabled(!self.enabled);
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::Accent,
+                ..
+            } => self.accent = !self.accent,
+            Push2Event::ButtonPressed { .. } => {
+                return Ok(self.rate.handle_event(push2, event)?.is_some());
+            }
+            Push2Event::PadPressed { coord, velocity } => {
+                let Some(note) = layout.note_for(coord) else {
+                    return Ok(false);
+                };
+                self.held.retain(|(c, _, _)| *c != coord);
+                self.held.push((coord, note, velocity));
+                // Held pads are still collected, so enabling picks them up.
+                return Ok(self.enabled);
+            }
+            Push2Event::PadReleased { coord, .. } => {
+                let count = self.held.len();
+                self.held.retain(|(c, _, _)| *c != coord);
+                return Ok(self.enabled && self.held.len() != count);
+            }
+            _ => return Ok(false),
+        }
+        self.update_lights(push2)?;
+        Ok(true)
+    }
+
+    /// Advances the arpeggiator by one clock tick.
+    /// Returns the notes to start and stop on this tick.
+    pub fn on_clock_tick(&mut self, tick: &ClockTick) -> Vec<NoteEvent> {
+        let mut events = Vec::new();
+        if let Some((note, off_tick)) = self.playing {
+            let stopped = !self.enabled || self.held.is_empty();
+            if tick.tick >= off_tick || stopped {
+                events.push(NoteEvent::NoteOff { note });
+                self.playing = None;
+            }
+        }
+        if tick.tick == 0 {
+            self.step = 0;
+        }
+        if !self.enabled || self.held.is_empty() {
+            return events;
+        }
+        let rate = self.rate.selected().unwrap_or(ControlName::Beat1_16);
+        let step_ticks = beat_button_ticks(rate, tick.ppqn).unwrap_or(1) as u64;
+        if !tick.tick.is_multiple_of(step_ticks) {
+            return events;
+        }
+        let sequence = self.sequence();
+        let index = self.next_index(sequence.len());
+        let (note, velocity) = sequence[index];
+        let velocity = if self.accent {
+            self.accent_velocity
+        } else {
+            velocity
+        };
+        // Release a note still sounding, e.g. after switching to a faster rate.
+        if let Some((previous, _)) = self.playing.take() {
+            events.push(NoteEvent::NoteOff { note: previous });
+        }
+        let length = ((step_ticks as f32 * self.gate.clamp(0.0, 1.0)).round() as u64).max(1);
+        events.push(NoteEvent::NoteOn { note, velocity });
+        self.playing = Some((note, tick.tick + length));
+        self.step += 1;
+        events
+    }
+
+    /// Lights the toggle, Accent and rate buttons, if they changed.
+    pub fn update_lights(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        for (name, on) in [
+            (self.toggle_button, self.enabled),
+            (ControlName::Accent, self.accent),
+        ] {
+            let light = if on { self.colors.on } else { self.colors.off };
+            if push2.state.buttons.get(&name).map(|b| b.light) != Some(light) {
+                push2.set_button_light(name, light)?;
+            }
+        }
+        self.rate.update_lights(push2)
+    }
+
+    /// The held notes sorted by pitch and repeated over the octave range.
+    fn sequence(&self) -> Vec<(u8, u8)> {
+        let mut notes: Vec<(u8, u8)> = self.held.iter().map(|(_, n, v)| (*n, *v)).collect();
+        notes.sort_by_key(|(note, _)| *note);
+        let mut sequence = Vec::with_capacity(notes.len() * self.octaves as usize);
+        for octave in 0..self.octaves.clamp(1, 4) {
+            for (note, velocity) in &notes {
+                if let Some(note) = note.checked_add(12 * octave).filter(|n| *n <= 127) {
+                    sequence.push((note, *velocity));
+                }
+            }
+        }
+        sequence
+    }
+
+    fn next_index(&mut self, len: usize) -> usize {
+        match self.mode {
+            ArpMode::Up => self.step % len,
+            ArpMode::Down => len - 1 - self.step % len,
+            ArpMode::UpDown if len > 1 => {
+                // Up then down, without repeating the top and bottom notes.
+                let i = self.step % (2 * len - 2);
+                if i < len { i } else { 2 * len - 2 - i }
+            }
+            ArpMode::UpDown => 0,
+            ArpMode::Random => (self.next_random() as usize) % len,
+        }
+    }
+
+    /// A small xorshift generator for the random mode.
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+
+    #[test]
+    fn passes_pads_through_while_disabled() {
+        let (backend, _tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        let layout = NoteLayout::default();
+        let mut arp = Arpeggiator::new();
+        let press = Push2Event::PadPressed {
+            coord: PadCoord::new(0, 7),
+            velocity: 100,
+        };
+        assert!(!arp.handle_event(&mut push2, &layout, &press).unwrap());
+        assert_eq!(arp.held_notes().count(), 1);
+        arp.set_enabled(true);
+        assert!(arp.handle_event(&mut push2, &layout, &press).unwrap());
+    }
+}
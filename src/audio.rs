@@ -0,0 +1,368 @@
+use crate::PadCoord;
+use hound::{SampleFormat as WavFormat, WavSpec, WavWriter};
+use log::warn;
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, StreamConfig};
+use rodio::decoder::DecoderError;
+use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, StreamError};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AudioError {
+    #[error("Audio output error: {0}")]
+    Stream(#[from] StreamError),
+    #[error("Could not list audio devices: {0}")]
+    Devices(#[from] rodio::DevicesError),
+    #[error("No audio output device matching '{0}'")]
+    DeviceNotFound(String),
+    #[error("Could not open audio file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not decode audio file: {0}")]
+    Decode(#[from] DecoderError),
+    #[error("No audio input device found")]
+    NoInputDevice,
+    #[error("Could not read the input device config: {0}")]
+    InputConfig(#[from] cpal::DefaultStreamConfigError),
+    #[error("Unsupported input sample format: {0}")]
+    UnsupportedFormat(SampleFormat),
+    #[error("Could not open the input stream: {0}")]
+    BuildStream(#[from] cpal::BuildStreamError),
+    #[error("Could not start the input stream: {0}")]
+    PlayStream(#[from] cpal::PlayStreamError),
+    #[error("Could not write WAV file: {0}")]
+    Wav(#[from] hound::Error),
+}
+
+/// Identifies a voice of the `AudioEngine`. See `AudioEngine::pad_voice`.
+pub type VoiceId = usize;
+
+/// The playback settings of a voice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceSettings {
+    /// The volume (0.0-1.0).
+    pub volume: f32,
+    /// The pitch shift in semitones.
+    pub pitch: f32,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 0.0,
+        }
+    }
+}
+
+/// Plays audio files on an output device, one sound per voice.
+///
+/// Playing a voice replaces what it was playing, so giving every pad its
+/// own voice makes pads retrigger like a sampler. Volume and pitch are
+/// kept per voice and apply to the sounding file as well as later ones.
+pub struct AudioEngine {
+    stream: OutputStream,
+    voices: HashMap<VoiceId, Sink>,
+    settings: HashMap<VoiceId, VoiceSettings>,
+    master_volume: f32,
+}
+
+impl AudioEngine {
+    /// Opens the default output device.
+    pub fn new() -> Result<Self, AudioError> {
+        Ok(Self::with_stream(
+            OutputStreamBuilder::open_default_stream()?
+        ))
+    }
+
+    /// Opens the first output device whose name contains `name`.
+    pub fn with_device(name: &str) -> Result<Self, AudioError> {
+        let device = rodio::cpal::default_host()
+            .output_devices()?
+            .find(|d| d.name().is_ok_and(|n| n.contains(name)))
+            .ok_or_else(|| AudioError::DeviceNotFound(name.to_string()))?;
+        let stream = OutputStreamBuilder::from_device(device)?.open_stream_or_fallback()?;
+        Ok(Self::with_stream(stream))
+    }
+
+    fn with_stream(mut stream: OutputStream) -> Self {
+        stream.log_on_drop(false);
+        Self {
+            stream,
+            voices: HashMap::new(),
+            settings: HashMap::new(),
+            master_volume: 1.0,
+        }
+    }
+
+    /// The names of the available output devices.
+    pub fn output_devices() -> Result<Vec<String>, AudioError> {
+        Ok(rodio::cpal::default_host()
+            .output_devices()?
+            .filter_map(|d| d.name().ok())
+            .collect())
+    }
+
+    /// The voice of a pad, so each of the 64 pads plays on its own.
+    pub fn pad_voice(coord: PadCoord) -> VoiceId {
+        coord.y as usize * 8 + coord.x as usize
+    }
+
+    /// Plays a file on a voice, replacing what the voice was playing.
+    pub fn play(&mut self, voice: VoiceId, path: &Path) -> Result<(), AudioError> {
+        let source = Decoder::try_from(File::open(path)?)?;
+        self.stop(voice);
+        let sink = Sink::connect_new(self.stream.mixer());
+        let settings = self.settings(voice);
+        sink.set_volume(settings.volume * self.master_volume);
+        sink.set_speed(2f32.powf(settings.pitch / 12.0));
+        sink.append(source);
+        self.voices.insert(voice, sink);
+        Ok(())
+    }
+
+    pub fn stop(&mut self, voice: VoiceId) {
+        if let Some(sink) = self.voices.remove(&voice) {
+            sink.stop();
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for (_, sink) in self.voices.drain() {
+            sink.stop();
+        }
+    }
+
+    /// True while a voice has something left to play.
+    pub fn is_playing(&self, voice: VoiceId) -> bool {
+        self.voices.get(&voice).is_some_and(|sink| !sink.empty())
+    }
+
+    pub fn settings(&self, voice: VoiceId) -> VoiceSettings {
+        self.settings.get(&voice).copied().unwrap_or_default()
+    }
+
+    /// Sets the volume (0.0-1.0) of a voice.
+    pub fn set_volume(&mut self, voice: VoiceId, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        self.settings.entry(voice).or_default().volume = volume;
+        if let Some(sink) = self.voices.get(&voice) {
+            sink.set_volume(volume * self.master_volume);
+        }
+    }
+
+    /// Sets the pitch shift of a voice, in semitones. The playback speed changes with it.
+    pub fn set_pitch(&mut self, voice: VoiceId, semitones: f32) {
+        self.settings.entry(voice).or_default().pitch = semitones;
+        if let Some(sink) = self.voices.get(&voice) {
+            sink.set_speed(2f32.powf(semitones / 12.0));
+        }
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    /// Sets the volume (0.0-1.0) applied on top of every voice's own volume.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+        for (voice, sink) in &self.voices {
+            let settings = self.settings.get(voice).copied().unwrap_or_default();
+            sink.set_volume(settings.volume * self.master_volume);
+        }
+    }
+}
+
+/// The state shared with the input stream's callback.
+struct Capture {
+    writer: Option<WavWriter<BufWriter<File>>>,
+    /// The highest level since the last `take_peak`.
+    peak: f32,
+    /// The number of frames summed up in each waveform peak.
+    window: usize,
+    window_frames: usize,
+    window_peak: (f32, f32),
+    /// Waveform peaks not yet taken by `take_peaks`.
+    peaks: Vec<(f32, f32)>,
+    error: Option<hound::Error>,
+}
+
+impl Capture {
+    fn push_frame(&mut self, frame: &[f32]) {
+        for sample in frame {
+            self.peak = self.peak.max(sample.abs());
+            self.window_peak.0 = self.window_peak.0.min(*sample);
+            self.window_peak.1 = self.window_peak.1.max(*sample);
+            if let Some(writer) = self.writer.as_mut() {
+                let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                if let Err(e) = writer.write_sample(value) {
+                    self.error = Some(e);
+                    self.writer = None;
+                }
+            }
+        }
+        self.window_frames += 1;
+        if self.window_frames >= self.window {
+            self.peaks.push(self.window_peak);
+            self.window_peak = (0.0, 0.0);
+            self.window_frames = 0;
+        }
+    }
+}
+
+/// Records an input device into 16-bit WAV files.
+///
+/// While recording, `take_peak` gives the level for a VU meter and
+/// `take_peaks` gives (min, max) pairs in the format drawn by
+/// `GuiApi::draw_waveform_peaks`, so a waveform can grow live.
+pub struct AudioRecorder {
+    device: cpal::Device,
+    /// The number of frames per waveform peak (e.g. 441 = 10ms at 44.1kHz).
+    pub peak_window: usize,
+    stream: Option<cpal::Stream>,
+    capture: Arc<Mutex<Capture>>,
+}
+
+impl AudioRecorder {
+    /// Uses the default input device.
+    pub fn new() -> Result<Self, AudioError> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or(AudioError::NoInputDevice)?;
+        Ok(Self::with_input(device))
+    }
+
+    /// Uses the first input device whose name contains `name`.
+    pub fn with_device(name: &str) -> Result<Self, AudioError> {
+        let device = cpal::default_host()
+            .input_devices()?
+            .find(|d| d.name().is_ok_and(|n| n.contains(name)))
+            .ok_or_else(|| AudioError::DeviceNotFound(name.to_string()))?;
+        Ok(Self::with_input(device))
+    }
+
+    fn with_input(device: cpal::Device) -> Self {
+        Self {
+            device,
+            peak_window: 441,
+            stream: None,
+            capture: Arc::new(Mutex::new(Capture {
+                writer: None,
+                peak: 0.0,
+                window: 441,
+                window_frames: 0,
+                window_peak: (0.0, 0.0),
+                peaks: Vec::new(),
+                error: None,
+            })),
+        }
+    }
+
+    /// The names of the available input devices.
+    pub fn input_devices() -> Result<Vec<String>, AudioError> {
+        Ok(cpal::default_host()
+            .input_devices()?
+            .filter_map(|d| d.name().ok())
+            .collect())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Starts recording into a new WAV file at `path`, stopping any recording in progress.
+    pub fn start(&mut self, path: &Path) -> Result<(), AudioError> {
+        self.stop()?;
+        let supported = self.device.default_input_config()?;
+        let config: StreamConfig = supported.config();
+        let spec = WavSpec {
+            channels: config.channels,
+            sample_rate: config.sample_rate.0,
+            bits_per_sample: 16,
+            sample_format: WavFormat::Int,
+        };
+        {
+            let mut capture = self.capture.lock().unwrap_or_else(|e| e.into_inner());
+            capture.writer = Some(WavWriter::create(path, spec)?);
+            capture.window = self.peak_window.max(1);
+            capture.window_frames = 0;
+            capture.window_peak = (0.0, 0.0);
+            capture.peaks.clear();
+            capture.peak = 0.0;
+            capture.error = None;
+        }
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => self.build_stream::<f32>(&config)?,
+            SampleFormat::I16 => self.build_stream::<i16>(&config)?,
+            SampleFormat::I32 => self.build_stream::<i32>(&config)?,
+            SampleFormat::U16 => self.build_stream::<u16>(&config)?,
+            format => return Err(AudioError::UnsupportedFormat(format)),
+        };
+        stream.play()?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn build_stream<T>(&self, config: &StreamConfig) -> Result<cpal::Stream, AudioError>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let capture = Arc::clone(&self.capture);
+        let channels = config.channels.max(1) as usize;
+        let mut frame = Vec::with_capacity(channels);
+        let stream = self.device.build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut capture = capture.lock().unwrap_or_else(|e| e.into_inner());
+                for samples in data.chunks(channels) {
+                    frame.clear();
+                    frame.extend(samples.iter().map(|s| s.to_sample::<f32>()));
+                    capture.push_frame(&frame);
+                }
+            },
+            |err| warn!("Audio input error: {err}"),
+            None,
+        )?;
+        Ok(stream)
+    }
+
+    /// Stops recording and finishes the WAV file.
+    pub fn stop(&mut self) -> Result<(), AudioError> {
+        // Dropping the stream stops the callback before the file is finalized.
+        self.stream = None;
+        let mut capture = self.capture.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(error) = capture.error.take() {
+            capture.writer = None;
+            return Err(error.into());
+        }
+        if let Some(writer) = capture.writer.take() {
+            writer.finalize()?;
+        }
+        Ok(())
+    }
+
+    /// The highest level (0.0-1.0) since the last call, for a VU meter.
+    pub fn take_peak(&self) -> f32 {
+        let mut capture = self.capture.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut capture.peak)
+    }
+
+    /// The (min, max) waveform peaks recorded since the last call, one per `peak_window` frames.
+    pub fn take_peaks(&self) -> Vec<(f32, f32)> {
+        let mut capture = self.capture.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut capture.peaks)
+    }
+}
+
+impl Drop for AudioRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            warn!("Failed to finish recording: {e}");
+        }
+    }
+}
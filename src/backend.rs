@@ -0,0 +1,110 @@
+use crate::Push2Error;
+use crate::color_correction::ColorCorrection;
+use crate::device::{Push2Model, PushDevice};
+use crate::display::Push2DisplayError;
+use midir::{MidiOutputConnection, SendError};
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Sends MIDI messages (pad colors and button lights) to a device.
+pub trait MidiOut: Send {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError>;
+}
+
+impl MidiOut for MidiOutputConnection {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        MidiOutputConnection::send(self, message)
+    }
+}
+
+/// Receives the frames written by `Push2Display::flush`.
+pub trait FrameSink: Send {
+    /// `frame` holds 960x160 raw Bgr565 pixels, row by row.
+    fn write_frame(&mut self, frame: &[u16]) -> Result<(), Push2DisplayError>;
+
+    /// Writes a frame where only `lines` changed since the last one.
+    /// Writes the whole frame unless the sink can do better.
+    fn write_lines(
+        &mut self,
+        frame: &[u16],
+        _lines: Range<usize>,
+    ) -> Result<(), Push2DisplayError> {
+        self.write_frame(frame)
+    }
+
+    /// Corrects the colors of the frames written from now on. Ignored by
+    /// sinks that are not a panel.
+    fn set_color_correction(&mut self, _correction: Option<ColorCorrection>) {}
+}
+
+/// Drops every frame, for devices without a pixel display.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullDisplay;
+
+impl FrameSink for NullDisplay {
+    fn write_frame(&mut self, _frame: &[u16]) -> Result<(), Push2DisplayError> {
+        Ok(())
+    }
+}
+
+/// The connections a `Push2` talks to.
+pub struct BackendParts {
+    pub display: Box<dyn FrameSink>,
+    pub midi_out: Box<dyn MidiOut>,
+    /// Raw MIDI messages coming from the device.
+    pub midi_in: Receiver<Vec<u8>>,
+    /// The model the connections lead to.
+    pub device: Box<dyn PushDevice>,
+}
+
+/// Something `Push2` can run on other than the USB/MIDI hardware, such as an emulator.
+/// See `Push2::with_backend`.
+pub trait Backend {
+    fn connect(self) -> Result<BackendParts, Push2Error>;
+}
+
+/// Counts the MIDI messages sent, without sending them anywhere.
+#[derive(Debug, Clone, Default)]
+pub struct NullMidiOut {
+    pub sent: Arc<AtomicUsize>,
+}
+
+impl MidiOut for NullMidiOut {
+    fn send(&mut self, _message: &[u8]) -> Result<(), SendError> {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// A Push 2 without hardware, for tests and benchmarks.
+///
+/// Frames are dropped, MIDI output is only counted, and raw MIDI sent to
+/// the `Sender` returned by `new` is read as if it came from the device.
+pub struct MockBackend {
+    pub midi_out: NullMidiOut,
+    midi_in: Receiver<Vec<u8>>,
+}
+
+impl MockBackend {
+    pub fn new() -> (Self, Sender<Vec<u8>>) {
+        let (tx, rx) = mpsc::channel();
+        let backend = Self {
+            midi_out: NullMidiOut::default(),
+            midi_in: rx,
+        };
+        (backend, tx)
+    }
+}
+
+impl Backend for MockBackend {
+    fn connect(self) -> Result<BackendParts, Push2Error> {
+        Ok(BackendParts {
+            display: Box::new(NullDisplay),
+            midi_out: Box::new(self.midi_out),
+            midi_in: self.midi_in,
+            device: Box::new(Push2Model),
+        })
+    }
+}
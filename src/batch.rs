@@ -0,0 +1,80 @@
+use crate::{ControlName, PadCoord, Push2Error};
+use midir::SendError;
+
+/// A pad or button light set as part of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LightTarget {
+    Pad(PadCoord),
+    Button(ControlName),
+}
+
+/// The outcome of setting many lights at once with `Push2::set_lights`.
+///
+/// A batch keeps going after a failed send, so every light that could be set
+/// is set. The failed ones keep their previous value in `Push2State` and can
+/// be sent again with `Push2::retry`.
+#[derive(Debug, Clone, Default)]
+pub struct BatchResult {
+    /// The number of lights in the batch.
+    pub total: usize,
+    /// The lights that could not be set, with the value they should have had.
+    pub failed: Vec<(LightTarget, u8, SendError)>,
+    /// The lights that were refused without being sent, e.g. a color a button
+    /// cannot show with `strict_button_lights`. Retrying them fails again.
+    pub rejected: Vec<(LightTarget, u8)>,
+}
+
+impl BatchResult {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty() && self.rejected.is_empty()
+    }
+
+    /// The number of lights that were set.
+    pub fn sent(&self) -> usize {
+        self.total - self.failed.len() - self.rejected.len()
+    }
+
+    /// Fails with the first send error if any light could not be set, or
+    /// else with the first rejected light.
+    pub fn into_result(self) -> Result<(), Push2Error> {
+        if let Some(&(first, _, source)) = self.failed.first() {
+            return Err(Push2Error::BatchSend {
+                failed: self.failed.len(),
+                total: self.total,
+                first,
+                source,
+            });
+        }
+        match self.rejected.first() {
+            None => Ok(()),
+            Some(&(first, _)) => Err(Push2Error::BatchRejected {
+                rejected: self.rejected.len(),
+                total: self.total,
+                first,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockBackend, Push2, Push2Colors};
+
+    #[test]
+    fn counts_rejected_lights() {
+        let (backend, _tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.strict_button_lights = true;
+        let batch = push2.set_lights([
+            (LightTarget::Button(ControlName::Undo), Push2Colors::RED),
+            (LightTarget::Pad(PadCoord::new(0, 0)), Push2Colors::RED),
+        ]);
+        assert_eq!(batch.sent(), 1);
+        assert!(!batch.is_ok());
+        assert!(matches!(
+            batch.into_result(),
+            Err(Push2Error::BatchRejected { rejected: 1, .. })
+        ));
+    }
+}
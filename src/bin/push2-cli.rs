@@ -0,0 +1,192 @@
+//! Diagnostics for setting up a Push 2.
+//!
+//! push2-cli ports | monitor | lights test | display test [image.bmp] | palette [page] | info
+//!           | diagnostics
+
+use embedded_graphics::{
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+};
+use push2::diagnostics::{self, stdin_confirm};
+use push2::palette::{PALETTE_PAGES, palette_index};
+use push2::{AppConfig, MidiHandler, MidiParser, PadCoord, Push2, Push2Event};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{env, error, fs, thread};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+const USAGE: &str = "Usage: push2-cli <command>
+
+Commands:
+  ports                   List the MIDI input and output ports
+  monitor                 Print the decoded events
+  lights test             Cycle the colors of every pad and button
+  display test [image]    Show a test pattern, or a BMP image
+  palette [page]          Show 64 palette colors on the pads (page 0 or 1)
+  info                    Ask the device for its identity and firmware version
+  diagnostics             Check every LED and the display, region by region";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["ports"] => ports(),
+        ["monitor"] => monitor(),
+        ["lights", "test"] => lights_test(),
+        ["display", "test"] => display_test(None),
+        ["display", "test", image] => display_test(Some(image)),
+        ["palette"] => palette(0),
+        ["palette", page] => match page.parse() {
+            Ok(page) => palette(page),
+            Err(_) => Err(format!("Invalid page '{}'", page).into()),
+        },
+        ["info"] => info(),
+        ["diagnostics"] => run_diagnostics(),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn ports() -> Result<()> {
+    let (inputs, outputs) = MidiHandler::list_ports()?;
+    println!("Inputs:");
+    for name in inputs {
+        println!("  {}", name);
+    }
+    println!("Outputs:");
+    for name in outputs {
+        println!("  {}", name);
+    }
+    Ok(())
+}
+
+fn monitor() -> Result<()> {
+    let mut push2 = Push2::new()?;
+    println!("Monitoring events. Press Ctrl-C to quit.");
+    loop {
+        while let Some(event) = push2.poll_event() {
+            println!("{:?}", event);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn lights_test() -> Result<()> {
+    let mut push2 = Push2::new()?;
+    let names: Vec<_> = push2.button_map.controls().map(|(_, name)| name).collect();
+    println!("Cycling all pads and buttons. Press Ctrl-C to quit.");
+    for color in (1..128u8).cycle() {
+        for y in 0..8 {
+            for x in 0..8 {
+                push2.set_pad_color(PadCoord { x, y }, color)?;
+            }
+        }
+        for name in &names {
+            push2.set_button_light(*name, color)?;
+        }
+        print!("\rColor {:3}", color);
+        thread::sleep(Duration::from_millis(250));
+    }
+    Ok(())
+}
+
+fn display_test(image: Option<&str>) -> Result<()> {
+    let mut push2 = Push2::new()?;
+    match image {
+        Some(path) => {
+            let bmp_data = fs::read(path)?;
+            push2.display.clear(Bgr565::BLACK)?;
+            push2.draw_bmp_to_display(&bmp_data, Point::zero())?;
+        }
+        None => diagnostics::draw_grid(&mut push2.display),
+    }
+    println!("Showing the test pattern. Press Ctrl-C to quit.");
+    // The display turns black if no frame arrives for 2 seconds.
+    loop {
+        push2.display.flush()?;
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+fn palette(page: u8) -> Result<()> {
+    if page >= PALETTE_PAGES {
+        return Err(format!("The palette has {} pages", PALETTE_PAGES).into());
+    }
+    let mut push2 = Push2::new()?;
+    push2.display.set_target_fps(60);
+    push2.show_palette_page(page)?;
+    println!("Press a pad to print its color. Press Ctrl-C to quit.");
+    loop {
+        while let Some(event) = push2.poll_event() {
+            if let Push2Event::PadPressed { coord, .. } = event {
+                println!("Color {}", palette_index(page, coord));
+            }
+        }
+        push2.display.flush()?;
+    }
+}
+
+fn run_diagnostics() -> Result<()> {
+    let mut push2 = Push2::new()?;
+    let report = push2.run_diagnostics(stdin_confirm)?;
+    for result in &report.results {
+        let status = if result.passed() { "ok" } else { "FAILED" };
+        println!(
+            "{:<20} {:<6} ({} send errors)",
+            format!("{:?}", result.region),
+            status,
+            result.send_errors
+        );
+    }
+    if !report.passed() {
+        return Err("Some regions failed".into());
+    }
+    Ok(())
+}
+
+/// Sends a MIDI identity request and prints the reply.
+fn info() -> Result<()> {
+    let config = AppConfig::new()?;
+    let (tx, rx) = mpsc::channel();
+    let mut handler = MidiHandler::new(&config, tx)?;
+    println!("Input:  {}", handler.input_port_name);
+    println!("Output: {}", handler.output_port_name);
+    handler
+        .conn_out
+        .send(&[0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7])?;
+
+    let mut parser = MidiParser::new();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(bytes) = rx.recv_timeout(timeout) else {
+            break;
+        };
+        parser.feed(&bytes);
+        while let Some(message) = parser.next_message() {
+            // F0 7E <device> 06 02 <manufacturer x3> <family x2> <model x2> <version x4> F7
+            if message.len() >= 15 && message[1] == 0x7E && message[3..5] == [0x06, 0x02] {
+                println!(
+                    "Manufacturer: {:02X} {:02X} {:02X}",
+                    message[5], message[6], message[7]
+                );
+                println!("Family:       {:02X} {:02X}", message[8], message[9]);
+                println!("Model:        {:02X} {:02X}", message[10], message[11]);
+                let build =
+                    message[14] as u16 | (message.get(15).copied().unwrap_or(0) as u16) << 7;
+                println!(
+                    "Firmware:     {}.{} build {}",
+                    message[12], message[13], build
+                );
+                return Ok(());
+            }
+        }
+    }
+    Err("No identity reply from the device".into())
+}
@@ -0,0 +1,14 @@
+//! Hosts the registered apps on a Push 2, picked from a launcher screen.
+//!
+//! Setup opens the launcher; the buttons under the display start the apps
+//! listed above them.
+
+use push2::{AppHost, AppRegistry, ControlName, Push2};
+use std::error;
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let mut host = AppHost::new(Push2::new()?).with_launcher(ControlName::Setup);
+    AppRegistry::builtin().install(&mut host);
+    host.run()?;
+    Ok(())
+}
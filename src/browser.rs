@@ -0,0 +1,254 @@
+use crate::button_map::decode_delta;
+use crate::display::Push2DisplayError;
+use crate::gui::{GuiApi, LIST_ROW_HEIGHT, ListColors};
+use crate::{ControlName, EncoderName, Push2Display, Push2Event};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text, TextStyleBuilder},
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BrowserError {
+    #[error("Failed to read directory {path}: {source}")]
+    ReadDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// A file or directory listed by the browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+type SelectCallback = Box<dyn FnMut(&Path) + Send>;
+
+/// A file browser drawn on the display.
+///
+/// Up/Down or the first track encoder move the selection, Right or Select
+/// opens a directory or picks a file, and Left goes to the parent directory.
+/// Only files with one of the `extensions` are listed, if any are set.
+pub struct Browser {
+    dir: PathBuf,
+    entries: Vec<BrowserEntry>,
+    selected: usize,
+    /// Lowercase file extensions to list, without the dot. Empty lists every file.
+    pub extensions: Vec<String>,
+    pub show_hidden: bool,
+    /// The encoder scrolling through the list.
+    pub encoder: EncoderName,
+    pub colors: ListColors,
+    on_select: Option<SelectCallback>,
+    dirty: bool,
+}
+
+impl Browser {
+    /// Opens a browser on `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, BrowserError> {
+        let mut browser = Self {
+            dir: dir.into(),
+            entries: Vec::new(),
+            selected: 0,
+            extensions: Vec::new(),
+            show_hidden: false,
+            encoder: EncoderName::Track1,
+            colors: ListColors::default(),
+            on_select: None,
+            dirty: true,
+        };
+        browser.refresh()?;
+        Ok(browser)
+    }
+
+    /// Lists only files with one of the given extensions, e.g. `&["wav", "aiff"]`.
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Result<Self, BrowserError> {
+        self.extensions = extensions.iter().map(|e| e.to_lowercase()).collect();
+        self.refresh()?;
+        Ok(self)
+    }
+
+    /// Registers the callback called with the path of a picked file.
+    pub fn on_select<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Path) + Send + 'static,
+    {
+        self.on_select = Some(Box::new(callback));
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn entries(&self) -> &[BrowserEntry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> Option<&BrowserEntry> {
+        self.entries.get(self.selected)
+    }
+
+    /// Re-reads the current directory, keeping the selection where possible.
+    pub fn refresh(&mut self) -> Result<(), BrowserError> {
+        let read_error = |source| BrowserError::ReadDir {
+            path: self.dir.clone(),
+            source,
+        };
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(read_error)? {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !self.show_hidden && name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            if !is_dir && !self.matches_extension(&path) {
+                continue;
+            }
+            entries.push(BrowserEntry { name, path, is_dir });
+        }
+        // Directories first, then by name.
+        entries.sort_by(|a, b| {
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        self.entries = entries;
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn matches_extension(&self, path: &Path) -> bool {
+        self.extensions.is_empty()
+            || path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .is_some_and(|e| self.extensions.contains(&e))
+    }
+
+    /// Changes to another directory, selecting its first entry.
+    pub fn open_dir(&mut self, dir: impl Into<PathBuf>) -> Result<(), BrowserError> {
+        let previous = std::mem::replace(&mut self.dir, dir.into());
+        self.selected = 0;
+        if let Err(e) = self.refresh() {
+            self.dir = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Goes to the parent directory, selecting the directory we came from.
+    pub fn open_parent(&mut self) -> Result<(), BrowserError> {
+        let Some(parent) = self.dir.parent().map(Path::to_path_buf) else {
+            return Ok(());
+        };
+        let child = self.dir.clone();
+        self.open_dir(parent)?;
+        if let Some(index) = self.entries.iter().position(|e| e.path == child) {
+            self.selected = index;
+        }
+        Ok(())
+    }
+
+    /// Moves the selection by `delta` entries.
+    pub fn move_selection(&mut self, delta: i32) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let last = self.entries.len() as i64 - 1;
+        let selected = (self.selected as i64 + delta as i64).clamp(0, last) as usize;
+        if selected != self.selected {
+            self.selected = selected;
+            self.dirty = true;
+        }
+    }
+
+    /// Opens the selected directory, or calls the select callback for a file.
+    pub fn activate(&mut self) -> Result<(), BrowserError> {
+        let Some(entry) = self.entries.get(self.selected).cloned() else {
+            return Ok(());
+        };
+        if entry.is_dir {
+            self.open_dir(entry.path)
+        } else {
+            if let Some(callback) = self.on_select.as_mut() {
+                callback(&entry.path);
+            }
+            Ok(())
+        }
+    }
+
+    /// Handles the navigation buttons and the scroll encoder.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Result<bool, BrowserError> {
+        match *event {
+            Push2Event::EncoderTwisted { name, raw_delta } if name == self.encoder => {
+                self.move_selection(decode_delta(raw_delta));
+            }
+            Push2Event::ButtonPressed { name, .. } => match name {
+                ControlName::Up => self.move_selection(-1),
+                ControlName::Down => self.move_selection(1),
+                ControlName::Left => self.open_parent()?,
+                ControlName::Right | ControlName::Select => self.activate()?,
+                _ => return Ok(false),
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Draws the current directory and its entries, if anything changed.
+    /// Returns true if the display was drawn to and needs a flush.
+    pub fn render(&mut self, display: &mut Push2Display) -> Result<bool, Push2DisplayError> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        let width = display.size().width;
+        let header = Rectangle::new(Point::zero(), Size::new(width, LIST_ROW_HEIGHT));
+        header
+            .into_styled(PrimitiveStyle::with_fill(self.colors.background))
+            .draw(display)
+            .unwrap(); // Infallible
+        let style = MonoTextStyle::new(&FONT_6X10, self.colors.text);
+        let text_style = TextStyleBuilder::new().baseline(Baseline::Top).build();
+        Text::with_text_style(
+            &self.dir.to_string_lossy(),
+            Point::new(4, 3),
+            style,
+            text_style,
+        )
+        .draw(display)
+        .unwrap(); // Infallible
+
+        let items: Vec<String> = self
+            .entries
+            .iter()
+            .map(|e| {
+                if e.is_dir {
+                    format!("{}/", e.name)
+                } else {
+                    e.name.clone()
+                }
+            })
+            .collect();
+        let list_area = Rectangle::new(
+            Point::new(0, LIST_ROW_HEIGHT as i32),
+            Size::new(width, display.size().height - LIST_ROW_HEIGHT),
+        );
+        let selected = (!items.is_empty()).then_some(self.selected);
+        display.draw_list(list_area, &items, selected, &self.colors)?;
+        self.dirty = false;
+        Ok(true)
+    }
+}
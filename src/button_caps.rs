@@ -0,0 +1,101 @@
+use crate::{ControlName, Push2Colors};
+
+/// The LED behind a button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedKind {
+    /// Shows any palette color.
+    Rgb,
+    /// Only shows shades of white: off, dim or bright.
+    White,
+}
+
+/// What the light of a button can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonCapabilities {
+    pub led: LedKind,
+    /// The light of the button when it is available but not active.
+    pub default_light: u8,
+}
+
+/// The buttons of the Push 2 with an RGB LED. Every other button is white only.
+const RGB_BUTTONS: [ControlName; 30] = [
+    ControlName::UpperRow1,
+    ControlName::UpperRow2,
+    ControlName::UpperRow3,
+    ControlName::UpperRow4,
+    ControlName::UpperRow5,
+    ControlName::UpperRow6,
+    ControlName::UpperRow7,
+    ControlName::UpperRow8,
+    ControlName::LowerRow1,
+    ControlName::LowerRow2,
+    ControlName::LowerRow3,
+    ControlName::LowerRow4,
+    ControlName::LowerRow5,
+    ControlName::LowerRow6,
+    ControlName::LowerRow7,
+    ControlName::LowerRow8,
+    ControlName::Beat1_32t,
+    ControlName::Beat1_32,
+    ControlName::Beat1_16t,
+    ControlName::Beat1_16,
+    ControlName::Beat1_8t,
+    ControlName::Beat1_8,
+    ControlName::Beat1_4t,
+    ControlName::Beat1_4,
+    ControlName::Play,
+    ControlName::Record,
+    ControlName::Automate,
+    ControlName::Mute,
+    ControlName::Solo,
+    ControlName::Stop,
+];
+
+impl ButtonCapabilities {
+    /// The capabilities of a Push 2 button.
+    pub fn of(name: ControlName) -> Self {
+        let led = if RGB_BUTTONS.contains(&name) {
+            LedKind::Rgb
+        } else {
+            LedKind::White
+        };
+        Self {
+            led,
+            default_light: Push2Colors::WHITE_LOW,
+        }
+    }
+
+    pub fn is_rgb(&self) -> bool {
+        self.led == LedKind::Rgb
+    }
+
+    /// Whether the button shows `light` as it is meant to look.
+    pub fn supports(&self, light: u8) -> bool {
+        match self.led {
+            LedKind::Rgb => light < 128,
+            LedKind::White => matches!(
+                light,
+                Push2Colors::BLACK | Push2Colors::WHITE_LOW | Push2Colors::WHITE_BRIGHT
+            ),
+        }
+    }
+
+    /// The closest light the button can show: colors become dim or bright
+    /// white on a white button, depending on their brightness.
+    pub fn translate(&self, light: u8) -> u8 {
+        if self.supports(light) {
+            return light;
+        }
+        match self.led {
+            LedKind::Rgb => Push2Colors::WHITE_BRIGHT,
+            LedKind::White => {
+                let (_, _, value) = Push2Colors::palette_hsv(light);
+                if value < 0.5 {
+                    Push2Colors::WHITE_LOW
+                } else {
+                    Push2Colors::WHITE_BRIGHT
+                }
+            }
+        }
+    }
+}
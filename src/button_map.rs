@@ -1,20 +1,89 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::app_config::config_dir;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ButtonMapError {
-    #[error("Failed to parse embedded button_map.ron: {0}")]
+    #[error("Failed to parse button_map.ron: {0}")]
     ParseError(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read the button map: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Note {address} is mapped to pad ({x}, {y}), off the 8x8 grid")]
+    PadOffGrid { address: u8, x: u8, y: u8 },
 }
 
-#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A pad on the 8x8 grid. `x` counts columns from the left and `y` rows from
+/// the top; see `GridOrigin` for counting rows from the bottom.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PadCoord {
     pub x: u8,
     pub y: u8,
 }
 
-#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The first MIDI note of the pads, sent by the bottom-left pad.
+const FIRST_PAD_NOTE: u8 = 36;
+
+impl PadCoord {
+    pub const fn new(x: u8, y: u8) -> Self {
+        Self { x, y }
+    }
+
+    /// The pad sending a note (36-99), with row 0 at the top.
+    pub fn from_note(address: u8) -> Option<Self> {
+        let index = address.checked_sub(FIRST_PAD_NOTE).filter(|i| *i < 64)?;
+        Some(Self::new(index % 8, 7 - index / 8))
+    }
+
+    /// The note this pad sends, with row 0 at the top. `None` off the grid.
+    pub fn to_note(self) -> Option<u8> {
+        (self.x < 8 && self.y < 8).then(|| FIRST_PAD_NOTE + self.x + (7 - self.y) * 8)
+    }
+
+    /// The same pad counted from the other edge of the grid: turns a
+    /// top-left coordinate into a bottom-left one and back.
+    pub fn flipped_y(self) -> Self {
+        Self::new(self.x, 7u8.saturating_sub(self.y))
+    }
+
+    /// Converts a top-left coordinate to `origin`, or back from it.
+    pub fn in_origin(self, origin: GridOrigin) -> Self {
+        match origin {
+            GridOrigin::TopLeft => self,
+            GridOrigin::BottomLeft => self.flipped_y(),
+        }
+    }
+
+    /// The pad sending a note, counted from `origin`.
+    pub fn from_note_in(address: u8, origin: GridOrigin) -> Option<Self> {
+        Self::from_note(address).map(|coord| coord.in_origin(origin))
+    }
+
+    /// The note of this pad, counted from `origin`. `None` off the grid.
+    pub fn to_note_in(self, origin: GridOrigin) -> Option<u8> {
+        self.in_origin(origin).to_note()
+    }
+}
+
+/// Which pad is (0, 0) for an app's own coordinates.
+///
+/// Events, `Push2State`, `set_pad_color` and the layouts, regions and apps in
+/// this crate always count from the top left. An app counting from the
+/// bottom converts at its edge, e.g. with `Push2::pad_at` or
+/// `PadCoord::in_origin`, with the origin set in `AppConfig::grid_origin`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridOrigin {
+    /// Row 0 is the top row, as on the display.
+    #[default]
+    TopLeft,
+    /// Row 0 is the bottom row, as the pad notes count up from it.
+    BottomLeft,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ControlName {
     TapTempo,
     Metronome,
@@ -83,7 +152,33 @@ pub enum ControlName {
     Select,
 }
 
-#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl ControlName {
+    /// The buttons above the display, from left to right.
+    pub const UPPER_ROW: [ControlName; 8] = [
+        ControlName::UpperRow1,
+        ControlName::UpperRow2,
+        ControlName::UpperRow3,
+        ControlName::UpperRow4,
+        ControlName::UpperRow5,
+        ControlName::UpperRow6,
+        ControlName::UpperRow7,
+        ControlName::UpperRow8,
+    ];
+
+    /// The buttons below the display, from left to right.
+    pub const LOWER_ROW: [ControlName; 8] = [
+        ControlName::LowerRow1,
+        ControlName::LowerRow2,
+        ControlName::LowerRow3,
+        ControlName::LowerRow4,
+        ControlName::LowerRow5,
+        ControlName::LowerRow6,
+        ControlName::LowerRow7,
+        ControlName::LowerRow8,
+    ];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EncoderName {
     Tempo,
     Swing,
@@ -98,22 +193,108 @@ pub enum EncoderName {
     Master,
 }
 
+impl EncoderName {
+    /// The 8 encoders above the display, from left to right.
+    pub const TRACKS: [EncoderName; 8] = [
+        EncoderName::Track1,
+        EncoderName::Track2,
+        EncoderName::Track3,
+        EncoderName::Track4,
+        EncoderName::Track5,
+        EncoderName::Track6,
+        EncoderName::Track7,
+        EncoderName::Track8,
+    ];
+
+    /// Returns the position (0-7) of a track encoder, or `None` for Tempo, Swing and Master.
+    pub fn track_index(&self) -> Option<u8> {
+        Self::TRACKS.iter().position(|e| e == self).map(|i| i as u8)
+    }
+
+    /// Whether the encoder clicks in steps, sending one tick per detent.
+    /// Tempo and Swing do; the track encoders and Master turn smoothly.
+    pub fn has_detents(&self) -> bool {
+        matches!(self, EncoderName::Tempo | EncoderName::Swing)
+    }
+}
+
+/// Converts the raw value of an `EncoderTwisted` event into a signed delta.
+/// The encoders send 1-63 for clockwise and 127-64 for counter-clockwise turns.
+pub fn decode_delta(raw_delta: u8) -> i32 {
+    if raw_delta >= 64 {
+        raw_delta as i32 - 128
+    } else {
+        raw_delta as i32
+    }
+}
+
 #[derive(Deserialize)]
 pub struct ButtonMap {
-    note_map: HashMap<u8, PadCoord>,
-    control_map: HashMap<u8, ControlName>,
-    encoder_map: HashMap<u8, EncoderName>,
+    note_map: BTreeMap<u8, PadCoord>,
+    control_map: BTreeMap<u8, ControlName>,
+    encoder_map: BTreeMap<u8, EncoderName>,
     #[serde(skip)]
     note_reverse_map: HashMap<PadCoord, u8>,
     #[serde(skip)]
     control_reverse_map: HashMap<ControlName, u8>,
+    #[serde(skip)]
+    control_overrides: HashMap<ControlName, u8>,
 }
 
 impl ButtonMap {
+    /// The Push 2 addresses from the embedded config/button_map.ron.
     pub fn new() -> Result<Self, ButtonMapError> {
-        let map_string = include_str!("../config/button_map.ron");
+        Self::from_ron(include_str!("../config/button_map.ron"))
+    }
+
+    /// Loads the addresses from a RON file in the format of config/button_map.ron.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ButtonMapError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// Loads "button_map.ron" from the push2 config directory if the user
+    /// wrote one, and the embedded map otherwise.
+    pub fn in_config_dir() -> Result<Self, ButtonMapError> {
+        match config_dir() {
+            Some(dir) => Self::in_dir(&dir),
+            None => Self::new(),
+        }
+    }
+
+    /// Loads "button_map.ron" from `dir` if the user wrote one, and the
+    /// embedded map otherwise.
+    pub fn in_dir(dir: &Path) -> Result<Self, ButtonMapError> {
+        let path = dir.join("button_map.ron");
+        if path.exists() {
+            info!("Loading button map from: {:?}", path);
+            Self::load(path)
+        } else {
+            Self::new()
+        }
+    }
+
+    /// Replaces the addresses with the ones in `path`, keeping the
+    /// `set_control_address` overrides.
+    pub fn reload(&mut self, path: impl AsRef<Path>) -> Result<(), ButtonMapError> {
+        let mut map = Self::load(path)?;
+        for (name, address) in &self.control_overrides {
+            map.set_control_address(*name, *address);
+        }
+        *self = map;
+        Ok(())
+    }
+
+    fn from_ron(map_string: &str) -> Result<Self, ButtonMapError> {
         let mut map: ButtonMap = ron::from_str(map_string).map_err(Box::new)?;
 
+        // Pads index 8x8 arrays, so a coordinate off the grid would panic later.
+        if let Some((&address, &PadCoord { x, y })) = map
+            .note_map
+            .iter()
+            .find(|(_, coord)| coord.to_note().is_none())
+        {
+            return Err(ButtonMapError::PadOffGrid { address, x, y });
+        }
         for (address, coord) in &map.note_map {
             map.note_reverse_map.insert(*coord, *address);
         }
@@ -145,7 +326,132 @@ impl ButtonMap {
     pub fn get_control_address(&self, name: ControlName) -> Option<u8> {
         self.control_reverse_map.get(&name).copied()
     }
+    /// The CC address of an encoder.
+    pub fn get_encoder_address(&self, name: EncoderName) -> Option<u8> {
+        self.encoder_map
+            .iter()
+            .find(|(_, encoder)| **encoder == name)
+            .map(|(address, _)| *address)
+    }
+    #[deprecated(note = "use `controls`, which also gives the names")]
     pub fn get_control_addresses(&self) -> impl Iterator<Item = &u8> {
         self.control_map.keys()
     }
+
+    /// Moves a control button to another CC address, e.g. for a remapped controller.
+    /// A control previously on that address is unmapped.
+    pub fn set_control_address(&mut self, name: ControlName, address: u8) {
+        if let Some(old_address) = self.control_reverse_map.remove(&name) {
+            self.control_map.remove(&old_address);
+        }
+        if let Some(old_name) = self.control_map.insert(address, name) {
+            self.control_reverse_map.remove(&old_name);
+        }
+        self.control_reverse_map.insert(name, address);
+        self.control_overrides.insert(name, address);
+    }
+
+    /// The controls moved with `set_control_address`, and their addresses.
+    pub fn control_overrides(&self) -> &HashMap<ControlName, u8> {
+        &self.control_overrides
+    }
+
+    /// Iterates over every pad and its note address, in address order.
+    pub fn pads(&self) -> impl ExactSizeIterator<Item = (u8, PadCoord)> + '_ {
+        self.note_map
+            .iter()
+            .map(|(address, coord)| (*address, *coord))
+    }
+
+    /// Iterates over every control button and its CC address, in address order.
+    pub fn controls(&self) -> impl ExactSizeIterator<Item = (u8, ControlName)> + '_ {
+        self.control_map
+            .iter()
+            .map(|(address, name)| (*address, *name))
+    }
+
+    /// Iterates over every encoder and its CC address, in address order.
+    pub fn encoders(&self) -> impl ExactSizeIterator<Item = (u8, EncoderName)> + '_ {
+        self.encoder_map
+            .iter()
+            .map(|(address, name)| (*address, *name))
+    }
+
+    /// The number of pads, 64 on a Push 2.
+    pub fn pad_count(&self) -> usize {
+        self.note_map.len()
+    }
+
+    /// The number of control buttons.
+    pub fn control_count(&self) -> usize {
+        self.control_map.len()
+    }
+
+    /// The number of encoders.
+    pub fn encoder_count(&self) -> usize {
+        self.encoder_map.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_encoder_deltas() {
+        assert_eq!(decode_delta(1), 1);
+        assert_eq!(decode_delta(63), 63);
+        assert_eq!(decode_delta(64), -64);
+        assert_eq!(decode_delta(127), -1);
+    }
+
+    #[test]
+    fn pad_notes_match_the_button_map() {
+        let map = ButtonMap::new().unwrap();
+        for (address, coord) in map.pads() {
+            assert_eq!(PadCoord::from_note(address), Some(coord));
+            assert_eq!(coord.to_note(), Some(address));
+        }
+        assert_eq!(PadCoord::from_note(35), None);
+        assert_eq!(PadCoord::from_note(100), None);
+        assert_eq!(PadCoord::new(8, 0).to_note(), None);
+    }
+
+    #[test]
+    fn rejects_pads_off_the_grid() {
+        let map =
+            ButtonMap::from_ron("(note_map: {36: (x: 0, y: 8)}, control_map: {}, encoder_map: {})");
+        assert!(matches!(
+            map,
+            Err(ButtonMapError::PadOffGrid {
+                address: 36,
+                x: 0,
+                y: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn enumerates_controls_in_address_order() {
+        let map = ButtonMap::new().unwrap();
+        assert_eq!(map.pad_count(), 64);
+        assert_eq!(map.pads().len(), 64);
+        let addresses: Vec<u8> = map.controls().map(|(address, _)| address).collect();
+        assert!(addresses.is_sorted());
+        assert_eq!(addresses.len(), map.control_count());
+        assert_eq!(map.encoders().len(), map.encoder_count());
+    }
+
+    #[test]
+    fn bottom_left_origin_flips_rows() {
+        let origin = GridOrigin::BottomLeft;
+        assert_eq!(
+            PadCoord::from_note_in(36, origin),
+            Some(PadCoord::new(0, 0))
+        );
+        assert_eq!(PadCoord::new(7, 7).to_note_in(origin), Some(99));
+        assert_eq!(PadCoord::new(2, 1).flipped_y(), PadCoord::new(2, 6));
+        let map = ButtonMap::new().unwrap();
+        assert_eq!(map.get_note(36), Some(PadCoord::new(0, 7)));
+    }
 }
@@ -0,0 +1,239 @@
+use crate::app_config::config_dir;
+use crate::fonts::{FontSize, TextStyle};
+use crate::{LightTarget, PadCoord, Push2, Push2Colors, Push2Error, Push2Event};
+use embedded_graphics::{
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+/// The gains are kept within this range, so a broken pad cannot be boosted to full velocity.
+const MIN_GAIN: f32 = 0.5;
+const MAX_GAIN: f32 = 2.0;
+
+#[derive(Error, Debug)]
+pub enum CalibrationError {
+    #[error("Failed to serialize calibration: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to parse calibration file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read or write calibration file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+    #[error("Calibration was cancelled")]
+    Cancelled,
+}
+
+/// Per-pad velocity gains that even out pads reading high or low.
+///
+/// Set `Push2::pad_calibration` to apply it to every `PadPressed` event,
+/// before `Push2::velocity_map`. `Push2::run_pad_calibration` measures it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PadCalibration {
+    /// The gain of each pad, indexed by `[y][x]`.
+    pub gains: [[f32; 8]; 8],
+}
+
+impl Default for PadCalibration {
+    fn default() -> Self {
+        Self {
+            gains: [[1.0; 8]; 8],
+        }
+    }
+}
+
+impl PadCalibration {
+    /// Gains that bring the mean velocity of every pad to the median of all
+    /// the means. Pads without a mean keep a gain of 1.
+    pub fn from_means(means: &[[Option<f32>; 8]; 8]) -> Self {
+        let mut sorted: Vec<f32> = means.iter().flatten().flatten().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        let mut calibration = Self::default();
+        let Some(&target) = sorted.get(sorted.len() / 2) else {
+            return calibration;
+        };
+        for (gains, means) in calibration.gains.iter_mut().zip(means) {
+            for (gain, mean) in gains.iter_mut().zip(means) {
+                if let Some(mean) = mean.filter(|m| *m > 0.0) {
+                    *gain = (target / mean).clamp(MIN_GAIN, MAX_GAIN);
+                }
+            }
+        }
+        calibration
+    }
+
+    pub fn gain(&self, coord: PadCoord) -> f32 {
+        self.gains
+            .get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// The velocity of a hit with the pad's gain applied (1-127).
+    pub fn apply(&self, coord: PadCoord, velocity: u8) -> u8 {
+        (velocity as f32 * self.gain(coord))
+            .round()
+            .clamp(1.0, 127.0) as u8
+    }
+
+    /// "pad_calibration.ron" in the push2 config directory.
+    pub fn config_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("pad_calibration.ron"))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CalibrationError> {
+        let ron = fs::read_to_string(path)?;
+        Ok(ron::from_str(&ron).map_err(Box::new)?)
+    }
+
+    /// Loads the calibration saved in the config directory, if there is one.
+    pub fn load_saved() -> Result<Option<Self>, CalibrationError> {
+        let path = Self::config_path().ok_or(CalibrationError::NoConfigDir)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        info!("Loading pad calibration from: {:?}", path);
+        Self::load(path).map(Some)
+    }
+
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), CalibrationError> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+
+    /// Saves to `config_path`.
+    pub fn save(&self) -> Result<(), CalibrationError> {
+        self.save_to(Self::config_path().ok_or(CalibrationError::NoConfigDir)?)
+    }
+}
+
+impl Push2 {
+    /// Asks the user to strike every pad `hits_per_pad` times, at the same
+    /// strength, and derives per-pad gains from the mean velocities.
+    ///
+    /// The pad to strike is lit white and turns green when done; the display
+    /// shows how many hits are left. Pressing any button cancels. The result
+    /// is set as `pad_calibration` and saved in the config directory.
+    pub fn run_pad_calibration(
+        &mut self,
+        hits_per_pad: usize,
+    ) -> Result<PadCalibration, Push2Error> {
+        // Measure the raw velocities.
+        let previous = (self.pad_calibration.take(), self.velocity_map.take());
+        let result = self.measure_pads(hits_per_pad.max(1));
+        (self.pad_calibration, self.velocity_map) = previous;
+        self.display.clear(Bgr565::BLACK).unwrap(); // Infallible
+        self.display.flush()?;
+        self.reset_all_lights().into_result()?;
+
+        let calibration = PadCalibration::from_means(&result?);
+        calibration.save()?;
+        self.pad_calibration = Some(calibration.clone());
+        Ok(calibration)
+    }
+
+    /// The mean velocity of each pad over `hits` strikes.
+    fn measure_pads(&mut self, hits: usize) -> Result<[[Option<f32>; 8]; 8], Push2Error> {
+        let mut means = [[None; 8]; 8];
+        self.reset_all_lights().into_result()?;
+        while self.poll_event().is_some() {}
+        for y in 0..8u8 {
+            for x in 0..8u8 {
+                let coord = PadCoord { x, y };
+                self.set_lights([(LightTarget::Pad(coord), Push2Colors::WHITE_BRIGHT)])
+                    .into_result()?;
+                let mut velocities = Vec::with_capacity(hits);
+                self.draw_calibration_prompt(coord, hits)?;
+                while velocities.len() < hits {
+                    match self.poll_event() {
+                        Some(Push2Event::PadPressed {
+                            coord: hit,
+                            velocity,
+                        }) if hit == coord => {
+                            velocities.push(velocity as f32);
+                            self.draw_calibration_prompt(coord, hits - velocities.len())?;
+                        }
+                        Some(Push2Event::ButtonPressed { .. }) => {
+                            return Err(CalibrationError::Cancelled.into());
+                        }
+                        Some(_) => {}
+                        None => thread::sleep(Duration::from_millis(1)),
+                    }
+                }
+                means[y as usize][x as usize] =
+                    Some(velocities.iter().sum::<f32>() / velocities.len() as f32);
+                self.set_lights([(LightTarget::Pad(coord), Push2Colors::GREEN)])
+                    .into_result()?;
+            }
+        }
+        Ok(means)
+    }
+
+    fn draw_calibration_prompt(&mut self, coord: PadCoord, left: usize) -> Result<(), Push2Error> {
+        self.display.clear(Bgr565::BLACK).unwrap(); // Infallible
+        let centered = TextStyleBuilder::new()
+            .alignment(Alignment::Center)
+            .baseline(Baseline::Middle)
+            .build();
+        let lines = [
+            format!(
+                "Strike the white pad ({}, {}) {} more times",
+                coord.x, coord.y, left
+            ),
+            "Hit every pad with the same strength. Press any button to cancel.".to_string(),
+        ];
+        for (line, y) in lines.iter().zip([64, 100]) {
+            Text::with_text_style(
+                line,
+                Point::new(480, y),
+                TextStyle::new(FontSize::Medium),
+                centered,
+            )
+            .draw(&mut self.display)
+            .unwrap(); // Infallible
+        }
+        self.display.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gains_bring_pads_to_the_median() {
+        let mut means = [[Some(80.0); 8]; 8];
+        means[0][0] = Some(40.0);
+        means[7][7] = Some(100.0);
+        means[3][3] = None;
+        let calibration = PadCalibration::from_means(&means);
+        assert_eq!(calibration.gain(PadCoord { x: 0, y: 0 }), 2.0);
+        assert_eq!(calibration.gain(PadCoord { x: 7, y: 7 }), 0.8);
+        assert_eq!(calibration.gain(PadCoord { x: 3, y: 3 }), 1.0);
+        assert_eq!(calibration.gain(PadCoord { x: 1, y: 1 }), 1.0);
+    }
+
+    #[test]
+    fn apply_stays_in_midi_range() {
+        let mut calibration = PadCalibration::default();
+        calibration.gains[0][0] = 2.0;
+        calibration.gains[0][1] = 0.5;
+        assert_eq!(calibration.apply(PadCoord { x: 0, y: 0 }, 100), 127);
+        assert_eq!(calibration.apply(PadCoord { x: 1, y: 0 }, 1), 1);
+        assert_eq!(calibration.apply(PadCoord { x: 2, y: 0 }, 64), 64);
+    }
+}
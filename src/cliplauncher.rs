@@ -0,0 +1,326 @@
+use crate::{ControlName, PadCoord, Push2, Push2Colors, Push2Error, Push2Event, PushApp};
+use std::time::{Duration, Instant};
+
+/// The number of tracks, one per pad column.
+pub const TRACK_COUNT: usize = 8;
+
+/// The right-hand column buttons, top to bottom, used to launch scenes.
+pub const SCENE_BUTTONS: [ControlName; 8] = [
+    ControlName::Beat1_4,
+    ControlName::Beat1_4t,
+    ControlName::Beat1_8,
+    ControlName::Beat1_8t,
+    ControlName::Beat1_16,
+    ControlName::Beat1_16t,
+    ControlName::Beat1_32,
+    ControlName::Beat1_32t,
+];
+
+/// The state of a clip slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClipState {
+    #[default]
+    Empty,
+    Loaded,
+    Playing,
+    /// Launched, waiting for the next launch point to start playing.
+    Queued,
+    Recording,
+}
+
+impl ClipState {
+    /// True for the states shown by a blinking pad.
+    pub fn blinks(&self) -> bool {
+        matches!(self, ClipState::Queued | ClipState::Recording)
+    }
+}
+
+/// A clip slot: a column (track) and row (scene) of the launcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClipSlot {
+    pub track: usize,
+    pub scene: usize,
+}
+
+/// The pad colors of each clip state, and the scene button lights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClipColors {
+    pub empty: u8,
+    pub loaded: u8,
+    pub playing: u8,
+    pub queued: u8,
+    pub recording: u8,
+    /// Light of a scene button whose scene has clips.
+    pub scene: u8,
+    /// Light of a scene button whose scene is empty.
+    pub scene_empty: u8,
+}
+
+impl Default for ClipColors {
+    fn default() -> Self {
+        Self {
+            empty: Push2Colors::BLACK,
+            loaded: Push2Colors::ORANGE,
+            playing: Push2Colors::GREEN_BRIGHT,
+            queued: Push2Colors::GREEN,
+            recording: Push2Colors::RED_BRIGHT,
+            scene: Push2Colors::WHITE_BRIGHT,
+            scene_empty: Push2Colors::WHITE_LOW,
+        }
+    }
+}
+
+impl ClipColors {
+    /// The steady color of a state.
+    pub fn color(&self, state: ClipState) -> u8 {
+        match state {
+            ClipState::Empty => self.empty,
+            ClipState::Loaded => self.loaded,
+            ClipState::Playing => self.playing,
+            ClipState::Queued => self.queued,
+            ClipState::Recording => self.recording,
+        }
+    }
+}
+
+type LaunchCallback = Box<dyn FnMut(ClipSlot) + Send>;
+type StopCallback = Box<dyn FnMut(usize) + Send>;
+
+/// A session-view style grid of clips: 8 tracks (pad columns) by any
+/// number of scenes (pad rows), shown 8 scenes at a time.
+///
+/// Pressing a pad with a clip launches it, pressing an empty pad stops
+/// its track, and the right-hand column buttons launch a whole scene.
+/// Up and Down scroll through the scenes. Launched clips are `Queued`;
+/// the app marks them `Playing` with `set_state` once they start.
+pub struct ClipLauncher {
+    clips: Vec<[ClipState; TRACK_COUNT]>,
+    scene_offset: usize,
+    pub colors: ClipColors,
+    /// How long a blinking pad stays on, then off.
+    pub blink_interval: Duration,
+    epoch: Instant,
+    on_launch: Option<LaunchCallback>,
+    on_stop: Option<StopCallback>,
+}
+
+impl ClipLauncher {
+    /// Creates a launcher with `scenes` rows of empty clips.
+    pub fn new(scenes: usize) -> Self {
+        Self {
+            clips: vec![[ClipState::Empty; TRACK_COUNT]; scenes.max(1)],
+            scene_offset: 0,
+            colors: ClipColors::default(),
+            blink_interval: Duration::from_millis(250),
+            epoch: Instant::now(),
+            on_launch: None,
+            on_stop: None,
+        }
+    }
+
+    /// Registers the callback called for every launched clip.
+    pub fn on_launch<F>(&mut self, callback: F)
+    where
+        F: FnMut(ClipSlot) + Send + 'static,
+    {
+        self.on_launch = Some(Box::new(callback));
+    }
+
+    /// Registers the callback called with the track index when a track is stopped.
+    pub fn on_stop<F>(&mut self, callback: F)
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        self.on_stop = Some(Box::new(callback));
+    }
+
+    pub fn scene_count(&self) -> usize {
+        self.clips.len()
+    }
+
+    /// Changes the number of scenes, keeping existing clips where possible.
+    pub fn set_scene_count(&mut self, scenes: usize) {
+        self.clips
+            .resize(scenes.max(1), [ClipState::Empty; TRACK_COUNT]);
+        self.scene_offset = self.scene_offset.min(self.max_offset());
+    }
+
+    pub fn state(&self, slot: ClipSlot) -> Option<ClipState> {
+        self.clips
+            .get(slot.scene)
+            .and_then(|row| row.get(slot.track))
+            .copied()
+    }
+
+    pub fn set_state(&mut self, slot: ClipSlot, state: ClipState) {
+        if let Some(clip) = self
+            .clips
+            .get_mut(slot.scene)
+            .and_then(|row| row.get_mut(slot.track))
+        {
+            *clip = state;
+        }
+    }
+
+    /// The scene shown on the top pad row.
+    pub fn scene_offset(&self) -> usize {
+        self.scene_offset
+    }
+
+    /// Scrolls the visible scenes by `delta` rows.
+    pub fn scroll(&mut self, delta: isize) {
+        self.scene_offset = self
+            .scene_offset
+            .saturating_add_signed(delta)
+            .min(self.max_offset());
+    }
+
+    fn max_offset(&self) -> usize {
+        self.clips.len().saturating_sub(8)
+    }
+
+    /// The clip slot under a pad, if the pad shows a scene.
+    pub fn slot_at(&self, coord: PadCoord) -> Option<ClipSlot> {
+        let scene = self.scene_offset + coord.y as usize;
+        ((coord.x as usize) < TRACK_COUNT && scene < self.clips.len()).then_some(ClipSlot {
+            track: coord.x as usize,
+            scene,
+        })
+    }
+
+    /// Queues a clip and calls the launch callback. Empty slots stop their track instead.
+    pub fn launch(&mut self, slot: ClipSlot) {
+        match self.state(slot) {
+            None => {}
+            Some(ClipState::Empty) => self.stop_track(slot.track),
+            Some(_) => {
+                self.set_state(slot, ClipState::Queued);
+                if let Some(callback) = self.on_launch.as_mut() {
+                    callback(slot);
+                }
+            }
+        }
+    }
+
+    /// Launches every clip of a scene, stopping the tracks with no clip in it.
+    pub fn launch_scene(&mut self, scene: usize) {
+        if scene >= self.clips.len() {
+            return;
+        }
+        for track in 0..TRACK_COUNT {
+            self.launch(ClipSlot { track, scene });
+        }
+    }
+
+    /// Stops the playing, queued or recording clips of a track and calls the stop callback.
+    pub fn stop_track(&mut self, track: usize) {
+        if track >= TRACK_COUNT {
+            return;
+        }
+        for row in &mut self.clips {
+            if matches!(
+                row[track],
+                ClipState::Playing | ClipState::Queued | ClipState::Recording
+            ) {
+                row[track] = ClipState::Loaded;
+            }
+        }
+        if let Some(callback) = self.on_stop.as_mut() {
+            callback(track);
+        }
+    }
+
+    /// Handles pad presses, scene buttons and scrolling.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> bool {
+        match *event {
+            Push2Event::PadPressed { coord, .. } => match self.slot_at(coord) {
+                Some(slot) => {
+                    self.launch(slot);
+                    true
+                }
+                None => false,
+            },
+            Push2Event::ButtonPressed {
+                name: ControlName::Up,
+                ..
+            } => {
+                self.scroll(-1);
+                true
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::Down,
+                ..
+            } => {
+                self.scroll(1);
+                true
+            }
+            Push2Event::ButtonPressed { name, .. } => {
+                match SCENE_BUTTONS.iter().position(|b| *b == name) {
+                    Some(row) => {
+                        self.launch_scene(self.scene_offset + row);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Draws the visible clips and scene buttons.
+    /// Only pads and lights that changed are sent to the hardware.
+    pub fn render(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        self.render_at(push2, Instant::now())
+    }
+
+    /// Draws the visible clips, with blinking pads in their phase at `now`.
+    pub fn render_at(&self, push2: &mut Push2, now: Instant) -> Result<(), Push2Error> {
+        let interval = self.blink_interval.as_millis().max(1);
+        let blink_on =
+            (now.saturating_duration_since(self.epoch).as_millis() / interval).is_multiple_of(2);
+        for y in 0..8u8 {
+            for x in 0..8u8 {
+                let coord = PadCoord { x, y };
+                let color = match self.slot_at(coord).and_then(|slot| self.state(slot)) {
+                    Some(state) if state.blinks() && !blink_on => self.colors.empty,
+                    Some(state) => self.colors.color(state),
+                    None => Push2Colors::BLACK,
+                };
+                if push2.state.pads[y as usize][x as usize].color != color {
+                    push2.set_pad_color(coord, color)?;
+                }
+            }
+        }
+        for (row, name) in SCENE_BUTTONS.iter().enumerate() {
+            let has_clips = self
+                .clips
+                .get(self.scene_offset + row)
+                .is_some_and(|clips| clips.iter().any(|c| *c != ClipState::Empty));
+            let light = if has_clips {
+                self.colors.scene
+            } else {
+                self.colors.scene_empty
+            };
+            if push2.state.buttons.get(name).map(|b| b.light) != Some(light) {
+                push2.set_button_light(*name, light)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PushApp for ClipLauncher {
+    fn name(&self) -> &str {
+        "Clips"
+    }
+
+    fn on_event(&mut self, _push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+        self.handle_event(event);
+        Ok(())
+    }
+
+    fn draw(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        self.render(push2)
+    }
+}
@@ -0,0 +1,412 @@
+use crate::midi_handler::{MidiHandler, MidiHandlerError};
+use crate::{
+    CONTINUE, ControlName, Push2, Push2Colors, Push2Error, Push2Event, START, STOP, TIMING_CLOCK,
+};
+use log::{info, warn};
+use midir::{MidiInputConnection, MidiOutputConnection};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+/// The resolution of MIDI clock (0xF8) messages.
+pub const MIDI_CLOCK_PPQN: u32 = 24;
+/// The default resolution, matching MIDI clock.
+pub const DEFAULT_PPQN: u32 = MIDI_CLOCK_PPQN;
+/// Taps further apart than this start a new tap-tempo measurement.
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+/// The number of taps averaged by tap tempo.
+const TAP_HISTORY: usize = 4;
+const MIN_BPM: f64 = 20.0;
+const MAX_BPM: f64 = 300.0;
+
+type TickCallback = Box<dyn FnMut(&ClockTick) + Send>;
+
+/// Passed to tick callbacks for every clock pulse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockTick {
+    /// The number of ticks since the clock was started.
+    pub tick: u64,
+    /// The resolution the clock was running at.
+    pub ppqn: u32,
+}
+
+impl ClockTick {
+    /// The quarter note this tick belongs to.
+    pub fn beat(&self) -> u64 {
+        self.tick / self.ppqn as u64
+    }
+
+    /// Returns true for the first tick of every quarter note.
+    pub fn is_beat(&self) -> bool {
+        self.tick.is_multiple_of(self.ppqn as u64)
+    }
+}
+
+/// The note length of a Beat1_* button, in ticks at `ppqn`.
+/// Returns `None` for other buttons.
+pub fn beat_button_ticks(name: ControlName, ppqn: u32) -> Option<u32> {
+    // The length as a fraction of a quarter note.
+    let (num, den) = match name {
+        ControlName::Beat1_4 => (1, 1),
+        ControlName::Beat1_4t => (2, 3),
+        ControlName::Beat1_8 => (1, 2),
+        ControlName::Beat1_8t => (1, 3),
+        ControlName::Beat1_16 => (1, 4),
+        ControlName::Beat1_16t => (1, 6),
+        ControlName::Beat1_32 => (1, 8),
+        ControlName::Beat1_32t => (1, 12),
+        _ => return None,
+    };
+    Some((ppqn * num / den).max(1))
+}
+
+/// Where the clock takes its timing from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// Ticks are generated from the clock's own BPM.
+    Internal,
+    /// Ticks follow MIDI clock, start, stop and continue from an input port.
+    External,
+}
+
+/// An incoming MIDI clock connection and its tempo estimate.
+struct ExternalSync {
+    _conn_in: MidiInputConnection<()>,
+    rx: Receiver<(Instant, u8)>,
+    pulses: u64,
+    last_pulse: Option<Instant>,
+    intervals: Vec<Duration>,
+}
+
+/// An internal tempo clock with tap tempo and a metronome LED.
+///
+/// Call `update` regularly from the main loop; every tick that became due
+/// since the last call is delivered to the registered callbacks.
+pub struct Clock {
+    bpm: f64,
+    pub ppqn: u32,
+    running: bool,
+    tick_count: u64,
+    next_tick_at: Option<Instant>,
+    tap_times: Vec<Instant>,
+    metronome: bool,
+    callbacks: Vec<TickCallback>,
+    source: ClockSource,
+    sync_out: Option<MidiOutputConnection>,
+    sync_in: Option<ExternalSync>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
+
+impl Clock {
+    pub fn new(bpm: f64) -> Self {
+        Self {
+            bpm: bpm.clamp(MIN_BPM, MAX_BPM),
+            ppqn: DEFAULT_PPQN,
+            running: false,
+            tick_count: 0,
+            next_tick_at: None,
+            tap_times: Vec::new(),
+            metronome: false,
+            callbacks: Vec::new(),
+            source: ClockSource::Internal,
+            sync_out: None,
+            sync_in: None,
+        }
+    }
+
+    /// Registers a callback called for every tick.
+    pub fn on_tick<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ClockTick) + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Changes the tempo. Takes effect from the next tick.
+    pub fn set_bpm(&mut self, bpm: f64) {
+        self.bpm = bpm.clamp(MIN_BPM, MAX_BPM);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Starts the transport from tick 0.
+    pub fn start(&mut self) {
+        self.tick_count = 0;
+        self.running = true;
+        self.next_tick_at = Some(Instant::now());
+        self.send_sync(START);
+    }
+
+    /// Continues from the current position.
+    pub fn resume(&mut self) {
+        self.running = true;
+        self.next_tick_at = Some(Instant::now());
+        self.send_sync(CONTINUE);
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+        self.next_tick_at = None;
+        self.send_sync(STOP);
+    }
+
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    /// Sends MIDI clock, start, stop and continue to an output port whose
+    /// name contains `port_name`, so other gear follows this clock.
+    pub fn send_sync_to(&mut self, port_name: &str) -> Result<(), MidiHandlerError> {
+        self.sync_out = Some(MidiHandler::connect_output(port_name)?);
+        Ok(())
+    }
+
+    /// Stops sending MIDI clock.
+    pub fn stop_sending_sync(&mut self) {
+        self.sync_out = None;
+    }
+
+    /// Follows MIDI clock from an input port whose name contains `port_name`.
+    /// The BPM is estimated from the incoming pulses.
+    pub fn follow_external(&mut self, port_name: &str) -> Result<(), MidiHandlerError> {
+        let (tx, rx) = mpsc::channel();
+        let conn_in = MidiHandler::connect_input(port_name, move |_stamp, message, _| {
+            if let [status @ (TIMING_CLOCK | START | CONTINUE | STOP)] = message {
+                let _ = tx.send((Instant::now(), *status));
+            }
+        })?;
+        info!("Clock following external MIDI clock from '{}'", port_name);
+        self.sync_in = Some(ExternalSync {
+            _conn_in: conn_in,
+            rx,
+            pulses: 0,
+            last_pulse: None,
+            intervals: Vec::new(),
+        });
+        self.source = ClockSource::External;
+        self.next_tick_at = None;
+        Ok(())
+    }
+
+    /// Returns to the internal clock, closing the external input.
+    pub fn use_internal(&mut self) {
+        self.sync_in = None;
+        self.source = ClockSource::Internal;
+        if self.running {
+            self.next_tick_at = Some(Instant::now());
+        }
+    }
+
+    /// The number of ticks since the clock was started.
+    pub fn position(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// The duration of a single tick at the current tempo.
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_secs_f64(60.0 / self.bpm / self.ppqn.max(1) as f64)
+    }
+
+    /// Fires every tick that is due and returns them, so components like the
+    /// sequencer can be driven without sharing them with a callback.
+    pub fn update(&mut self) -> Vec<ClockTick> {
+        self.update_at(Instant::now())
+    }
+
+    /// Fires every tick that is due at `now`.
+    pub fn update_at(&mut self, now: Instant) -> Vec<ClockTick> {
+        let mut fired = Vec::new();
+        if self.source == ClockSource::External {
+            self.update_external(&mut fired);
+            return fired;
+        }
+        while let Some(next) = self.next_tick_at {
+            if !self.running || now < next {
+                break;
+            }
+            fired.push(self.fire_tick());
+            self.next_tick_at = Some(next + self.tick_duration());
+        }
+        fired
+    }
+
+    /// Registers a tap. After two or more taps in a row, the tempo is set
+    /// from the average interval between them.
+    pub fn tap(&mut self) {
+        self.tap_at(Instant::now());
+    }
+
+    pub fn tap_at(&mut self, now: Instant) {
+        if let Some(last) = self.tap_times.last()
+            && now.duration_since(*last) > TAP_TIMEOUT
+        {
+            self.tap_times.clear();
+        }
+        self.tap_times.push(now);
+        if self.tap_times.len() > TAP_HISTORY {
+            self.tap_times.remove(0);
+        }
+        if self.tap_times.len() >= 2 {
+            let first = self.tap_times[0];
+            let last = self.tap_times[self.tap_times.len() - 1];
+            let average =
+                last.duration_since(first).as_secs_f64() / (self.tap_times.len() - 1) as f64;
+            if average > 0.0 {
+                self.set_bpm(60.0 / average);
+            }
+        }
+    }
+
+    pub fn metronome_enabled(&self) -> bool {
+        self.metronome
+    }
+
+    pub fn set_metronome(&mut self, enabled: bool) {
+        self.metronome = enabled;
+    }
+
+    /// Handles the TapTempo and Metronome buttons.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> bool {
+        match event {
+            Push2Event::ButtonPressed {
+                name: ControlName::TapTempo,
+                ..
+            } => {
+                self.tap();
+                true
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::Metronome,
+                ..
+            } => {
+                self.metronome = !self.metronome;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The light the Metronome button should show right now.
+    /// It flashes bright for the first sixteenth of every beat while running.
+    pub fn metronome_light(&self) -> u8 {
+        if !self.metronome {
+            return Push2Colors::BLACK;
+        }
+        let ppqn = self.ppqn.max(1) as u64;
+        let on_beat = self.tick_count > 0 && (self.tick_count - 1) % ppqn < (ppqn / 4).max(1);
+        if self.running && on_beat {
+            Push2Colors::WHITE_BRIGHT
+        } else {
+            Push2Colors::WHITE_LOW
+        }
+    }
+
+    /// Sends the Metronome button light, if it changed.
+    pub fn update_leds(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let light = self.metronome_light();
+        let current = push2
+            .state
+            .buttons
+            .get(&ControlName::Metronome)
+            .map(|b| b.light);
+        if current != Some(light) {
+            push2.set_button_light(ControlName::Metronome, light)?;
+        }
+        Ok(())
+    }
+
+    /// Applies queued messages from the external clock input.
+    fn update_external(&mut self, fired: &mut Vec<ClockTick>) {
+        let Some(sync) = self.sync_in.as_mut() else {
+            return;
+        };
+        let messages: Vec<(Instant, u8)> = sync.rx.try_iter().collect();
+        for (at, status) in messages {
+            match status {
+                START => {
+                    self.tick_count = 0;
+                    self.running = true;
+                    if let Some(sync) = self.sync_in.as_mut() {
+                        sync.pulses = 0;
+                    }
+                }
+                CONTINUE => self.running = true,
+                STOP => self.running = false,
+                TIMING_CLOCK => {
+                    let Some(sync) = self.sync_in.as_mut() else {
+                        return;
+                    };
+                    if let Some(last) = sync.last_pulse {
+                        sync.intervals.push(at.duration_since(last));
+                        if sync.intervals.len() > MIDI_CLOCK_PPQN as usize {
+                            sync.intervals.remove(0);
+                        }
+                        let total: Duration = sync.intervals.iter().sum();
+                        let average = total.as_secs_f64() / sync.intervals.len() as f64;
+                        if average > 0.0 {
+                            self.bpm =
+                                (60.0 / average / MIDI_CLOCK_PPQN as f64).clamp(MIN_BPM, MAX_BPM);
+                        }
+                    }
+                    sync.last_pulse = Some(at);
+                    if !self.running {
+                        continue;
+                    }
+                    // Spread or merge pulses so the tick resolution stays at `ppqn`.
+                    let pulse = sync.pulses;
+                    sync.pulses += 1;
+                    let ppqn = self.ppqn.max(1) as u64;
+                    let ticks = (pulse + 1) * ppqn / MIDI_CLOCK_PPQN as u64
+                        - pulse * ppqn / MIDI_CLOCK_PPQN as u64;
+                    for _ in 0..ticks {
+                        fired.push(self.fire_tick());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn fire_tick(&mut self) -> ClockTick {
+        let tick = ClockTick {
+            tick: self.tick_count,
+            // `ppqn` is public and may be 0; `ClockTick::beat` divides by it.
+            ppqn: self.ppqn.max(1),
+        };
+        for callback in self.callbacks.iter_mut() {
+            callback(&tick);
+        }
+        if self.source == ClockSource::Internal && self.sync_out.is_some() {
+            // Send as many 24 PPQN pulses as fall inside this tick.
+            let ppqn = self.ppqn.max(1) as u64;
+            let t = self.tick_count;
+            let pulses =
+                (t + 1) * MIDI_CLOCK_PPQN as u64 / ppqn - t * MIDI_CLOCK_PPQN as u64 / ppqn;
+            for _ in 0..pulses {
+                self.send_sync(TIMING_CLOCK);
+            }
+        }
+        self.tick_count += 1;
+        tick
+    }
+
+    /// Sends a realtime message to the sync output, if one is connected.
+    fn send_sync(&mut self, status: u8) {
+        if let Some(conn_out) = self.sync_out.as_mut()
+            && let Err(e) = conn_out.send(&[status])
+        {
+            warn!("Failed to send MIDI clock message {:#04x}: {}", status, e);
+        }
+    }
+}
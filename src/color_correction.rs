@@ -0,0 +1,105 @@
+//! Gamma and contrast correction for the display panel.
+
+use serde::{Deserialize, Serialize};
+
+/// Corrects the colors sent to the display, so a UI looks the same on the
+/// hardware as on a desktop monitor or in the emulator.
+///
+/// Set per install in the `AppConfig`, e.g. `color_correction: Some((gamma: 1.2, contrast: 0.9))`.
+/// The default changes nothing.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ColorCorrection {
+    /// Above 1.0 lifts dark colors, below 1.0 darkens them.
+    pub gamma: f32,
+    /// Scales each channel's distance from mid-grey: below 1.0 flattens,
+    /// above 1.0 adds contrast.
+    pub contrast: f32,
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            contrast: 1.0,
+        }
+    }
+}
+
+impl ColorCorrection {
+    pub fn new(gamma: f32, contrast: f32) -> Self {
+        Self { gamma, contrast }
+    }
+
+    /// Corrects a channel value between 0.0 and 1.0.
+    pub fn correct(&self, value: f32) -> f32 {
+        let value = value.powf(1.0 / self.gamma.max(0.01));
+        ((value - 0.5) * self.contrast + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+/// A `ColorCorrection` looked up per channel of a Bgr565 pixel.
+#[derive(Debug, Clone)]
+pub(crate) struct ColorLut {
+    /// Each channel's corrected values, already shifted into place.
+    red: [u16; 32],
+    green: [u16; 64],
+    blue: [u16; 32],
+}
+
+impl ColorLut {
+    pub(crate) fn new(correction: &ColorCorrection) -> Self {
+        let channel = |max: u16, shift: u16| {
+            move |value: usize| {
+                let corrected = correction.correct(value as f32 / max as f32);
+                ((corrected * max as f32).round() as u16) << shift
+            }
+        };
+        Self {
+            red: std::array::from_fn(channel(31, 0)),
+            green: std::array::from_fn(channel(63, 5)),
+            blue: std::array::from_fn(channel(31, 11)),
+        }
+    }
+
+    pub(crate) fn apply(&self, pixel: u16) -> u16 {
+        self.red[(pixel & 0x1f) as usize]
+            | self.green[((pixel >> 5) & 0x3f) as usize]
+            | self.blue[(pixel >> 11) as usize]
+    }
+
+    /// Corrects `frame` into `out`, which is resized to fit.
+    pub(crate) fn apply_frame(&self, frame: &[u16], out: &mut Vec<u16>) {
+        out.clear();
+        out.extend(frame.iter().map(|pixel| self.apply(*pixel)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_changes_nothing() {
+        let lut = ColorLut::new(&ColorCorrection::default());
+        for pixel in [0x0000, 0xffff, 0xf800, 0x07e0, 0x001f, 0x1234] {
+            assert_eq!(lut.apply(pixel), pixel);
+        }
+    }
+
+    #[test]
+    fn gamma_lifts_dark_colors() {
+        let lut = ColorLut::new(&ColorCorrection::new(2.0, 1.0));
+        // Red at 4 of 31.
+        assert!(lut.apply(0x0004) > 0x0004);
+        assert_eq!(lut.apply(0x0000), 0x0000);
+        assert_eq!(lut.apply(0xffff), 0xffff);
+    }
+
+    #[test]
+    fn low_contrast_pulls_toward_grey() {
+        let lut = ColorLut::new(&ColorCorrection::new(1.0, 0.5));
+        assert_eq!(lut.apply(0x001f) & 0x1f, 23);
+        assert_eq!(lut.apply(0x0000) & 0x1f, 8);
+    }
+}
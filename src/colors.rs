@@ -159,3 +159,125 @@ pub const LIME_PALE: u8 = 124;
 pub const GREEN_PALE2: u8 = 125;
 pub const CYAN_PALE: u8 = 126;
 pub const BLUE_PALE: u8 = 127;
+
+/// The approximate hue (degrees), saturation and value (0.0-1.0) of a palette color.
+///
+/// Colors 22-114 sweep from yellow to red in 10° steps, each hue in a low,
+/// normal and bright version.
+pub fn palette_hsv(index: u8) -> (f32, f32, f32) {
+    const LEVELS: [f32; 3] = [0.35, 0.65, 1.0];
+    match index {
+        BLACK => (0.0, 0.0, 0.0),
+        PINK => (330.0, 0.5, 1.0),
+        RED => (0.0, 1.0, 1.0),
+        ORANGE | ORANGE2 => (30.0, 1.0, 1.0),
+        BROWN_PALE => (30.0, 0.5, 0.6),
+        BROWN => (30.0, 0.8, 0.4),
+        YELLOW_PALE => (60.0, 0.5, 1.0),
+        YELLOW => (60.0, 1.0, 1.0),
+        GREEN_LIME => (90.0, 1.0, 1.0),
+        GREEN_LIGHT => (120.0, 0.6, 1.0),
+        GREEN => (120.0, 1.0, 1.0),
+        GREEN_TURTLE => (150.0, 0.8, 0.6),
+        GREEN_PALE => (120.0, 0.4, 1.0),
+        TURQUOISE_PALE => (175.0, 0.4, 1.0),
+        TURQUOISE => (175.0, 1.0, 1.0),
+        BLUE_SKY => (200.0, 1.0, 1.0),
+        PURPLE_PALE => (270.0, 0.4, 1.0),
+        PURPLE_BLUE => (255.0, 1.0, 1.0),
+        PURPLE => (280.0, 1.0, 1.0),
+        BLUE_SKY_DARK => (200.0, 1.0, 0.5),
+        YELLOW_AMBER_BRIGHT => (45.0, 1.0, 1.0),
+        YELLOW_LOW..=RED_BRIGHT => {
+            let step = index - YELLOW_LOW;
+            let hue = (60.0 + (step / 3) as f32 * 10.0) % 360.0;
+            (hue, 1.0, LEVELS[(step % 3) as usize])
+        }
+        WARM_WHITE_LOW..=WARM_WHITE_BRIGHT => {
+            (40.0, 0.2, LEVELS[(index - WARM_WHITE_LOW) as usize])
+        }
+        WHITE_LOW => (0.0, 0.0, 0.5),
+        WHITE_BRIGHT => (0.0, 0.0, 1.0),
+        ORANGE_LOW..=ORANGE_BRIGHT => (30.0, 1.0, LEVELS[(index - ORANGE_LOW) as usize]),
+        YELLOW_PALE2 => (60.0, 0.4, 1.0),
+        LIME_PALE => (90.0, 0.4, 1.0),
+        GREEN_PALE2 => (120.0, 0.4, 1.0),
+        CYAN_PALE => (180.0, 0.4, 1.0),
+        _ => (240.0, 0.4, 1.0),
+    }
+}
+
+/// Converts hue (degrees), saturation and value (0.0-1.0) into red, green and blue (0.0-1.0).
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    [r + m, g + m, b + m]
+}
+
+/// The approximate red, green and blue (0.0-1.0) of a palette color.
+pub fn palette_rgb(index: u8) -> [f32; 3] {
+    let (h, s, v) = palette_hsv(index);
+    hsv_to_rgb(h, s, v)
+}
+
+fn nearest_index(rgb: [f32; 3]) -> u8 {
+    let distance = |index: u8| {
+        let other = palette_rgb(index);
+        (0..3).map(|i| (rgb[i] - other[i]).powi(2)).sum::<f32>()
+    };
+    (0..128u8)
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        .unwrap_or(BLACK)
+}
+
+/// The palette color closest to a hue (degrees), saturation and value (0.0-1.0).
+pub fn hsv_to_nearest_index(h: f32, s: f32, v: f32) -> u8 {
+    nearest_index(hsv_to_rgb(h, s, v))
+}
+
+/// The palette color closest to `t` (0.0-1.0) of the way from `from` to `to`.
+pub fn blend(from: u8, to: u8, t: f32) -> u8 {
+    match t {
+        t if t <= 0.0 => from,
+        t if t >= 1.0 => to,
+        t => {
+            let start = palette_rgb(from);
+            let end = palette_rgb(to);
+            nearest_index([0, 1, 2].map(|i| start[i] + (end[i] - start[i]) * t))
+        }
+    }
+}
+
+/// The palette color closest to `color` at half brightness.
+pub fn dimmed(color: u8) -> u8 {
+    let (h, s, v) = palette_hsv(color);
+    hsv_to_nearest_index(h, s, v * 0.5)
+}
+
+/// `steps` palette colors fading from `from` to `to`, both included.
+pub fn gradient(from: u8, to: u8, steps: usize) -> Vec<u8> {
+    let start = palette_rgb(from);
+    let end = palette_rgb(to);
+    (0..steps)
+        .map(|step| match step {
+            0 => from,
+            _ if step == steps - 1 => to,
+            _ => {
+                let t = step as f32 / (steps - 1) as f32;
+                nearest_index([0, 1, 2].map(|i| start[i] + (end[i] - start[i]) * t))
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,238 @@
+//! Applies changes to the config files while the device is running.
+
+use crate::app_config::{ConfigFile, PortSelectionPolicy, config_dir};
+use crate::midi_handler::MidiHandler;
+use crate::midi_parser::MidiParser;
+use crate::{AppConfig, Push2, Push2Error, Push2Event};
+use log::{info, warn};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long a file has to stay unchanged before it is reloaded, as editors
+/// often write a file in several steps.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+#[derive(Error, Debug)]
+pub enum ConfigWatchError {
+    #[error("Could not watch the config directory: {0}")]
+    Notify(#[from] notify::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+}
+
+/// Watches the config directory for changes to the files in `ConfigFile`.
+pub struct ConfigWatcher {
+    dir: PathBuf,
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<notify::Event>>,
+    /// Changed files waiting for the writes to settle.
+    changed: Vec<ConfigFile>,
+    last_change: Option<Instant>,
+    settled: VecDeque<ConfigFile>,
+}
+
+impl ConfigWatcher {
+    /// Watches `dir` and its "themes" and "actions" folders.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, ConfigWatchError> {
+        let dir = dir.into();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(&dir, RecursiveMode::Recursive)?;
+        info!("Watching {:?} for config changes", dir);
+        Ok(Self {
+            dir,
+            _watcher: watcher,
+            rx,
+            changed: Vec::new(),
+            last_change: None,
+            settled: VecDeque::new(),
+        })
+    }
+
+    /// Watches the push2 config directory.
+    pub fn in_config_dir() -> Result<Self, ConfigWatchError> {
+        Self::new(config_dir().ok_or(ConfigWatchError::NoConfigDir)?)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The next config file that changed and has not been written to since.
+    /// Non-blocking.
+    pub fn next_change(&mut self) -> Option<ConfigFile> {
+        self.next_change_at(Instant::now())
+    }
+
+    fn next_change_at(&mut self, now: Instant) -> Option<ConfigFile> {
+        while let Ok(result) = self.rx.try_recv() {
+            let event = match result {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Config watch error: {}", e);
+                    continue;
+                }
+            };
+            if event.kind.is_access() || event.kind.is_remove() {
+                continue;
+            }
+            for path in &event.paths {
+                self.changed_path(path, now);
+            }
+        }
+        if self
+            .last_change
+            .is_some_and(|last| now.saturating_duration_since(last) >= SETTLE_TIME)
+        {
+            self.last_change = None;
+            self.settled.extend(self.changed.drain(..));
+        }
+        self.settled.pop_front()
+    }
+
+    fn changed_path(&mut self, path: &Path, now: Instant) {
+        let Some(file) = path
+            .strip_prefix(&self.dir)
+            .ok()
+            .and_then(ConfigFile::from_path)
+        else {
+            return;
+        };
+        if !self.changed.contains(&file) {
+            self.changed.push(file);
+        }
+        self.last_change = Some(now);
+    }
+}
+
+impl Push2 {
+    /// Watches the directory of the `AppConfig` (or the push2 config
+    /// directory) and applies the changed files as `poll_event` is called,
+    /// reporting each with a `Push2Event::ConfigReloaded`.
+    pub fn watch_config(&mut self) -> Result<(), ConfigWatchError> {
+        let dir = self
+            .app_config
+            .as_ref()
+            .and_then(|config| config.path.as_ref()?.parent().map(Path::to_path_buf))
+            .or_else(config_dir)
+            .ok_or(ConfigWatchError::NoConfigDir)?;
+        self.config_watcher = Some(ConfigWatcher::new(dir)?);
+        Ok(())
+    }
+
+    /// Applies the next changed config file. A file that fails to load is
+    /// logged and the running config is kept.
+    pub(crate) fn poll_config(&mut self) -> Option<Push2Event> {
+        loop {
+            let watcher = self.config_watcher.as_mut()?;
+            let file = watcher.next_change()?;
+            let path = watcher.dir().join(file.path());
+            match self.reload_config(&file, &path) {
+                Ok(()) => {
+                    info!("Reloaded {:?}", path);
+                    return Some(Push2Event::ConfigReloaded { file });
+                }
+                Err(e) => warn!(
+                    "Keeping the running config, {:?} failed to load: {}",
+                    path, e
+                ),
+            }
+        }
+    }
+
+    /// Applies a config file that changed on disk.
+    pub fn reload_config(&mut self, file: &ConfigFile, path: &Path) -> Result<(), Push2Error> {
+        match file {
+            ConfigFile::AppConfig => {
+                let mut config = AppConfig::load_from(path)?;
+                let reconnect = match &self.app_config {
+                    Some(running) => {
+                        config.headless = running.headless;
+                        running.midi_differs(&config)
+                    }
+                    None => false,
+                };
+                self.display.set_color_correction(config.color_correction);
+                if reconnect {
+                    self.reconnect_midi(&config)?;
+                }
+                self.app_config = Some(config);
+            }
+            ConfigFile::ButtonMap => {
+                self.button_map.reload(path)?;
+                // The lights are at other addresses now.
+                self.apply_state(&self.state.clone())?;
+            }
+            ConfigFile::Theme(_) | ConfigFile::Actions(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Opens the ports of `config` in place of the running ones and lights
+    /// the device again, as it may be another one. The running ports stay
+    /// open if the new ones fail, and a reload never asks on stdin.
+    fn reconnect_midi(&mut self, config: &AppConfig) -> Result<(), Push2Error> {
+        info!("MIDI ports changed, reconnecting");
+        let mut config = config.clone();
+        config.port_selection = config.effective_port_selection();
+        if config.port_selection == PortSelectionPolicy::Interactive {
+            config.port_selection = PortSelectionPolicy::Fail;
+        }
+        let (tx, rx) = mpsc::channel();
+        let MidiHandler {
+            _conn_in,
+            conn_out,
+            _route_conns,
+            ..
+        } = MidiHandler::new(&config, tx)?;
+        self.midi_out = Box::new(conn_out);
+        self._conn_in = Some(_conn_in);
+        self._route_conns = _route_conns;
+        self.event_rx = rx;
+        self.parser = MidiParser::new();
+        self.apply_state(&self.state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_config_files() {
+        let file = |path: &str| ConfigFile::from_path(Path::new(path));
+        assert_eq!(file("app_config.ron"), Some(ConfigFile::AppConfig));
+        assert_eq!(file("button_map.ron"), Some(ConfigFile::ButtonMap));
+        assert_eq!(
+            file("themes/ocean.ron"),
+            Some(ConfigFile::Theme("ocean".to_string()))
+        );
+        assert_eq!(
+            file("actions/keyboard.ron"),
+            Some(ConfigFile::Actions("keyboard".to_string()))
+        );
+        assert_eq!(file("app_config.ron~"), None);
+        assert_eq!(file("scripts/toggle_pads.rhai"), None);
+        assert_eq!(file("themes/old/ocean.ron"), None);
+        let theme = ConfigFile::Theme("ocean".to_string());
+        assert_eq!(ConfigFile::from_path(&theme.path()), Some(theme));
+    }
+
+    #[test]
+    fn waits_for_writes_to_settle() {
+        let dir = std::env::temp_dir().join("push2_config_watch_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut watcher = ConfigWatcher::new(&dir).unwrap();
+        let start = Instant::now();
+        watcher.changed_path(&dir.join("button_map.ron"), start);
+        watcher.changed_path(&dir.join("button_map.ron"), start);
+        assert_eq!(watcher.next_change_at(start), None);
+        let later = start + SETTLE_TIME;
+        assert_eq!(watcher.next_change_at(later), Some(ConfigFile::ButtonMap));
+        assert_eq!(watcher.next_change_at(later), None);
+    }
+}
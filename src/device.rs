@@ -0,0 +1,220 @@
+use crate::Push2Error;
+use crate::backend::{Backend, BackendParts, MidiOut, NullDisplay};
+use crate::midi_handler::MidiHandler;
+use midir::{MidiInputConnection, MidiOutputConnection, SendError};
+use std::ops::RangeInclusive;
+use std::sync::mpsc;
+
+/// The number of characters of a Push 1 display line.
+pub const PUSH1_LINE_LENGTH: usize = 68;
+/// The number of lines of the Push 1 display.
+pub const PUSH1_LINE_COUNT: usize = 4;
+
+/// The hardware specifics that differ between Push generations.
+///
+/// `Push2` sends every pad color and button light through the device, so
+/// apps using the high-level event/LED API run unchanged on both.
+pub trait PushDevice: Send {
+    fn name(&self) -> &str;
+
+    /// The notes sent by the 64 pads.
+    fn pad_notes(&self) -> RangeInclusive<u8> {
+        36..=99
+    }
+
+    /// Converts a `Push2Colors` pad color into the device's palette.
+    fn pad_color(&self, color: u8) -> u8 {
+        color
+    }
+
+    /// Converts a `Push2Colors` button light into the device's LED values.
+    fn button_light(&self, light: u8) -> u8 {
+        light
+    }
+
+    /// True if the device has the 960x160 pixel display.
+    fn has_pixel_display(&self) -> bool {
+        true
+    }
+
+    /// The number of lines of a character display (0 = none).
+    fn text_line_count(&self) -> usize {
+        0
+    }
+
+    /// The MIDI messages writing a line of a character display.
+    /// Devices without one return nothing.
+    fn text_line_messages(&self, _line: usize, _text: &str) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
+}
+
+/// The Ableton Push 2.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Push2Model;
+
+impl PushDevice for Push2Model {
+    fn name(&self) -> &str {
+        "Ableton Push 2"
+    }
+}
+
+/// The original Ableton Push (2013).
+///
+/// Pads, buttons and encoders use the same addresses as the Push 2. The
+/// display shows 4 lines of 68 characters, written over SysEx, and the pad
+/// palette is the one shared with the Launchpad.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Push1Model;
+
+/// The palette family (light, full, dim, dark) of every hue group of
+/// `Push2Colors` from YELLOW_LOW to RED_BRIGHT, three colors per group.
+const LIVE_PALETTE_HUES: [u8; 31] = [
+    12, 16, 16, 16, 20, 20, 20, 24, 24, 28, 32, 32, 36, 36, 40, 40, 44, 44, 44, 48, 48, 48, 52, 52,
+    52, 56, 56, 56, 4, 4, 4,
+];
+
+/// Converts a `Push2Colors` color into the palette shared by the Push 1
+/// and the Novation Launchpads.
+pub fn live_palette_color(color: u8) -> u8 {
+    match color {
+        0 => 0,
+        1 => 57,
+        2 => 5,
+        3 | 4 => 9,
+        5 | 6 => 11,
+        7 => 12,
+        8 => 13,
+        9 => 17,
+        10 | 13 => 20,
+        11 | 12 => 21,
+        14 => 32,
+        15 => 33,
+        16 => 41,
+        17 => 48,
+        18 | 19 => 49,
+        20 => 43,
+        21 => 9,
+        // Each hue group has a low, normal and bright shade.
+        22..=114 => {
+            let group = (color - 22) / 3;
+            let shade = (color - 22) % 3;
+            LIVE_PALETTE_HUES[group as usize] + if shade == 0 { 2 } else { 1 }
+        }
+        115 | 118 => 1,
+        116 => 2,
+        117 | 119 => 3,
+        120 => 10,
+        121 | 122 => 9,
+        123 => 12,
+        124 => 16,
+        125 => 20,
+        126 => 36,
+        _ => 44,
+    }
+}
+
+impl PushDevice for Push1Model {
+    fn name(&self) -> &str {
+        "Ableton Push"
+    }
+
+    fn pad_color(&self, color: u8) -> u8 {
+        live_palette_color(color)
+    }
+
+    /// Push 1 buttons are single-color: 0 is off, 1 dim and 4 fully lit.
+    fn button_light(&self, light: u8) -> u8 {
+        match light {
+            0 => 0,
+            crate::colors::WHITE_LOW | crate::colors::WARM_WHITE_LOW => 1,
+            _ => 4,
+        }
+    }
+
+    fn has_pixel_display(&self) -> bool {
+        false
+    }
+
+    fn text_line_count(&self) -> usize {
+        PUSH1_LINE_COUNT
+    }
+
+    /// Clears the line, then writes the text padded or cut to 68 ASCII characters.
+    fn text_line_messages(&self, line: usize, text: &str) -> Vec<Vec<u8>> {
+        if line >= PUSH1_LINE_COUNT {
+            return Vec::new();
+        }
+        let line = line as u8;
+        let clear = vec![0xF0, 0x47, 0x7F, 0x15, 0x1C + line, 0x00, 0x00, 0xF7];
+        let mut write = vec![0xF0, 0x47, 0x7F, 0x15, 0x18 + line, 0x00, 0x45, 0x00];
+        write.extend(
+            text.chars()
+                .map(|c| {
+                    if c.is_ascii() && !c.is_ascii_control() {
+                        c as u8
+                    } else {
+                        b'?'
+                    }
+                })
+                .chain(std::iter::repeat(b' '))
+                .take(PUSH1_LINE_LENGTH),
+        );
+        write.push(0xF7);
+        vec![clear, write]
+    }
+}
+
+/// Connects `Push2` to an original Push over its MIDI user port.
+///
+/// ```no_run
+/// let push = push2::Push2::with_backend(push2::Push1::default())?;
+/// # Ok::<(), push2::Push2Error>(())
+/// ```
+pub struct Push1 {
+    /// A substring of the names of the user port, for input and output.
+    pub port_name: String,
+}
+
+impl Push1 {
+    pub fn new(port_name: &str) -> Self {
+        Self {
+            port_name: port_name.to_string(),
+        }
+    }
+}
+
+impl Default for Push1 {
+    fn default() -> Self {
+        Self::new("Ableton Push User Port")
+    }
+}
+
+/// The MIDI connections of a Push 1. The input connection is kept alive
+/// alongside the output, since `Push2` only holds the output.
+struct Push1Midi {
+    conn_out: MidiOutputConnection,
+    _conn_in: MidiInputConnection<()>,
+}
+
+impl MidiOut for Push1Midi {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        self.conn_out.send(message)
+    }
+}
+
+impl Backend for Push1 {
+    fn connect(self) -> Result<BackendParts, Push2Error> {
+        let (tx, rx) = mpsc::channel();
+        let _conn_in = MidiHandler::connect_input(&self.port_name, move |_stamp, message, _| {
+            let _ = tx.send(message.to_vec());
+        })?;
+        let conn_out = MidiHandler::connect_output(&self.port_name)?;
+        Ok(BackendParts {
+            display: Box::new(NullDisplay),
+            midi_out: Box::new(Push1Midi { conn_out, _conn_in }),
+            midi_in: rx,
+            device: Box::new(Push1Model),
+        })
+    }
+}
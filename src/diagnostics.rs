@@ -0,0 +1,248 @@
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::{LightTarget, PadCoord, Push2, Push2Colors, Push2Display, Push2Error};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_10X20},
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::Text,
+};
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+/// How long each light stays on while walking the LEDs.
+const WALK_STEP: Duration = Duration::from_millis(60);
+/// The colors every pad row and button is shown in.
+const TEST_COLORS: [u8; 4] = [
+    Push2Colors::RED,
+    Push2Colors::GREEN,
+    Push2Colors::BLUE,
+    Push2Colors::WHITE_BRIGHT,
+];
+
+/// A part of the hardware checked by `Push2::run_diagnostics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticRegion {
+    /// A row of pads, 0 being the top row.
+    PadRow(u8),
+    Buttons,
+    DisplayGradient,
+    DisplayGrid,
+}
+
+/// The outcome of checking one region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticResult {
+    pub region: DiagnosticRegion,
+    /// Whether the user confirmed the region looked right.
+    pub confirmed: bool,
+    /// The number of MIDI messages or frames that failed to send.
+    pub send_errors: usize,
+}
+
+impl DiagnosticResult {
+    pub fn passed(&self) -> bool {
+        self.confirmed && self.send_errors == 0
+    }
+}
+
+/// Every region checked by `Push2::run_diagnostics`, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticsReport {
+    pub results: Vec<DiagnosticResult>,
+}
+
+impl DiagnosticsReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(DiagnosticResult::passed)
+    }
+
+    /// The regions that were not confirmed or had send errors.
+    pub fn failures(&self) -> impl Iterator<Item = &DiagnosticResult> {
+        self.results.iter().filter(|r| !r.passed())
+    }
+}
+
+/// Asks a yes/no question on stdin. Anything but "n" or "no" counts as yes.
+pub fn stdin_confirm(question: &str) -> bool {
+    print!("{} [Y/n] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    let answer = answer.trim().to_lowercase();
+    answer != "n" && answer != "no"
+}
+
+/// Fills the display with red, green, blue and grey gradients, dark to bright from left to right.
+pub fn draw_gradient(display: &mut Push2Display) {
+    let band_height = (DISPLAY_HEIGHT / 4) as u32;
+    for x in 0..DISPLAY_WIDTH as u32 {
+        let level = x as f32 / (DISPLAY_WIDTH - 1) as f32;
+        let r = (level * Bgr565::MAX_R as f32).round() as u8;
+        let g = (level * Bgr565::MAX_G as f32).round() as u8;
+        let b = (level * Bgr565::MAX_B as f32).round() as u8;
+        let grey = Bgr565::new(r, g, b);
+        for (band, color) in [
+            Bgr565::new(r, 0, 0),
+            Bgr565::new(0, g, 0),
+            Bgr565::new(0, 0, b),
+            grey,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            Rectangle::new(
+                Point::new(x as i32, (band as u32 * band_height) as i32),
+                Size::new(1, band_height),
+            )
+            .into_styled(PrimitiveStyle::with_fill(color))
+            .draw(display)
+            .unwrap(); // Infallible
+        }
+    }
+}
+
+/// Draws color bars, a grid every 40 pixels and the display size.
+pub fn draw_grid(display: &mut Push2Display) {
+    let bars = [
+        Bgr565::WHITE,
+        Bgr565::YELLOW,
+        Bgr565::CYAN,
+        Bgr565::GREEN,
+        Bgr565::MAGENTA,
+        Bgr565::RED,
+        Bgr565::BLUE,
+        Bgr565::BLACK,
+    ];
+    let bar_width = (DISPLAY_WIDTH / bars.len()) as u32;
+    for (i, color) in bars.iter().enumerate() {
+        Rectangle::new(
+            Point::new(i as i32 * bar_width as i32, 0),
+            Size::new(bar_width, DISPLAY_HEIGHT as u32),
+        )
+        .into_styled(PrimitiveStyle::with_fill(*color))
+        .draw(display)
+        .unwrap(); // Infallible
+    }
+    let grid = PrimitiveStyle::with_stroke(Bgr565::new(8, 16, 8), 1);
+    for x in (0..DISPLAY_WIDTH as i32).step_by(40) {
+        Rectangle::new(Point::new(x, 0), Size::new(1, DISPLAY_HEIGHT as u32))
+            .into_styled(grid)
+            .draw(display)
+            .unwrap(); // Infallible
+    }
+    for y in (0..DISPLAY_HEIGHT as i32).step_by(40) {
+        Rectangle::new(Point::new(0, y), Size::new(DISPLAY_WIDTH as u32, 1))
+            .into_styled(grid)
+            .draw(display)
+            .unwrap(); // Infallible
+    }
+    let label = format!("{}x{}", DISPLAY_WIDTH, DISPLAY_HEIGHT);
+    let style = MonoTextStyle::new(&FONT_10X20, Bgr565::WHITE);
+    Rectangle::new(Point::new(420, 65), Size::new(120, 30))
+        .into_styled(PrimitiveStyle::with_fill(Bgr565::BLACK))
+        .draw(display)
+        .unwrap(); // Infallible
+    Text::new(&label, Point::new(435, 86), style)
+        .draw(display)
+        .unwrap(); // Infallible
+}
+
+impl Push2 {
+    /// Walks every pad and button LED and shows test patterns on the display,
+    /// asking `confirm` after each region whether it looked right.
+    ///
+    /// `confirm` gets a question and returns the answer; `stdin_confirm`
+    /// asks on the terminal. All lights are turned off at the end.
+    pub fn run_diagnostics<F>(&mut self, mut confirm: F) -> Result<DiagnosticsReport, Push2Error>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut report = DiagnosticsReport::default();
+        self.reset_all_lights().into_result()?;
+
+        for y in 0..8 {
+            let row: Vec<LightTarget> = (0..8)
+                .map(|x| LightTarget::Pad(PadCoord { x, y }))
+                .collect();
+            let send_errors = self.walk_lights(&row);
+            let confirmed = confirm(&format!(
+                "Did every pad of row {} light up in red, green, blue and white?",
+                y + 1
+            ));
+            report.results.push(DiagnosticResult {
+                region: DiagnosticRegion::PadRow(y),
+                confirmed,
+                send_errors,
+            });
+        }
+
+        let buttons: Vec<LightTarget> = self
+            .button_map
+            .controls()
+            .map(|(_, name)| LightTarget::Button(name))
+            .collect();
+        let send_errors = self.walk_lights(&buttons);
+        let confirmed = confirm("Did every button light up?");
+        report.results.push(DiagnosticResult {
+            region: DiagnosticRegion::Buttons,
+            confirmed,
+            send_errors,
+        });
+
+        for region in [
+            DiagnosticRegion::DisplayGradient,
+            DiagnosticRegion::DisplayGrid,
+        ] {
+            let question = if region == DiagnosticRegion::DisplayGradient {
+                draw_gradient(&mut self.display);
+                "Does the display show smooth red, green, blue and grey gradients?"
+            } else {
+                draw_grid(&mut self.display);
+                "Does the display show 8 color bars under an even grid?"
+            };
+            let send_errors = self.display.flush().is_err() as usize;
+            let confirmed = confirm(question);
+            report.results.push(DiagnosticResult {
+                region,
+                confirmed,
+                send_errors,
+            });
+        }
+
+        self.display.clear(Bgr565::BLACK).unwrap(); // Infallible
+        self.display.flush()?;
+        self.reset_all_lights().into_result()?;
+        Ok(report)
+    }
+
+    /// Lights each target in turn, then all of them in every test color.
+    /// Returns the number of failed sends.
+    fn walk_lights(&mut self, targets: &[LightTarget]) -> usize {
+        let mut errors = 0;
+        for target in targets {
+            errors += self
+                .set_lights([(*target, Push2Colors::WHITE_BRIGHT)])
+                .failed
+                .len();
+            thread::sleep(WALK_STEP);
+            errors += self
+                .set_lights([(*target, Push2Colors::BLACK)])
+                .failed
+                .len();
+        }
+        for color in TEST_COLORS {
+            let batch = self.set_lights(targets.iter().map(|t| (*t, color)));
+            errors += batch.failed.len();
+            thread::sleep(WALK_STEP * 8);
+        }
+        errors += self
+            .set_lights(targets.iter().map(|t| (*t, Push2Colors::BLACK)))
+            .failed
+            .len();
+        errors
+    }
+}
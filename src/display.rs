@@ -3,38 +3,155 @@ use embedded_graphics_core::{
     geometry::Size,
     pixelcolor::{Bgr565, IntoStorage},
     prelude::*,
+    primitives::Rectangle,
 };
 
+use crate::backend::FrameSink;
+use crate::color_correction::ColorCorrection;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::color_correction::ColorLut;
+use crate::fps::{FpsStats, FramePacer};
+use crate::low_power::{LowPowerMode, LowPowerState};
+#[cfg(feature = "recording")]
+use crate::recording::{FrameRecorder, RecordingError};
+use embedded_graphics::draw_target::{DrawTargetExt, Translated};
+#[cfg(feature = "recording")]
+use log::warn;
+#[cfg(not(target_arch = "wasm32"))]
 use rusb::{Context, Device, DeviceDescriptor, DeviceHandle, UsbContext};
+use std::ops::{Deref, DerefMut, Range};
+use std::time::Instant;
 use thiserror::Error;
 
 pub struct Push2Display {
-    pub(crate) handle: DeviceHandle<Context>,
     pub(crate) frame_buffer: Box<[u16]>,
+    sink: Box<dyn FrameSink>,
+    low_power: Option<LowPowerState>,
+    /// Shown in place of the frame buffer while set.
+    overlay: Option<Box<[u16]>>,
+    #[cfg(feature = "recording")]
+    recorder: Option<FrameRecorder>,
+    pacer: FramePacer,
+    /// The buffer `begin_frame` draws into, kept between frames.
+    spare: Option<Box<[u16]>>,
+    /// Whether the sink holds the whole current frame, which `flush_region`
+    /// builds on. Anything that changes what the whole screen shows clears it.
+    frame_sent: bool,
+}
+
+/// A rectangular part of the display, from `Push2Display::cropped` or
+/// `clipped`. Pixels drawn outside of it are dropped.
+pub struct SubDisplay<'a> {
+    display: &'a mut Push2Display,
+    /// The part of the display, in display coordinates.
+    area: Rectangle,
+    /// Added to every point drawn.
+    offset: Point,
+}
+
+/// A frame drawn on a back buffer, from `Push2Display::begin_frame`.
+///
+/// It derefs to the display, so everything that draws on the display draws
+/// on the frame. The frame buffer underneath is only replaced, all at once,
+/// by `present`; dropping the frame without presenting it discards the drawing.
+pub struct Frame<'a> {
+    display: &'a mut Push2Display,
+    /// The frame buffer as it was before the frame began.
+    front: Option<Box<[u16]>>,
+}
+
+/// The Push 2 display hardware, written to over USB bulk transfers.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct UsbDisplay {
+    handle: DeviceHandle<Context>,
     transfer_buffer: Vec<u8>,
+    correction: Option<ColorLut>,
+    /// The frame after color correction, kept between frames.
+    corrected: Vec<u16>,
 }
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Push2DisplayError {
     #[error("Ableton Push2 Not found")]
     Push2NotFound,
 
+    #[cfg(not(target_arch = "wasm32"))]
     #[error(transparent)]
     USBError(#[from] rusb::Error),
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Could not claim USB interface {interface} of the display: {source}")]
+    Claim { interface: u8, source: rusb::Error },
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("USB transfer to endpoint {endpoint:#04x} failed: {source}")]
+    Transfer { endpoint: u8, source: rusb::Error },
+
     #[error("Failed to parse BMP image")]
     BmpParseError,
 }
 
+impl Push2DisplayError {
+    /// True if sending the frame again, or opening the display again once
+    /// it is plugged in, may work.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Push2DisplayError::Push2NotFound => true,
+            #[cfg(not(target_arch = "wasm32"))]
+            Push2DisplayError::USBError(e)
+            | Push2DisplayError::Claim { source: e, .. }
+            | Push2DisplayError::Transfer { source: e, .. } => matches!(
+                e,
+                rusb::Error::Timeout
+                    | rusb::Error::Busy
+                    | rusb::Error::Pipe
+                    | rusb::Error::Io
+                    | rusb::Error::Interrupted
+                    | rusb::Error::NoDevice
+            ),
+            Push2DisplayError::BmpParseError => false,
+        }
+    }
+
+    /// What the user can do about the error, if anything.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Push2DisplayError::Push2NotFound => {
+                Some("Check that the Push 2 is plugged in and powered on")
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Push2DisplayError::USBError(rusb::Error::Access)
+            | Push2DisplayError::Claim {
+                source: rusb::Error::Access,
+                ..
+            } => Some("Allow access to the device, e.g. with a udev rule for vendor 2982"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Push2DisplayError::Claim {
+                source: rusb::Error::Busy,
+                ..
+            } => Some("Close other programs using the display, such as Ableton Live"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Push2DisplayError::Transfer {
+                source: rusb::Error::NoDevice,
+                ..
+            } => Some("The Push 2 was unplugged; open the display again once it is back"),
+            _ => None,
+        }
+    }
+}
+
 pub const DISPLAY_WIDTH: usize = 960;
 pub const DISPLAY_HEIGHT: usize = 160;
 
-const PUSH2_BULK_EP_OUT: u8 = 0x01;
+pub(crate) const PUSH2_BULK_EP_OUT: u8 = 0x01;
 const BYTES_PER_LINE: usize = 2048; // 960 * 2 + 128 filler
-const PUSH_2_VENDOR_ID: u16 = 0x2982;
-const PUSH_2_PRODUCT_ID: u16 = 0x1967;
+/// The size of an encoded frame, as sent over USB.
+pub const FRAME_BYTES: usize = BYTES_PER_LINE * DISPLAY_HEIGHT;
+pub(crate) const PUSH_2_VENDOR_ID: u16 = 0x2982;
+pub(crate) const PUSH_2_PRODUCT_ID: u16 = 0x1967;
 
-const HEADER: [u8; 16] = [
+pub(crate) const HEADER: [u8; 16] = [
     0xff, 0xcc, 0xaa, 0x88, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
 ];
 const MASK: [u8; 4] = [0xe7, 0xf3, 0xe7, 0xff];
@@ -42,47 +159,411 @@ const MASK: [u8; 4] = [0xe7, 0xf3, 0xe7, 0xff];
 impl Push2Display {
     /// Open the Push2 display. and init the frame buffer with black.
     /// the frame buffer is not send send until you call `flush`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Result<Push2Display, Push2DisplayError> {
+        Self::with_color_correction(None)
+    }
+
+    /// Opens the Push2 display with the colors of every frame corrected for the panel.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_color_correction(
+        correction: Option<ColorCorrection>,
+    ) -> Result<Push2Display, Push2DisplayError> {
+        let mut display = UsbDisplay::open()?;
+        display.set_color_correction(correction);
+        Ok(Self::with_sink(Box::new(display)))
+    }
+
+    /// In the browser the device has to be picked by the user first; see `web::WebBackend`.
+    #[cfg(target_arch = "wasm32")]
     pub fn new() -> Result<Push2Display, Push2DisplayError> {
+        Err(Push2DisplayError::Push2NotFound)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_color_correction(
+        _correction: Option<ColorCorrection>,
+    ) -> Result<Push2Display, Push2DisplayError> {
+        Err(Push2DisplayError::Push2NotFound)
+    }
+
+    /// Creates a display whose frames are written to `sink` instead of the hardware.
+    pub fn with_sink(sink: Box<dyn FrameSink>) -> Push2Display {
+        let buffer: Box<[u16]> = vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT].into_boxed_slice();
+        Push2Display {
+            frame_buffer: buffer,
+            sink,
+            low_power: None,
+            overlay: None,
+            #[cfg(feature = "recording")]
+            recorder: None,
+            pacer: FramePacer::default(),
+            spare: None,
+            frame_sent: false,
+        }
+    }
+
+    /// Draws into an overlay shown by `flush` in place of the frame buffer,
+    /// such as a menu over the app. The app keeps drawing into the frame
+    /// buffer underneath, which is shown again after `clear_overlay`.
+    ///
+    /// The overlay starts as a copy of the frame buffer and keeps what was
+    /// drawn into it between calls.
+    pub fn draw_overlay<F>(&mut self, draw: F)
+    where
+        F: FnOnce(&mut Push2Display),
+    {
+        let mut overlay = self
+            .overlay
+            .take()
+            .unwrap_or_else(|| self.frame_buffer.clone());
+        std::mem::swap(&mut self.frame_buffer, &mut overlay);
+        draw(self);
+        std::mem::swap(&mut self.frame_buffer, &mut overlay);
+        self.overlay = Some(overlay);
+        self.frame_sent = false;
+    }
+
+    pub fn clear_overlay(&mut self) {
+        self.overlay = None;
+        self.frame_sent = false;
+    }
+
+    pub fn has_overlay(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    /// The frame `flush` sends: the overlay if set, the frame buffer otherwise.
+    #[cfg(feature = "mirror")]
+    pub(crate) fn visible_frame(&self) -> &[u16] {
+        self.overlay.as_deref().unwrap_or(&self.frame_buffer)
+    }
+
+    /// Switches the low power mode on with `Some`, or back to full quality with `None`.
+    pub fn set_low_power(&mut self, mode: Option<LowPowerMode>) {
+        self.low_power = mode.map(LowPowerState::new);
+        self.frame_sent = false;
+    }
+
+    pub fn low_power(&self) -> Option<LowPowerMode> {
+        self.low_power.as_ref().map(|state| state.mode)
+    }
+
+    /// Records every frame flushed from now on to a ".gif" or ".mp4" file, at
+    /// most `fps` frames per second, replacing a recording already running.
+    /// See `FrameRecorder`.
+    #[cfg(feature = "recording")]
+    pub fn start_recording(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        fps: u32,
+    ) -> Result<(), RecordingError> {
+        self.stop_recording()?;
+        self.recorder = Some(FrameRecorder::create(path, fps)?);
+        Ok(())
+    }
+
+    /// Finishes the recording and closes its file.
+    #[cfg(feature = "recording")]
+    pub fn stop_recording(&mut self) -> Result<(), RecordingError> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "recording")]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Starts drawing a frame on a back buffer holding a copy of the frame
+    /// buffer, shown once the frame is presented:
+    ///
+    /// ```ignore
+    /// let mut frame = push2.display.begin_frame();
+    /// frame.clear(Bgr565::BLACK)?;
+    /// frame.draw_encoder_bar(0, value, Bgr565::GREEN)?;
+    /// frame.present()?;
+    /// ```
+    pub fn begin_frame(&mut self) -> Frame<'_> {
+        let mut back = self
+            .spare
+            .take()
+            .unwrap_or_else(|| vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT].into_boxed_slice());
+        back.copy_from_slice(&self.frame_buffer);
+        let front = std::mem::replace(&mut self.frame_buffer, back);
+        Frame {
+            display: self,
+            front: Some(front),
+        }
+    }
+
+    /// A drawing area covering only `area`, with (0, 0) at its top-left.
+    /// Nothing drawn outside of it reaches the display, so a widget given
+    /// one needs no bounds checks of its own.
+    pub fn cropped(&mut self, area: &Rectangle) -> SubDisplay<'_> {
+        let area = area.intersection(&self.bounding_box());
+        SubDisplay {
+            display: self,
+            area,
+            offset: area.top_left,
+        }
+    }
+
+    /// A drawing area covering only `area`, keeping the display's coordinates.
+    pub fn clipped(&mut self, area: &Rectangle) -> SubDisplay<'_> {
+        let area = area.intersection(&self.bounding_box());
+        SubDisplay {
+            display: self,
+            area,
+            offset: Point::zero(),
+        }
+    }
+
+    /// The whole display with (0, 0) moved to `offset`.
+    pub fn translated(&mut self, offset: Point) -> Translated<'_, Self> {
+        DrawTargetExt::translated(self, offset)
+    }
+
+    /// The full-height column above one of the 8 track encoders (0-7), as a
+    /// `cropped` area.
+    pub fn encoder_column(&mut self, index: u8) -> SubDisplay<'_> {
+        let width = (DISPLAY_WIDTH / 8) as u32;
+        let area = Rectangle::new(
+            Point::new(index.min(7) as i32 * width as i32, 0),
+            Size::new(width, DISPLAY_HEIGHT as u32),
+        );
+        self.cropped(&area)
+    }
+
+    /// Paces `flush` to `fps` frames per second: it waits until the next
+    /// frame is due, so a loop drawing and flushing needs no sleep of its
+    /// own. 0 turns pacing off.
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.pacer.set_target_fps(fps);
+    }
+
+    /// Corrects the colors of the frames flushed from now on, e.g. after the
+    /// `AppConfig` changed. Only the hardware display is corrected.
+    pub fn set_color_correction(&mut self, correction: Option<ColorCorrection>) {
+        self.sink.set_color_correction(correction);
+        self.frame_sent = false;
+    }
+
+    pub fn target_fps(&self) -> Option<u32> {
+        self.pacer.target_fps()
+    }
+
+    /// Writes the frame buffer to the display. If no frame arrives in 2 seconds, the display is turned black
+    /// In low power mode, the frame may be skipped; see `LowPowerMode`.
+    ///
+    /// With a target set by `set_target_fps`, waits for the next frame first.
+    pub fn flush(&mut self) -> Result<FpsStats, Push2DisplayError> {
+        self.flush_lines(0..DISPLAY_HEIGHT)
+    }
+
+    /// Like `flush`, for a frame where only `area` changed: sinks that can
+    /// take single lines only get the lines it covers (see
+    /// `FrameSink::write_lines`).
+    ///
+    /// This saves no USB bandwidth: the Push 2 only takes whole frames, so
+    /// it is still sent the full frame. Only the CPU time spent encoding the
+    /// other lines is saved. An empty `area` sends the last frame again.
+    ///
+    /// The whole frame is flushed until one was sent, and in low power mode.
+    pub fn flush_region(&mut self, area: Rectangle) -> Result<FpsStats, Push2DisplayError> {
+        let top = area.top_left.y.clamp(0, DISPLAY_HEIGHT as i32);
+        let bottom = area
+            .top_left
+            .y
+            .saturating_add(area.size.height as i32)
+            .clamp(top, DISPLAY_HEIGHT as i32);
+        self.flush_lines(top as usize..bottom as usize)
+    }
+
+    fn flush_lines(&mut self, lines: Range<usize>) -> Result<FpsStats, Push2DisplayError> {
+        self.pacer.wait();
+        let started = Instant::now();
+        let frame = self.overlay.as_deref().unwrap_or(&self.frame_buffer);
+        #[cfg(feature = "recording")]
+        if let Some(recorder) = &mut self.recorder
+            && let Err(e) = recorder.push(frame)
+        {
+            warn!("Recording stopped: {}", e);
+            self.recorder = None;
+        }
+        match &mut self.low_power {
+            Some(state) => {
+                if let Some(frame) = state.prepare(frame) {
+                    self.sink.write_frame(frame)?;
+                }
+            }
+            None if lines == (0..DISPLAY_HEIGHT) || !self.frame_sent => {
+                self.sink.write_frame(frame)?;
+                self.frame_sent = true;
+            }
+            None => self.sink.write_lines(frame, lines)?,
+        }
+        Ok(self.pacer.record(started, started.elapsed()))
+    }
+}
+
+impl Frame<'_> {
+    /// Makes the drawing the frame buffer and flushes it.
+    pub fn present(mut self) -> Result<FpsStats, Push2DisplayError> {
+        self.display.spare = self.front.take();
+        self.display.flush()
+    }
+}
+
+impl Drop for Frame<'_> {
+    fn drop(&mut self) {
+        if let Some(front) = self.front.take() {
+            let back = std::mem::replace(&mut self.display.frame_buffer, front);
+            self.display.spare = Some(back);
+        }
+    }
+}
+
+impl Deref for Frame<'_> {
+    type Target = Push2Display;
+
+    fn deref(&self) -> &Push2Display {
+        self.display
+    }
+}
+
+impl DerefMut for Frame<'_> {
+    fn deref_mut(&mut self) -> &mut Push2Display {
+        self.display
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl UsbDisplay {
+    pub fn open() -> Result<UsbDisplay, Push2DisplayError> {
         let mut context = Context::new()?;
         let (_, _, handle) = open_device(&mut context, PUSH_2_VENDOR_ID, PUSH_2_PRODUCT_ID)
             .ok_or(Push2DisplayError::Push2NotFound)?;
 
-        handle.claim_interface(0)?;
-        let buffer: Box<[u16]> = vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT].into_boxed_slice();
-        let transfer_buffer = vec![0u8; BYTES_PER_LINE * DISPLAY_HEIGHT];
+        handle
+            .claim_interface(0)
+            .map_err(|source| Push2DisplayError::Claim {
+                interface: 0,
+                source,
+            })?;
+        let transfer_buffer = vec![0u8; FRAME_BYTES];
 
-        Ok(Push2Display {
+        Ok(UsbDisplay {
             handle,
-            frame_buffer: buffer,
             transfer_buffer,
+            correction: None,
+            corrected: Vec::new(),
         })
     }
 
-    /// Writes the frame buffer to the display. If no frame arrives in 2 seconds, the display is turned black
-    pub fn flush(&mut self) -> Result<(), Push2DisplayError> {
+    fn send_transfer_buffer(&mut self) -> Result<(), Push2DisplayError> {
         use std::time::Duration;
         let timeout = Duration::from_secs(1);
-        self.update_transfer_buffer();
-
+        let transfer = |source| Push2DisplayError::Transfer {
+            endpoint: PUSH2_BULK_EP_OUT,
+            source,
+        };
         self.handle
-            .write_bulk(PUSH2_BULK_EP_OUT, &HEADER, timeout)?;
+            .write_bulk(PUSH2_BULK_EP_OUT, &HEADER, timeout)
+            .map_err(transfer)?;
         self.handle
-            .write_bulk(PUSH2_BULK_EP_OUT, &self.transfer_buffer, timeout)?;
-
+            .write_bulk(PUSH2_BULK_EP_OUT, &self.transfer_buffer, timeout)
+            .map_err(transfer)?;
         Ok(())
     }
+}
+
+/// Encodes a 960x160 frame into the masked, padded line format of the Push 2 display.
+/// `buffer` must hold `FRAME_BYTES` bytes.
+///
+/// With the `parallel` feature the lines are encoded on the rayon thread pool.
+pub fn encode_frame(frame: &[u16], buffer: &mut [u8]) {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        buffer[..FRAME_BYTES]
+            .par_chunks_exact_mut(BYTES_PER_LINE)
+            .zip(frame.par_chunks_exact(DISPLAY_WIDTH))
+            // Lines are small: hand them out in batches.
+            .with_min_len(16)
+            .for_each(|(out, line)| encode_line(line, out));
+    }
+    #[cfg(not(feature = "parallel"))]
+    for (out, line) in buffer
+        .chunks_exact_mut(BYTES_PER_LINE)
+        .zip(frame.chunks_exact(DISPLAY_WIDTH))
+    {
+        encode_line(line, out);
+    }
+}
 
-    fn update_transfer_buffer(&mut self) {
-        for r in 0..DISPLAY_HEIGHT {
-            for c in 0..DISPLAY_WIDTH {
-                let i = r * DISPLAY_WIDTH + c;
-                let b: [u8; 2] = u16::to_le_bytes(self.frame_buffer[i]);
-                let di = r * BYTES_PER_LINE + c * 2;
+/// Encodes only `lines` of a frame into the matching lines of `buffer`.
+pub(crate) fn encode_lines(frame: &[u16], buffer: &mut [u8], lines: Range<usize>) {
+    for line in lines.start..lines.end.min(DISPLAY_HEIGHT) {
+        encode_line(
+            &frame[line * DISPLAY_WIDTH..(line + 1) * DISPLAY_WIDTH],
+            &mut buffer[line * BYTES_PER_LINE..(line + 1) * BYTES_PER_LINE],
+        );
+    }
+}
 
-                self.transfer_buffer[di] = b[0] ^ MASK[di % 4];
-                self.transfer_buffer[di + 1] = b[1] ^ MASK[(di + 1) % 4];
+/// Encodes one line four pixels at a time: lines start on a multiple of 4
+/// bytes, so the XOR mask lines up with every 8-byte word.
+fn encode_line(line: &[u16], out: &mut [u8]) {
+    let mask = u64::from_le_bytes([
+        MASK[0], MASK[1], MASK[2], MASK[3], MASK[0], MASK[1], MASK[2], MASK[3],
+    ]);
+    for (pixels, bytes) in line.chunks_exact(4).zip(out.chunks_exact_mut(8)) {
+        let word = pixels[0] as u64
+            | (pixels[1] as u64) << 16
+            | (pixels[2] as u64) << 32
+            | (pixels[3] as u64) << 48;
+        bytes.copy_from_slice(&(word ^ mask).to_le_bytes());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FrameSink for UsbDisplay {
+    fn write_frame(&mut self, frame: &[u16]) -> Result<(), Push2DisplayError> {
+        let frame = match &self.correction {
+            Some(lut) => {
+                lut.apply_frame(frame, &mut self.corrected);
+                &self.corrected
             }
-        }
+            None => frame,
+        };
+        encode_frame(frame, &mut self.transfer_buffer);
+        self.send_transfer_buffer()
+    }
+
+    /// `None` sends the frames as drawn.
+    fn set_color_correction(&mut self, correction: Option<ColorCorrection>) {
+        self.correction = correction.map(|c| ColorLut::new(&c));
+    }
+
+    /// The transfer buffer keeps the other lines from the last frame.
+    fn write_lines(&mut self, frame: &[u16], lines: Range<usize>) -> Result<(), Push2DisplayError> {
+        let frame = match &self.correction {
+            Some(lut) => {
+                let pixels =
+                    lines.start * DISPLAY_WIDTH..lines.end.min(DISPLAY_HEIGHT) * DISPLAY_WIDTH;
+                self.corrected.resize(frame.len(), 0);
+                for i in pixels {
+                    self.corrected[i] = lut.apply(frame[i]);
+                }
+                &self.corrected
+            }
+            None => frame,
+        };
+        encode_lines(frame, &mut self.transfer_buffer, lines);
+        self.send_transfer_buffer()
     }
 }
 
@@ -105,12 +586,37 @@ impl DrawTarget for Push2Display {
     }
 }
 
+impl DrawTarget for SubDisplay<'_> {
+    type Color = Bgr565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (area, offset) = (self.area, self.offset);
+        self.display.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point + offset, color))
+                .filter(|Pixel(point, _)| area.contains(*point)),
+        )
+    }
+}
+
+impl Dimensions for SubDisplay<'_> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.area.top_left - self.offset, self.area.size)
+    }
+}
+
 impl OriginDimensions for Push2Display {
     fn size(&self) -> Size {
         Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32)
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn open_device<T: UsbContext>(
     context: &mut T,
     vid: u16,
@@ -137,3 +643,152 @@ fn open_device<T: UsbContext>(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_frame_masks_each_byte() {
+        let frame: Vec<u16> = (0..DISPLAY_WIDTH * DISPLAY_HEIGHT)
+            .map(|i| (i * 7919) as u16)
+            .collect();
+        let mut buffer = vec![0u8; FRAME_BYTES];
+        encode_frame(&frame, &mut buffer);
+        for r in [0, 1, 80, DISPLAY_HEIGHT - 1] {
+            for c in [0, 1, 2, 3, 477, DISPLAY_WIDTH - 1] {
+                let b = frame[r * DISPLAY_WIDTH + c].to_le_bytes();
+                let di = r * BYTES_PER_LINE + c * 2;
+                assert_eq!(buffer[di], b[0] ^ MASK[di % 4]);
+                assert_eq!(buffer[di + 1], b[1] ^ MASK[(di + 1) % 4]);
+            }
+        }
+        // The filler at the end of each line is left alone.
+        assert_eq!(buffer[BYTES_PER_LINE - 1], 0);
+    }
+    #[test]
+    fn frames_replace_the_buffer_when_presented() {
+        use crate::backend::NullDisplay;
+        use embedded_graphics_core::pixelcolor::RgbColor;
+
+        let mut display = Push2Display::with_sink(Box::new(NullDisplay));
+        let mut frame = display.begin_frame();
+        frame.clear(Bgr565::RED).unwrap();
+        drop(frame);
+        assert_eq!(display.frame_buffer[0], 0);
+
+        let mut frame = display.begin_frame();
+        frame.clear(Bgr565::RED).unwrap();
+        frame.present().unwrap();
+        assert_eq!(display.frame_buffer[0], Bgr565::RED.into_storage());
+        // The next frame starts from the presented one.
+        let frame = display.begin_frame();
+        assert_eq!(frame.frame_buffer[0], Bgr565::RED.into_storage());
+    }
+
+    /// Records the lines of each write.
+    struct LineSink(std::sync::Arc<std::sync::Mutex<Vec<Range<usize>>>>);
+
+    impl FrameSink for LineSink {
+        fn write_frame(&mut self, _frame: &[u16]) -> Result<(), Push2DisplayError> {
+            self.0.lock().unwrap().push(0..DISPLAY_HEIGHT);
+            Ok(())
+        }
+        fn write_lines(
+            &mut self,
+            _frame: &[u16],
+            lines: Range<usize>,
+        ) -> Result<(), Push2DisplayError> {
+            self.0.lock().unwrap().push(lines);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn flush_region_sends_the_covered_lines() {
+        use std::sync::{Arc, Mutex};
+
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let mut display = Push2Display::with_sink(Box::new(LineSink(written.clone())));
+        // The first flush sends the whole frame.
+        display.flush_region(Rectangle::zero()).unwrap();
+        display
+            .flush_region(Rectangle::new(Point::new(120, 20), Size::new(120, 30)))
+            .unwrap();
+        display
+            .flush_region(Rectangle::new(Point::new(0, 150), Size::new(10, 40)))
+            .unwrap();
+        display.flush_region(Rectangle::zero()).unwrap();
+        display.flush().unwrap();
+        assert_eq!(
+            *written.lock().unwrap(),
+            [0..160, 20..50, 150..160, 0..0, 0..160]
+        );
+    }
+
+    #[test]
+    fn flush_region_resends_the_frame_after_a_whole_screen_change() {
+        use std::sync::{Arc, Mutex};
+
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let mut display = Push2Display::with_sink(Box::new(LineSink(written.clone())));
+        let region = Rectangle::new(Point::new(0, 20), Size::new(10, 30));
+        display.flush_region(region).unwrap();
+        display.flush_region(region).unwrap();
+        display.draw_overlay(|_| {});
+        display.flush_region(region).unwrap();
+        display.clear_overlay();
+        display.flush_region(region).unwrap();
+        display.set_low_power(None);
+        display.flush_region(region).unwrap();
+        display.set_color_correction(None);
+        display.flush_region(region).unwrap();
+        display.flush_region(region).unwrap();
+        assert_eq!(
+            *written.lock().unwrap(),
+            [0..160, 20..50, 0..160, 0..160, 0..160, 0..160, 20..50]
+        );
+    }
+
+    #[test]
+    fn cropped_areas_stay_inside() {
+        use crate::backend::NullDisplay;
+        use embedded_graphics_core::pixelcolor::RgbColor;
+
+        let mut display = Push2Display::with_sink(Box::new(NullDisplay));
+        let mut column = display.encoder_column(1);
+        assert_eq!(column.bounding_box().size, Size::new(120, 160));
+        column.clear(Bgr565::RED).unwrap();
+        Pixel(Point::new(200, 10), Bgr565::GREEN)
+            .draw(&mut column)
+            .unwrap();
+        let red = Bgr565::RED.into_storage();
+        assert_eq!(display.frame_buffer[119], 0);
+        assert_eq!(display.frame_buffer[120], red);
+        assert_eq!(display.frame_buffer[239], red);
+        assert_eq!(display.frame_buffer[240], 0);
+        assert_eq!(display.frame_buffer[10 * DISPLAY_WIDTH + 320], 0);
+    }
+
+    #[test]
+    fn encode_lines_leaves_other_lines_alone() {
+        let frame = vec![0xFFFF; DISPLAY_WIDTH * DISPLAY_HEIGHT];
+        let mut buffer = vec![0u8; FRAME_BYTES];
+        encode_lines(&frame, &mut buffer, 2..3);
+        assert_eq!(buffer[BYTES_PER_LINE], 0);
+        assert_eq!(buffer[2 * BYTES_PER_LINE], 0xFF ^ MASK[0]);
+        assert_eq!(buffer[3 * BYTES_PER_LINE], 0);
+    }
+
+    #[test]
+    fn transfer_timeouts_are_recoverable() {
+        let timeout = Push2DisplayError::Transfer {
+            endpoint: PUSH2_BULK_EP_OUT,
+            source: rusb::Error::Timeout,
+        };
+        assert!(timeout.is_recoverable());
+        assert!(timeout.to_string().contains("0x01"));
+        assert!(!Push2DisplayError::BmpParseError.is_recoverable());
+        assert!(Push2DisplayError::Push2NotFound.hint().is_some());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::button_map::decode_delta;
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::{ControlName, EncoderName, Push2Event};
+use egui::epaint::{ClippedPrimitive, Color32, ImageData, Primitive, Vertex};
+use egui::{Context, Event, Key, Modifiers, Pos2, RawInput, Rect, TextureId};
+use embedded_graphics::{
+    pixelcolor::{Bgr565, Rgb888},
+    prelude::*,
+    primitives::Rectangle,
+};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// An RGBA texture uploaded by egui.
+struct Texture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color32>,
+}
+
+impl Texture {
+    /// The texel at normalized coordinates, nearest neighbour.
+    fn sample(&self, uv: Pos2) -> Color32 {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Runs an `egui` UI on the 960x160 display.
+///
+/// egui's triangles are rasterized in software into any `DrawTarget`, such
+/// as `Push2Display`. Pass every event to `handle_event`: the arrows move
+/// between and adjust widgets, Select activates, the first track encoder
+/// moves the focus and the second one acts as left/right arrows, e.g. for
+/// sliders.
+pub struct EguiDisplay {
+    pub ctx: Context,
+    textures: HashMap<TextureId, Texture>,
+    events: Vec<Event>,
+    modifiers: Modifiers,
+    /// The frame being painted, as RGB.
+    pixels: Vec<[u8; 3]>,
+    started: Instant,
+}
+
+impl Default for EguiDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EguiDisplay {
+    pub fn new() -> Self {
+        Self {
+            ctx: Context::default(),
+            textures: HashMap::new(),
+            events: Vec::new(),
+            modifiers: Modifiers::default(),
+            pixels: vec![[0; 3]; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            started: Instant::now(),
+        }
+    }
+
+    fn press(&mut self, key: Key, modifiers: Modifiers) {
+        for pressed in [true, false] {
+            self.events.push(Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers,
+            });
+        }
+    }
+
+    /// Turns device events into egui input for the next frame.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> bool {
+        match *event {
+            Push2Event::ButtonPressed { name, .. } => {
+                let key = match name {
+                    ControlName::Up => Key::ArrowUp,
+                    ControlName::Down => Key::ArrowDown,
+                    ControlName::Left => Key::ArrowLeft,
+                    ControlName::Right => Key::ArrowRight,
+                    ControlName::Select => Key::Enter,
+                    ControlName::Shift => {
+                        self.modifiers.shift = true;
+                        return true;
+                    }
+                    _ => return false,
+                };
+                self.press(key, self.modifiers);
+                true
+            }
+            Push2Event::ButtonReleased {
+                name: ControlName::Shift,
+            } => {
+                self.modifiers.shift = false;
+                true
+            }
+            Push2Event::EncoderTwisted { name, raw_delta } => {
+                let forward = decode_delta(raw_delta) > 0;
+                match name {
+                    EncoderName::Track1 => {
+                        let modifiers = Modifiers {
+                            shift: !forward,
+                            ..self.modifiers
+                        };
+                        self.press(Key::Tab, modifiers);
+                    }
+                    EncoderName::Track2 if forward => self.press(Key::ArrowRight, self.modifiers),
+                    EncoderName::Track2 => self.press(Key::ArrowLeft, self.modifiers),
+                    _ => return false,
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs one egui frame with `ui` and draws it into `target`.
+    pub fn run<D, F>(&mut self, target: &mut D, ui: F) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Bgr565>,
+        F: FnMut(&Context),
+    {
+        let screen = Rect::from_min_size(
+            Pos2::ZERO,
+            egui::vec2(DISPLAY_WIDTH as f32, DISPLAY_HEIGHT as f32),
+        );
+        let input = RawInput {
+            screen_rect: Some(screen),
+            time: Some(self.started.elapsed().as_secs_f64()),
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            focused: true,
+            ..RawInput::default()
+        };
+        self.ctx.set_pixels_per_point(1.0);
+        let output = self.ctx.run(input, ui);
+
+        for (id, delta) in output.textures_delta.set {
+            let ImageData::Color(image) = delta.image;
+            let [width, height] = image.size;
+            match delta.pos {
+                None => {
+                    self.textures.insert(
+                        id,
+                        Texture {
+                            width,
+                            height,
+                            pixels: image.pixels.clone(),
+                        },
+                    );
+                }
+                Some([x0, y0]) => {
+                    if let Some(texture) = self.textures.get_mut(&id) {
+                        for y in 0..height {
+                            for x in 0..width {
+                                let index = (y0 + y) * texture.width + x0 + x;
+                                if let Some(texel) = texture.pixels.get_mut(index) {
+                                    *texel = image.pixels[y * width + x];
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pixels.fill([0; 3]);
+        let primitives = self.ctx.tessellate(output.shapes, 1.0);
+        for primitive in &primitives {
+            self.paint(primitive);
+        }
+        for id in output.textures_delta.free {
+            self.textures.remove(&id);
+        }
+
+        let area = Rectangle::new(
+            Point::zero(),
+            Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32),
+        );
+        target.fill_contiguous(
+            &area,
+            self.pixels
+                .iter()
+                .map(|&[r, g, b]| Bgr565::from(Rgb888::new(r, g, b))),
+        )
+    }
+
+    fn paint(&mut self, primitive: &ClippedPrimitive) {
+        let Primitive::Mesh(mesh) = &primitive.primitive else {
+            return;
+        };
+        let Some(texture) = self.textures.get(&mesh.texture_id) else {
+            return;
+        };
+        let clip = primitive.clip_rect.intersect(Rect::from_min_size(
+            Pos2::ZERO,
+            egui::vec2(DISPLAY_WIDTH as f32, DISPLAY_HEIGHT as f32),
+        ));
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            fill_triangle(&mut self.pixels, texture, clip, [a, b, c]);
+        }
+    }
+}
+
+/// Blends a textured, vertex-colored triangle into the RGB pixels.
+fn fill_triangle(pixels: &mut [[u8; 3]], texture: &Texture, clip: Rect, [a, b, c]: [&Vertex; 3]) {
+    let area = (b.pos - a.pos).x * (c.pos - a.pos).y - (b.pos - a.pos).y * (c.pos - a.pos).x;
+    if area.abs() < f32::EPSILON {
+        return;
+    }
+    let min_x = a.pos.x.min(b.pos.x).min(c.pos.x).max(clip.min.x).floor() as i32;
+    let max_x = a.pos.x.max(b.pos.x).max(c.pos.x).min(clip.max.x).ceil() as i32;
+    let min_y = a.pos.y.min(b.pos.y).min(c.pos.y).max(clip.min.y).floor() as i32;
+    let max_y = a.pos.y.max(b.pos.y).max(c.pos.y).min(clip.max.y).ceil() as i32;
+    for y in min_y.max(0)..max_y.min(DISPLAY_HEIGHT as i32) {
+        for x in min_x.max(0)..max_x.min(DISPLAY_WIDTH as i32) {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let edge = |from: Pos2, to: Pos2| {
+                ((to - from).x * (p - from).y - (to - from).y * (p - from).x) / area
+            };
+            let (wa, wb, wc) = (edge(b.pos, c.pos), edge(c.pos, a.pos), edge(a.pos, b.pos));
+            if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                continue;
+            }
+            let uv = Pos2::new(
+                a.uv.x * wa + b.uv.x * wb + c.uv.x * wc,
+                a.uv.y * wa + b.uv.y * wb + c.uv.y * wc,
+            );
+            let texel = texture.sample(uv);
+            // Premultiplied vertex color times premultiplied texel.
+            let channel = |i: usize| {
+                let vertex =
+                    a.color[i] as f32 * wa + b.color[i] as f32 * wb + c.color[i] as f32 * wc;
+                vertex * texel[i] as f32 / 255.0
+            };
+            let alpha = channel(3) / 255.0;
+            let pixel = &mut pixels[y as usize * DISPLAY_WIDTH + x as usize];
+            for (i, value) in pixel.iter_mut().enumerate() {
+                *value = (channel(i) + *value as f32 * (1.0 - alpha))
+                    .round()
+                    .min(255.0) as u8;
+            }
+        }
+    }
+}
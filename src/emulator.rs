@@ -0,0 +1,223 @@
+use crate::backend::{Backend, BackendParts, FrameSink, MidiOut};
+use crate::device::Push2Model;
+use crate::display::Push2DisplayError;
+use crate::websocket;
+use crate::{
+    ButtonMap, CONTROL_CHANGE, ControlName, EncoderName, NOTE_OFF, NOTE_ON, PadCoord, Push2Error,
+};
+use log::{info, warn};
+use midir::SendError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use thiserror::Error;
+use tungstenite::Message;
+
+/// The page served to browsers.
+const EMULATOR_PAGE: &str = include_str!("emulator.html");
+
+#[derive(Error, Debug)]
+pub enum EmulatorError {
+    #[error("Failed to start the emulator server: {0}")]
+    Bind(#[from] io::Error),
+}
+
+/// JSON messages exchanged with the emulator page, tagged by `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EmulatorMessage {
+    /// Sent once to every page: the addresses of every control.
+    Layout {
+        pads: Vec<(u8, PadCoord)>,
+        controls: Vec<(u8, ControlName)>,
+        encoders: Vec<(u8, EncoderName)>,
+    },
+    /// A raw MIDI message, in either direction.
+    Midi { data: Vec<u8> },
+}
+
+/// What a newly connected page needs to catch up with the device.
+#[derive(Default)]
+struct Shared {
+    clients: Vec<Sender<Message>>,
+    /// The last LED message of every pad and button, by (is CC, address).
+    leds: HashMap<(bool, u8), Vec<u8>>,
+    /// The last frame, as little-endian Bgr565 bytes.
+    frame: Option<Vec<u8>>,
+}
+
+impl Shared {
+    fn broadcast(&mut self, message: Message) {
+        self.clients
+            .retain(|client| client.send(message.clone()).is_ok());
+    }
+}
+
+/// A software Push 2 running in the browser.
+///
+/// `Push2::with_backend(Push2Virtual::new(addr))` serves a page at
+/// `http://<addr>/` showing the display, pads, buttons, encoders and slider.
+/// Clicking the page produces the same MIDI a real device would, so the
+/// whole crate API can be used without hardware. Several pages can be open
+/// at once.
+pub struct Push2Virtual {
+    addr: SocketAddr,
+}
+
+impl Push2Virtual {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
+impl Default for Push2Virtual {
+    /// Serves the emulator on localhost:8080.
+    fn default() -> Self {
+        Self::new(SocketAddr::from(([127, 0, 0, 1], 8080)))
+    }
+}
+
+impl Backend for Push2Virtual {
+    fn connect(self) -> Result<BackendParts, Push2Error> {
+        let listener = TcpListener::bind(self.addr).map_err(EmulatorError::from)?;
+        info!("Push 2 emulator running at http://{}/", self.addr);
+        let layout = layout_message()?;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let (midi_tx, midi_rx) = mpsc::channel();
+        let accept_shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = Arc::clone(&accept_shared);
+                let midi_tx = midi_tx.clone();
+                let layout = layout.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_connection(stream, shared, layout, midi_tx) {
+                        warn!("Emulator client disconnected: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(BackendParts {
+            display: Box::new(VirtualDisplay {
+                shared: Arc::clone(&shared),
+            }),
+            midi_out: Box::new(VirtualMidiOut { shared }),
+            midi_in: midi_rx,
+            device: Box::new(Push2Model),
+        })
+    }
+}
+
+/// Forwards pad colors and button lights to the pages.
+struct VirtualMidiOut {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl MidiOut for VirtualMidiOut {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        let text = serde_json::to_string(&EmulatorMessage::Midi {
+            data: message.to_vec(),
+        })
+        .map_err(|_| SendError::Other("failed to encode message"))?;
+        let mut shared = self
+            .shared
+            .lock()
+            .map_err(|_| SendError::Other("emulator state poisoned"))?;
+        if let [status, address, ..] = *message
+            && matches!(status & 0xF0, NOTE_ON | NOTE_OFF | CONTROL_CHANGE)
+        {
+            shared
+                .leds
+                .insert((status & 0xF0 == CONTROL_CHANGE, address), message.to_vec());
+        }
+        shared.broadcast(Message::text(text));
+        Ok(())
+    }
+}
+
+/// Forwards flushed frames to the pages, as binary messages.
+struct VirtualDisplay {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl FrameSink for VirtualDisplay {
+    fn write_frame(&mut self, frame: &[u16]) -> Result<(), Push2DisplayError> {
+        let bytes: Vec<u8> = frame.iter().flat_map(|pixel| pixel.to_le_bytes()).collect();
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.frame = Some(bytes.clone());
+            shared.broadcast(Message::binary(bytes));
+        }
+        Ok(())
+    }
+}
+
+fn layout_message() -> Result<String, Push2Error> {
+    let map = ButtonMap::new()?;
+    let message = EmulatorMessage::Layout {
+        pads: map.pads().collect(),
+        controls: map.controls().collect(),
+        encoders: map.encoders().collect(),
+    };
+    // The layout only holds plain values, so encoding cannot fail.
+    Ok(serde_json::to_string(&message).unwrap_or_default())
+}
+
+/// Serves the page to plain HTTP requests and runs WebSocket connections.
+fn serve_connection(
+    mut stream: TcpStream,
+    shared: Arc<Mutex<Shared>>,
+    layout: String,
+    midi_tx: Sender<Vec<u8>>,
+) -> Result<(), tungstenite::Error> {
+    let mut request = [0u8; 2048];
+    let len = stream.peek(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..len]).to_ascii_lowercase();
+    if !request.contains("upgrade: websocket") {
+        // Drain the request before answering so the browser sees a clean close.
+        let _ = stream.read(&mut [0u8; 2048])?;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            EMULATOR_PAGE.len(),
+            EMULATOR_PAGE
+        );
+        stream.write_all(response.as_bytes())?;
+        return Ok(());
+    }
+
+    let mut socket = websocket::accept(stream)?;
+
+    // Catch the page up with the layout, the lights and the last frame. The
+    // lock is only held to copy them, so a slow page cannot stall the device.
+    let (message_tx, message_rx) = mpsc::channel();
+    let (leds, frame) = match shared.lock() {
+        Ok(mut shared) => {
+            shared.clients.push(message_tx);
+            (
+                shared.leds.values().cloned().collect(),
+                shared.frame.clone(),
+            )
+        }
+        Err(_) => (Vec::new(), None),
+    };
+    socket.send(Message::text(layout))?;
+    for data in leds {
+        let text = serde_json::to_string(&EmulatorMessage::Midi { data }).unwrap_or_default();
+        socket.send(Message::text(text))?;
+    }
+    if let Some(frame) = frame {
+        socket.send(Message::binary(frame))?;
+    }
+
+    websocket::run_client(&mut socket, &message_rx, |text| {
+        match serde_json::from_str::<EmulatorMessage>(text) {
+            Ok(EmulatorMessage::Midi { data }) => return midi_tx.send(data).is_ok(),
+            Ok(_) => {}
+            Err(e) => warn!("Ignoring invalid emulator message: {}", e),
+        }
+        true
+    })
+}
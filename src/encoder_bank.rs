@@ -0,0 +1,131 @@
+use crate::params::{Param, ParamChange, Params};
+use crate::{ControlName, Push2, Push2Colors, Push2Error, Push2Event};
+
+/// Pages the 8 track encoders through a `Params` set with PageLeft/PageRight.
+///
+/// Only slots whose value changed (or every slot, after a page switch) are
+/// redrawn, and the page buttons are lit when there is a page to move to.
+pub struct EncoderBank {
+    pub params: Params,
+    /// Light of a page button that has a page to move to.
+    pub page_light_on: u8,
+    /// Light of a page button at the first/last page.
+    pub page_light_off: u8,
+    dirty: [bool; 8],
+}
+
+impl EncoderBank {
+    pub fn new(params: Params) -> Self {
+        Self {
+            params,
+            page_light_on: Push2Colors::WHITE_BRIGHT,
+            page_light_off: Push2Colors::BLACK,
+            dirty: [true; 8],
+        }
+    }
+
+    /// Creates a bank from a list of parameters, 8 per page in order.
+    pub fn paged(list: Vec<Param>) -> Self {
+        let mut params = Params::new();
+        for (i, param) in list.into_iter().enumerate() {
+            let id = params.add(param);
+            params.bind(i / 8, (i % 8) as u8, id);
+        }
+        Self::new(params)
+    }
+
+    pub fn page(&self) -> usize {
+        self.params.page()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.params.page_count()
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        if page != self.params.page() && page < self.params.page_count() {
+            self.params.set_page(page);
+            self.dirty = [true; 8];
+        }
+    }
+
+    pub fn next_page(&mut self) {
+        self.set_page(self.page() + 1);
+    }
+
+    pub fn previous_page(&mut self) {
+        if let Some(page) = self.page().checked_sub(1) {
+            self.set_page(page);
+        }
+    }
+
+    /// Forces every slot to be redrawn on the next `render`.
+    pub fn invalidate(&mut self) {
+        self.dirty = [true; 8];
+    }
+
+    /// Handles PageLeft/PageRight and the track encoders.
+    /// Returns the parameter change, if an encoder changed a value.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Option<ParamChange> {
+        match event {
+            Push2Event::ButtonPressed {
+                name: ControlName::PageLeft,
+                ..
+            } => {
+                self.previous_page();
+                None
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::PageRight,
+                ..
+            } => {
+                self.next_page();
+                None
+            }
+            Push2Event::EncoderTwisted { name, .. } => {
+                let change = self.params.handle_event(event)?;
+                if let Some(slot) = name.track_index() {
+                    self.dirty[slot as usize] = true;
+                }
+                Some(change)
+            }
+            _ => None,
+        }
+    }
+
+    /// Redraws changed slots and updates the page button lights.
+    /// Returns true if the display was drawn to and needs a flush.
+    pub fn render(&mut self, push2: &mut Push2) -> Result<bool, Push2Error> {
+        let mut drawn = false;
+        for slot in 0..8u8 {
+            if self.dirty[slot as usize] {
+                self.params.draw_slot(&mut push2.display, slot)?;
+                self.dirty[slot as usize] = false;
+                drawn = true;
+            }
+        }
+        let page = self.page();
+        let has_previous = page > 0;
+        let has_next = page + 1 < self.page_count();
+        self.update_light(push2, ControlName::PageLeft, has_previous)?;
+        self.update_light(push2, ControlName::PageRight, has_next)?;
+        Ok(drawn)
+    }
+
+    fn update_light(
+        &self,
+        push2: &mut Push2,
+        name: ControlName,
+        on: bool,
+    ) -> Result<(), Push2Error> {
+        let light = if on {
+            self.page_light_on
+        } else {
+            self.page_light_off
+        };
+        if push2.state.buttons.get(&name).map(|b| b.light) != Some(light) {
+            push2.set_button_light(name, light)?;
+        }
+        Ok(())
+    }
+}
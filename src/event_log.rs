@@ -0,0 +1,128 @@
+use crate::{EVENT_SCHEMA_VERSION, Push2Event};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum EventLogError {
+    #[error("Could not read or write the event log: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid event log line {line}: {source}")]
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("Failed to encode an event: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("Event log has schema version {found}, expected {EVENT_SCHEMA_VERSION}")]
+    Version { found: u32 },
+}
+
+/// The first line of an event log.
+#[derive(Serialize, Deserialize)]
+struct LogHeader {
+    schema_version: u32,
+}
+
+/// An event and when it happened, in milliseconds since recording started.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub at_ms: u64,
+    pub event: Push2Event,
+}
+
+/// Writes events as JSON lines, after a header line with the schema version.
+pub struct EventRecorder<W: Write> {
+    writer: W,
+    started: Instant,
+}
+
+impl<W: Write> EventRecorder<W> {
+    pub fn new(mut writer: W) -> Result<Self, EventLogError> {
+        let header = LogHeader {
+            schema_version: EVENT_SCHEMA_VERSION,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self {
+            writer,
+            started: Instant::now(),
+        })
+    }
+
+    /// Appends an event, timed from when the recorder was created.
+    pub fn record(&mut self, event: &Push2Event) -> Result<(), EventLogError> {
+        let record = EventRecord {
+            at_ms: self.started.elapsed().as_millis() as u64,
+            event: event.clone(),
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), EventLogError> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Plays back a log written by `EventRecorder`.
+///
+/// The caller passes the playback time to `poll`, so a replay gives the same
+/// events in the same order every time, at any speed.
+pub struct EventReplayer {
+    records: VecDeque<EventRecord>,
+}
+
+impl EventReplayer {
+    /// Reads a whole log, checking its schema version.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, EventLogError> {
+        let mut records = VecDeque::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parse_error = |source| EventLogError::Parse {
+                line: index + 1,
+                source,
+            };
+            if index == 0 {
+                let header: LogHeader = serde_json::from_str(&line).map_err(parse_error)?;
+                if header.schema_version != EVENT_SCHEMA_VERSION {
+                    return Err(EventLogError::Version {
+                        found: header.schema_version,
+                    });
+                }
+                continue;
+            }
+            records.push_back(serde_json::from_str(&line).map_err(parse_error)?);
+        }
+        Ok(Self { records })
+    }
+
+    /// The number of events left.
+    pub fn remaining(&self) -> usize {
+        self.records.len()
+    }
+
+    /// The next event due at `elapsed` into the playback, if any.
+    pub fn poll(&mut self, elapsed: Duration) -> Option<Push2Event> {
+        let due = self.records.front()?.at_ms <= elapsed.as_millis() as u64;
+        if due {
+            self.records.pop_front().map(|r| r.event)
+        } else {
+            None
+        }
+    }
+}
+
+impl Iterator for EventReplayer {
+    type Item = EventRecord;
+
+    fn next(&mut self) -> Option<EventRecord> {
+        self.records.pop_front()
+    }
+}
@@ -0,0 +1,865 @@
+use embedded_graphics::{
+    mono_font::{
+        MonoFont, MonoTextStyle, iso_8859_1, iso_8859_5, jis_x0201,
+        mapping::{ISO_8859_1, ISO_8859_5, JIS_X0201, StrGlyphMapping},
+    },
+    pixelcolor::Bgr565,
+    prelude::*,
+    primitives::Rectangle,
+    text::{
+        Baseline,
+        renderer::{TextMetrics, TextRenderer},
+    },
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "fontdue")]
+use std::sync::Mutex;
+use std::{fs, io};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FontError {
+    #[error("Could not read the font file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid BDF font at line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[cfg(feature = "fontdue")]
+    #[error("Invalid TrueType font: {0}")]
+    Ttf(&'static str),
+}
+
+/// The bundled fonts, sized for the 160 pixel high display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontSize {
+    /// 6x10: encoder labels and lists, 16 lines on the display.
+    Small,
+    /// 8x13: values and short messages.
+    Medium,
+    /// 10x20: titles, readable from a distance.
+    Large,
+}
+
+/// The character sets of the bundled fonts, in the order they are tried.
+const SCRIPTS: [&StrGlyphMapping<'static>; 3] = [&ISO_8859_1, &ISO_8859_5, &JIS_X0201];
+
+impl FontSize {
+    /// The Latin-1 font of this size.
+    pub fn font(self) -> &'static MonoFont<'static> {
+        self.fonts()[0]
+    }
+
+    /// The Latin-1, Cyrillic and half-width katakana fonts of this size.
+    /// There is no 6x10 katakana font, so `Small` uses a 6x13 one.
+    pub fn fonts(self) -> [&'static MonoFont<'static>; 3] {
+        match self {
+            FontSize::Small => [
+                &iso_8859_1::FONT_6X10,
+                &iso_8859_5::FONT_6X10,
+                &jis_x0201::FONT_6X13,
+            ],
+            FontSize::Medium => [
+                &iso_8859_1::FONT_8X13,
+                &iso_8859_5::FONT_8X13,
+                &jis_x0201::FONT_8X13,
+            ],
+            FontSize::Large => [
+                &iso_8859_1::FONT_10X20,
+                &iso_8859_5::FONT_10X20,
+                &jis_x0201::FONT_10X20,
+            ],
+        }
+    }
+}
+
+/// Rewrites kana into the half-width katakana the bundled fonts have:
+/// hiragana becomes katakana, and voiced kana are split into the base and a
+/// separate voicing mark (が becomes ｶﾞ).
+fn fold_kana(text: &str) -> String {
+    const FULL_WIDTH: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+    const VOICED: &str = "ガギグゲゴザジズゼゾダヂヅデドバビブベボ";
+    const SEMI_VOICED: &str = "パピプペポ";
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        // Hiragana sit 0x60 below the matching katakana.
+        let c = match c {
+            '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        };
+        if let Some(i) = FULL_WIDTH.chars().position(|k| k == c) {
+            folded.push(char::from_u32(0xFF61 + i as u32).unwrap_or(c));
+        } else if VOICED.contains(c) || SEMI_VOICED.contains(c) {
+            // Each voiced kana follows its base, and each semi-voiced one follows the voiced.
+            let (steps, mark) = if VOICED.contains(c) {
+                (1, 'ﾞ')
+            } else {
+                (2, 'ﾟ')
+            };
+            let base = char::from_u32(c as u32 - steps).unwrap_or(c);
+            folded.push_str(&fold_kana(&base.to_string()));
+            folded.push(mark);
+        } else if c == 'ヴ' {
+            folded.push_str("ｳﾞ");
+        } else {
+            folded.push(c);
+        }
+    }
+    folded
+}
+
+/// Splits text into runs of characters from the same entry of `SCRIPTS`.
+/// Characters no bundled font has stay in the Latin-1 runs, drawn as '?'.
+fn script_runs(text: &str) -> Vec<(usize, String)> {
+    let mut runs: Vec<(usize, String)> = Vec::new();
+    for c in fold_kana(text).chars() {
+        let script = SCRIPTS.iter().position(|s| s.contains(c)).unwrap_or(0);
+        match runs.last_mut() {
+            Some((last, run)) if *last == script => run.push(c),
+            _ => runs.push((script, c.to_string())),
+        }
+    }
+    runs
+}
+
+/// The y of the baseline of a line drawn at `y` with `baseline` in `font`.
+fn alphabetic_y(font: &MonoFont, y: i32, baseline: Baseline) -> i32 {
+    let height = font.character_size.height as i32;
+    let top = y - match baseline {
+        Baseline::Top => 0,
+        Baseline::Bottom => height - 1,
+        Baseline::Middle => (height - 1) / 2,
+        Baseline::Alphabetic => font.baseline as i32,
+    };
+    top + font.baseline as i32
+}
+
+/// A font text can be drawn in.
+#[derive(Debug, Clone)]
+pub enum Font {
+    Mono(&'static MonoFont<'static>),
+    /// The bundled fonts of one size, with Latin-1, Cyrillic and kana
+    /// coverage picked per character.
+    Bundled(FontSize),
+    /// A font loaded at runtime with `BdfFont::load`.
+    Bdf(Arc<BdfFont>),
+    /// A TrueType or OpenType font loaded with `TtfFont::load`.
+    #[cfg(feature = "fontdue")]
+    Ttf(Arc<TtfFont>),
+}
+
+impl From<FontSize> for Font {
+    fn from(size: FontSize) -> Self {
+        Font::Bundled(size)
+    }
+}
+
+impl From<BdfFont> for Font {
+    fn from(font: BdfFont) -> Self {
+        Font::Bdf(Arc::new(font))
+    }
+}
+
+#[cfg(feature = "fontdue")]
+impl From<TtfFont> for Font {
+    fn from(font: TtfFont) -> Self {
+        Font::Ttf(Arc::new(font))
+    }
+}
+
+/// The font and colors of text, for use with `embedded_graphics::text::Text`.
+///
+/// ```ignore
+/// let style = TextStyle::new(FontSize::Large).with_color(Bgr565::YELLOW);
+/// Text::new("Tempo", Point::new(10, 30), style).draw(&mut push2.display)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextStyle {
+    pub font: Font,
+    pub color: Bgr565,
+    /// Fills the cell behind each character when set.
+    pub background: Option<Bgr565>,
+}
+
+impl TextStyle {
+    /// White text without a background.
+    pub fn new(font: impl Into<Font>) -> Self {
+        Self {
+            font: font.into(),
+            color: Bgr565::WHITE,
+            background: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Bgr565) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_background(mut self, background: Bgr565) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    fn mono_style(&self, font: &'static MonoFont<'static>) -> MonoTextStyle<'static, Bgr565> {
+        let mut style = MonoTextStyle::new(font, self.color);
+        style.background_color = self.background;
+        style
+    }
+
+    fn draw_bundled<D>(
+        &self,
+        size: FontSize,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Bgr565>,
+    {
+        let fonts = size.fonts();
+        let mut next = Point::new(position.x, alphabetic_y(fonts[0], position.y, baseline));
+        for (script, run) in script_runs(text) {
+            next = self.mono_style(fonts[script]).draw_string(
+                &run,
+                next,
+                Baseline::Alphabetic,
+                target,
+            )?;
+        }
+        Ok(Point::new(next.x, position.y))
+    }
+
+    fn measure_bundled(
+        &self,
+        size: FontSize,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+    ) -> TextMetrics {
+        let fonts = size.fonts();
+        let y = alphabetic_y(fonts[0], position.y, baseline);
+        let width: i32 = script_runs(text)
+            .iter()
+            .map(|(script, run)| {
+                self.mono_style(fonts[*script])
+                    .measure_string(run, Point::zero(), Baseline::Alphabetic)
+                    .next_position
+                    .x
+            })
+            .sum();
+        TextMetrics {
+            bounding_box: Rectangle::new(
+                Point::new(position.x, y - fonts[0].baseline as i32),
+                Size::new(width.max(0) as u32, fonts[0].character_size.height),
+            ),
+            next_position: position + Point::new(width, 0),
+        }
+    }
+}
+
+impl TextRenderer for TextStyle {
+    type Color = Bgr565;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        match &self.font {
+            Font::Mono(font) => self
+                .mono_style(font)
+                .draw_string(text, position, baseline, target),
+            Font::Bundled(size) => self.draw_bundled(*size, text, position, baseline, target),
+            Font::Bdf(font) => font.draw_string(
+                text,
+                position,
+                baseline,
+                self.color,
+                self.background,
+                target,
+            ),
+            #[cfg(feature = "fontdue")]
+            Font::Ttf(font) => font.draw_string(
+                text,
+                position,
+                baseline,
+                self.color,
+                self.background,
+                target,
+            ),
+        }
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        match &self.font {
+            Font::Mono(font) => self
+                .mono_style(font)
+                .draw_whitespace(width, position, baseline, target),
+            Font::Bundled(size) => self
+                .mono_style(size.font())
+                .draw_whitespace(width, position, baseline, target),
+            Font::Bdf(font) => {
+                if let Some(background) = self.background {
+                    let area = Rectangle::new(
+                        Point::new(position.x, font.top(position.y, baseline)),
+                        Size::new(width, font.line_height()),
+                    );
+                    target.fill_solid(&area, background)?;
+                }
+                Ok(position + Size::new(width, 0))
+            }
+            #[cfg(feature = "fontdue")]
+            Font::Ttf(font) => {
+                if let Some(background) = self.background {
+                    let area = Rectangle::new(
+                        Point::new(position.x, font.top(position.y, baseline)),
+                        Size::new(width, font.line_height()),
+                    );
+                    target.fill_solid(&area, background)?;
+                }
+                Ok(position + Size::new(width, 0))
+            }
+        }
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        match &self.font {
+            Font::Mono(font) => self
+                .mono_style(font)
+                .measure_string(text, position, baseline),
+            Font::Bundled(size) => self.measure_bundled(*size, text, position, baseline),
+            Font::Bdf(font) => font.measure_string(text, position, baseline),
+            #[cfg(feature = "fontdue")]
+            Font::Ttf(font) => font.measure_string(text, position, baseline),
+        }
+    }
+
+    fn line_height(&self) -> u32 {
+        match &self.font {
+            Font::Mono(font) => font.character_size.height + font.character_spacing,
+            Font::Bundled(size) => size.font().character_size.height,
+            Font::Bdf(font) => font.line_height(),
+            #[cfg(feature = "fontdue")]
+            Font::Ttf(font) => font.line_height(),
+        }
+    }
+}
+
+/// One character of a `BdfFont`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BdfGlyph {
+    width: u32,
+    height: u32,
+    /// The offset of the bitmap's left column from the pen position.
+    x_offset: i32,
+    /// The offset of the bitmap's bottom row above the baseline.
+    y_offset: i32,
+    /// How far the pen moves after this character.
+    advance: i32,
+    /// One row per line, with `(width + 7) / 8` bytes per row.
+    bitmap: Vec<u8>,
+}
+
+impl BdfGlyph {
+    fn is_set(&self, x: u32, y: u32) -> bool {
+        let bytes_per_row = self.width.div_ceil(8);
+        self.bitmap
+            .get((y * bytes_per_row + x / 8) as usize)
+            .is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0)
+    }
+}
+
+/// A bitmap font in the BDF format, loaded at runtime.
+///
+/// Many pixel fonts (Terminus, Unifont, Spleen...) are distributed as BDF,
+/// or can be converted to it with `pcf2bdf` or FontForge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BdfFont {
+    glyphs: HashMap<char, BdfGlyph>,
+    /// The rows above the baseline.
+    pub ascent: u32,
+    /// The rows below the baseline.
+    pub descent: u32,
+    /// Drawn in place of characters the font does not have.
+    default_char: Option<char>,
+}
+
+impl BdfFont {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FontError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, FontError> {
+        let mut font = BdfFont {
+            glyphs: HashMap::new(),
+            ascent: 0,
+            descent: 0,
+            default_char: None,
+        };
+        let mut bounding_box: Option<(u32, u32, i32, i32)> = None;
+        let mut lines = source.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let error = |message: &str| FontError::Parse {
+                line: index + 1,
+                message: message.to_string(),
+            };
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let [w, h, x, y] =
+                        numbers(words).ok_or_else(|| error("bad FONTBOUNDINGBOX"))?;
+                    bounding_box = Some((w as u32, h as u32, x, y));
+                }
+                Some("FONT_ASCENT") => {
+                    let [ascent] = numbers(words).ok_or_else(|| error("bad FONT_ASCENT"))?;
+                    font.ascent = ascent.max(0) as u32;
+                }
+                Some("FONT_DESCENT") => {
+                    let [descent] = numbers(words).ok_or_else(|| error("bad FONT_DESCENT"))?;
+                    font.descent = descent.max(0) as u32;
+                }
+                Some("DEFAULT_CHAR") => {
+                    let [code] = numbers(words).ok_or_else(|| error("bad DEFAULT_CHAR"))?;
+                    font.default_char = char::from_u32(code as u32);
+                }
+                Some("STARTCHAR") => {
+                    let (code, glyph) = parse_glyph(&mut lines, bounding_box)?;
+                    if let Some(c) = code.and_then(char::from_u32) {
+                        font.glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if font.ascent == 0
+            && font.descent == 0
+            && let Some((_, h, _, y)) = bounding_box
+        {
+            font.descent = (-y).max(0) as u32;
+            font.ascent = h.saturating_sub(font.descent);
+        }
+        if font.glyphs.is_empty() {
+            return Err(FontError::Parse {
+                line: source.lines().count(),
+                message: "no characters".to_string(),
+            });
+        }
+        if font
+            .default_char
+            .is_none_or(|c| !font.glyphs.contains_key(&c))
+        {
+            font.default_char = ['?', ' '].into_iter().find(|c| font.glyphs.contains_key(c));
+        }
+        Ok(font)
+    }
+
+    pub fn has_char(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.ascent + self.descent
+    }
+
+    fn glyph(&self, c: char) -> Option<&BdfGlyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&self.default_char?))
+    }
+
+    /// The y of the top of a line drawn at `y` with `baseline`.
+    fn top(&self, y: i32, baseline: Baseline) -> i32 {
+        let height = self.line_height() as i32;
+        y - match baseline {
+            Baseline::Top => 0,
+            Baseline::Bottom => height - 1,
+            Baseline::Middle => (height - 1) / 2,
+            Baseline::Alphabetic => self.ascent as i32 - 1,
+        }
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let width: i32 = text
+            .chars()
+            .filter_map(|c| self.glyph(c))
+            .map(|g| g.advance)
+            .sum();
+        TextMetrics {
+            bounding_box: Rectangle::new(
+                Point::new(position.x, self.top(position.y, baseline)),
+                Size::new(width.max(0) as u32, self.line_height()),
+            ),
+            next_position: position + Point::new(width, 0),
+        }
+    }
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        color: Bgr565,
+        background: Option<Bgr565>,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Bgr565>,
+    {
+        let top = self.top(position.y, baseline);
+        // The row just above the baseline.
+        let base_row = top + self.ascent as i32 - 1;
+        let mut x = position.x;
+        for c in text.chars() {
+            let Some(glyph) = self.glyph(c) else {
+                continue;
+            };
+            if let Some(background) = background {
+                let cell = Rectangle::new(
+                    Point::new(x, top),
+                    Size::new(glyph.advance.max(0) as u32, self.line_height()),
+                );
+                target.fill_solid(&cell, background)?;
+            }
+            let glyph_top = base_row - glyph.y_offset - glyph.height as i32 + 1;
+            let pixels = (0..glyph.height).flat_map(|gy| {
+                (0..glyph.width)
+                    .filter(move |&gx| glyph.is_set(gx, gy))
+                    .map(move |gx| {
+                        Pixel(
+                            Point::new(x + glyph.x_offset + gx as i32, glyph_top + gy as i32),
+                            color,
+                        )
+                    })
+            });
+            target.draw_iter(pixels)?;
+            x += glyph.advance;
+        }
+        Ok(Point::new(x, position.y))
+    }
+}
+
+/// The metrics and coverage bitmap of a `TtfFont` character.
+#[cfg(feature = "fontdue")]
+type RasterGlyph = (fontdue::Metrics, Arc<[u8]>);
+
+/// A TrueType or OpenType font, rasterized at one pixel size with `fontdue`.
+///
+/// Pixels are blended with the background color when the style has one, and
+/// drawn where the glyph covers at least half the pixel otherwise.
+#[cfg(feature = "fontdue")]
+#[derive(Debug)]
+pub struct TtfFont {
+    font: fontdue::Font,
+    /// The pixel size glyphs are rasterized at.
+    pub size: f32,
+    /// The rows above the baseline.
+    pub ascent: u32,
+    /// The rows below the baseline.
+    pub descent: u32,
+    glyphs: Mutex<HashMap<char, RasterGlyph>>,
+}
+
+#[cfg(feature = "fontdue")]
+impl TtfFont {
+    pub fn load(path: impl AsRef<Path>, size: f32) -> Result<Self, FontError> {
+        Self::from_bytes(&fs::read(path)?, size)
+    }
+
+    pub fn from_bytes(data: &[u8], size: f32) -> Result<Self, FontError> {
+        let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+            .map_err(FontError::Ttf)?;
+        let (ascent, descent) = font
+            .horizontal_line_metrics(size)
+            .map_or((size, 0.0), |m| (m.ascent, -m.descent));
+        Ok(Self {
+            font,
+            size,
+            ascent: ascent.ceil().max(0.0) as u32,
+            descent: descent.ceil().max(0.0) as u32,
+            glyphs: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn has_char(&self, c: char) -> bool {
+        self.font.has_glyph(c)
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.ascent + self.descent
+    }
+
+    /// The metrics and coverage bitmap of a character, rasterized on first use.
+    fn glyph(&self, c: char) -> RasterGlyph {
+        let mut glyphs = self.glyphs.lock().unwrap_or_else(|e| e.into_inner());
+        glyphs
+            .entry(c)
+            .or_insert_with(|| {
+                let (metrics, bitmap) = self.font.rasterize(c, self.size);
+                (metrics, bitmap.into())
+            })
+            .clone()
+    }
+
+    /// The y of the top of a line drawn at `y` with `baseline`.
+    fn top(&self, y: i32, baseline: Baseline) -> i32 {
+        let height = self.line_height() as i32;
+        y - match baseline {
+            Baseline::Top => 0,
+            Baseline::Bottom => height - 1,
+            Baseline::Middle => (height - 1) / 2,
+            Baseline::Alphabetic => self.ascent as i32 - 1,
+        }
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let width: f32 = text
+            .chars()
+            .map(|c| self.font.metrics(c, self.size).advance_width)
+            .sum();
+        let width = width.round() as i32;
+        TextMetrics {
+            bounding_box: Rectangle::new(
+                Point::new(position.x, self.top(position.y, baseline)),
+                Size::new(width.max(0) as u32, self.line_height()),
+            ),
+            next_position: position + Point::new(width, 0),
+        }
+    }
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        color: Bgr565,
+        background: Option<Bgr565>,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Bgr565>,
+    {
+        let top = self.top(position.y, baseline);
+        // The row just below the baseline.
+        let base_row = top + self.ascent as i32;
+        let mut pen = position.x as f32;
+        for c in text.chars() {
+            let (metrics, bitmap) = self.glyph(c);
+            let x = pen.round() as i32;
+            if let Some(background) = background {
+                let cell = Rectangle::new(
+                    Point::new(x, top),
+                    Size::new(metrics.advance_width.round() as u32, self.line_height()),
+                );
+                target.fill_solid(&cell, background)?;
+            }
+            let glyph_top = base_row - metrics.ymin - metrics.height as i32;
+            let pixels = bitmap.iter().enumerate().filter_map(|(i, &coverage)| {
+                let pixel_color = match background {
+                    Some(background) if coverage > 0 => blend(color, background, coverage),
+                    None if coverage >= 128 => color,
+                    _ => return None,
+                };
+                let point = Point::new(
+                    x + metrics.xmin + (i % metrics.width) as i32,
+                    glyph_top + (i / metrics.width) as i32,
+                );
+                Some(Pixel(point, pixel_color))
+            });
+            target.draw_iter(pixels)?;
+            pen += metrics.advance_width;
+        }
+        Ok(Point::new(pen.round() as i32, position.y))
+    }
+}
+
+/// Mixes `foreground` over `background`, `alpha` being the coverage out of 255.
+#[cfg(feature = "fontdue")]
+fn blend(foreground: Bgr565, background: Bgr565, alpha: u8) -> Bgr565 {
+    let mix =
+        |f: u8, b: u8| ((f as u32 * alpha as u32 + b as u32 * (255 - alpha as u32)) / 255) as u8;
+    Bgr565::new(
+        mix(foreground.r(), background.r()),
+        mix(foreground.g(), background.g()),
+        mix(foreground.b(), background.b()),
+    )
+}
+
+/// Parses exactly `N` integers.
+fn numbers<'a, const N: usize>(mut words: impl Iterator<Item = &'a str>) -> Option<[i32; N]> {
+    let mut values = [0; N];
+    for value in values.iter_mut() {
+        *value = words.next()?.parse().ok()?;
+    }
+    Some(values)
+}
+
+/// Parses the lines after STARTCHAR up to ENDCHAR. Returns the encoding and the glyph.
+fn parse_glyph<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    bounding_box: Option<(u32, u32, i32, i32)>,
+) -> Result<(Option<u32>, BdfGlyph), FontError> {
+    let mut code = None;
+    let (width, height, x_offset, y_offset) = bounding_box.unwrap_or_default();
+    let mut glyph = BdfGlyph {
+        width,
+        height,
+        x_offset,
+        y_offset,
+        advance: width as i32,
+        bitmap: Vec::new(),
+    };
+    let mut last_line = 0;
+    while let Some((index, line)) = lines.next() {
+        last_line = index + 1;
+        let error = |message: &str| FontError::Parse {
+            line: index + 1,
+            message: message.to_string(),
+        };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("ENCODING") => {
+                let [value] = numbers(words.take(1)).ok_or_else(|| error("bad ENCODING"))?;
+                code = u32::try_from(value).ok();
+            }
+            Some("DWIDTH") => {
+                let [advance, _] = numbers(words).ok_or_else(|| error("bad DWIDTH"))?;
+                glyph.advance = advance;
+            }
+            Some("BBX") => {
+                let [w, h, x, y] = numbers(words).ok_or_else(|| error("bad BBX"))?;
+                glyph.width = w.max(0) as u32;
+                glyph.height = h.max(0) as u32;
+                glyph.x_offset = x;
+                glyph.y_offset = y;
+            }
+            Some("BITMAP") => {
+                let bytes_per_row = glyph.width.div_ceil(8) as usize;
+                for _ in 0..glyph.height {
+                    let (index, row) = lines.next().ok_or_else(|| error("truncated BITMAP"))?;
+                    let row = row.trim();
+                    for i in 0..bytes_per_row {
+                        let byte = row
+                            .get(i * 2..i * 2 + 2)
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                            .ok_or_else(|| FontError::Parse {
+                                line: index + 1,
+                                message: "bad BITMAP row".to_string(),
+                            })?;
+                        glyph.bitmap.push(byte);
+                    }
+                }
+            }
+            Some("ENDCHAR") => return Ok((code, glyph)),
+            _ => {}
+        }
+    }
+    Err(FontError::Parse {
+        line: last_line,
+        message: "missing ENDCHAR".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 4 6 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 5
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR A
+ENCODING 65
+DWIDTH 5 0
+BBX 4 5 0 0
+BITMAP
+60
+90
+F0
+90
+90
+ENDCHAR
+STARTCHAR question
+ENCODING 63
+DWIDTH 5 0
+BBX 3 2 1 3
+BITMAP
+E0
+20
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn parses_glyphs_and_metrics() {
+        let font = BdfFont::parse(FONT).unwrap();
+        assert_eq!((font.ascent, font.descent), (5, 1));
+        assert!(font.has_char('A'));
+        assert!(!font.has_char('B'));
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.advance), (4, 5, 5));
+        assert!(a.is_set(1, 0) && !a.is_set(0, 0) && a.is_set(3, 2));
+        // Missing characters fall back to '?'.
+        assert_eq!(font.glyph('B').unwrap().height, 2);
+    }
+
+    #[test]
+    fn measures_by_advance() {
+        let font = BdfFont::parse(FONT).unwrap();
+        let metrics = font.measure_string("AA", Point::new(10, 20), Baseline::Top);
+        assert_eq!(
+            metrics.bounding_box,
+            Rectangle::new(Point::new(10, 20), Size::new(10, 6))
+        );
+        assert_eq!(metrics.next_position, Point::new(20, 20));
+    }
+
+    #[test]
+    fn kana_is_folded_to_half_width() {
+        assert_eq!(fold_kana("ひらがな"), "ﾋﾗｶﾞﾅ");
+        assert_eq!(fold_kana("パン・ヴ"), "ﾊﾟﾝ･ｳﾞ");
+        assert_eq!(fold_kana("Café"), "Café");
+    }
+
+    #[test]
+    fn text_is_split_by_script() {
+        let runs = script_runs("Bass Привет ドラム");
+        let scripts: Vec<usize> = runs.iter().map(|(script, _)| *script).collect();
+        assert_eq!(scripts, [0, 1, 0, 2]);
+        assert_eq!(runs[3].1, "ﾄﾞﾗﾑ");
+    }
+
+    #[test]
+    fn bundled_text_measures_every_script() {
+        let style = TextStyle::new(FontSize::Medium);
+        let metrics = style.measure_string("Ёжик ｶﾅ", Point::zero(), Baseline::Top);
+        assert_eq!(metrics.next_position, Point::new(8 * 7, 0));
+    }
+
+    #[test]
+    fn missing_endchar_is_an_error() {
+        let source = FONT.replace("ENDCHAR\nENDFONT", "ENDFONT");
+        assert!(matches!(
+            BdfFont::parse(&source),
+            Err(FontError::Parse { .. })
+        ));
+    }
+}
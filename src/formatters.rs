@@ -0,0 +1,108 @@
+//! Value formatters for parameter and encoder text, so the same kind of value
+//! reads the same in every app.
+//!
+//! Each one fits `Param::with_formatter`:
+//!
+//! ```ignore
+//! let volume = Param::new("Volume", 0.0, 1.0, 0.5).with_formatter(formatters::gain_db);
+//! ```
+
+/// Gains below this are shown as "-inf dB".
+const SILENCE_DB: f32 = -70.0;
+
+/// A level in decibels: "-6.0 dB".
+pub fn decibels(db: f32) -> String {
+    if db <= SILENCE_DB {
+        "-inf dB".to_string()
+    } else {
+        format!("{:.1} dB", db)
+    }
+}
+
+/// A linear gain (1.0 = unity) in decibels: 0.5 is "-6.0 dB".
+pub fn gain_db(gain: f32) -> String {
+    if gain <= 0.0 {
+        return decibels(f32::NEG_INFINITY);
+    }
+    decibels(20.0 * gain.log10())
+}
+
+/// A 0.0-1.0 value as a whole percentage: "50%".
+pub fn percent(value: f32) -> String {
+    format!("{:.0}%", value * 100.0)
+}
+
+/// A pitch offset: "+7.0 st".
+pub fn semitones(semitones: f32) -> String {
+    format!("{:+.1} st", semitones)
+}
+
+/// A duration, in seconds from one second up: "250 ms", "1.50 s".
+pub fn milliseconds(ms: f32) -> String {
+    if ms.abs() >= 1000.0 {
+        format!("{:.2} s", ms / 1000.0)
+    } else {
+        format!("{:.0} ms", ms)
+    }
+}
+
+/// A length in beats (quarter notes) as a note value: 0.25 is "1/16",
+/// 1/6 is "1/16T" (triplet), 0.75 is "1/8D" (dotted) and 8.0 is "2 bars".
+/// Other lengths are shown in beats.
+pub fn beats(beats: f32) -> String {
+    const DENOMINATORS: [u32; 7] = [1, 2, 4, 8, 16, 32, 64];
+    let whole_notes = beats / 4.0;
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+    if whole_notes >= 1.0 && close(whole_notes, whole_notes.round()) {
+        return match whole_notes.round() as u32 {
+            1 => "1 bar".to_string(),
+            bars => format!("{} bars", bars),
+        };
+    }
+    for d in DENOMINATORS {
+        let plain = 1.0 / d as f32;
+        if close(whole_notes, plain) {
+            return format!("1/{}", d);
+        }
+        if close(whole_notes, plain * 2.0 / 3.0) {
+            return format!("1/{}T", d);
+        }
+        if close(whole_notes, plain * 1.5) {
+            return format!("1/{}D", d);
+        }
+    }
+    format!("{:.2} beats", beats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert_eq!(gain_db(0.5), "-6.0 dB");
+        assert_eq!(gain_db(1.0), "0.0 dB");
+        assert_eq!(gain_db(0.0), "-inf dB");
+        assert_eq!(decibels(-3.04), "-3.0 dB");
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(percent(0.5), "50%");
+        assert_eq!(semitones(7.0), "+7.0 st");
+        assert_eq!(semitones(-12.0), "-12.0 st");
+        assert_eq!(milliseconds(250.0), "250 ms");
+        assert_eq!(milliseconds(1500.0), "1.50 s");
+    }
+
+    #[test]
+    fn note_values() {
+        assert_eq!(beats(0.25), "1/16");
+        assert_eq!(beats(1.0 / 6.0), "1/16T");
+        assert_eq!(beats(0.75), "1/8D");
+        assert_eq!(beats(1.0), "1/4");
+        assert_eq!(beats(4.0), "1 bar");
+        assert_eq!(beats(8.0), "2 bars");
+        assert_eq!(beats(1.3), "1.30 beats");
+    }
+}
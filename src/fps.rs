@@ -0,0 +1,131 @@
+//! Frame pacing and frame rate measurement for `Push2Display::flush`.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How much each new frame moves the measured frame rate.
+const SMOOTHING: f32 = 0.1;
+
+/// Returned by `Push2Display::flush`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FpsStats {
+    /// The measured frame rate, smoothed over the last few frames.
+    pub fps: f32,
+    /// Frames missed since the target was set, because drawing took longer
+    /// than a frame. Always 0 without a target.
+    pub dropped_frames: u64,
+    /// How long writing the last frame to the display took.
+    pub last_transfer: Duration,
+}
+
+/// Keeps the flushes to a target frame rate.
+#[derive(Debug, Default)]
+pub(crate) struct FramePacer {
+    interval: Option<Duration>,
+    /// When the next frame is due.
+    next_frame: Option<Instant>,
+    last_flush: Option<Instant>,
+    stats: FpsStats,
+}
+
+impl FramePacer {
+    /// 0 turns pacing off.
+    pub(crate) fn set_target_fps(&mut self, fps: u32) {
+        self.interval = (fps > 0).then(|| Duration::from_secs(1) / fps);
+        self.next_frame = None;
+        self.stats.dropped_frames = 0;
+    }
+
+    pub(crate) fn target_fps(&self) -> Option<u32> {
+        self.interval
+            .map(|interval| (1.0 / interval.as_secs_f64()).round() as u32)
+    }
+
+    /// Sleeps until the next frame is due.
+    pub(crate) fn wait(&mut self) {
+        let delay = self.schedule(Instant::now());
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+
+    /// How long to wait at `now` for the next frame to be due. Frames more
+    /// than a whole interval late are counted as dropped, and the cadence
+    /// restarts from `now`.
+    fn schedule(&mut self, now: Instant) -> Duration {
+        let Some(interval) = self.interval else {
+            return Duration::ZERO;
+        };
+        let mut due = self.next_frame.unwrap_or(now);
+        let late = now.saturating_duration_since(due);
+        if late >= interval {
+            self.stats.dropped_frames += (late.as_nanos() / interval.as_nanos()) as u64;
+            due = now;
+        }
+        self.next_frame = Some(due + interval);
+        due.saturating_duration_since(now)
+    }
+
+    /// Measures a frame written at `now`, which took `transfer` to write.
+    pub(crate) fn record(&mut self, now: Instant, transfer: Duration) -> FpsStats {
+        if let Some(last) = self.last_flush {
+            let elapsed = now.saturating_duration_since(last).as_secs_f32();
+            if elapsed > 0.0 {
+                let fps = 1.0 / elapsed;
+                self.stats.fps = if self.stats.fps == 0.0 {
+                    fps
+                } else {
+                    self.stats.fps + (fps - self.stats.fps) * SMOOTHING
+                };
+            }
+        }
+        self.last_flush = Some(now);
+        self.stats.last_transfer = transfer;
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paces_to_the_target() {
+        let mut pacer = FramePacer::default();
+        pacer.set_target_fps(50);
+        let start = Instant::now();
+        assert_eq!(pacer.schedule(start), Duration::ZERO);
+        // Drawing took 5 ms of the 20 ms frame.
+        let now = start + Duration::from_millis(5);
+        assert_eq!(pacer.schedule(now), Duration::from_millis(15));
+        assert_eq!(pacer.target_fps(), Some(50));
+    }
+
+    #[test]
+    fn counts_dropped_frames() {
+        let mut pacer = FramePacer::default();
+        pacer.set_target_fps(50);
+        let start = Instant::now();
+        pacer.schedule(start);
+        // Due at 20 ms, drawn at 65 ms: the frames due at 20 and 40 ms were missed.
+        let now = start + Duration::from_millis(65);
+        assert_eq!(pacer.schedule(now), Duration::ZERO);
+        let stats = pacer.record(now, Duration::from_millis(2));
+        assert_eq!(stats.dropped_frames, 2);
+        assert_eq!(
+            pacer.schedule(now + Duration::from_millis(4)),
+            Duration::from_millis(16)
+        );
+    }
+
+    #[test]
+    fn measures_the_frame_rate() {
+        let mut pacer = FramePacer::default();
+        let start = Instant::now();
+        pacer.record(start, Duration::ZERO);
+        let stats = pacer.record(start + Duration::from_millis(25), Duration::from_millis(3));
+        assert!((stats.fps - 40.0).abs() < 0.01);
+        assert_eq!(stats.last_transfer, Duration::from_millis(3));
+        assert_eq!(stats.dropped_frames, 0);
+    }
+}
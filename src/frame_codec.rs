@@ -0,0 +1,349 @@
+use std::time::Instant;
+use thiserror::Error;
+
+const KEY_FRAME: u8 = 0;
+const DELTA_FRAME: u8 = 1;
+const HEADER_LEN: usize = 5;
+/// Set in a token header when the token is a run of one repeated value.
+const RUN_FLAG: u16 = 0x8000;
+const MAX_TOKEN_LEN: usize = 0x7FFF;
+/// Runs shorter than this are cheaper to send as part of a literal.
+const MIN_RUN: usize = 3;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FrameCodecError {
+    #[error("The frame message is truncated")]
+    Truncated,
+    #[error("Unknown frame kind {0}")]
+    UnknownKind(u8),
+    #[error("A delta frame arrived before any key frame")]
+    MissingKeyFrame,
+    #[error("The frame message holds {got} pixels instead of {expected}")]
+    WrongSize { expected: usize, got: usize },
+}
+
+/// Compresses a stream of Bgr565 frames for slow links, such as the remote mirror over Wi-Fi.
+///
+/// Each message is either a key frame or a delta, the XOR of the frame with
+/// the last one sent, so unchanged pixels become runs of zeros. The pixels
+/// are then run-length encoded. All values are little-endian:
+///
+/// - byte 0: 0 for a key frame, 1 for a delta
+/// - bytes 1-4: width and height as u16
+/// - then tokens until every pixel is covered: a u16 header `n`; if its high bit
+///   is set, one u16 value repeated `n & 0x7FFF` times, otherwise `n` u16 values.
+///
+/// With `with_max_bandwidth`, frames that would go over the budget are
+/// dropped, and the next frame sent is a delta against the last one that was.
+#[derive(Debug)]
+pub struct FrameEncoder {
+    previous: Option<(usize, usize, Vec<u16>)>,
+    /// A delta is replaced by a key frame every this many frames.
+    pub key_interval: u32,
+    /// The average number of bytes per second to stay under.
+    pub max_bandwidth: Option<usize>,
+    since_key: u32,
+    key_requested: bool,
+    budget: f64,
+    last_encode: Option<Instant>,
+    /// The number of frames dropped to stay under `max_bandwidth`.
+    pub dropped: usize,
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            key_interval: 120,
+            max_bandwidth: None,
+            since_key: 0,
+            key_requested: false,
+            budget: 0.0,
+            last_encode: None,
+            dropped: 0,
+        }
+    }
+}
+
+impl FrameEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_bandwidth(mut self, bytes_per_second: usize) -> Self {
+        self.max_bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    pub fn with_key_interval(mut self, frames: u32) -> Self {
+        self.key_interval = frames.max(1);
+        self
+    }
+
+    /// Makes the next frame sent a key frame, e.g. when a client joins.
+    pub fn request_key_frame(&mut self) {
+        self.key_requested = true;
+    }
+
+    /// Encodes a `width` x `height` frame. Returns `None` when the frame is
+    /// the same as the last one sent, or is dropped to stay under the bandwidth cap.
+    pub fn encode(&mut self, width: usize, height: usize, pixels: &[u16]) -> Option<Vec<u8>> {
+        let now = Instant::now();
+        if let Some(rate) = self.max_bandwidth {
+            let elapsed = self
+                .last_encode
+                .map_or(1.0, |last| now.duration_since(last).as_secs_f64());
+            // Allow bursts of up to one second's worth of data.
+            self.budget = (self.budget + elapsed * rate as f64).min(rate as f64);
+        }
+        self.last_encode = Some(now);
+
+        let delta_base = match &self.previous {
+            Some((w, h, previous))
+                if *w == width
+                    && *h == height
+                    && !self.key_requested
+                    && self.since_key < self.key_interval =>
+            {
+                Some(previous)
+            }
+            _ => None,
+        };
+        let message = match delta_base {
+            Some(previous) => {
+                if previous.as_slice() == pixels {
+                    return None;
+                }
+                let delta: Vec<u16> = pixels.iter().zip(previous).map(|(a, b)| a ^ b).collect();
+                encode_message(DELTA_FRAME, width, height, &delta)
+            }
+            None => encode_message(KEY_FRAME, width, height, pixels),
+        };
+
+        if self.max_bandwidth.is_some() {
+            if self.budget <= 0.0 {
+                self.dropped += 1;
+                return None;
+            }
+            self.budget -= message.len() as f64;
+        }
+        if message[0] == KEY_FRAME {
+            self.since_key = 0;
+            self.key_requested = false;
+        } else {
+            self.since_key += 1;
+        }
+        self.previous = Some((width, height, pixels.to_vec()));
+        Some(message)
+    }
+}
+
+fn encode_message(kind: u8, width: usize, height: usize, pixels: &[u16]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + pixels.len() / 8);
+    out.push(kind);
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < pixels.len() {
+        let value = pixels[i];
+        let run = pixels[i..]
+            .iter()
+            .take(MAX_TOKEN_LEN)
+            .take_while(|&&p| p == value)
+            .count();
+        if run >= MIN_RUN {
+            push_literal(&mut out, &pixels[literal_start..i]);
+            out.extend_from_slice(&(RUN_FLAG | run as u16).to_le_bytes());
+            out.extend_from_slice(&value.to_le_bytes());
+            i += run;
+            literal_start = i;
+        } else {
+            i += run;
+        }
+    }
+    push_literal(&mut out, &pixels[literal_start..]);
+    out
+}
+
+fn push_literal(out: &mut Vec<u8>, pixels: &[u16]) {
+    for chunk in pixels.chunks(MAX_TOKEN_LEN) {
+        out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        for pixel in chunk {
+            out.extend_from_slice(&pixel.to_le_bytes());
+        }
+    }
+}
+
+/// Rebuilds the frames written by a `FrameEncoder`.
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    width: usize,
+    height: usize,
+    frame: Option<Vec<u16>>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Applies a message and returns the frame, as raw Bgr565 pixels row by row.
+    pub fn decode(&mut self, message: &[u8]) -> Result<&[u16], FrameCodecError> {
+        let header = message
+            .get(..HEADER_LEN)
+            .ok_or(FrameCodecError::Truncated)?;
+        let width = u16::from_le_bytes([header[1], header[2]]) as usize;
+        let height = u16::from_le_bytes([header[3], header[4]]) as usize;
+        let pixels = decode_tokens(&message[HEADER_LEN..], width * height)?;
+        match header[0] {
+            KEY_FRAME => {
+                self.width = width;
+                self.height = height;
+                self.frame = Some(pixels);
+            }
+            DELTA_FRAME => {
+                let frame = self
+                    .frame
+                    .as_mut()
+                    .filter(|_| self.width == width && self.height == height)
+                    .ok_or(FrameCodecError::MissingKeyFrame)?;
+                for (pixel, delta) in frame.iter_mut().zip(pixels) {
+                    *pixel ^= delta;
+                }
+            }
+            kind => return Err(FrameCodecError::UnknownKind(kind)),
+        }
+        Ok(self.frame.as_deref().unwrap_or_default())
+    }
+}
+
+fn decode_tokens(mut bytes: &[u8], expected: usize) -> Result<Vec<u16>, FrameCodecError> {
+    // The size comes from the message, so only reserve what its bytes can
+    // encode: at most one full run per 4 bytes.
+    let encodable = bytes.len() / 4 * MAX_TOKEN_LEN;
+    let mut pixels = Vec::with_capacity(expected.min(encodable));
+    let next_u16 = |bytes: &mut &[u8]| -> Result<u16, FrameCodecError> {
+        let (value, rest) = bytes
+            .split_first_chunk()
+            .ok_or(FrameCodecError::Truncated)?;
+        *bytes = rest;
+        Ok(u16::from_le_bytes(*value))
+    };
+    while !bytes.is_empty() {
+        let header = next_u16(&mut bytes)?;
+        let len = (header & !RUN_FLAG) as usize;
+        if header & RUN_FLAG != 0 {
+            let value = next_u16(&mut bytes)?;
+            pixels.extend(std::iter::repeat_n(value, len));
+        } else {
+            for _ in 0..len {
+                pixels.push(next_u16(&mut bytes)?);
+            }
+        }
+        if pixels.len() > expected {
+            break;
+        }
+    }
+    if pixels.len() != expected {
+        return Err(FrameCodecError::WrongSize {
+            expected,
+            got: pixels.len(),
+        });
+    }
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(seed: u16) -> Vec<u16> {
+        (0..960 * 160)
+            .map(|i: usize| {
+                if i.is_multiple_of(7) {
+                    seed ^ i as u16
+                } else {
+                    0x1234
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn key_and_delta_frames_round_trip() {
+        let mut encoder = FrameEncoder::new();
+        let mut decoder = FrameDecoder::new();
+        let first = frame(1);
+        let mut second = first.clone();
+        second[500..600].fill(0xFFFF);
+
+        let key = encoder.encode(960, 160, &first).unwrap();
+        assert_eq!(key[0], KEY_FRAME);
+        assert_eq!(decoder.decode(&key).unwrap(), first.as_slice());
+
+        let delta = encoder.encode(960, 160, &second).unwrap();
+        assert_eq!(delta[0], DELTA_FRAME);
+        assert!(delta.len() < 1000);
+        assert_eq!(decoder.decode(&delta).unwrap(), second.as_slice());
+    }
+
+    #[test]
+    fn unchanged_frames_are_skipped() {
+        let mut encoder = FrameEncoder::new();
+        let pixels = frame(2);
+        assert!(encoder.encode(960, 160, &pixels).is_some());
+        assert!(encoder.encode(960, 160, &pixels).is_none());
+    }
+
+    #[test]
+    fn long_literals_and_runs_are_split() {
+        let mut encoder = FrameEncoder::new();
+        let mut decoder = FrameDecoder::new();
+        let mut pixels: Vec<u16> = (0..40_000).map(|i| i as u16).collect();
+        pixels.extend(std::iter::repeat_n(7, 40_000));
+        let message = encoder.encode(400, 200, &pixels).unwrap();
+        assert_eq!(decoder.decode(&message).unwrap(), pixels.as_slice());
+    }
+
+    #[test]
+    fn delta_needs_a_key_frame() {
+        let mut encoder = FrameEncoder::new();
+        encoder.encode(960, 160, &frame(1)).unwrap();
+        let delta = encoder.encode(960, 160, &frame(2)).unwrap();
+        assert_eq!(
+            FrameDecoder::new().decode(&delta),
+            Err(FrameCodecError::MissingKeyFrame)
+        );
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        // A 65535x65535 key frame holding a single run of 3 pixels.
+        let message = [KEY_FRAME, 0xFF, 0xFF, 0xFF, 0xFF, 0x03, 0x80, 0x00, 0x00];
+        assert_eq!(
+            FrameDecoder::new().decode(&message),
+            Err(FrameCodecError::WrongSize {
+                expected: 65535 * 65535,
+                got: 3
+            })
+        );
+    }
+
+    #[test]
+    fn frames_over_the_budget_are_dropped() {
+        let mut encoder = FrameEncoder::new().with_max_bandwidth(1000);
+        assert!(encoder.encode(960, 160, &frame(1)).is_some());
+        assert!(encoder.encode(960, 160, &frame(2)).is_none());
+        assert_eq!(encoder.dropped, 1);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{ControlName, PadCoord, Push2, Push2Error, Push2Event};
+
+/// The pad grid, buttons and LEDs shared by grid controllers.
+///
+/// Grid apps written against this trait run on any controller that
+/// implements it, such as the Push 2 or a Novation `Launchpad`. Coordinates
+/// start at (0, 0) on the top-left pad; colors are `Push2Colors` values,
+/// converted to the controller's palette by each implementation.
+pub trait GridController {
+    /// The number of pad columns and rows.
+    fn grid_size(&self) -> (u8, u8) {
+        (8, 8)
+    }
+
+    fn set_pad_color(&mut self, coord: PadCoord, color: u8) -> Result<(), Push2Error>;
+
+    /// Lights a button. Buttons the controller does not have are ignored.
+    fn set_button_light(&mut self, name: ControlName, light: u8) -> Result<(), Push2Error>;
+
+    /// Polls for the next pad or button event. This is non-blocking.
+    fn poll_event(&mut self) -> Option<Push2Event>;
+
+    /// Turns every pad off.
+    fn clear_pads(&mut self) -> Result<(), Push2Error> {
+        let (width, height) = self.grid_size();
+        for y in 0..height {
+            for x in 0..width {
+                self.set_pad_color(PadCoord { x, y }, 0)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GridController for Push2 {
+    fn set_pad_color(&mut self, coord: PadCoord, color: u8) -> Result<(), Push2Error> {
+        Push2::set_pad_color(self, coord, color)
+    }
+
+    fn set_button_light(&mut self, name: ControlName, light: u8) -> Result<(), Push2Error> {
+        Push2::set_button_light(self, name, light)
+    }
+
+    fn poll_event(&mut self) -> Option<Push2Event> {
+        Push2::poll_event(self)
+    }
+
+    fn clear_pads(&mut self) -> Result<(), Push2Error> {
+        Push2::clear_pads(self).into_result()
+    }
+}
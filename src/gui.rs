@@ -1,9 +1,11 @@
 use crate::display::{Push2Display, Push2DisplayError};
 use embedded_graphics::{
     image::Image,
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
     pixelcolor::Bgr565,
     prelude::*,
     primitives::{Line, Primitive, PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
 };
 use tinybmp::Bmp;
 
@@ -38,6 +40,42 @@ pub const ENCODER_BAR_HEIGHT: u32 = 8;
 pub const ENCODER_BAR_Y_POS: i32 = 0;
 /// Horizontal padding *inside* the 120px region for the bar.
 pub const ENCODER_BAR_PADDING_X: u32 = 10;
+/// The Y-position (from top) of the parameter name under an encoder bar.
+pub const ENCODER_LABEL_Y_POS: i32 = 16;
+/// The Y-position (from top) of the parameter value under its name.
+pub const ENCODER_VALUE_Y_POS: i32 = 32;
+/// The height of the area used by the bar, label and value of an encoder.
+pub const ENCODER_AREA_HEIGHT: u32 = 48;
+/// The width of a VU meter, centered in its encoder region.
+pub const VU_METER_WIDTH: u32 = 16;
+/// The Y-position (from top) of a VU meter, below the encoder area.
+pub const VU_METER_Y_POS: i32 = 56;
+/// The height of a VU meter, reaching down to the bottom margin of the display.
+pub const VU_METER_HEIGHT: u32 = 96;
+
+/// The height of a row drawn by `GuiApi::draw_list`.
+pub const LIST_ROW_HEIGHT: u32 = 16;
+
+/// The colors of a list drawn by `GuiApi::draw_list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListColors {
+    pub background: Bgr565,
+    pub text: Bgr565,
+    /// The background of the selected row.
+    pub highlight: Bgr565,
+    pub highlight_text: Bgr565,
+}
+
+impl Default for ListColors {
+    fn default() -> Self {
+        Self {
+            background: Bgr565::BLACK,
+            text: Bgr565::WHITE,
+            highlight: Bgr565::WHITE,
+            highlight_text: Bgr565::BLACK,
+        }
+    }
+}
 
 /// A trait for high-level GUI drawing operations on the Push 2 display.
 /// By implementing this as a trait, we separate the core display driver
@@ -65,6 +103,44 @@ pub trait GuiApi {
     /// * `index` - The encoder index (0-7).
     /// * `color` - The stroke color of the outline.
     fn draw_encoder_outline(&mut self, index: u8, color: Bgr565) -> Result<(), Push2DisplayError>;
+
+    /// Draws a line of text centered in one of the 8 encoder regions.
+    ///
+    /// * `index` - The encoder index (0-7).
+    /// * `y` - The top of the text, e.g. `ENCODER_LABEL_Y_POS`.
+    fn draw_encoder_label(
+        &mut self,
+        index: u8,
+        y: i32,
+        text: &str,
+        color: Bgr565,
+    ) -> Result<(), Push2DisplayError>;
+
+    /// Fills the bar/label area of one of the 8 encoder regions.
+    fn clear_encoder_area(&mut self, index: u8, color: Bgr565) -> Result<(), Push2DisplayError>;
+
+    /// Draws a vertical level meter under one of the 8 encoder regions.
+    ///
+    /// * `index` - The encoder index (0-7).
+    /// * `level` - The level (0.0-1.0), filled from the bottom.
+    /// * `background` - The color of the unfilled part.
+    fn draw_vu_meter(
+        &mut self,
+        index: u8,
+        level: f32,
+        color: Bgr565,
+        background: Bgr565,
+    ) -> Result<(), Push2DisplayError>;
+
+    /// Draws a scrolling list of text rows into `area`, one row per item.
+    /// The list scrolls so the selected item is always visible.
+    fn draw_list(
+        &mut self,
+        area: Rectangle,
+        items: &[String],
+        selected: Option<usize>,
+        colors: &ListColors,
+    ) -> Result<(), Push2DisplayError>;
 }
 
 impl GuiApi for Push2Display {
@@ -174,6 +250,123 @@ impl GuiApi for Push2Display {
 
         Ok(())
     }
+
+    fn draw_encoder_label(
+        &mut self,
+        index: u8,
+        y: i32,
+        text: &str,
+        color: Bgr565,
+    ) -> Result<(), Push2DisplayError> {
+        if index > 7 {
+            return Ok(()); // Invalid index
+        }
+        let center_x = (index as u32 * ENCODER_REGION_WIDTH + ENCODER_REGION_WIDTH / 2) as i32;
+        let character_style = MonoTextStyle::new(&FONT_6X10, color);
+        let text_style = TextStyleBuilder::new()
+            .alignment(Alignment::Center)
+            .baseline(Baseline::Top)
+            .build();
+        Text::with_text_style(text, Point::new(center_x, y), character_style, text_style)
+            .draw(self)
+            .unwrap(); // Infallible
+        Ok(())
+    }
+
+    fn clear_encoder_area(&mut self, index: u8, color: Bgr565) -> Result<(), Push2DisplayError> {
+        if index > 7 {
+            return Ok(()); // Invalid index
+        }
+        let top_left = Point::new((index as u32 * ENCODER_REGION_WIDTH) as i32, 0);
+        Rectangle::new(
+            top_left,
+            Size::new(ENCODER_REGION_WIDTH, ENCODER_AREA_HEIGHT),
+        )
+        .into_styled(PrimitiveStyle::with_fill(color))
+        .draw(self)
+        .unwrap(); // Infallible
+        Ok(())
+    }
+
+    fn draw_vu_meter(
+        &mut self,
+        index: u8,
+        level: f32,
+        color: Bgr565,
+        background: Bgr565,
+    ) -> Result<(), Push2DisplayError> {
+        if index > 7 {
+            return Ok(()); // Invalid index
+        }
+        let left = (index as u32 * ENCODER_REGION_WIDTH
+            + (ENCODER_REGION_WIDTH - VU_METER_WIDTH) / 2) as i32;
+        Rectangle::new(
+            Point::new(left, VU_METER_Y_POS),
+            Size::new(VU_METER_WIDTH, VU_METER_HEIGHT),
+        )
+        .into_styled(PrimitiveStyle::with_fill(background))
+        .draw(self)
+        .unwrap(); // Infallible
+        let fill_height = (VU_METER_HEIGHT as f32 * level.clamp(0.0, 1.0)).round() as u32;
+        if fill_height == 0 {
+            return Ok(()); // Nothing to draw
+        }
+        let top = VU_METER_Y_POS + (VU_METER_HEIGHT - fill_height) as i32;
+        Rectangle::new(
+            Point::new(left, top),
+            Size::new(VU_METER_WIDTH, fill_height),
+        )
+        .into_styled(PrimitiveStyle::with_fill(color))
+        .draw(self)
+        .unwrap(); // Infallible
+        Ok(())
+    }
+
+    fn draw_list(
+        &mut self,
+        area: Rectangle,
+        items: &[String],
+        selected: Option<usize>,
+        colors: &ListColors,
+    ) -> Result<(), Push2DisplayError> {
+        area.into_styled(PrimitiveStyle::with_fill(colors.background))
+            .draw(self)
+            .unwrap(); // Infallible
+        let rows = (area.size.height / LIST_ROW_HEIGHT).max(1) as usize;
+        // Scroll just far enough to keep the selected row on screen.
+        let first = match selected {
+            Some(index) if index >= rows => index + 1 - rows,
+            _ => 0,
+        };
+        let max_chars = (area.size.width.saturating_sub(8) / 6) as usize;
+        let text_style = TextStyleBuilder::new().baseline(Baseline::Top).build();
+        for (row, (index, item)) in items.iter().enumerate().skip(first).take(rows).enumerate() {
+            let top = area.top_left.y + (row as u32 * LIST_ROW_HEIGHT) as i32;
+            let text_color = if selected == Some(index) {
+                Rectangle::new(
+                    Point::new(area.top_left.x, top),
+                    Size::new(area.size.width, LIST_ROW_HEIGHT),
+                )
+                .into_styled(PrimitiveStyle::with_fill(colors.highlight))
+                .draw(self)
+                .unwrap(); // Infallible
+                colors.highlight_text
+            } else {
+                colors.text
+            };
+            let text: String = item.chars().take(max_chars).collect();
+            let character_style = MonoTextStyle::new(&FONT_6X10, text_color);
+            Text::with_text_style(
+                &text,
+                Point::new(area.top_left.x + 4, top + 3),
+                character_style,
+                text_style,
+            )
+            .draw(self)
+            .unwrap(); // Infallible
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "waveform")]
@@ -0,0 +1,175 @@
+use crate::{ControlName, LightTarget, PadCoord, Push2, Push2Display, Push2Error};
+use log::warn;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use thiserror::Error;
+
+/// How long the driver thread waits for a command before polling for events again.
+const DRIVER_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Error, Debug)]
+pub enum HandleError {
+    #[error("The driver thread has stopped")]
+    Closed,
+}
+
+type DrawFn = Box<dyn FnOnce(&mut Push2Display) + Send>;
+
+enum HandleCommand {
+    SetPadColor(PadCoord, u8),
+    SetButtonLight(ControlName, u8),
+    Draw(DrawFn),
+    Flush,
+}
+
+/// A cloneable handle queuing light and display commands to a driver thread
+/// that owns the `Push2`.
+///
+/// Created by `Push2::spawn_driver`. Commands are applied in the order they
+/// were sent, right away or once per frame (see `ApplyMode`), and failures
+/// are logged by the driver. The driver also polls for events, so use
+/// `Push2::subscribe` before spawning it to receive them. The driver stops
+/// once every handle is dropped.
+#[derive(Clone)]
+pub struct Push2Handle {
+    tx: Sender<HandleCommand>,
+}
+
+impl Push2Handle {
+    fn send(&self, command: HandleCommand) -> Result<(), HandleError> {
+        self.tx.send(command).map_err(|_| HandleError::Closed)
+    }
+
+    pub fn set_pad_color(&self, coord: PadCoord, color: u8) -> Result<(), HandleError> {
+        self.send(HandleCommand::SetPadColor(coord, color))
+    }
+
+    pub fn set_button_light(&self, name: ControlName, light: u8) -> Result<(), HandleError> {
+        self.send(HandleCommand::SetButtonLight(name, light))
+    }
+
+    /// Draws into the frame buffer on the driver thread. Call `flush` to show it.
+    pub fn draw<F>(&self, draw: F) -> Result<(), HandleError>
+    where
+        F: FnOnce(&mut Push2Display) + Send + 'static,
+    {
+        self.send(HandleCommand::Draw(Box::new(draw)))
+    }
+
+    /// Sends the frame buffer to the display.
+    pub fn flush(&self) -> Result<(), HandleError> {
+        self.send(HandleCommand::Flush)
+    }
+}
+
+/// When the driver applies queued commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApplyMode {
+    /// Each command as soon as it arrives.
+    #[default]
+    Immediate,
+    /// All commands of a frame at once when `flush` is called, so many pad
+    /// changes never show half-applied. Repeated changes to a light within
+    /// a frame only send the last one.
+    PerFrame,
+}
+
+impl Push2 {
+    /// Moves the device to a driver thread applying commands immediately,
+    /// and returns a handle to it. The thread returns the `Push2` when it stops.
+    pub fn spawn_driver(self) -> (Push2Handle, JoinHandle<Push2>) {
+        self.spawn_driver_with(ApplyMode::Immediate)
+    }
+
+    /// Moves the device to a driver thread and returns a handle to it.
+    /// The thread returns the `Push2` when it stops.
+    pub fn spawn_driver_with(mut self, mode: ApplyMode) -> (Push2Handle, JoinHandle<Push2>) {
+        let (tx, rx) = mpsc::channel();
+        let driver = thread::spawn(move || {
+            let mut frame = Vec::new();
+            loop {
+                while self.poll_event().is_some() {}
+                let command = match rx.recv_timeout(DRIVER_POLL_INTERVAL) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return self,
+                };
+                let result = match (mode, command) {
+                    (ApplyMode::Immediate, command) => self.apply_command(command),
+                    (ApplyMode::PerFrame, HandleCommand::Flush) => {
+                        self.apply_frame(std::mem::take(&mut frame))
+                    }
+                    (ApplyMode::PerFrame, command) => {
+                        frame.push(command);
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    warn!("Failed to apply a handle command: {}", e);
+                }
+            }
+        });
+        (Push2Handle { tx }, driver)
+    }
+
+    fn apply_command(&mut self, command: HandleCommand) -> Result<(), Push2Error> {
+        match command {
+            HandleCommand::SetPadColor(coord, color) => {
+                self.set_lights_if_changed(vec![(LightTarget::Pad(coord), color)])
+            }
+            HandleCommand::SetButtonLight(name, light) => {
+                self.set_lights_if_changed(vec![(LightTarget::Button(name), light)])
+            }
+            HandleCommand::Draw(draw) => {
+                draw(&mut self.display);
+                Ok(())
+            }
+            HandleCommand::Flush => {
+                self.display.flush()?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Applies the lights and drawings of a frame, then shows it.
+    fn apply_frame(&mut self, frame: Vec<HandleCommand>) -> Result<(), Push2Error> {
+        let mut lights: Vec<(LightTarget, u8)> = Vec::new();
+        for command in frame {
+            let light = match command {
+                HandleCommand::SetPadColor(coord, color) => (LightTarget::Pad(coord), color),
+                HandleCommand::SetButtonLight(name, light) => (LightTarget::Button(name), light),
+                HandleCommand::Draw(draw) => {
+                    draw(&mut self.display);
+                    continue;
+                }
+                HandleCommand::Flush => continue,
+            };
+            lights.retain(|(target, _)| *target != light.0);
+            lights.push(light);
+        }
+        self.set_lights_if_changed(lights)?;
+        self.display.flush()?;
+        Ok(())
+    }
+
+    /// Sends the lights that differ from `Push2State`, as audio callbacks may repeat them.
+    fn set_lights_if_changed(&mut self, lights: Vec<(LightTarget, u8)>) -> Result<(), Push2Error> {
+        let changed: Vec<(LightTarget, u8)> = lights
+            .into_iter()
+            .filter(|&(target, value)| {
+                let current = match target {
+                    LightTarget::Pad(coord) => self
+                        .state
+                        .pads
+                        .get(coord.y as usize)
+                        .and_then(|row| row.get(coord.x as usize))
+                        .map(|pad| pad.color),
+                    LightTarget::Button(name) => self.state.buttons.get(&name).map(|b| b.light),
+                };
+                current != Some(value)
+            })
+            .collect();
+        self.set_lights(changed).into_result()
+    }
+}
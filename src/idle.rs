@@ -0,0 +1,217 @@
+use crate::display::Push2Display;
+use crate::{ControlName, PadCoord, Push2, Push2Colors, Push2Error, Push2Event};
+use embedded_graphics::{
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+};
+use log::warn;
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// How often the screensaver redraws; the display turns black without a frame for 2 seconds.
+const SCREENSAVER_FRAME: Duration = Duration::from_millis(50);
+
+type ScreensaverFn = Box<dyn FnMut(&mut Push2Display, Duration) + Send>;
+
+/// What the display shows while idle.
+pub enum Screensaver {
+    /// Leaves the display as it is.
+    Keep,
+    /// Turns the display black.
+    Blank,
+    /// Draws a frame from the time spent idle.
+    Animate(ScreensaverFn),
+}
+
+/// Puts the device to sleep after a period without interaction.
+///
+/// Set `Push2::idle` to enable it. `poll_event` then returns `IdleEntered`
+/// once nothing was touched for `timeout`, dims the lights and runs the
+/// screensaver. The next event wakes it up: `IdleExited` is returned first,
+/// with the lights and display showing `Push2State` and the frame buffer
+/// again, followed by the event itself.
+///
+/// Neither the dimming nor the screensaver change `Push2State` or the frame
+/// buffer, so the app can keep drawing while idle.
+pub struct IdleMonitor {
+    pub timeout: Duration,
+    /// When true, the lights are dimmed to half brightness while idle.
+    pub dim_lights: bool,
+    pub screensaver: Screensaver,
+    last_interaction: Instant,
+    idle_since: Option<Instant>,
+    last_frame: Option<Instant>,
+    /// The frame the screensaver draws on, kept apart from the app's.
+    screensaver_frame: Option<Box<[u16]>>,
+}
+
+impl IdleMonitor {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            dim_lights: true,
+            screensaver: Screensaver::Blank,
+            last_interaction: Instant::now(),
+            idle_since: None,
+            last_frame: None,
+            screensaver_frame: None,
+        }
+    }
+
+    pub fn with_dim_lights(mut self, dim_lights: bool) -> Self {
+        self.dim_lights = dim_lights;
+        self
+    }
+
+    pub fn with_screensaver(mut self, screensaver: Screensaver) -> Self {
+        self.screensaver = screensaver;
+        self
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle_since.is_some()
+    }
+
+    /// How long nothing was touched.
+    pub fn idle_time(&self) -> Duration {
+        self.last_interaction.elapsed()
+    }
+}
+
+impl Push2 {
+    /// Enters idle mode when the timeout passed, and runs the screensaver.
+    /// Returns `IdleEntered` when going idle.
+    pub(crate) fn update_idle(&mut self) -> Option<Push2Event> {
+        let mut monitor = self.idle.take()?;
+        let now = Instant::now();
+        let mut event = None;
+        if !monitor.is_idle() && now.duration_since(monitor.last_interaction) >= monitor.timeout {
+            self.enter_idle(&mut monitor, now);
+            event = Some(Push2Event::IdleEntered);
+        }
+        if let Some(idle_since) = monitor.idle_since
+            && monitor
+                .last_frame
+                .is_none_or(|last| now.duration_since(last) >= SCREENSAVER_FRAME)
+        {
+            // Draw on the screensaver's own frame, then give the app its frame back.
+            let frame = monitor
+                .screensaver_frame
+                .get_or_insert_with(|| self.display.frame_buffer.clone());
+            if !matches!(monitor.screensaver, Screensaver::Keep) {
+                mem::swap(&mut self.display.frame_buffer, frame);
+            }
+            match &mut monitor.screensaver {
+                Screensaver::Keep => {}
+                Screensaver::Blank => self.display.clear(Bgr565::BLACK).unwrap(), // Infallible
+                Screensaver::Animate(draw) => draw(&mut self.display, now - idle_since),
+            }
+            if let Err(e) = self.display.flush() {
+                warn!("Failed to draw the screensaver: {}", e);
+            }
+            if !matches!(monitor.screensaver, Screensaver::Keep) {
+                mem::swap(&mut self.display.frame_buffer, frame);
+            }
+            monitor.last_frame = Some(now);
+        }
+        self.idle = Some(monitor);
+        event
+    }
+
+    /// Records an interaction. Returns `IdleExited` if it woke the device up.
+    pub(crate) fn wake(&mut self) -> Option<Push2Event> {
+        let mut monitor = self.idle.take()?;
+        monitor.last_interaction = Instant::now();
+        let event = monitor.idle_since.take().map(|_| {
+            monitor.screensaver_frame = None;
+            if let Err(e) = self.display.flush() {
+                warn!("Failed to restore the display: {}", e);
+            }
+            if monitor.dim_lights
+                && let Err(e) = self.send_lights(|light| light)
+            {
+                warn!("Failed to restore the lights: {}", e);
+            }
+            Push2Event::IdleExited
+        });
+        self.idle = Some(monitor);
+        event
+    }
+
+    fn enter_idle(&mut self, monitor: &mut IdleMonitor, now: Instant) {
+        if monitor.dim_lights
+            && let Err(e) = self.send_lights(Push2Colors::dimmed)
+        {
+            warn!("Failed to dim the lights: {}", e);
+        }
+        monitor.idle_since = Some(now);
+        monitor.last_frame = None;
+    }
+
+    /// Sends every lit pad and button with its light from `Push2State`
+    /// passed through `map`, leaving the state as it is.
+    fn send_lights(&mut self, map: impl Fn(u8) -> u8) -> Result<(), Push2Error> {
+        let pads: Vec<(PadCoord, u8)> = self
+            .button_map
+            .pads()
+            .filter_map(|(_, coord)| {
+                let pad = self
+                    .state
+                    .pads
+                    .get(coord.y as usize)?
+                    .get(coord.x as usize)?;
+                Some((coord, pad.shown()))
+            })
+            .collect();
+        for (coord, color) in pads {
+            if color != Push2Colors::BLACK {
+                self.send_pad_color(coord, map(color))?;
+            }
+        }
+        let buttons: Vec<(ControlName, u8)> = self
+            .button_map
+            .controls()
+            .map(|(_, name)| (name, self.state.buttons.get(&name).map_or(0, |b| b.light)))
+            .collect();
+        for (name, light) in buttons {
+            if light != Push2Colors::BLACK {
+                self.send_button_light(name, map(light))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+
+    #[test]
+    fn keeps_what_the_app_draws_while_idle() {
+        let (backend, tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.idle = Some(IdleMonitor::new(Duration::ZERO));
+        let coord = PadCoord::new(0, 0);
+        push2.set_pad_color(coord, Push2Colors::RED).unwrap();
+        assert!(matches!(push2.poll_event(), Some(Push2Event::IdleEntered)));
+        assert_eq!(push2.state.pads[0][0].color, Push2Colors::RED);
+
+        push2.set_pad_color(coord, Push2Colors::GREEN).unwrap();
+        push2.display.frame_buffer[0] = 0x1234;
+        next_screensaver_frame(&mut push2);
+        assert_eq!(push2.display.frame_buffer[0], 0x1234);
+
+        // The top-left pad wakes it up.
+        tx.send(vec![0x90, 92, 100]).unwrap();
+        assert!(matches!(push2.poll_event(), Some(Push2Event::IdleExited)));
+        assert_eq!(push2.state.pads[0][0].color, Push2Colors::GREEN);
+        assert_eq!(push2.display.frame_buffer[0], 0x1234);
+    }
+
+    /// Lets the screensaver draw another frame.
+    fn next_screensaver_frame(push2: &mut Push2) {
+        std::thread::sleep(SCREENSAVER_FRAME);
+        assert!(push2.poll_event().is_none());
+    }
+}
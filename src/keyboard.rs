@@ -0,0 +1,174 @@
+//! Turns pads and buttons into OS keyboard shortcuts, so the Push can be
+//! used as a macro pad for any software.
+
+use crate::actions::{ActionMap, ActionMapError};
+use crate::{Push2Event, Push2State};
+use enigo::{Direction, Enigo, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum KeyboardError {
+    #[error("Could not connect to the OS input system: {0}")]
+    Connect(#[from] enigo::NewConError),
+    #[error("Failed to send a key: {0}")]
+    Input(#[from] enigo::InputError),
+    #[error("Failed to load the keyboard mapping: {0}")]
+    Mapping(#[from] ActionMapError),
+    #[error("Failed to parse embedded keyboard.ron: {0}")]
+    Default(#[from] Box<ron::error::SpannedError>),
+    #[error("F{0} is not a function key")]
+    FunctionKey(u8),
+}
+
+/// A key on the computer keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Key {
+    /// The key that types a character, e.g. `Char('z')`.
+    Char(char),
+    Ctrl,
+    Shift,
+    Alt,
+    /// The Windows or Command key.
+    Meta,
+    Enter,
+    Escape,
+    Tab,
+    Space,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// A function key, F1-F12.
+    F(u8),
+}
+
+impl Key {
+    fn to_enigo(self) -> Result<enigo::Key, KeyboardError> {
+        use enigo::Key as K;
+        Ok(match self {
+            Key::Char(c) => K::Unicode(c),
+            Key::Ctrl => K::Control,
+            Key::Shift => K::Shift,
+            Key::Alt => K::Alt,
+            Key::Meta => K::Meta,
+            Key::Enter => K::Return,
+            Key::Escape => K::Escape,
+            Key::Tab => K::Tab,
+            Key::Space => K::Space,
+            Key::Backspace => K::Backspace,
+            Key::Delete => K::Delete,
+            Key::Up => K::UpArrow,
+            Key::Down => K::DownArrow,
+            Key::Left => K::LeftArrow,
+            Key::Right => K::RightArrow,
+            Key::Home => K::Home,
+            Key::End => K::End,
+            Key::PageUp => K::PageUp,
+            Key::PageDown => K::PageDown,
+            Key::F(n) => match n {
+                1 => K::F1,
+                2 => K::F2,
+                3 => K::F3,
+                4 => K::F4,
+                5 => K::F5,
+                6 => K::F6,
+                7 => K::F7,
+                8 => K::F8,
+                9 => K::F9,
+                10 => K::F10,
+                11 => K::F11,
+                12 => K::F12,
+                _ => return Err(KeyboardError::FunctionKey(n)),
+            },
+        })
+    }
+}
+
+/// What a bound control types.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyAction {
+    /// Keys pressed in order and released in reverse, e.g. `Shortcut([Ctrl, Char('z')])`.
+    Shortcut(Vec<Key>),
+    /// Text typed as is.
+    Text(String),
+}
+
+/// Sends keyboard shortcuts for the controls bound in an `ActionMap`.
+///
+/// Set `Push2::keyboard` to send them for every event returned by
+/// `poll_event`. The events are still returned to the app.
+pub struct KeyboardBridge {
+    pub map: ActionMap<KeyAction>,
+    enigo: Enigo,
+}
+
+impl KeyboardBridge {
+    pub fn new(map: ActionMap<KeyAction>) -> Result<Self, KeyboardError> {
+        Ok(Self {
+            map,
+            enigo: Enigo::new(&Settings::default())?,
+        })
+    }
+
+    /// The shortcuts in the embedded config/keyboard.ron.
+    pub fn default_map() -> Result<ActionMap<KeyAction>, KeyboardError> {
+        let bindings = ron::from_str(include_str!("../config/keyboard.ron")).map_err(Box::new)?;
+        Ok(ActionMap { bindings })
+    }
+
+    /// Uses "actions/keyboard.ron" from the push2 config directory if the
+    /// user wrote one, and the default shortcuts otherwise.
+    pub fn in_config_dir() -> Result<Self, KeyboardError> {
+        Self::new(ActionMap::in_config_dir("keyboard", Self::default_map()?)?)
+    }
+
+    /// Sends the shortcut bound to an event, if any. Returns true if one was sent.
+    pub fn handle_event(
+        &mut self,
+        event: &Push2Event,
+        state: &Push2State,
+    ) -> Result<bool, KeyboardError> {
+        let Some(action) = self.map.resolve(event, state).cloned() else {
+            return Ok(false);
+        };
+        self.send(&action)?;
+        Ok(true)
+    }
+
+    pub fn send(&mut self, action: &KeyAction) -> Result<(), KeyboardError> {
+        match action {
+            KeyAction::Shortcut(keys) => {
+                let keys = keys
+                    .iter()
+                    .map(|k| k.to_enigo())
+                    .collect::<Result<Vec<_>, _>>()?;
+                for key in &keys {
+                    self.enigo.key(*key, Direction::Press)?;
+                }
+                for key in keys.iter().rev() {
+                    self.enigo.key(*key, Direction::Release)?;
+                }
+            }
+            KeyAction::Text(text) => self.enigo.text(text)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_map_parses() {
+        let map = KeyboardBridge::default_map().unwrap();
+        assert!(!map.bindings.is_empty());
+    }
+}
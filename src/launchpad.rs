@@ -0,0 +1,208 @@
+use crate::device::live_palette_color;
+use crate::grid::GridController;
+use crate::midi_handler::MidiHandler;
+use crate::{CONTROL_CHANGE, ControlName, NOTE_OFF, NOTE_ON, PadCoord, Push2Error, Push2Event};
+use midir::{MidiInputConnection, MidiOutputConnection};
+use std::sync::mpsc::{self, Receiver};
+
+/// The Launchpad models that have a programmer mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchpadModel {
+    MiniMk3,
+    X,
+    ProMk3,
+}
+
+impl LaunchpadModel {
+    /// A substring of the name of the port used in programmer mode.
+    pub fn port_name(&self) -> &'static str {
+        match self {
+            LaunchpadModel::MiniMk3 => "Launchpad Mini MK3 LPMiniMK3 MIDI",
+            LaunchpadModel::X => "Launchpad X LPX MIDI",
+            LaunchpadModel::ProMk3 => "Launchpad Pro MK3 LPProMK3 MIDI",
+        }
+    }
+
+    /// The SysEx device id of the model.
+    fn device_id(&self) -> u8 {
+        match self {
+            LaunchpadModel::MiniMk3 => 0x0D,
+            LaunchpadModel::X => 0x0C,
+            LaunchpadModel::ProMk3 => 0x0E,
+        }
+    }
+}
+
+/// The top row buttons, left to right, as the Push 2 buttons with the same role.
+const TOP_BUTTONS: [ControlName; 8] = [
+    ControlName::Up,
+    ControlName::Down,
+    ControlName::Left,
+    ControlName::Right,
+    ControlName::Session,
+    ControlName::Note,
+    ControlName::Layout,
+    ControlName::User,
+];
+
+/// The side buttons, top to bottom. Like the Push 2 scene buttons, they are
+/// the Beat1_* buttons.
+const SIDE_BUTTONS: [ControlName; 8] = [
+    ControlName::Beat1_4,
+    ControlName::Beat1_4t,
+    ControlName::Beat1_8,
+    ControlName::Beat1_8t,
+    ControlName::Beat1_16,
+    ControlName::Beat1_16t,
+    ControlName::Beat1_32,
+    ControlName::Beat1_32t,
+];
+
+/// A Novation Launchpad in programmer mode, as a `GridController`.
+///
+/// The top row buttons map to Up/Down/Left/Right/Session/Note/Layout/User
+/// and the side buttons to the Beat1_* buttons, so Push 2 grid apps work
+/// unchanged.
+pub struct Launchpad {
+    pub model: LaunchpadModel,
+    midi_out: MidiOutputConnection,
+    event_rx: Receiver<Vec<u8>>,
+    _conn_in: MidiInputConnection<()>,
+}
+
+impl Launchpad {
+    /// Connects to a Launchpad and switches it to programmer mode.
+    pub fn new(model: LaunchpadModel) -> Result<Self, Push2Error> {
+        Self::with_port(model, model.port_name())
+    }
+
+    /// Connects using a custom port name, for systems that name ports differently.
+    pub fn with_port(model: LaunchpadModel, port_name: &str) -> Result<Self, Push2Error> {
+        let (tx, rx) = mpsc::channel();
+        let _conn_in = MidiHandler::connect_input(port_name, move |_stamp, message, _| {
+            let _ = tx.send(message.to_vec());
+        })?;
+        let midi_out = MidiHandler::connect_output(port_name)?;
+        let mut launchpad = Self {
+            model,
+            midi_out,
+            event_rx: rx,
+            _conn_in,
+        };
+        launchpad.set_programmer_mode(true)?;
+        launchpad.clear_pads()?;
+        Ok(launchpad)
+    }
+
+    /// Switches between programmer mode and the Live mode of the device.
+    pub fn set_programmer_mode(&mut self, enabled: bool) -> Result<(), Push2Error> {
+        let message = [
+            0xF0,
+            0x00,
+            0x20,
+            0x29,
+            0x02,
+            self.model.device_id(),
+            0x0E,
+            enabled as u8,
+            0xF7,
+        ];
+        self.midi_out.send(&message)?;
+        Ok(())
+    }
+
+    /// The note of a pad: 11 on the bottom-left pad, 88 on the top-right one.
+    pub fn pad_note(coord: PadCoord) -> Option<u8> {
+        (coord.x < 8 && coord.y < 8).then(|| 10 * (8 - coord.y) + coord.x + 1)
+    }
+
+    pub fn pad_coord(note: u8) -> Option<PadCoord> {
+        let (row, column) = (note / 10, note % 10);
+        ((1..=8).contains(&row) && (1..=8).contains(&column)).then(|| PadCoord {
+            x: column - 1,
+            y: 8 - row,
+        })
+    }
+
+    /// The CC address of a button, if the Launchpad has it.
+    pub fn button_address(name: ControlName) -> Option<u8> {
+        if let Some(i) = TOP_BUTTONS.iter().position(|b| *b == name) {
+            return Some(91 + i as u8);
+        }
+        SIDE_BUTTONS
+            .iter()
+            .position(|b| *b == name)
+            .map(|i| 89 - 10 * i as u8)
+    }
+
+    pub fn button_name(address: u8) -> Option<ControlName> {
+        match address {
+            91..=98 => Some(TOP_BUTTONS[(address - 91) as usize]),
+            19..=89 if address % 10 == 9 => Some(SIDE_BUTTONS[((89 - address) / 10) as usize]),
+            _ => None,
+        }
+    }
+}
+
+impl GridController for Launchpad {
+    fn set_pad_color(&mut self, coord: PadCoord, color: u8) -> Result<(), Push2Error> {
+        if let Some(note) = Self::pad_note(coord) {
+            self.midi_out
+                .send(&[NOTE_ON, note, live_palette_color(color)])?;
+        }
+        Ok(())
+    }
+
+    fn set_button_light(&mut self, name: ControlName, light: u8) -> Result<(), Push2Error> {
+        if let Some(address) = Self::button_address(name) {
+            self.midi_out
+                .send(&[CONTROL_CHANGE, address, live_palette_color(light)])?;
+        }
+        Ok(())
+    }
+
+    fn poll_event(&mut self) -> Option<Push2Event> {
+        while let Ok(message) = self.event_rx.try_recv() {
+            let [status, address, value, ..] = message[..] else {
+                continue;
+            };
+            let event = match status {
+                NOTE_ON | NOTE_OFF => Self::pad_coord(address).map(|coord| {
+                    if status == NOTE_ON && value > 0 {
+                        Push2Event::PadPressed {
+                            coord,
+                            velocity: value,
+                        }
+                    } else {
+                        Push2Event::PadReleased {
+                            coord,
+                            release_velocity: value,
+                        }
+                    }
+                }),
+                CONTROL_CHANGE => Self::button_name(address).map(|name| {
+                    if value > 0 {
+                        Push2Event::ButtonPressed {
+                            name,
+                            velocity: value,
+                        }
+                    } else {
+                        Push2Event::ButtonReleased { name }
+                    }
+                }),
+                _ => None,
+            };
+            if event.is_some() {
+                return event;
+            }
+        }
+        None
+    }
+}
+
+impl Drop for Launchpad {
+    fn drop(&mut self) {
+        // Hand the device back to its Live mode.
+        let _ = self.set_programmer_mode(false);
+    }
+}
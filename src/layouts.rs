@@ -0,0 +1,344 @@
+use crate::music::{Key, Scale};
+use crate::{PadCoord, Push2, Push2Colors, Push2Error, Push2Event};
+
+/// How notes are spread over the 8x8 grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayoutKind {
+    /// Each row continues where the row below it ended (8 semitones per row).
+    Chromatic,
+    /// Only notes of the scale are placed on the grid. Each row starts
+    /// three scale degrees (a fourth) above the row below it.
+    InKey,
+    /// Isomorphic chromatic layout, each row a perfect fourth (5 semitones)
+    /// above the row below it. This is the default of Ableton's Note mode.
+    Fourths,
+}
+
+/// The musical role of the note under a pad, used for coloring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadRole {
+    Root,
+    InScale,
+    OutOfScale,
+}
+
+/// The pad colors used for each `PadRole`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutColors {
+    pub root: u8,
+    pub in_scale: u8,
+    pub out_of_scale: u8,
+}
+
+impl Default for LayoutColors {
+    fn default() -> Self {
+        Self {
+            root: Push2Colors::BLUE,
+            in_scale: Push2Colors::WHITE_BRIGHT,
+            out_of_scale: Push2Colors::BLACK,
+        }
+    }
+}
+
+/// Maps grid pads to MIDI notes.
+///
+/// Rows are counted from the bottom of the grid (y = 7), so the lowest
+/// note is always on the bottom-left pad, like on the stock Push.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteLayout {
+    pub kind: LayoutKind,
+    /// The root pitch class (0 = C, 11 = B).
+    pub root: u8,
+    pub scale: Scale,
+    /// The octave of the bottom-left pad. Its note is `12 * octave + root`.
+    pub octave: u8,
+    pub colors: LayoutColors,
+}
+
+impl Default for NoteLayout {
+    fn default() -> Self {
+        Self::new(LayoutKind::Fourths, 0, Scale::Major)
+    }
+}
+
+impl NoteLayout {
+    /// Creates a layout starting on octave 3 (the bottom-left pad is note 36 for C).
+    pub fn new(kind: LayoutKind, root: u8, scale: Scale) -> Self {
+        Self {
+            kind,
+            root: root % 12,
+            scale,
+            octave: 3,
+            colors: LayoutColors::default(),
+        }
+    }
+
+    /// The key (root and scale) of this layout.
+    pub fn key(&self) -> Key {
+        Key::new(self.root, self.scale)
+    }
+
+    /// The MIDI note of the bottom-left pad.
+    pub fn base_note(&self) -> u8 {
+        self.octave.saturating_mul(12).saturating_add(self.root)
+    }
+
+    /// Returns the MIDI note under a pad, or `None` if it falls outside 0-127.
+    pub fn note_for(&self, coord: PadCoord) -> Option<u8> {
+        if coord.x > 7 || coord.y > 7 {
+            return None;
+        }
+        let row = coord.flipped_y().y as u32;
+        let col = coord.x as u32;
+        let base = self.base_note() as u32;
+        let note = match self.kind {
+            LayoutKind::Chromatic => base + row * 8 + col,
+            LayoutKind::Fourths => base + row * 5 + col,
+            LayoutKind::InKey => {
+                let intervals = self.scale.intervals();
+                let len = intervals.len() as u32;
+                let degree = row * 3 + col;
+                base + (degree / len) * 12 + intervals[(degree % len) as usize] as u32
+            }
+        };
+        u8::try_from(note).ok().filter(|n| *n <= 127)
+    }
+
+    /// Returns every pad that plays the given note.
+    /// Isomorphic layouts can place the same note on several pads.
+    pub fn coords_for_note(&self, note: u8) -> Vec<PadCoord> {
+        let mut coords = Vec::new();
+        for y in 0..8 {
+            for x in 0..8 {
+                let coord = PadCoord { x, y };
+                if self.note_for(coord) == Some(note) {
+                    coords.push(coord);
+                }
+            }
+        }
+        coords
+    }
+
+    /// Returns the role of a note relative to the layout's root and scale.
+    pub fn note_role(&self, note: u8) -> PadRole {
+        let interval = (note as i16 - self.root as i16).rem_euclid(12) as u8;
+        if interval == 0 {
+            PadRole::Root
+        } else if self.scale.contains_interval(interval) {
+            PadRole::InScale
+        } else {
+            PadRole::OutOfScale
+        }
+    }
+
+    /// Returns the role of the note under a pad.
+    pub fn pad_role(&self, coord: PadCoord) -> Option<PadRole> {
+        self.note_for(coord).map(|note| self.note_role(note))
+    }
+
+    /// Returns the palette color a pad should show under this layout.
+    pub fn pad_color(&self, coord: PadCoord) -> u8 {
+        match self.pad_role(coord) {
+            Some(PadRole::Root) => self.colors.root,
+            Some(PadRole::InScale) => self.colors.in_scale,
+            Some(PadRole::OutOfScale) => self.colors.out_of_scale,
+            None => Push2Colors::BLACK,
+        }
+    }
+
+    /// Shifts the layout up by one octave, if the top pad stays in MIDI range.
+    pub fn octave_up(&mut self) {
+        let mut shifted = *self;
+        shifted.octave += 1;
+        if shifted.note_for(PadCoord { x: 7, y: 0 }).is_some() {
+            *self = shifted;
+        }
+    }
+
+    /// Shifts the layout down by one octave.
+    pub fn octave_down(&mut self) {
+        self.octave = self.octave.saturating_sub(1);
+    }
+
+    /// Colors every pad of the grid according to this layout.
+    pub fn paint(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        for y in 0..8 {
+            for x in 0..8 {
+                let coord = PadCoord { x, y };
+                push2.set_pad_color(coord, self.pad_color(coord))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The region a pad belongs to in the drum layout, with its index inside that region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrumPadRole {
+    /// One of the 16 drum pads in the bottom-left quadrant (0 = bottom-left, counting up in rows of 4).
+    Drum(u8),
+    /// One of the 16 loop-length pads in the bottom-right quadrant (0 = top-left, reading left to right).
+    LoopLength(u8),
+    /// One of the 32 sequencer steps in the top four rows (0 = top-left, reading left to right).
+    Step(u8),
+}
+
+/// A pad event scoped to a region of the drum layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrumEvent {
+    DrumPressed { index: u8, note: u8, velocity: u8 },
+    DrumReleased { index: u8, note: u8 },
+    LoopLengthPressed { index: u8 },
+    LoopLengthReleased { index: u8 },
+    StepPressed { index: u8, velocity: u8 },
+    StepReleased { index: u8 },
+}
+
+/// The pad colors used for each drum layout region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrumColors {
+    pub drum: u8,
+    pub loop_length: u8,
+    pub step: u8,
+}
+
+impl Default for DrumColors {
+    fn default() -> Self {
+        Self {
+            drum: Push2Colors::YELLOW,
+            loop_length: Push2Colors::WHITE_LOW,
+            step: Push2Colors::BLACK,
+        }
+    }
+}
+
+/// Splits the grid like the stock Push drum mode: a 4x4 drum rack in the
+/// bottom-left quadrant, a 4x4 loop-length selector in the bottom-right
+/// quadrant, and 32 sequencer steps in the top four rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrumLayout {
+    /// The MIDI note of drum pad 0 (36 is the General MIDI kick).
+    pub base_note: u8,
+    pub colors: DrumColors,
+}
+
+impl Default for DrumLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DrumLayout {
+    pub const DRUM_COUNT: u8 = 16;
+    pub const LOOP_LENGTH_COUNT: u8 = 16;
+    pub const STEP_COUNT: u8 = 32;
+
+    pub fn new() -> Self {
+        Self {
+            base_note: 36,
+            colors: DrumColors::default(),
+        }
+    }
+
+    /// Returns the region and index of a pad.
+    pub fn role(&self, coord: PadCoord) -> Option<DrumPadRole> {
+        match (coord.x, coord.y) {
+            (0..=7, 0..=3) => Some(DrumPadRole::Step(coord.y * 8 + coord.x)),
+            (0..=3, 4..=7) => Some(DrumPadRole::Drum((7 - coord.y) * 4 + coord.x)),
+            (4..=7, 4..=7) => Some(DrumPadRole::LoopLength((coord.y - 4) * 4 + coord.x - 4)),
+            _ => None,
+        }
+    }
+
+    /// Returns the pad for a region and index, or `None` if the index is out of range.
+    pub fn coord_for(&self, role: DrumPadRole) -> Option<PadCoord> {
+        match role {
+            DrumPadRole::Drum(i) if i < Self::DRUM_COUNT => Some(PadCoord {
+                x: i % 4,
+                y: 7 - i / 4,
+            }),
+            DrumPadRole::LoopLength(i) if i < Self::LOOP_LENGTH_COUNT => Some(PadCoord {
+                x: 4 + i % 4,
+                y: 4 + i / 4,
+            }),
+            DrumPadRole::Step(i) if i < Self::STEP_COUNT => Some(PadCoord { x: i % 8, y: i / 8 }),
+            _ => None,
+        }
+    }
+
+    /// Returns the MIDI note of a drum pad. Pads outside the drum region have no note.
+    pub fn note_for(&self, coord: PadCoord) -> Option<u8> {
+        match self.role(coord)? {
+            DrumPadRole::Drum(i) => self.base_note.checked_add(i).filter(|n| *n <= 127),
+            _ => None,
+        }
+    }
+
+    /// Returns the drum pad index that plays a MIDI note.
+    pub fn drum_for_note(&self, note: u8) -> Option<u8> {
+        note.checked_sub(self.base_note)
+            .filter(|i| *i < Self::DRUM_COUNT)
+    }
+
+    /// Translates a grid event into a region-scoped `DrumEvent`.
+    /// Non-pad events return `None`.
+    pub fn translate(&self, event: &Push2Event) -> Option<DrumEvent> {
+        match *event {
+            Push2Event::PadPressed { coord, velocity } => match self.role(coord)? {
+                DrumPadRole::Drum(index) => Some(DrumEvent::DrumPressed {
+                    index,
+                    note: self.note_for(coord)?,
+                    velocity,
+                }),
+                DrumPadRole::LoopLength(index) => Some(DrumEvent::LoopLengthPressed { index }),
+                DrumPadRole::Step(index) => Some(DrumEvent::StepPressed { index, velocity }),
+            },
+            Push2Event::PadReleased { coord, .. } => match self.role(coord)? {
+                DrumPadRole::Drum(index) => Some(DrumEvent::DrumReleased {
+                    index,
+                    note: self.note_for(coord)?,
+                }),
+                DrumPadRole::LoopLength(index) => Some(DrumEvent::LoopLengthReleased { index }),
+                DrumPadRole::Step(index) => Some(DrumEvent::StepReleased { index }),
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the palette color a pad should show for its region.
+    pub fn pad_color(&self, coord: PadCoord) -> u8 {
+        match self.role(coord) {
+            Some(DrumPadRole::Drum(_)) => self.colors.drum,
+            Some(DrumPadRole::LoopLength(_)) => self.colors.loop_length,
+            Some(DrumPadRole::Step(_)) => self.colors.step,
+            None => Push2Colors::BLACK,
+        }
+    }
+
+    /// Colors every pad of the grid according to its region.
+    pub fn paint(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        for y in 0..8 {
+            for x in 0..8 {
+                let coord = PadCoord { x, y };
+                push2.set_pad_color(coord, self.pad_color(coord))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_do_not_overflow() {
+        let mut layout = NoteLayout::new(LayoutKind::Chromatic, 2, Scale::Major);
+        assert_eq!(layout.note_role(0), PadRole::OutOfScale);
+        assert_eq!(layout.note_role(255), PadRole::OutOfScale);
+        // A root set past 11 is still a pitch class.
+        layout.root = 14;
+        assert_eq!(layout.note_role(2), PadRole::Root);
+        assert_eq!(layout.note_role(4), PadRole::InScale);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{ClockTick, LightTarget, Push2, Push2Colors, Push2Error};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+/// How an animated light changes over one period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Animation {
+    /// `color` for the first half of the period, the base light for the second.
+    Blink(u8),
+    /// Fades `color` in and out.
+    Breathe(u8),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveAnimation {
+    animation: Animation,
+    /// The length of one blink or breath, in quarter notes.
+    period_beats: f32,
+    /// The light shown before the animation started, restored when it stops.
+    base: Option<u8>,
+}
+
+impl ActiveAnimation {
+    /// The light shown at a clock position.
+    fn light_at(&self, tick: &ClockTick, base: u8) -> u8 {
+        let period = ((self.period_beats * tick.ppqn as f32).round() as u64).max(2);
+        let phase = (tick.tick % period) as f32 / period as f32;
+        match self.animation {
+            Animation::Blink(color) if phase < 0.5 => color,
+            Animation::Blink(_) => base,
+            Animation::Breathe(color) => {
+                let level = 0.5 - 0.5 * (phase * TAU).cos();
+                let (h, s, v) = Push2Colors::palette_hsv(color);
+                Push2Colors::hsv_to_nearest_index(h, s, v * level)
+            }
+        }
+    }
+}
+
+/// Blinks and breathes pad and button lights in software, in time with a `Clock`.
+///
+/// This does not rely on the firmware's MIDI channel animations, so it works
+/// the same on every device. Call `tick` for every `ClockTick`; stopping an
+/// animation restores the light it started from.
+#[derive(Debug, Default)]
+pub struct LedAnimator {
+    animations: HashMap<LightTarget, ActiveAnimation>,
+}
+
+impl LedAnimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Animates a light, one blink or breath every `period_beats` quarter notes.
+    /// Replaces any animation already running on it.
+    pub fn start(&mut self, target: LightTarget, animation: Animation, period_beats: f32) {
+        let base = self.animations.get(&target).and_then(|a| a.base);
+        self.animations.insert(
+            target,
+            ActiveAnimation {
+                animation,
+                period_beats,
+                base,
+            },
+        );
+    }
+
+    pub fn blink(&mut self, target: LightTarget, color: u8, period_beats: f32) {
+        self.start(target, Animation::Blink(color), period_beats);
+    }
+
+    pub fn breathe(&mut self, target: LightTarget, color: u8, period_beats: f32) {
+        self.start(target, Animation::Breathe(color), period_beats);
+    }
+
+    pub fn is_animating(&self, target: LightTarget) -> bool {
+        self.animations.contains_key(&target)
+    }
+
+    /// Stops the animation of a light and restores its base color.
+    pub fn stop(&mut self, push2: &mut Push2, target: LightTarget) -> Result<(), Push2Error> {
+        match self.animations.remove(&target).and_then(|a| a.base) {
+            Some(base) => push2.set_lights([(target, base)]).into_result(),
+            None => Ok(()),
+        }
+    }
+
+    /// Stops every animation and restores the base colors.
+    pub fn stop_all(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let restored: Vec<(LightTarget, u8)> = self
+            .animations
+            .drain()
+            .filter_map(|(target, a)| a.base.map(|base| (target, base)))
+            .collect();
+        push2.set_lights(restored).into_result()
+    }
+
+    /// Sends the lights of every animation at a clock position, if they changed.
+    pub fn tick(&mut self, push2: &mut Push2, tick: &ClockTick) -> Result<(), Push2Error> {
+        let mut lights = Vec::new();
+        for (target, active) in &mut self.animations {
+            let current = current_light(push2, *target);
+            let base = *active.base.get_or_insert(current);
+            let light = active.light_at(tick, base);
+            if light != current {
+                lights.push((*target, light));
+            }
+        }
+        push2.set_lights(lights).into_result()
+    }
+}
+
+/// The light a pad or button shows, according to `Push2State`.
+fn current_light(push2: &Push2, target: LightTarget) -> u8 {
+    match target {
+        LightTarget::Pad(coord) => push2
+            .state
+            .pads
+            .get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .map_or(0, |pad| pad.color),
+        LightTarget::Button(name) => push2.state.buttons.get(&name).map_or(0, |b| b.light),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockBackend, PadCoord};
+
+    #[test]
+    fn ignores_pads_off_the_grid() {
+        let (backend, _tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        let mut animator = LedAnimator::new();
+        animator.blink(LightTarget::Pad(PadCoord::new(8, 9)), Push2Colors::RED, 1.0);
+        let tick = ClockTick { tick: 0, ppqn: 24 };
+        animator.tick(&mut push2, &tick).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+//! Named snapshots of the pad and button lights, applied at once or crossfaded.
+
+use crate::{ControlName, LightTarget, PadCoord, Push2, Push2Colors, Push2Error, Push2State};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The colors of every pad and of some buttons, e.g. the look of one mode.
+///
+/// Buttons not in `buttons` are left as they are when the scene is applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedScene {
+    pub name: String,
+    /// Palette colors by row, then column.
+    pub pads: [[u8; 8]; 8],
+    pub buttons: HashMap<ControlName, u8>,
+}
+
+impl LedScene {
+    /// A scene with every pad off.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pads: [[0; 8]; 8],
+            buttons: HashMap::new(),
+        }
+    }
+
+    /// The lights currently set on the device.
+    pub fn capture(name: impl Into<String>, state: &Push2State) -> Self {
+        Self {
+            name: name.into(),
+            pads: state.pads.map(|row| row.map(|pad| pad.color)),
+            buttons: state
+                .buttons
+                .iter()
+                .map(|(name, button)| (*name, button.light))
+                .collect(),
+        }
+    }
+
+    pub fn with_pad(mut self, coord: PadCoord, color: u8) -> Self {
+        self.set_pad(coord, color);
+        self
+    }
+
+    pub fn with_button(mut self, name: ControlName, light: u8) -> Self {
+        self.buttons.insert(name, light);
+        self
+    }
+
+    /// Colors a pad. Pads off the grid are ignored.
+    pub fn set_pad(&mut self, coord: PadCoord, color: u8) {
+        if let Some(pad) = self
+            .pads
+            .get_mut(coord.y as usize)
+            .and_then(|row| row.get_mut(coord.x as usize))
+        {
+            *pad = color;
+        }
+    }
+
+    /// The scene `t` (0.0-1.0) of the way to `to`. Pads are blended through
+    /// the nearest palette colors; buttons switch halfway.
+    pub fn blend(&self, to: &LedScene, t: f32) -> LedScene {
+        let mut pads = self.pads;
+        for (row, to_row) in pads.iter_mut().zip(&to.pads) {
+            for (pad, to_pad) in row.iter_mut().zip(to_row) {
+                *pad = Push2Colors::blend(*pad, *to_pad, t);
+            }
+        }
+        let mut buttons = self.buttons.clone();
+        if t >= 0.5 {
+            buttons.extend(&to.buttons);
+        }
+        LedScene {
+            name: to.name.clone(),
+            pads,
+            buttons,
+        }
+    }
+
+    /// The lights that differ from `state`.
+    fn changes(&self, state: &Push2State) -> Vec<(LightTarget, u8)> {
+        let pads = (0..8u8).flat_map(|y| (0..8u8).map(move |x| PadCoord { x, y }));
+        let pads = pads
+            .map(|coord| {
+                let color = self.pads[coord.y as usize][coord.x as usize];
+                (coord, color)
+            })
+            .filter(|(coord, color)| state.pads[coord.y as usize][coord.x as usize].color != *color)
+            .map(|(coord, color)| (LightTarget::Pad(coord), color));
+        let buttons = self
+            .buttons
+            .iter()
+            .filter(|(name, light)| state.buttons.get(name).map_or(0, |b| b.light) != **light)
+            .map(|(name, light)| (LightTarget::Button(*name), *light));
+        pads.chain(buttons).collect()
+    }
+}
+
+/// A crossfade between two scenes, from `Push2::fade_to_scene`.
+///
+/// Call `update` from the main loop until it returns true.
+#[derive(Debug, Clone)]
+pub struct SceneFade {
+    from: LedScene,
+    to: LedScene,
+    started: Instant,
+    duration: Duration,
+}
+
+impl SceneFade {
+    pub fn new(from: LedScene, to: LedScene, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            started: Instant::now(),
+            duration,
+        }
+    }
+
+    /// The scene faded to.
+    pub fn target(&self) -> &LedScene {
+        &self.to
+    }
+
+    /// How far the fade has got, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.started.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// Sends the lights that changed since the last update. Returns true
+    /// once the target scene is shown.
+    pub fn update(&self, push2: &mut Push2) -> Result<bool, Push2Error> {
+        let t = self.progress();
+        push2.apply_scene(&self.from.blend(&self.to, t))?;
+        Ok(t >= 1.0)
+    }
+}
+
+impl Push2 {
+    /// Sets every light of a scene, sending only the ones that differ from
+    /// what is shown.
+    pub fn apply_scene(&mut self, scene: &LedScene) -> Result<(), Push2Error> {
+        let lights = scene.changes(&self.state);
+        self.set_lights(lights).into_result()
+    }
+
+    /// Starts a crossfade from the current lights to a scene.
+    pub fn fade_to_scene(&self, scene: LedScene, duration: Duration) -> SceneFade {
+        SceneFade::new(LedScene::capture("", &self.state), scene, duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_pads_and_switches_buttons_halfway() {
+        let from = LedScene::new("off").with_button(ControlName::Play, 0);
+        let to = LedScene::new("red")
+            .with_pad(PadCoord::new(0, 0), Push2Colors::RED)
+            .with_button(ControlName::Play, 127);
+        assert_eq!(from.blend(&to, 0.0).pads[0][0], Push2Colors::BLACK);
+        assert_eq!(from.blend(&to, 1.0).pads[0][0], Push2Colors::RED);
+        assert_eq!(from.blend(&to, 0.2).buttons[&ControlName::Play], 0);
+        assert_eq!(from.blend(&to, 0.6).buttons[&ControlName::Play], 127);
+    }
+
+    #[test]
+    fn only_changed_lights_are_sent() {
+        let mut state = Push2State::new();
+        state.pads[0][1].color = Push2Colors::RED;
+        let scene = LedScene::new("scene")
+            .with_pad(PadCoord::new(1, 0), Push2Colors::RED)
+            .with_pad(PadCoord::new(2, 0), Push2Colors::GREEN);
+        assert_eq!(
+            scene.changes(&state),
+            vec![(LightTarget::Pad(PadCoord::new(2, 0)), Push2Colors::GREEN)]
+        );
+    }
+}
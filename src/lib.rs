@@ -1,24 +1,207 @@
 // --- Module Declarations ---
+pub mod actions;
+pub mod animations;
 pub mod app_config;
+pub mod app_host;
+pub mod app_registry;
+pub mod apps;
+pub mod arpeggiator;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod backend;
+pub mod batch;
+pub mod browser;
+pub mod button_caps;
 pub mod button_map;
+pub mod calibration;
+pub mod cliplauncher;
+pub mod clock;
+pub mod color_correction;
 pub mod colors;
+#[cfg(feature = "watch")]
+pub mod config_watch;
+pub mod device;
+pub mod diagnostics;
 pub mod display;
+#[cfg(feature = "egui")]
+pub mod egui_ui;
+#[cfg(feature = "emulator")]
+pub mod emulator;
+pub mod encoder_bank;
+#[cfg(feature = "json")]
+pub mod event_log;
+pub mod fonts;
+pub mod formatters;
+pub mod fps;
+pub mod frame_codec;
+pub mod grid;
 pub mod gui;
+pub mod handle;
+pub mod idle;
+#[cfg(feature = "keyboard")]
+pub mod keyboard;
+pub mod launchpad;
+pub mod layouts;
+pub mod led_animator;
+pub mod led_scene;
+pub mod looper;
+pub mod low_power;
 pub mod midi_handler;
+pub mod midi_learn;
+pub mod midi_parser;
+#[cfg(feature = "mirror")]
+pub mod mirror;
+pub mod mixer;
+pub mod music;
+pub mod note_output;
+pub mod note_repeat;
+pub mod pad_chord;
+pub mod pad_stats;
+pub mod padded_grid;
+pub mod palette;
+pub mod params;
+pub mod playhead;
+pub mod pressure_lights;
+pub mod project;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "recording")]
+pub mod recording;
+pub mod region;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod reserved;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod sequencer;
+pub mod session;
+pub mod settings;
+#[cfg(feature = "slint")]
+pub mod slint_platform;
 pub mod state;
+pub mod status_bar;
+pub mod subscription;
+pub mod theme;
+pub mod toggle;
+pub mod touch_strip;
+pub mod transport;
+pub mod undo;
+pub mod velocity;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod web;
+#[cfg(any(feature = "remote", feature = "emulator"))]
+mod websocket;
 // --- Public API Re-exports ---
-pub use app_config::{AppConfig, ConfigError};
-pub use button_map::{ButtonMap, ButtonMapError, ControlName, EncoderName, PadCoord};
+pub use actions::{ActionBinding, ActionEvent, ActionMap, ActionMapError, Gesture};
+pub use animations::grid::{AnimationHandle, GridAnimation, GridAnimator};
+pub use app_config::{
+    AppConfig, ConfigError, ConfigFile, ConfigOverrides, MessageFilter, MidiBackend,
+    PortSelectionPolicy, RouteEndpoint, RouteRule,
+};
+pub use app_host::{AppHost, PushApp};
+pub use app_registry::{AppEntry, AppFactory, AppRegistry};
+pub use arpeggiator::{ArpColors, ArpMode, Arpeggiator};
+#[cfg(feature = "audio")]
+pub use audio::{AudioEngine, AudioError, AudioRecorder, VoiceId, VoiceSettings};
+pub use backend::{
+    Backend, BackendParts, FrameSink, MidiOut, MockBackend, NullDisplay, NullMidiOut,
+};
+pub use batch::{BatchResult, LightTarget};
+pub use browser::{Browser, BrowserEntry, BrowserError};
+pub use button_caps::{ButtonCapabilities, LedKind};
+pub use button_map::{ButtonMap, ButtonMapError, ControlName, EncoderName, GridOrigin, PadCoord};
+pub use calibration::{CalibrationError, PadCalibration};
+pub use cliplauncher::{ClipColors, ClipLauncher, ClipSlot, ClipState};
+pub use clock::{Clock, ClockSource, ClockTick};
+pub use color_correction::ColorCorrection;
 pub use colors as Push2Colors;
-pub use display::{Push2Display, Push2DisplayError};
-use embedded_graphics::prelude::Point;
-pub use gui::GuiApi;
-pub use midi_handler::{MidiHandler, MidiHandlerError};
-use midir::{MidiInputConnection, MidiOutputConnection, SendError};
-pub use state::Push2State;
+#[cfg(feature = "watch")]
+pub use config_watch::{ConfigWatchError, ConfigWatcher};
+pub use device::{Push1, Push1Model, Push2Model, PushDevice};
+pub use diagnostics::{DiagnosticRegion, DiagnosticResult, DiagnosticsReport};
+#[cfg(not(target_arch = "wasm32"))]
+pub use display::UsbDisplay;
+pub use display::{Frame, Push2Display, Push2DisplayError, SubDisplay};
+#[cfg(feature = "egui")]
+pub use egui_ui::EguiDisplay;
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_10X20},
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+#[cfg(feature = "emulator")]
+pub use emulator::{EmulatorError, Push2Virtual};
+pub use encoder_bank::EncoderBank;
+#[cfg(feature = "json")]
+pub use event_log::{EventLogError, EventRecord, EventRecorder, EventReplayer};
+#[cfg(feature = "fontdue")]
+pub use fonts::TtfFont;
+pub use fonts::{BdfFont, Font, FontError, FontSize, TextStyle};
+pub use fps::FpsStats;
+pub use frame_codec::{FrameCodecError, FrameDecoder, FrameEncoder};
+pub use grid::GridController;
+pub use gui::{GuiApi, ListColors};
+pub use handle::{ApplyMode, HandleError, Push2Handle};
+pub use idle::{IdleMonitor, Screensaver};
+#[cfg(feature = "keyboard")]
+pub use keyboard::{KeyAction, KeyboardBridge, KeyboardError};
+pub use launchpad::{Launchpad, LaunchpadModel};
+pub use layouts::{DrumEvent, DrumLayout, DrumPadRole, LayoutKind, NoteLayout, PadRole};
+pub use led_animator::{Animation, LedAnimator};
+pub use led_scene::{LedScene, SceneFade};
+use log::warn;
+pub use looper::{LoopEvent, Looper};
+pub use low_power::LowPowerMode;
+pub use midi_handler::{MidiHandler, MidiHandlerError, PortEvent, PortWatcher};
+pub use midi_learn::{LearnedControl, MidiLearn, MidiLearnError, MidiLearnEvent};
+pub use midi_parser::MidiParser;
+use midir::{MidiInputConnection, SendError};
+#[cfg(feature = "mirror")]
+pub use mirror::{MirrorError, MirrorWindow};
+pub use mixer::{LowerRowMode, Mixer, MixerColors, MixerModel};
+pub use music::{Chord, ChordQuality, Key, Scale};
+pub use note_output::{NoteEvent, NoteOutput};
+pub use note_repeat::NoteRepeat;
+pub use pad_chord::{ChordDetector, PadCoords};
+pub use pad_stats::{PadStat, PadStats};
+pub use padded_grid::{PaddedGrid, PaddedGridEvent, PagingButtons};
+pub use params::{Param, ParamChange, ParamId, Params};
+pub use playhead::{Playhead, PlayheadShape};
+pub use pressure_lights::{PressureColors, PressureLights};
+pub use project::{DeviceSnapshot, ProjectError, ProjectEvent, ProjectStore};
+#[cfg(feature = "recording")]
+pub use recording::{FrameRecorder, RecordingError};
+pub use region::PadRegion;
+#[cfg(feature = "remote")]
+pub use remote::{RemoteCommand, RemoteError, RemoteMessage, RemoteServer};
+pub use reserved::{ReservedAction, ReservedControls};
+#[cfg(feature = "scripting")]
+pub use scripting::{ScriptEngine, ScriptError};
+pub use sequencer::{Pattern, Step, StepSequencer, StepTrigger};
+use serde::{Deserialize, Serialize};
+pub use session::{Session, SessionError, SessionStore};
+pub use settings::{HardwareSettings, PadSensitivity, SettingsError, SettingsMenu};
+#[cfg(feature = "slint")]
+pub use slint_platform::SlintDisplay;
+pub use state::{HighlightLayer, PadDataMap, Push2State, StateError};
+pub use status_bar::{StatusBar, StatusBarColors, StatusIcon};
+use std::fs;
+use std::path::Path;
 use std::sync::mpsc::{self, Receiver};
+use std::time::Instant;
+use subscription::Subscriber;
+pub use subscription::{DEFAULT_SUBSCRIPTION_CAPACITY, EventSubscription};
+pub use theme::{Theme, ThemeColor, ThemeError};
 use thiserror::Error;
+pub use toggle::{RadioGroup, ToggleButton};
+pub use touch_strip::{TOUCH_STRIP_LEDS, TouchStripMode};
+pub use transport::{Transport, TransportColors, TransportEvent, TransportState};
+pub use undo::{Command, FnCommand, SetParam, UndoEvent, UndoStack};
+pub use velocity::{VelocityCurve, VelocityMap};
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Push2Error {
     #[error("Configuration error: {0}")]
     Config(#[from] ConfigError),
@@ -33,19 +216,112 @@ pub enum Push2Error {
     MidiInit(#[from] MidiHandlerError),
     #[error("MIDI send error: {0}")]
     MidiSend(#[from] SendError),
+    #[error("Could not set the color of pad ({}, {}): {source}", coord.x, coord.y)]
+    PadSend { coord: PadCoord, source: SendError },
+    #[error("Could not set the light of {name:?}: {source}")]
+    ButtonSend {
+        name: ControlName,
+        source: SendError,
+    },
+    #[error("{failed} of {total} lights could not be set, the first being {first:?}: {source}")]
+    BatchSend {
+        failed: usize,
+        total: usize,
+        first: LightTarget,
+        source: SendError,
+    },
+    #[error("{rejected} of {total} lights were rejected, the first being {first:?}")]
+    BatchRejected {
+        rejected: usize,
+        total: usize,
+        first: LightTarget,
+    },
+    #[error("{name:?} cannot show light {light}")]
+    UnsupportedLight { name: ControlName, light: u8 },
+    #[error("State error: {0}")]
+    State(#[from] StateError),
+    #[cfg(feature = "emulator")]
+    #[error("Emulator error: {0}")]
+    Emulator(#[from] EmulatorError),
+    #[error("Calibration error: {0}")]
+    Calibration(#[from] CalibrationError),
+    #[error("Settings error: {0}")]
+    Settings(#[from] SettingsError),
+    #[cfg(feature = "remote")]
+    #[error("Remote error: {0}")]
+    Remote(#[from] RemoteError),
+}
+impl Push2Error {
+    /// True if trying again may work, such as a MIDI send to a busy port or
+    /// a display transfer that timed out. False for errors that need the
+    /// config or the code to change first.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Push2Error::Display(e) => e.is_recoverable(),
+            Push2Error::MidiInit(e) => e.is_recoverable(),
+            Push2Error::MidiSend(e)
+            | Push2Error::PadSend { source: e, .. }
+            | Push2Error::ButtonSend { source: e, .. }
+            | Push2Error::BatchSend { source: e, .. } => matches!(e, SendError::Other(_)),
+            _ => false,
+        }
+    }
+
+    /// What the user can do about the error, if anything.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Push2Error::Display(e) => e.hint(),
+            Push2Error::MidiInit(e) => e.hint(),
+            Push2Error::Config(_) => Some("Fix or delete app_config.ron in the config directory"),
+            Push2Error::BatchSend { .. } => Some("Send the failed lights again with Push2::retry"),
+            Push2Error::UnsupportedLight { .. } | Push2Error::BatchRejected { .. } => {
+                Some("Use a color the button can show, or turn off strict_button_lights")
+            }
+            _ => None,
+        }
+    }
 }
 // --- MIDI Message Constants ---
 pub const NOTE_ON: u8 = 144;
 pub const NOTE_OFF: u8 = 128;
 pub const CONTROL_CHANGE: u8 = 176;
 pub const PITCH_BEND: u8 = 224;
+pub const POLY_PRESSURE: u8 = 160;
+pub const CHANNEL_PRESSURE: u8 = 208;
+/// The mod wheel controller, sent by the touch strip in `TouchStripMode::ModWheel`.
+pub const MOD_WHEEL: u8 = 1;
+// --- MIDI Realtime Constants ---
+pub const TIMING_CLOCK: u8 = 0xF8;
+pub const START: u8 = 0xFA;
+pub const CONTINUE: u8 = 0xFB;
+pub const STOP: u8 = 0xFC;
+/// The version of the serialized `Push2Event` format.
+/// It changes whenever an event is renamed or a field changes.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+/// How the pads report pressure while held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AftertouchMode {
+    /// One `ChannelPressure` value for all pads (mono aftertouch)
+    Channel,
+    /// A `PadAftertouch` value per pad
+    Polyphonic,
+}
 /// High-level events from the Ableton Push 2
-#[derive(Debug, Clone, Copy)]
+///
+/// Events serialize as objects tagged by `type`, in the format versioned by
+/// `EVENT_SCHEMA_VERSION`, e.g.
+/// `{"type": "pad_pressed", "coord": {"x": 0, "y": 7}, "velocity": 100}` or
+/// `{"type": "button_released", "name": "Play"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Push2Event {
     /// A grid pad was pressed
     PadPressed { coord: PadCoord, velocity: u8 },
-    /// A grid pad was released
-    PadReleased { coord: PadCoord },
+    /// A grid pad was released, with the release velocity of its NoteOff (0 if none)
+    PadReleased {
+        coord: PadCoord,
+        release_velocity: u8,
+    },
     /// A control button was pressed
     ButtonPressed { name: ControlName, velocity: u8 },
     /// A control button was released
@@ -54,91 +330,545 @@ pub enum Push2Event {
     EncoderTwisted { name: EncoderName, raw_delta: u8 },
     /// The touch slider was moved
     SliderMoved { value: u16 },
+    /// The pressure on a held pad changed (polyphonic aftertouch)
+    PadAftertouch { coord: PadCoord, pressure: u8 },
+    /// The pressure on the pads changed (channel aftertouch)
+    ChannelPressure { value: u8 },
+    /// Several pads were pressed together (see `ChordDetector`)
+    PadChord { coords: PadCoords },
+    /// Nothing was touched for the `IdleMonitor` timeout
+    IdleEntered,
+    /// An event woke the device up; it follows this one
+    IdleExited,
+    /// A control change from an address the `ButtonMap` does not know, e.g.
+    /// a control added by a firmware update. Add the address to the button
+    /// map to get button or encoder events for it.
+    UnknownControl { address: u8, value: u8 },
+    /// A config file changed and was applied (see `Push2::watch_config`).
+    /// Themes and action bindings are only reported; load them again with
+    /// `Theme::named` or `ActionMap::in_config_dir`.
+    ConfigReloaded { file: ConfigFile },
 }
 /// Main struct for interfacing with the Ableton Push 2
 pub struct Push2 {
     pub display: Push2Display,
     /// The MIDI output connection, for sending light/color data
-    pub midi_out: MidiOutputConnection,
+    pub midi_out: Box<dyn MidiOut>,
     pub button_map: ButtonMap,
+    /// The model-specific palette, pad notes and display of the connected device
+    pub device: Box<dyn PushDevice>,
     pub state: Push2State,
+    /// When set, pad presses are also sent as notes on this output
+    pub note_output: Option<NoteOutput>,
+    /// When set, pads pressed together are also reported as a `PadChord`
+    pub chord_detector: Option<ChordDetector>,
+    /// When set, the velocity of each pad is evened out before `velocity_map` is applied
+    pub pad_calibration: Option<PadCalibration>,
+    /// When set, the velocity of pad presses is remapped before they are emitted
+    pub velocity_map: Option<VelocityMap>,
+    /// When set, pad velocities and pressure are collected from the returned events
+    pub pad_stats: Option<PadStats>,
+    /// When set, pressed pads are highlighted with this color until released
+    pub press_highlight: Option<u8>,
+    /// When set, held pads are lit by how hard they are pressed
+    pub pressure_lights: Option<PressureLights>,
+    /// The origin of the coordinates given to `pad_at` and returned by
+    /// `grid_position`. The rest of the API always counts from the top left.
+    pub grid_origin: GridOrigin,
+    /// When true, `set_button_light` fails on colors a white-only button
+    /// cannot show instead of lighting the closest white
+    pub strict_button_lights: bool,
+    /// When set, the lights are dimmed and the screensaver runs after a period without interaction
+    pub idle: Option<IdleMonitor>,
+    /// When set, these buttons are handled by the crate and not returned by `poll_event`
+    pub reserved_controls: Option<ReservedControls>,
+    /// When set, the settings menu opened by `ReservedAction::Settings`
+    pub settings_menu: Option<SettingsMenu>,
+    /// When set, events are mirrored to the remote clients
+    #[cfg(feature = "remote")]
+    pub remote: Option<RemoteServer>,
+    /// When set, bound events are also sent as keyboard shortcuts
+    #[cfg(feature = "keyboard")]
+    pub keyboard: Option<KeyboardBridge>,
+    /// When set, the encoder values and slider position are saved as they change
+    pub session: Option<SessionStore>,
+    /// The config the MIDI ports were opened with; `None` on other backends
+    pub app_config: Option<AppConfig>,
+    /// When set, changed config files are applied by `poll_event`
+    #[cfg(feature = "watch")]
+    pub config_watcher: Option<ConfigWatcher>,
     event_rx: Receiver<Vec<u8>>,
-    _conn_in: MidiInputConnection<()>,
+    parser: MidiParser,
+    /// An event held back to return after `IdleExited`
+    pending: Option<Push2Event>,
+    subscribers: Vec<Subscriber>,
+    _conn_in: Option<MidiInputConnection<()>>,
+    _route_conns: Vec<MidiInputConnection<()>>,
 }
 impl Push2 {
     /// Connects to the Push 2 display and MIDI ports.
     ///
-    /// The user is responsible for loading and providing the `AppConfig`
+    /// The `AppConfig` is loaded from the config directory, with the
+    /// environment overrides applied.
     pub fn new() -> Result<Self, Push2Error> {
-        let app_config = AppConfig::new()?;
+        Self::with_config(AppConfig::new()?)
+    }
+    /// Connects using an `AppConfig` loaded by the caller, e.g. with `AppConfig::from_args`.
+    pub fn with_config(mut app_config: AppConfig) -> Result<Self, Push2Error> {
         // --- MIDI Setup ---
         let (tx, rx) = mpsc::channel();
         let midi_handler = MidiHandler::new(&app_config, tx)?;
-        let button_map = ButtonMap::new()?;
+        // Remember the ports picked when the configured ones were missing.
+        if app_config.set_ports(
+            &midi_handler.input_port_name,
+            &midi_handler.output_port_name,
+        ) && !app_config.headless
+            && let Err(e) = app_config.save()
+        {
+            warn!("Failed to save the selected MIDI ports: {}", e);
+        }
+        // The button map lives next to the app config it came with.
+        let button_map = match app_config.path.as_deref().and_then(Path::parent) {
+            Some(dir) => ButtonMap::in_dir(dir)?,
+            None => ButtonMap::in_config_dir()?,
+        };
         // --- Display Setup ---
-        let display = Push2Display::new()?;
-        let MidiHandler { _conn_in, conn_out } = midi_handler;
+        let display = Push2Display::with_color_correction(app_config.color_correction)?;
+        let MidiHandler {
+            _conn_in,
+            conn_out,
+            _route_conns,
+            ..
+        } = midi_handler;
         let state = Push2State::new();
         let mut push2 = Self {
             display,
-            midi_out: conn_out,
+            midi_out: Box::new(conn_out),
             button_map,
+            device: Box::new(Push2Model),
             event_rx: rx,
-            _conn_in,
+            parser: MidiParser::new(),
+            pending: None,
+            subscribers: Vec::new(),
+            _conn_in: Some(_conn_in),
+            _route_conns,
             state,
+            note_output: None,
+            chord_detector: None,
+            pad_calibration: None,
+            velocity_map: None,
+            pad_stats: None,
+            press_highlight: None,
+            pressure_lights: None,
+            grid_origin: app_config.grid_origin,
+            strict_button_lights: false,
+            idle: None,
+            reserved_controls: None,
+            settings_menu: None,
+            #[cfg(feature = "remote")]
+            remote: None,
+            #[cfg(feature = "keyboard")]
+            keyboard: None,
+            session: None,
+            app_config: Some(app_config),
+            #[cfg(feature = "watch")]
+            config_watcher: None,
+        };
+        let batch = push2.reset_all_lights();
+        push2.retry(&batch).into_result()?;
+        Ok(push2)
+    }
+    /// Runs on a backend other than the hardware, such as `Push2Virtual`.
+    /// The MIDI routes of the `AppConfig` are not used.
+    pub fn with_backend<B: Backend>(backend: B) -> Result<Self, Push2Error> {
+        let BackendParts {
+            display,
+            midi_out,
+            midi_in,
+            device,
+        } = backend.connect()?;
+        let mut push2 = Self {
+            display: Push2Display::with_sink(display),
+            midi_out,
+            button_map: ButtonMap::new()?,
+            device,
+            event_rx: midi_in,
+            parser: MidiParser::new(),
+            pending: None,
+            subscribers: Vec::new(),
+            _conn_in: None,
+            _route_conns: Vec::new(),
+            state: Push2State::new(),
+            note_output: None,
+            chord_detector: None,
+            pad_calibration: None,
+            velocity_map: None,
+            pad_stats: None,
+            press_highlight: None,
+            pressure_lights: None,
+            grid_origin: GridOrigin::TopLeft,
+            strict_button_lights: false,
+            idle: None,
+            reserved_controls: None,
+            settings_menu: None,
+            #[cfg(feature = "remote")]
+            remote: None,
+            #[cfg(feature = "keyboard")]
+            keyboard: None,
+            session: None,
+            app_config: None,
+            #[cfg(feature = "watch")]
+            config_watcher: None,
         };
-        push2.reset_all_lights()?;
+        let batch = push2.reset_all_lights();
+        push2.retry(&batch).into_result()?;
         Ok(push2)
     }
-    fn reset_all_lights(&mut self) -> Result<(), Push2Error> {
-        // --- Reset all 64 pads ---
-        // The pads are MIDI notes 36 through 99.
-        for address in 36..=99 {
-            let message = [NOTE_OFF, address, 0];
-            self.midi_out.send(&message)?;
+    /// Turns off every pad and button light. Same as `clear_all`.
+    pub fn reset_all_lights(&mut self) -> BatchResult {
+        self.clear_all()
+    }
+    /// Turns off every pad and button light, and clears the pad highlights.
+    pub fn clear_all(&mut self) -> BatchResult {
+        for pad in self.state.pads.iter_mut().flatten() {
+            pad.highlights = Default::default();
         }
-        // --- Reset all control buttons ---
-        // We can iterate the keys of the control_map to get all button addresses.
-        for address in self.button_map.get_control_addresses() {
-            let message = [CONTROL_CHANGE, *address, 0];
-            self.midi_out.send(&message)?;
+        let pads = self
+            .button_map
+            .pads()
+            .map(|(_, coord)| LightTarget::Pad(coord));
+        let buttons = self
+            .button_map
+            .controls()
+            .map(|(_, name)| LightTarget::Button(name));
+        let lights: Vec<(LightTarget, u8)> = pads.chain(buttons).map(|t| (t, 0)).collect();
+        self.set_lights(lights)
+    }
+    /// Turns off every pad, leaving the button lights alone.
+    pub fn clear_pads(&mut self) -> BatchResult {
+        self.clear_region(PadRegion::FULL)
+    }
+    /// Turns off every button light, leaving the pads alone.
+    pub fn clear_buttons(&mut self) -> BatchResult {
+        let lights: Vec<(LightTarget, u8)> = self
+            .button_map
+            .controls()
+            .map(|(_, name)| (LightTarget::Button(name), 0))
+            .collect();
+        self.set_lights(lights)
+    }
+    /// Turns off the pads of a region, e.g. the part of the grid a mode owns,
+    /// and clears their highlights.
+    pub fn clear_region(&mut self, region: PadRegion) -> BatchResult {
+        for coord in region.coords() {
+            if let Some(pad) = self
+                .state
+                .pads
+                .get_mut(coord.y as usize)
+                .and_then(|row| row.get_mut(coord.x as usize))
+            {
+                pad.highlights = Default::default();
+            }
         }
-        Ok(())
+        let lights: Vec<(LightTarget, u8)> = region
+            .coords()
+            .map(|coord| (LightTarget::Pad(coord), 0))
+            .collect();
+        self.set_lights(lights)
     }
-    pub fn set_pad_color(&mut self, coord: PadCoord, color: u8) -> Result<(), Push2Error> {
-        // Send MIDI message
-        if let Some(address) = self.button_map.get_note_address(coord) {
-            let message = if color == 0 {
-                [NOTE_OFF, address, 0]
-            } else {
-                [NOTE_ON, address, color]
+    /// Sets many pad colors and button lights, carrying on past failed sends.
+    pub fn set_lights(
+        &mut self,
+        lights: impl IntoIterator<Item = (LightTarget, u8)>,
+    ) -> BatchResult {
+        let mut batch = BatchResult::default();
+        for (target, value) in lights {
+            batch.total += 1;
+            let result = match target {
+                LightTarget::Pad(coord) => self.set_pad_color(coord, value),
+                LightTarget::Button(name) => self.set_button_light(name, value),
             };
-            self.midi_out.send(&message)?;
-            // Update state
-            let pad = &mut self.state.pads[coord.y as usize][coord.x as usize];
-            pad.color = color;
-            Ok(())
-        } else {
-            Ok(())
+            match result {
+                Ok(()) => {}
+                Err(Push2Error::PadSend { source, .. } | Push2Error::ButtonSend { source, .. }) => {
+                    batch.failed.push((target, value, source));
+                }
+                Err(e) => {
+                    warn!("Rejected light {:?}: {}", target, e);
+                    batch.rejected.push((target, value));
+                }
+            }
+        }
+        batch
+    }
+    /// Sends the failed lights of a batch again.
+    /// Returns the lights that still failed.
+    pub fn retry(&mut self, batch: &BatchResult) -> BatchResult {
+        self.set_lights(
+            batch
+                .failed
+                .iter()
+                .map(|&(target, value, _)| (target, value)),
+        )
+    }
+    /// Colors every pad with the palette color `color_of` returns for its coordinate.
+    pub fn color_grid_by<F>(&mut self, mut color_of: F) -> Result<(), Push2Error>
+    where
+        F: FnMut(PadCoord) -> u8,
+    {
+        let lights: Vec<(LightTarget, u8)> = (0..8)
+            .flat_map(|y| (0..8).map(move |x| PadCoord { x, y }))
+            .map(|coord| (LightTarget::Pad(coord), color_of(coord)))
+            .collect();
+        self.set_lights(lights).into_result()
+    }
+    /// The pad at column `x` and row `y` counted from `grid_origin`, as a
+    /// coordinate for the rest of the API.
+    pub fn pad_at(&self, x: u8, y: u8) -> PadCoord {
+        PadCoord::new(x, y).in_origin(self.grid_origin)
+    }
+    /// Where a pad is, counted from `grid_origin`, e.g. for the coordinate
+    /// of a `PadPressed` event.
+    pub fn grid_position(&self, coord: PadCoord) -> PadCoord {
+        coord.in_origin(self.grid_origin)
+    }
+    /// Sets the color of a pad. A highlighted pad shows it once the
+    /// highlight is cleared.
+    pub fn set_pad_color(&mut self, coord: PadCoord, color: u8) -> Result<(), Push2Error> {
+        if self.button_map.get_note_address(coord).is_none() {
+            return Ok(());
+        }
+        // A highlighted pad keeps showing the highlight until it is cleared.
+        if self.state.pads[coord.y as usize][coord.x as usize]
+            .highlight()
+            .is_none()
+        {
+            self.send_pad_color(coord, color)?;
+        }
+        self.state.pads[coord.y as usize][coord.x as usize].color = color;
+        Ok(())
+    }
+    /// Shows `color` on a pad over its own color until `clear_highlight`,
+    /// e.g. for a press flash or a playhead. A higher `layer` is shown over
+    /// a lower one; `set_pad_color` changes the color shown afterwards.
+    pub fn highlight_pad(
+        &mut self,
+        coord: PadCoord,
+        layer: HighlightLayer,
+        color: u8,
+    ) -> Result<(), Push2Error> {
+        self.set_highlight(coord, layer, Some(color))
+    }
+    /// Clears the highlight of a layer, showing the one below or the pad's
+    /// own color.
+    pub fn clear_highlight(
+        &mut self,
+        coord: PadCoord,
+        layer: HighlightLayer,
+    ) -> Result<(), Push2Error> {
+        self.set_highlight(coord, layer, None)
+    }
+    /// Clears every highlight of every pad.
+    pub fn clear_highlights(&mut self) -> Result<(), Push2Error> {
+        for coord in PadRegion::FULL.coords() {
+            for layer in HighlightLayer::ALL {
+                self.clear_highlight(coord, layer)?;
+            }
+        }
+        Ok(())
+    }
+    fn set_highlight(
+        &mut self,
+        coord: PadCoord,
+        layer: HighlightLayer,
+        color: Option<u8>,
+    ) -> Result<(), Push2Error> {
+        if self.button_map.get_note_address(coord).is_none() {
+            return Ok(());
+        }
+        let Some(pad) = self
+            .state
+            .pads
+            .get_mut(coord.y as usize)
+            .and_then(|row| row.get_mut(coord.x as usize))
+        else {
+            return Ok(());
+        };
+        let mut changed = *pad;
+        changed.highlights[layer as usize] = color;
+        if changed.shown() != pad.shown() {
+            self.send_pad_color(coord, changed.shown())?;
         }
+        self.state.pads[coord.y as usize][coord.x as usize] = changed;
+        Ok(())
     }
+    /// Sends a pad color without changing `Push2State`.
+    pub(crate) fn send_pad_color(&mut self, coord: PadCoord, color: u8) -> Result<(), Push2Error> {
+        let Some(address) = self.button_map.get_note_address(coord) else {
+            return Ok(());
+        };
+        let message = if color == 0 {
+            [NOTE_OFF, address, 0]
+        } else {
+            [NOTE_ON, address, self.device.pad_color(color)]
+        };
+        self.midi_out
+            .send(&message)
+            .map_err(|source| Push2Error::PadSend { coord, source })
+    }
+    /// Lights a button. Colors it cannot show are translated with
+    /// `ButtonCapabilities::translate`, or rejected if `strict_button_lights` is set.
     pub fn set_button_light(&mut self, name: ControlName, light: u8) -> Result<(), Push2Error> {
-        // Send MIDI message
-        if let Some(address) = self.button_map.get_control_address(name) {
-            let message = if light == 0 {
-                [CONTROL_CHANGE, address, 0]
-            } else {
-                [CONTROL_CHANGE, address, light]
-            };
-            self.midi_out.send(&message)?;
-            // Update state
-            let button = self.state.buttons.entry(name).or_default();
-            button.light = light;
-            Ok(())
+        let capabilities = ButtonCapabilities::of(name);
+        if self.strict_button_lights && !capabilities.supports(light) {
+            return Err(Push2Error::UnsupportedLight { name, light });
+        }
+        if self.button_map.get_control_address(name).is_none() {
+            return Ok(());
+        }
+        self.send_button_light(name, light)?;
+        self.state.buttons.entry(name).or_default().light = light;
+        Ok(())
+    }
+    /// Sends a button light without changing `Push2State`.
+    pub(crate) fn send_button_light(
+        &mut self,
+        name: ControlName,
+        light: u8,
+    ) -> Result<(), Push2Error> {
+        let Some(address) = self.button_map.get_control_address(name) else {
+            return Ok(());
+        };
+        let message = if light == 0 {
+            [CONTROL_CHANGE, address, 0]
         } else {
-            Ok(())
+            let shown = ButtonCapabilities::of(name).translate(light);
+            [CONTROL_CHANGE, address, self.device.button_light(shown)]
+        };
+        self.midi_out
+            .send(&message)
+            .map_err(|source| Push2Error::ButtonSend { name, source })
+    }
+    /// Writes a line of text in a way that works on both Push models.
+    ///
+    /// On a Push 1 the line is sent to its character display (4 lines). On a
+    /// Push 2 it is drawn into one of 4 rows of the frame buffer; call
+    /// `display.flush()` to show it.
+    pub fn set_text_line(&mut self, line: usize, text: &str) -> Result<(), Push2Error> {
+        if !self.device.has_pixel_display() {
+            for message in self.device.text_line_messages(line, text) {
+                self.midi_out.send(&message)?;
+            }
+            return Ok(());
+        }
+        let row_height = (display::DISPLAY_HEIGHT / 4) as u32;
+        let top = (line as u32 * row_height) as i32;
+        Rectangle::new(
+            Point::new(0, top),
+            Size::new(display::DISPLAY_WIDTH as u32, row_height),
+        )
+        .into_styled(PrimitiveStyle::with_fill(Bgr565::BLACK))
+        .draw(&mut self.display)
+        .unwrap();
+        let style = MonoTextStyle::new(&FONT_10X20, Bgr565::WHITE);
+        Text::with_baseline(text, Point::new(4, top + 10), style, Baseline::Middle)
+            .draw(&mut self.display)
+            .unwrap();
+        Ok(())
+    }
+    /// Switches the pads between channel and polyphonic aftertouch (Push 2 only).
+    pub fn set_aftertouch_mode(&mut self, mode: AftertouchMode) -> Result<(), Push2Error> {
+        let mode = match mode {
+            AftertouchMode::Channel => 0,
+            AftertouchMode::Polyphonic => 1,
+        };
+        self.midi_out
+            .send(&[0xF0, 0x00, 0x21, 0x1D, 0x01, 0x01, 0x1E, mode, 0xF7])?;
+        Ok(())
+    }
+    /// Lights the touch strip LEDs (Push 2 only), from the bottom up, each
+    /// with a brightness from 0 to 7. The strip stops lighting itself until
+    /// `release_touch_strip` is called.
+    pub fn set_touch_strip_leds(
+        &mut self,
+        leds: &[u8; TOUCH_STRIP_LEDS],
+    ) -> Result<(), Push2Error> {
+        self.midi_out.send(&touch_strip::host_control_message())?;
+        self.midi_out.send(&touch_strip::leds_message(leds))?;
+        Ok(())
+    }
+    /// Shows a level (0.0-1.0) on the touch strip as a bar from the bottom.
+    pub fn show_level(&mut self, level: f32) -> Result<(), Push2Error> {
+        self.set_touch_strip_leds(&touch_strip::level_leds(level))
+    }
+    /// Shows a 14-bit pitch bend (-8192 to 8191) on the touch strip as a bar from the center.
+    pub fn show_pitch_bend(&mut self, bend: i16) -> Result<(), Push2Error> {
+        self.set_touch_strip_leds(&touch_strip::pitch_bend_leds(bend))
+    }
+    /// Lets the touch strip light itself again, as after power-on.
+    pub fn release_touch_strip(&mut self) -> Result<(), Push2Error> {
+        self.set_touch_strip_mode(TouchStripMode::default())
+    }
+    /// Lets the touch strip light itself in `mode`, e.g. as a mod wheel.
+    pub fn set_touch_strip_mode(&mut self, mode: TouchStripMode) -> Result<(), Push2Error> {
+        self.midi_out.send(&touch_strip::mode_message(mode))?;
+        Ok(())
+    }
+    /// Replays every pad color and button light of `state` to the hardware,
+    /// and takes over its encoder values and slider position.
+    ///
+    /// Every light is attempted, and the ones that failed are sent once more
+    /// before an error is returned.
+    pub fn apply_state(&mut self, state: &Push2State) -> Result<(), Push2Error> {
+        let mut lights = Vec::new();
+        for (y, row) in state.pads.iter().enumerate() {
+            for (x, pad) in row.iter().enumerate() {
+                let coord = PadCoord {
+                    x: x as u8,
+                    y: y as u8,
+                };
+                lights.push((LightTarget::Pad(coord), pad.color));
+            }
+        }
+        for (name, button) in &state.buttons {
+            lights.push((LightTarget::Button(*name), button.light));
+        }
+        let batch = self.set_lights(lights);
+        self.state.encoders = state.encoders.clone();
+        for (name, encoder) in &state.encoders {
+            self.state
+                .set_encoder_range(*name, encoder.min, encoder.max);
         }
+        self.state.slider = state.slider;
+        self.retry(&batch).into_result()
     }
+
+    /// Writes the current state to a RON file.
+    pub fn save_state(&self, path: &Path) -> Result<(), Push2Error> {
+        let state_string = self.state.to_ron()?;
+        fs::write(path, state_string).map_err(StateError::from)?;
+        Ok(())
+    }
+
+    /// Reads a state written by `save_state` and applies it to the hardware.
+    pub fn load_state(&mut self, path: &Path) -> Result<(), Push2Error> {
+        let state_string = fs::read_to_string(path).map_err(StateError::from)?;
+        let state = Push2State::from_ron(&state_string)?;
+        self.apply_state(&state)
+    }
+
+    /// Executes every command received from the remote clients.
+    /// Returns the number of commands executed.
+    #[cfg(feature = "remote")]
+    pub fn process_remote_commands(&mut self) -> Result<usize, Push2Error> {
+        let mut count = 0;
+        while let Some(command) = self.remote.as_ref().and_then(|r| r.poll_command()) {
+            command.apply(self)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     pub fn draw_bmp_to_display(
         &mut self,
         bmp_data: &[u8],
@@ -150,10 +880,25 @@ impl Push2 {
     /// Polls for the next high-level `Push2Event`.
     /// This is non-blocking
     pub fn poll_event(&mut self) -> Option<Push2Event> {
-        while let Ok(message) = self.event_rx.try_recv() {
-            if message.is_empty() {
-                continue;
-            }
+        self.update_session();
+        if let Some(event) = self.pending.take().or_else(|| self.update_idle()) {
+            self.publish(&event);
+            return Some(event);
+        }
+        if let Some(chord) = self
+            .chord_detector
+            .as_mut()
+            .and_then(|d| d.poll(Instant::now()))
+        {
+            self.publish(&chord);
+            return Some(chord);
+        }
+        #[cfg(feature = "watch")]
+        if let Some(reloaded) = self.poll_config() {
+            self.publish(&reloaded);
+            return Some(reloaded);
+        }
+        while let Some(message) = self.next_message() {
             let status = message[0];
             // Try to parse the raw MIDI message into a high-level event
             let event = match status {
@@ -166,12 +911,26 @@ impl Push2 {
                     let velocity = message[2];
                     if let Some(pad_coord) = self.button_map.get_note(address) {
                         if status == NOTE_ON && velocity > 0 {
+                            let velocity = match &self.pad_calibration {
+                                Some(calibration) => calibration.apply(pad_coord, velocity),
+                                None => velocity,
+                            };
+                            let velocity = match self.velocity_map {
+                                Some(map) => map.apply(
+                                    velocity,
+                                    self.state.is_button_held(ControlName::Accent),
+                                ),
+                                None => velocity,
+                            };
                             Some(Push2Event::PadPressed {
                                 coord: pad_coord,
                                 velocity,
                             })
                         } else {
-                            Some(Push2Event::PadReleased { coord: pad_coord })
+                            Some(Push2Event::PadReleased {
+                                coord: pad_coord,
+                                release_velocity: velocity,
+                            })
                         }
                     } else {
                         None // Unknown note
@@ -198,9 +957,36 @@ impl Push2 {
                             name: encoder_name,
                             raw_delta: velocity,
                         })
+                    } else if address == MOD_WHEEL {
+                        // The touch strip in `TouchStripMode::ModWheel`, scaled to 14 bits.
+                        Some(Push2Event::SliderMoved {
+                            value: (velocity as u16) << 7,
+                        })
                     } else {
-                        None // Unknown CC
+                        Some(Push2Event::UnknownControl {
+                            address,
+                            value: velocity,
+                        })
+                    }
+                }
+                // --- POLYPHONIC AFTERTOUCH (160) ---
+                POLY_PRESSURE => {
+                    if message.len() < 3 {
+                        continue;
+                    }
+                    self.button_map
+                        .get_note(message[1])
+                        .map(|coord| Push2Event::PadAftertouch {
+                            coord,
+                            pressure: message[2],
+                        })
+                }
+                // --- CHANNEL AFTERTOUCH (208) ---
+                CHANNEL_PRESSURE => {
+                    if message.len() < 2 {
+                        continue;
                     }
+                    Some(Push2Event::ChannelPressure { value: message[1] })
                 }
                 // --- PITCH BEND (224) ---
                 PITCH_BEND => {
@@ -217,11 +1003,113 @@ impl Push2 {
             };
             // If we parsed a valid event, return it
             if let Some(parsed_event) = event {
-                self.state.update_from_event(&parsed_event);
+                if self.handle_reserved(&parsed_event) || self.handle_settings(&parsed_event) {
+                    // Using the menu still counts as interaction.
+                    if let Some(exited) = self.wake() {
+                        self.publish(&exited);
+                        return Some(exited);
+                    }
+                    continue;
+                }
+                if let Push2Event::PadPressed { coord, .. } = parsed_event
+                    && let Some(detector) = self.chord_detector.as_mut()
+                {
+                    detector.press(coord, Instant::now());
+                }
+                if let Some(exited) = self.wake() {
+                    self.pending = Some(parsed_event);
+                    self.publish(&exited);
+                    return Some(exited);
+                }
+                self.publish(&parsed_event);
                 return Some(parsed_event);
             }
         }
         // No events in the queue
         None
     }
+    /// Runs the action of a reserved button.
+    /// Returns true if the event is about a reserved button and must not be returned.
+    fn handle_reserved(&mut self, event: &Push2Event) -> bool {
+        let Some(name) = self
+            .reserved_controls
+            .as_ref()
+            .and_then(|r| r.reserved_control(event))
+        else {
+            return false;
+        };
+        if !matches!(event, Push2Event::ButtonPressed { .. }) {
+            return true;
+        }
+        // Taken out while the callback runs, so it can use the whole device.
+        let Some(mut reserved) = self.reserved_controls.take() else {
+            return true;
+        };
+        match reserved.action_mut(name) {
+            Some(ReservedAction::Callback(callback)) => callback(self),
+            Some(ReservedAction::Settings) => self.toggle_settings(),
+            _ => {}
+        }
+        // Keep a replacement the callback installed.
+        if self.reserved_controls.is_none() {
+            self.reserved_controls = Some(reserved);
+        }
+        true
+    }
+    /// The next complete MIDI message, reading more input as needed.
+    fn next_message(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if let Some(message) = self.parser.next_message() {
+                return Some(message);
+            }
+            let bytes = self.event_rx.try_recv().ok()?;
+            self.parser.feed(&bytes);
+        }
+    }
+    /// Highlights a pressed pad with `press_highlight`, until it is released.
+    fn highlight_press(&mut self, event: &Push2Event) {
+        let Some(color) = self.press_highlight else {
+            return;
+        };
+        let result = match *event {
+            Push2Event::PadPressed { coord, .. } => {
+                self.highlight_pad(coord, HighlightLayer::Press, color)
+            }
+            Push2Event::PadReleased { coord, .. } => {
+                self.clear_highlight(coord, HighlightLayer::Press)
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!("Failed to highlight a pressed pad: {}", e);
+        }
+    }
+    /// Updates the state and forwards an event about to be returned by `poll_event`.
+    fn publish(&mut self, event: &Push2Event) {
+        self.state.update_from_event(event);
+        self.track_session(event);
+        self.highlight_press(event);
+        self.show_pressure(event);
+        self.subscribers.retain(|s| s.send(event));
+        if let Some(stats) = self.pad_stats.as_mut() {
+            stats.record(event);
+        }
+        if let Some(note_output) = self.note_output.as_mut()
+            && let Err(e) = note_output.handle_event(event)
+        {
+            warn!("Failed to send translated note: {}", e);
+        }
+        #[cfg(feature = "remote")]
+        if let Some(remote) = self.remote.as_ref()
+            && let Err(e) = remote.broadcast_event(event)
+        {
+            warn!("Failed to mirror event: {}", e);
+        }
+        #[cfg(feature = "keyboard")]
+        if let Some(keyboard) = self.keyboard.as_mut()
+            && let Err(e) = keyboard.handle_event(event, &self.state)
+        {
+            warn!("Failed to send keyboard shortcut: {}", e);
+        }
+    }
 }
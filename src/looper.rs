@@ -0,0 +1,257 @@
+use crate::clock::ClockTick;
+use crate::{PadCoord, Push2Event};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// A pad event recorded into a loop.
+#[derive(Debug, Clone)]
+pub struct LoopEvent {
+    /// The position inside the loop, in beats (quarter notes).
+    pub beat: f64,
+    pub event: Push2Event,
+    /// The first pass of the loop this event is played on. Events are not
+    /// replayed on the pass they were recorded on, since they were just played live.
+    first_pass: u64,
+}
+
+/// Records pad presses during a loop and replays them, quantized, on every
+/// following pass.
+///
+/// Positions are kept in beats, so a loop keeps its timing at any tempo. Drive it
+/// either from its own timing with `update` or from a `Clock` with
+/// `on_clock_tick`; both return the events that became due, ready to be
+/// handled like live events (e.g. passed to `NoteOutput::handle_event`).
+pub struct Looper {
+    pub bpm: f64,
+    /// The loop length in beats.
+    length: f64,
+    /// Grid used to quantize recorded presses, in steps per beat.
+    /// `None` keeps the exact timing.
+    pub quantize: Option<u32>,
+    events: Vec<LoopEvent>,
+    recording: bool,
+    playing: bool,
+    /// When `position` was last moved by `update_at`, while the loop runs on
+    /// its own timing. Beats are counted from here, so a change of `bpm` only
+    /// affects the time after it.
+    updated_at: Option<Instant>,
+    /// The beat reached by the last update, counted from the start.
+    position: f64,
+    /// The quantization shift applied to each held pad's press, reused for its release.
+    held: HashMap<PadCoord, f64>,
+}
+
+impl Looper {
+    /// Creates an empty loop of `beats` quarter notes.
+    pub fn new(bpm: f64, beats: f64) -> Self {
+        Self {
+            bpm,
+            length: beats.max(1.0 / 64.0),
+            quantize: Some(4),
+            events: Vec::new(),
+            recording: false,
+            playing: false,
+            updated_at: None,
+            position: 0.0,
+            held: HashMap::new(),
+        }
+    }
+
+    pub fn with_quantize(mut self, steps_per_beat: Option<u32>) -> Self {
+        self.quantize = steps_per_beat.filter(|s| *s > 0);
+        self
+    }
+
+    /// The loop length in beats.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Changes the loop length. Events past the new end are dropped.
+    pub fn set_length(&mut self, beats: f64) {
+        self.length = beats.max(1.0 / 64.0);
+        let length = self.length;
+        self.events.retain(|e| e.beat < length);
+    }
+
+    pub fn events(&self) -> &[LoopEvent] {
+        &self.events
+    }
+
+    /// Removes every recorded event.
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.held.clear();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Arms or disarms recording. Recording overdubs onto existing events.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+        if !recording {
+            self.held.clear();
+        }
+    }
+
+    /// Starts the loop from its beginning.
+    pub fn start(&mut self) {
+        self.start_at(Instant::now());
+    }
+
+    pub fn start_at(&mut self, now: Instant) {
+        self.playing = true;
+        self.updated_at = Some(now);
+        self.position = 0.0;
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.updated_at = None;
+        self.held.clear();
+    }
+
+    /// The current pass of the loop, counted from 0.
+    pub fn pass(&self) -> u64 {
+        (self.position / self.length) as u64
+    }
+
+    /// The position inside the loop, in beats.
+    pub fn loop_position(&self) -> f64 {
+        self.position % self.length
+    }
+
+    /// Records a pad event at the current position.
+    /// Returns true if the event was recorded.
+    pub fn handle_event(&mut self, event: &Push2Event) -> bool {
+        let position = self.position_at(Instant::now()).unwrap_or(self.position);
+        self.record_at(event, position)
+    }
+
+    /// Records a pad event at `now`, for loops driven by `update_at`.
+    pub fn handle_event_at(&mut self, event: &Push2Event, now: Instant) -> bool {
+        let Some(position) = self.position_at(now) else {
+            return false;
+        };
+        self.record_at(event, position)
+    }
+
+    /// Returns the events that became due since the last update.
+    pub fn update(&mut self) -> Vec<Push2Event> {
+        self.update_at(Instant::now())
+    }
+
+    /// Returns the events that became due up to `now`.
+    pub fn update_at(&mut self, now: Instant) -> Vec<Push2Event> {
+        let Some(position) = self.position_at(now) else {
+            return Vec::new();
+        };
+        self.updated_at = Some(now);
+        self.advance_to(position)
+    }
+
+    /// Drives the loop from a `Clock` tick instead of its own timing.
+    /// A tick of 0 restarts the loop.
+    pub fn on_clock_tick(&mut self, tick: &ClockTick) -> Vec<Push2Event> {
+        if tick.tick == 0 {
+            self.playing = true;
+            self.updated_at = None;
+            self.position = 0.0;
+        }
+        let position = tick.tick as f64 / tick.ppqn.max(1) as f64;
+        self.advance_to(position)
+    }
+
+    /// The position at `now` at the current tempo, if the loop runs on its
+    /// own timing.
+    fn position_at(&self, now: Instant) -> Option<f64> {
+        let updated_at = self.updated_at?;
+        let beats =
+            now.saturating_duration_since(updated_at).as_secs_f64() * self.bpm.max(1.0) / 60.0;
+        Some(self.position + beats)
+    }
+
+    fn record_at(&mut self, event: &Push2Event, position: f64) -> bool {
+        if !self.playing || !self.recording {
+            return false;
+        }
+        let pass = (position / self.length) as u64;
+        let beat = position % self.length;
+        let shifted = match *event {
+            Push2Event::PadPressed { coord, .. } => {
+                let quantized = match self.quantize {
+                    Some(steps) => (beat * steps as f64).round() / steps as f64,
+                    None => beat,
+                };
+                self.held.insert(coord, quantized - beat);
+                quantized
+            }
+            // Releases move by the same amount as their press to keep the note length.
+            Push2Event::PadReleased { coord, .. } => beat + self.held.remove(&coord).unwrap_or(0.0),
+            _ => return false,
+        };
+        // A press quantized past the end of the loop wraps to the next pass.
+        let wrapped = shifted >= self.length;
+        let beat = shifted.rem_euclid(self.length);
+        let first_pass = pass + 1 + wrapped as u64;
+        let index = self.events.partition_point(|e| e.beat <= beat);
+        self.events.insert(
+            index,
+            LoopEvent {
+                beat,
+                event: event.clone(),
+                first_pass,
+            },
+        );
+        true
+    }
+
+    /// Moves the position forward, collecting every event in between.
+    fn advance_to(&mut self, position: f64) -> Vec<Push2Event> {
+        let mut due = Vec::new();
+        if !self.playing || position <= self.position {
+            return due;
+        }
+        let first = (self.position / self.length) as u64;
+        let last = (position / self.length) as u64;
+        for pass in first..=last {
+            let loop_start = pass as f64 * self.length;
+            for e in &self.events {
+                let at = loop_start + e.beat;
+                if pass >= e.first_pass && at >= self.position && at < position {
+                    due.push(e.event.clone());
+                }
+            }
+        }
+        self.position = position;
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn tempo_changes_keep_the_position() {
+        let start = Instant::now();
+        let mut looper = Looper::new(120.0, 4.0);
+        looper.start_at(start);
+        let second = start + Duration::from_secs(1);
+        looper.update_at(second);
+        assert_eq!(looper.loop_position(), 2.0);
+
+        looper.bpm = 240.0;
+        looper.update_at(second);
+        assert_eq!(looper.loop_position(), 2.0);
+        looper.update_at(second + Duration::from_millis(250));
+        assert_eq!(looper.loop_position(), 3.0);
+    }
+}
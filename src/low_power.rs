@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+/// The display turns black when no frame arrives for 2 seconds.
+const MAX_FRAME_INTERVAL: Duration = Duration::from_millis(1500);
+/// How often an unchanged frame is sent again to keep the display on.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A display mode for always-on installations: dimmer, fewer colors and
+/// fewer frames, for less USB traffic and device heat.
+///
+/// Set it with `Push2Display::set_low_power`; drawing is unchanged, the
+/// reduction is applied when the frame is flushed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LowPowerMode {
+    /// Scales every pixel, from 0.0 (black) to 1.0.
+    pub brightness: f32,
+    pub grayscale: bool,
+    /// The number of levels kept per color channel (2-64).
+    pub levels: u8,
+    /// Flushes closer together than this are skipped. At most 1.5 seconds,
+    /// as the display turns black after 2 seconds without a frame.
+    pub frame_interval: Duration,
+}
+
+impl Default for LowPowerMode {
+    fn default() -> Self {
+        Self {
+            brightness: 0.5,
+            grayscale: false,
+            levels: 8,
+            frame_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+impl LowPowerMode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_brightness(mut self, brightness: f32) -> Self {
+        self.brightness = brightness;
+        self
+    }
+
+    pub fn with_grayscale(mut self, grayscale: bool) -> Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    pub fn with_levels(mut self, levels: u8) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn with_frame_interval(mut self, frame_interval: Duration) -> Self {
+        self.frame_interval = frame_interval;
+        self
+    }
+
+    /// The reduced value of a raw Bgr565 pixel.
+    pub fn apply(&self, pixel: u16) -> u16 {
+        let r = (pixel & 0x1F) as f32 / 31.0;
+        let g = ((pixel >> 5) & 0x3F) as f32 / 63.0;
+        let b = (pixel >> 11) as f32 / 31.0;
+        let (r, g, b) = if self.grayscale {
+            let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+            (luma, luma, luma)
+        } else {
+            (r, g, b)
+        };
+        let steps = (self.levels.clamp(2, 64) - 1) as f32;
+        let brightness = self.brightness.clamp(0.0, 1.0);
+        let reduce = |v: f32, max: f32| {
+            let level = (v * brightness * steps).round() / steps;
+            (level * max).round() as u16
+        };
+        reduce(b, 31.0) << 11 | reduce(g, 63.0) << 5 | reduce(r, 31.0)
+    }
+}
+
+/// The low power mode of a display, with what it last sent.
+pub(crate) struct LowPowerState {
+    pub(crate) mode: LowPowerMode,
+    /// The reduced value of every Bgr565 pixel.
+    table: Box<[u16]>,
+    last_sent: Option<Instant>,
+    sent_frame: Vec<u16>,
+}
+
+impl LowPowerState {
+    pub(crate) fn new(mode: LowPowerMode) -> Self {
+        Self {
+            mode,
+            table: (0..=u16::MAX).map(|pixel| mode.apply(pixel)).collect(),
+            last_sent: None,
+            sent_frame: Vec::new(),
+        }
+    }
+
+    /// The reduced frame to send, or `None` when this flush is skipped.
+    pub(crate) fn prepare(&mut self, frame: &[u16]) -> Option<&[u16]> {
+        let now = Instant::now();
+        let since = self.last_sent.map(|last| now.duration_since(last));
+        if since.is_some_and(|since| since < self.mode.frame_interval.min(MAX_FRAME_INTERVAL)) {
+            return None;
+        }
+        let reduced: Vec<u16> = frame.iter().map(|&p| self.table[p as usize]).collect();
+        if reduced == self.sent_frame && since.is_some_and(|since| since < KEEPALIVE_INTERVAL) {
+            return None;
+        }
+        self.sent_frame = reduced;
+        self.last_sent = Some(now);
+        Some(&self.sent_frame)
+    }
+}
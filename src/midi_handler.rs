@@ -1,15 +1,19 @@
-use crate::app_config::AppConfig;
+use crate::app_config::{AppConfig, MidiBackend, PortSelectionPolicy, RouteEndpoint, RouteRule};
 use log::{info, warn};
 use midir::{
-    ConnectError, Ignore, InitError, MidiInput, MidiInputConnection, MidiInputPort, MidiOutput,
-    MidiOutputConnection, MidiOutputPort, PortInfoError,
+    ConnectError, Ignore, InitError, MidiIO, MidiInput, MidiInputConnection, MidiInputPort,
+    MidiOutput, MidiOutputConnection, MidiOutputPort, PortInfoError,
 };
+use regex::Regex;
 use std::io::{self, Write, stdin, stdout};
 use std::num::ParseIntError;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum MidiHandlerError {
     #[error("MidiInput initialization failed: {0}")]
     InputInit(#[from] InitError),
@@ -17,10 +21,16 @@ pub enum MidiHandlerError {
     OutputInit(InitError),
     #[error("Failed to get port name: {0}")]
     PortName(#[from] PortInfoError),
-    #[error("Input connection failed: {0}")]
-    InputConnection(#[from] ConnectError<MidiInput>),
-    #[error("Output connection failed: {0}")]
-    OutputConnection(#[from] ConnectError<MidiOutput>),
+    #[error("Input connection to '{port}' failed: {source}")]
+    InputConnection {
+        port: String,
+        source: ConnectError<MidiInput>,
+    },
+    #[error("Output connection to '{port}' failed: {source}")]
+    OutputConnection {
+        port: String,
+        source: ConnectError<MidiOutput>,
+    },
     #[error("No MIDI input ports found")]
     NoInputPorts,
     #[error("No MIDI output ports found")]
@@ -29,10 +39,57 @@ pub enum MidiHandlerError {
     InvalidPortSelection(#[from] ParseIntError),
     #[error("STDIO error: {0}")]
     IOError(#[from] io::Error),
-    #[error("Invalid input port index")]
-    InvalidInputPortIndex,
-    #[error("Invalid output port index")]
-    InvalidOutputPortIndex,
+    #[error("There is no input port {0}")]
+    InvalidInputPortIndex(usize),
+    #[error("There is no output port {0}")]
+    InvalidOutputPortIndex(usize),
+    #[error("No MIDI port matching '{0}' found")]
+    PortNotFound(String),
+    #[error("Invalid port pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    #[error("The config asks for the {0:?} MIDI backend, but this build uses {1:?}")]
+    BackendUnavailable(MidiBackend, MidiBackend),
+}
+
+impl MidiHandlerError {
+    /// True if connecting again may work, e.g. once the Push 2 is plugged in
+    /// or another program lets go of the port.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            MidiHandlerError::InputConnection { .. }
+                | MidiHandlerError::OutputConnection { .. }
+                | MidiHandlerError::NoInputPorts
+                | MidiHandlerError::NoOutputPorts
+                | MidiHandlerError::PortNotFound(_)
+                | MidiHandlerError::InvalidPortSelection(_)
+                | MidiHandlerError::InvalidInputPortIndex(_)
+                | MidiHandlerError::InvalidOutputPortIndex(_)
+        )
+    }
+
+    /// What the user can do about the error, if anything.
+    pub fn hint(&self) -> Option<&'static str> {
+        Some(match self {
+            MidiHandlerError::InputConnection { .. }
+            | MidiHandlerError::OutputConnection { .. } => {
+                "Close other programs using the port, such as Ableton Live, and try again"
+            }
+            MidiHandlerError::NoInputPorts
+            | MidiHandlerError::NoOutputPorts
+            | MidiHandlerError::PortNotFound(_) => {
+                "Check that the Push 2 is plugged in and powered, or fix the port names in app_config.ron"
+            }
+            MidiHandlerError::InvalidPortSelection(_)
+            | MidiHandlerError::InvalidInputPortIndex(_)
+            | MidiHandlerError::InvalidOutputPortIndex(_) => "Enter one of the port numbers listed",
+            MidiHandlerError::InvalidPattern(_) => "Fix the port pattern in app_config.ron",
+            MidiHandlerError::BackendUnavailable(..) => {
+                "Rebuild with the MIDI backend in app_config.ron, or remove midi_backend from it"
+            }
+            _ => return None,
+        })
+    }
 }
 
 /// Holds the MIDI connections.
@@ -41,44 +98,302 @@ pub enum MidiHandlerError {
 pub struct MidiHandler {
     pub _conn_in: MidiInputConnection<()>,
     pub conn_out: MidiOutputConnection,
+    /// Input connections opened for routes from external ports
+    pub _route_conns: Vec<MidiInputConnection<()>>,
+    /// The name of the input port that was opened
+    pub input_port_name: String,
+    /// The name of the output port that was opened
+    pub output_port_name: String,
+}
+
+/// A MIDI port that appeared or disappeared, reported by a `PortWatcher`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortEvent {
+    InputAdded(String),
+    InputRemoved(String),
+    OutputAdded(String),
+    OutputRemoved(String),
+}
+
+/// Watches the MIDI ports whose names contain a filter, e.g. "Ableton Push 2",
+/// to react to the device being plugged in or out.
+pub struct PortWatcher {
+    pub filter: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl PortWatcher {
+    /// Starts from no known ports, so the first `poll` reports every matching port as added.
+    pub fn new(filter: &str) -> Self {
+        Self {
+            filter: filter.to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Lists the ports again and reports the changes since the last call.
+    pub fn poll(&mut self) -> Result<Vec<PortEvent>, MidiHandlerError> {
+        let (inputs, outputs) = MidiHandler::list_ports()?;
+        let inputs: Vec<String> = inputs
+            .into_iter()
+            .filter(|name| name.contains(&self.filter))
+            .collect();
+        let outputs: Vec<String> = outputs
+            .into_iter()
+            .filter(|name| name.contains(&self.filter))
+            .collect();
+        let mut events = Vec::new();
+        events.extend(added(&self.inputs, &inputs).map(PortEvent::InputAdded));
+        events.extend(added(&inputs, &self.inputs).map(PortEvent::InputRemoved));
+        events.extend(added(&self.outputs, &outputs).map(PortEvent::OutputAdded));
+        events.extend(added(&outputs, &self.outputs).map(PortEvent::OutputRemoved));
+        self.inputs = inputs;
+        self.outputs = outputs;
+        Ok(events)
+    }
+
+    /// Polls every `interval` on a background thread, sending the changes.
+    /// The thread stops when the receiver is dropped.
+    pub fn spawn(mut self, interval: Duration) -> Receiver<PortEvent> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                match self.poll() {
+                    Ok(events) => {
+                        for event in events {
+                            if tx.send(event).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => warn!("Failed to list MIDI ports: {}", e),
+                }
+                thread::sleep(interval);
+            }
+        });
+        rx
+    }
+}
+
+/// The names in `now` that are not in `before`.
+fn added<'a>(before: &'a [String], now: &'a [String]) -> impl Iterator<Item = String> + 'a {
+    now.iter().filter(|name| !before.contains(name)).cloned()
+}
+
+/// Where a routed message is delivered.
+enum RouteSink {
+    Events(Sender<Vec<u8>>),
+    Port(MidiOutputConnection),
+}
+
+impl RouteSink {
+    fn send(&mut self, message: &[u8]) {
+        match self {
+            RouteSink::Events(tx) => {
+                let _ = tx.send(message.to_vec());
+            }
+            RouteSink::Port(conn_out) => {
+                if let Err(e) = conn_out.send(message) {
+                    warn!("Failed to forward routed message: {}", e);
+                }
+            }
+        }
+    }
 }
 
 impl MidiHandler {
     /// Creates a new MidiHandler, finds and connects to ports.
     pub fn new(config: &AppConfig, tx: Sender<Vec<u8>>) -> Result<Self, MidiHandlerError> {
+        let backend = MidiBackend::current();
+        if let Some(wanted) = config.midi_backend
+            && wanted != backend
+        {
+            return Err(MidiHandlerError::BackendUnavailable(wanted, backend));
+        }
+        info!("Using the {:?} MIDI backend", backend);
+
         // --- Input Connection ---
         let mut midi_in = MidiInput::new("push2")?;
         midi_in.ignore(Ignore::None);
 
-        let in_port = Self::select_input_port(&midi_in, &config.midi_input_port)?;
+        let policy = config.effective_port_selection();
+        let in_port = Self::select_input_port(&midi_in, &config.midi_input_port, &policy)?;
         let in_port_name = midi_in.port_name(&in_port)?;
 
+        // --- Routing ---
+        let (mut forwards, _route_conns) = Self::connect_routes(&config.routes, &tx);
+
         info!("Opening input connection to: {}", in_port_name);
-        let _conn_in = midi_in.connect(
-            &in_port,
-            "push2-input-connection",
-            move |_stamp, message, _| {
-                tx.send(message.to_vec()).unwrap();
-            },
-            (),
-        )?;
+        let _conn_in = midi_in
+            .connect(
+                &in_port,
+                "push2-input-connection",
+                move |_stamp, message, _| {
+                    for (rule, sink) in forwards.iter_mut() {
+                        if rule.matches(message) {
+                            sink.send(message);
+                        }
+                    }
+                    tx.send(message.to_vec()).unwrap();
+                },
+                (),
+            )
+            .map_err(|source| MidiHandlerError::InputConnection {
+                port: in_port_name.clone(),
+                source,
+            })?;
 
         // --- Output Connection ---
         let midi_out = MidiOutput::new("push2_output").map_err(MidiHandlerError::OutputInit)?;
-        let out_port = Self::select_output_port(&midi_out, &config.midi_output_port)?;
+        let out_port = Self::select_output_port(&midi_out, &config.midi_output_port, &policy)?;
         let out_port_name = midi_out.port_name(&out_port)?;
 
         info!("Opening output connection to: {}", out_port_name);
-        let conn_out = midi_out.connect(&out_port, "push2-output-connection")?;
+        let conn_out = midi_out
+            .connect(&out_port, "push2-output-connection")
+            .map_err(|source| MidiHandlerError::OutputConnection {
+                port: out_port_name.clone(),
+                source,
+            })?;
 
-        Ok(MidiHandler { _conn_in, conn_out })
+        Ok(MidiHandler {
+            _conn_in,
+            conn_out,
+            _route_conns,
+            input_port_name: in_port_name,
+            output_port_name: out_port_name,
+        })
     }
 
-    /// Finds the configured input port, or falls back to manual selection.
+    /// The names of the available input and output ports.
+    pub fn list_ports() -> Result<(Vec<String>, Vec<String>), MidiHandlerError> {
+        let midi_in = MidiInput::new("push2_port_list")?;
+        let midi_out = MidiOutput::new("push2_port_list").map_err(MidiHandlerError::OutputInit)?;
+        let inputs = midi_in
+            .ports()
+            .iter()
+            .map(|port| midi_in.port_name(port))
+            .collect::<Result<_, _>>()?;
+        let outputs = midi_out
+            .ports()
+            .iter()
+            .map(|port| midi_out.port_name(port))
+            .collect::<Result<_, _>>()?;
+        Ok((inputs, outputs))
+    }
 
+    /// Opens the ports used by the routing rules.
+    /// Returns the sinks for messages coming from the Push 2, and the input
+    /// connections for routes from other ports. Routes whose ports cannot be
+    /// opened are skipped with a warning, so a missing synth never blocks startup.
+    fn connect_routes(
+        routes: &[RouteRule],
+        tx: &Sender<Vec<u8>>,
+    ) -> (Vec<(RouteRule, RouteSink)>, Vec<MidiInputConnection<()>>) {
+        let mut forwards = Vec::new();
+        let mut route_conns = Vec::new();
+        for rule in routes {
+            let sink = match &rule.destination {
+                RouteEndpoint::Push2 => RouteSink::Events(tx.clone()),
+                RouteEndpoint::Port(name) => match Self::connect_output(name) {
+                    Ok(conn_out) => RouteSink::Port(conn_out),
+                    Err(e) => {
+                        warn!("Skipping route to '{}': {}", name, e);
+                        continue;
+                    }
+                },
+            };
+            match &rule.source {
+                RouteEndpoint::Push2 => {
+                    if matches!(sink, RouteSink::Events(_)) {
+                        warn!("Skipping route from the Push 2 to itself");
+                        continue;
+                    }
+                    forwards.push((rule.clone(), sink));
+                }
+                RouteEndpoint::Port(name) => {
+                    let route = rule.clone();
+                    let mut sink = sink;
+                    let conn = Self::connect_input(name, move |_stamp, message, _| {
+                        if route.matches(message) {
+                            sink.send(message);
+                        }
+                    });
+                    match conn {
+                        Ok(conn) => route_conns.push(conn),
+                        Err(e) => warn!("Skipping route from '{}': {}", name, e),
+                    }
+                }
+            }
+        }
+        (forwards, route_conns)
+    }
+
+    /// Connects to an additional output port (e.g. a DAW or hardware sequencer).
+    /// The first port whose name contains `port_name` is used.
+    pub fn connect_output(port_name: &str) -> Result<MidiOutputConnection, MidiHandlerError> {
+        let midi_out = MidiOutput::new("push2_aux_output").map_err(MidiHandlerError::OutputInit)?;
+        let port = Self::find_port(&midi_out, port_name)?;
+        let name = midi_out.port_name(&port)?;
+        info!("Opening auxiliary output connection to: {}", name);
+        midi_out
+            .connect(&port, "push2-aux-output-connection")
+            .map_err(|source| MidiHandlerError::OutputConnection { port: name, source })
+    }
+
+    /// Connects to an additional input port, calling `callback` for every message.
+    /// The first port whose name contains `port_name` is used.
+    pub fn connect_input<F>(
+        port_name: &str,
+        callback: F,
+    ) -> Result<MidiInputConnection<()>, MidiHandlerError>
+    where
+        F: FnMut(u64, &[u8], &mut ()) + Send + 'static,
+    {
+        let mut midi_in = MidiInput::new("push2_aux_input")?;
+        midi_in.ignore(Ignore::None);
+        let port = Self::find_port(&midi_in, port_name)?;
+        let name = midi_in.port_name(&port)?;
+        info!("Opening auxiliary input connection to: {}", name);
+        midi_in
+            .connect(&port, "push2-aux-input-connection", callback, ())
+            .map_err(|source| MidiHandlerError::InputConnection { port: name, source })
+    }
+
+    /// Finds the first port whose name contains `port_name`.
+    fn find_port<T: MidiIO>(io: &T, port_name: &str) -> Result<T::Port, MidiHandlerError> {
+        for port in io.ports() {
+            if io.port_name(&port)?.contains(port_name) {
+                return Ok(port);
+            }
+        }
+        Err(MidiHandlerError::PortNotFound(port_name.to_string()))
+    }
+
+    /// Finds the first port whose name matches the regular expression `pattern`.
+    fn find_matching_port<T: MidiIO>(
+        io: &T,
+        ports: &[T::Port],
+        pattern: &str,
+    ) -> Result<T::Port, MidiHandlerError> {
+        let regex = Regex::new(pattern)?;
+        for port in ports {
+            let name = io.port_name(port)?;
+            if regex.is_match(&name) {
+                info!("Found port matching '{}': {}", pattern, name);
+                return Ok(port.clone());
+            }
+        }
+        Err(MidiHandlerError::PortNotFound(pattern.to_string()))
+    }
+
+    /// Finds the configured input port, or falls back according to `policy`.
     fn select_input_port(
         midi_in: &MidiInput,
         config_port_name: &str,
+        policy: &PortSelectionPolicy,
     ) -> Result<MidiInputPort, MidiHandlerError> {
         let in_ports = midi_in.ports();
         // Try to find port from config
@@ -89,6 +404,22 @@ impl MidiHandler {
             }
         }
 
+        match policy {
+            PortSelectionPolicy::ConfiguredOnly => {
+                return Err(MidiHandlerError::PortNotFound(config_port_name.to_string()));
+            }
+            PortSelectionPolicy::FirstMatching(pattern) => {
+                return Self::find_matching_port(midi_in, &in_ports, pattern);
+            }
+            PortSelectionPolicy::Interactive | PortSelectionPolicy::Fail => {}
+        }
+
+        // The client number in the configured name may have changed: look for the Push 2.
+        let pattern = MidiBackend::current().push2_port_pattern();
+        if let Ok(port) = Self::find_matching_port(midi_in, &in_ports, pattern) {
+            return Ok(port);
+        }
+
         // Configured port not found, fall back to old logic
         warn!(
             "Configured input port '{}' not found. Falling back to manual selection.",
@@ -104,6 +435,9 @@ impl MidiHandler {
                 );
                 Ok(in_ports[0].clone())
             }
+            _ if *policy == PortSelectionPolicy::Fail => {
+                Err(MidiHandlerError::PortNotFound(config_port_name.to_string()))
+            }
             _ => {
                 println!("\nAvailable input ports:");
                 for (i, port) in in_ports.iter().enumerate() {
@@ -117,16 +451,16 @@ impl MidiHandler {
                 in_ports
                     .get(port_index)
                     .cloned()
-                    .ok_or(MidiHandlerError::InvalidInputPortIndex)
+                    .ok_or(MidiHandlerError::InvalidInputPortIndex(port_index))
             }
         }
     }
 
-    /// Finds the configured output port, or falls back to manual selection.
-
+    /// Finds the configured output port, or falls back according to `policy`.
     fn select_output_port(
         midi_out: &MidiOutput,
         config_port_name: &str,
+        policy: &PortSelectionPolicy,
     ) -> Result<MidiOutputPort, MidiHandlerError> {
         let out_ports = midi_out.ports();
         // Try to find output port from config
@@ -137,6 +471,22 @@ impl MidiHandler {
             }
         }
 
+        match policy {
+            PortSelectionPolicy::ConfiguredOnly => {
+                return Err(MidiHandlerError::PortNotFound(config_port_name.to_string()));
+            }
+            PortSelectionPolicy::FirstMatching(pattern) => {
+                return Self::find_matching_port(midi_out, &out_ports, pattern);
+            }
+            PortSelectionPolicy::Interactive | PortSelectionPolicy::Fail => {}
+        }
+
+        // The client number in the configured name may have changed: look for the Push 2.
+        let pattern = MidiBackend::current().push2_port_pattern();
+        if let Ok(port) = Self::find_matching_port(midi_out, &out_ports, pattern) {
+            return Ok(port);
+        }
+
         // Configured port not found, fall back to old logic
         warn!(
             "Configured output port '{}' not found. Falling back to manual selection.",
@@ -152,6 +502,9 @@ impl MidiHandler {
                 );
                 Ok(out_ports[0].clone())
             }
+            _ if *policy == PortSelectionPolicy::Fail => {
+                Err(MidiHandlerError::PortNotFound(config_port_name.to_string()))
+            }
             _ => {
                 println!("\nAvailable output ports:");
                 for (i, port) in out_ports.iter().enumerate() {
@@ -165,8 +518,8 @@ impl MidiHandler {
                 out_ports
                     .get(port_index)
                     .cloned()
-                    .ok_or(MidiHandlerError::InvalidOutputPortIndex)
+                    .ok_or(MidiHandlerError::InvalidOutputPortIndex(port_index))
             }
         }
     }
-}
\ No newline at end of file
+}
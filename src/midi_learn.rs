@@ -0,0 +1,185 @@
+use crate::app_config::config_dir;
+use crate::{ControlName, EncoderName, PadCoord, Push2Event};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MidiLearnError {
+    #[error("Failed to serialize bindings: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to parse bindings file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read or write bindings file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+}
+
+/// A control that can be bound to an action.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LearnedControl {
+    Pad(PadCoord),
+    Button(ControlName),
+    Encoder(EncoderName),
+}
+
+impl LearnedControl {
+    /// The control that produced an event. Releases and the slider are not learnable.
+    pub fn from_event(event: &Push2Event) -> Option<Self> {
+        match *event {
+            Push2Event::PadPressed { coord, .. } => Some(LearnedControl::Pad(coord)),
+            Push2Event::ButtonPressed { name, .. } => Some(LearnedControl::Button(name)),
+            Push2Event::EncoderTwisted { name, .. } => Some(LearnedControl::Encoder(name)),
+            _ => None,
+        }
+    }
+}
+
+/// Produced by `MidiLearn::handle_event`.
+#[derive(Debug, Clone)]
+pub enum MidiLearnEvent {
+    /// A control was bound to an action while learning.
+    Learned {
+        action: String,
+        control: LearnedControl,
+    },
+    /// An event came from a control bound to an action.
+    Action { action: String, event: Push2Event },
+}
+
+/// Binds controls to app-defined action ids by pressing them.
+///
+/// Call `learn` with an action id, then press a pad or button (or turn an
+/// encoder) to bind it. Afterwards `handle_event` resolves events from that
+/// control to the action. The bindings are saved after each change.
+pub struct MidiLearn {
+    bindings: HashMap<LearnedControl, String>,
+    learning: Option<String>,
+    /// Where the bindings are saved, if anywhere.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for MidiLearn {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MidiLearn {
+    /// Bindings kept in memory only.
+    pub fn new() -> Self {
+        Self {
+            bindings: HashMap::new(),
+            learning: None,
+            path: None,
+        }
+    }
+
+    /// Loads the bindings of an app from "midi_learn/<app_name>.ron" in the
+    /// push2 config directory, where they are saved from then on.
+    pub fn in_config_dir(app_name: &str) -> Result<Self, MidiLearnError> {
+        let mut path = config_dir().ok_or(MidiLearnError::NoConfigDir)?;
+        path.push("midi_learn");
+        path.push(format!("{app_name}.ron"));
+        let mut learn = Self::new();
+        if path.exists() {
+            info!("Loading MIDI learn bindings from: {:?}", path);
+            let ron = fs::read_to_string(&path)?;
+            learn.bindings = ron::from_str(&ron).map_err(Box::new)?;
+        }
+        learn.path = Some(path);
+        Ok(learn)
+    }
+
+    /// Writes the bindings to `path`, if set.
+    pub fn save(&self) -> Result<(), MidiLearnError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let ron = ron::ser::to_string_pretty(&self.bindings, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+
+    /// Binds the next pressed control to `action`.
+    pub fn learn(&mut self, action: &str) {
+        self.learning = Some(action.to_string());
+    }
+
+    pub fn cancel(&mut self) {
+        self.learning = None;
+    }
+
+    /// The action waiting for a control, while in learn mode.
+    pub fn learning(&self) -> Option<&str> {
+        self.learning.as_deref()
+    }
+
+    /// Binds a control to an action, replacing the control's previous binding.
+    pub fn bind(&mut self, control: LearnedControl, action: &str) -> Result<(), MidiLearnError> {
+        self.bindings.insert(control, action.to_string());
+        self.save()
+    }
+
+    /// Removes every binding of an action.
+    pub fn unbind(&mut self, action: &str) -> Result<(), MidiLearnError> {
+        self.bindings.retain(|_, a| a != action);
+        self.save()
+    }
+
+    pub fn clear(&mut self) -> Result<(), MidiLearnError> {
+        self.bindings.clear();
+        self.save()
+    }
+
+    pub fn action(&self, control: LearnedControl) -> Option<&str> {
+        self.bindings.get(&control).map(String::as_str)
+    }
+
+    /// The controls bound to an action.
+    pub fn controls(&self, action: &str) -> Vec<LearnedControl> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(control, _)| *control)
+            .collect()
+    }
+
+    /// The action an event resolves to. Releases resolve through the
+    /// pad or button that was released.
+    pub fn resolve(&self, event: &Push2Event) -> Option<&str> {
+        let control = match *event {
+            Push2Event::PadReleased { coord, .. } => LearnedControl::Pad(coord),
+            Push2Event::ButtonReleased { name } => LearnedControl::Button(name),
+            Push2Event::PadAftertouch { coord, .. } => LearnedControl::Pad(coord),
+            _ => LearnedControl::from_event(event)?,
+        };
+        self.action(control)
+    }
+
+    /// Binds the control while learning, otherwise resolves the event.
+    pub fn handle_event(
+        &mut self,
+        event: &Push2Event,
+    ) -> Result<Option<MidiLearnEvent>, MidiLearnError> {
+        if self.learning.is_some()
+            && let Some(control) = LearnedControl::from_event(event)
+            && let Some(action) = self.learning.take()
+        {
+            info!("Bound {:?} to '{}'", control, action);
+            self.bind(control, &action)?;
+            return Ok(Some(MidiLearnEvent::Learned { action, control }));
+        }
+        Ok(self.resolve(event).map(|action| MidiLearnEvent::Action {
+            action: action.to_string(),
+            event: event.clone(),
+        }))
+    }
+}
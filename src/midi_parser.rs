@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+
+/// Splits a raw MIDI byte stream into complete, status-prefixed messages.
+///
+/// Bytes can arrive in chunks of any size: a chunk may hold several messages,
+/// part of one, or data bytes relying on running status. System exclusive
+/// messages are buffered until their closing 0xF7, and realtime bytes
+/// (clock, start, stop...) are passed through as soon as they are seen, even
+/// in the middle of another message.
+#[derive(Debug, Default)]
+pub struct MidiParser {
+    /// The status of the last channel message, reused by running status.
+    running_status: Option<u8>,
+    /// The message being assembled, starting with its status byte.
+    partial: Vec<u8>,
+    in_sysex: bool,
+    ready: VecDeque<Vec<u8>>,
+}
+
+impl MidiParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of data bytes following a status byte, or `None` for SysEx.
+    fn data_len(status: u8) -> Option<usize> {
+        match status {
+            0x80..=0xBF | 0xE0..=0xEF => Some(2),
+            0xC0..=0xDF => Some(1),
+            0xF1 | 0xF3 => Some(1),
+            0xF2 => Some(2),
+            SYSEX_START => None,
+            _ => Some(0),
+        }
+    }
+
+    /// Adds bytes from the input. The complete messages are returned by `next_message`.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.feed_byte(byte);
+        }
+    }
+
+    fn feed_byte(&mut self, byte: u8) {
+        if byte >= 0xF8 {
+            // Realtime messages may appear anywhere and do not affect running status.
+            self.ready.push_back(vec![byte]);
+            return;
+        }
+        if self.in_sysex {
+            if byte == SYSEX_END {
+                self.partial.push(byte);
+                self.ready.push_back(std::mem::take(&mut self.partial));
+                self.in_sysex = false;
+                return;
+            }
+            if byte < 0x80 {
+                self.partial.push(byte);
+                return;
+            }
+            // Any other status byte cuts an unterminated SysEx short; drop it.
+            self.partial.clear();
+            self.in_sysex = false;
+        }
+        if byte >= 0x80 {
+            self.start_message(byte);
+            return;
+        }
+        // A data byte: continue the current message, or start one with running status.
+        if self.partial.is_empty() {
+            let Some(status) = self.running_status else {
+                return;
+            };
+            self.partial.push(status);
+        }
+        self.partial.push(byte);
+        self.complete_if_full();
+    }
+
+    fn start_message(&mut self, status: u8) {
+        self.partial.clear();
+        match status {
+            SYSEX_START => {
+                self.running_status = None;
+                self.in_sysex = true;
+                self.partial.push(status);
+            }
+            // A stray end of SysEx.
+            SYSEX_END => self.running_status = None,
+            0x80..=0xEF => {
+                self.running_status = Some(status);
+                self.partial.push(status);
+            }
+            _ => {
+                // System common messages cancel running status.
+                self.running_status = None;
+                self.partial.push(status);
+                self.complete_if_full();
+            }
+        }
+    }
+
+    fn complete_if_full(&mut self) {
+        let Some(&status) = self.partial.first() else {
+            return;
+        };
+        if Self::data_len(status).is_some_and(|len| self.partial.len() > len) {
+            self.ready.push_back(std::mem::take(&mut self.partial));
+        }
+    }
+
+    /// The next complete message, if any.
+    pub fn next_message(&mut self) -> Option<Vec<u8>> {
+        self.ready.pop_front()
+    }
+
+    /// Drops any partial message and forgets the running status.
+    pub fn reset(&mut self) {
+        self.running_status = None;
+        self.partial.clear();
+        self.in_sysex = false;
+        self.ready.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut parser = MidiParser::new();
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        std::iter::from_fn(|| parser.next_message()).collect()
+    }
+
+    #[test]
+    fn complete_messages_pass_through() {
+        let messages = parse(&[&[0x90, 36, 100], &[0xB0, 71, 1]]);
+        assert_eq!(messages, vec![vec![0x90, 36, 100], vec![0xB0, 71, 1]]);
+    }
+
+    #[test]
+    fn several_messages_in_one_chunk() {
+        let messages = parse(&[&[0x90, 36, 100, 0x80, 36, 0, 0xD0, 42]]);
+        assert_eq!(
+            messages,
+            vec![vec![0x90, 36, 100], vec![0x80, 36, 0], vec![0xD0, 42]]
+        );
+    }
+
+    #[test]
+    fn running_status() {
+        let messages = parse(&[&[0x90, 36, 100, 37, 90], &[38, 0]]);
+        assert_eq!(
+            messages,
+            vec![vec![0x90, 36, 100], vec![0x90, 37, 90], vec![0x90, 38, 0]]
+        );
+    }
+
+    #[test]
+    fn running_status_with_one_data_byte() {
+        let messages = parse(&[&[0xD0, 10, 20, 30]]);
+        assert_eq!(
+            messages,
+            vec![vec![0xD0, 10], vec![0xD0, 20], vec![0xD0, 30]]
+        );
+    }
+
+    #[test]
+    fn message_split_across_chunks() {
+        let messages = parse(&[&[0xE0], &[0x12], &[0x34]]);
+        assert_eq!(messages, vec![vec![0xE0, 0x12, 0x34]]);
+    }
+
+    #[test]
+    fn data_without_status_is_dropped() {
+        let messages = parse(&[&[36, 100], &[0x90, 36, 100]]);
+        assert_eq!(messages, vec![vec![0x90, 36, 100]]);
+    }
+
+    #[test]
+    fn fragmented_sysex() {
+        let messages = parse(&[
+            &[0xF0, 0x00, 0x21],
+            &[0x1D, 0x01],
+            &[0x01, 0x1E, 0x00, 0xF7],
+        ]);
+        assert_eq!(
+            messages,
+            vec![vec![0xF0, 0x00, 0x21, 0x1D, 0x01, 0x01, 0x1E, 0x00, 0xF7]]
+        );
+    }
+
+    #[test]
+    fn sysex_cancels_running_status() {
+        let messages = parse(&[&[0x90, 36, 100, 0xF0, 0x7E, 0xF7, 37, 90]]);
+        assert_eq!(messages, vec![vec![0x90, 36, 100], vec![0xF0, 0x7E, 0xF7]]);
+    }
+
+    #[test]
+    fn realtime_inside_messages() {
+        let messages = parse(&[&[0x90, 36, 0xF8, 100, 0xF0, 0x01, 0xFA, 0x02, 0xF7]]);
+        assert_eq!(
+            messages,
+            vec![
+                vec![0xF8],
+                vec![0x90, 36, 100],
+                vec![0xFA],
+                vec![0xF0, 0x01, 0x02, 0xF7],
+            ]
+        );
+    }
+
+    #[test]
+    fn realtime_keeps_running_status() {
+        let messages = parse(&[&[0xB0, 71, 1, 0xF8, 72, 127]]);
+        assert_eq!(
+            messages,
+            vec![vec![0xB0, 71, 1], vec![0xF8], vec![0xB0, 72, 127]]
+        );
+    }
+
+    #[test]
+    fn unterminated_sysex_is_dropped() {
+        let messages = parse(&[&[0xF0, 0x01, 0x02, 0x90, 36, 100]]);
+        assert_eq!(messages, vec![vec![0x90, 36, 100]]);
+    }
+}
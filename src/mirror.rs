@@ -0,0 +1,145 @@
+//! A desktop window mirroring the display and pad colors, e.g. for screen
+//! recordings where the hardware is not in view.
+
+use crate::Push2;
+use crate::colors::palette_rgb;
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::state::PadState;
+use minifb::{Window, WindowOptions};
+use thiserror::Error;
+
+/// The size of a pad in the window, and the space between pads.
+const PAD_SIZE: usize = 30;
+const PAD_GAP: usize = 4;
+const MARGIN: usize = 16;
+const GRID_SIZE: usize = 8 * PAD_SIZE + 7 * PAD_GAP;
+const GRID_LEFT: usize = (DISPLAY_WIDTH - GRID_SIZE) / 2;
+const GRID_TOP: usize = DISPLAY_HEIGHT + MARGIN;
+pub const MIRROR_WIDTH: usize = DISPLAY_WIDTH;
+pub const MIRROR_HEIGHT: usize = GRID_TOP + GRID_SIZE + MARGIN;
+
+const BACKGROUND: u32 = 0x1a1a1a;
+/// The outline of a held pad.
+const PRESSED: u32 = 0xffffff;
+
+#[derive(Error, Debug)]
+pub enum MirrorError {
+    #[error("Could not open the mirror window: {0}")]
+    Window(#[from] minifb::Error),
+}
+
+/// A window showing what is on the display and the pad colors from
+/// `Push2::state`, with held pads outlined.
+///
+/// Call `update` after each `flush`:
+///
+/// ```ignore
+/// let mut mirror = MirrorWindow::open()?;
+/// while mirror.is_open() {
+///     app.draw(&mut push2.display)?;
+///     push2.display.flush()?;
+///     mirror.update(&push2)?;
+/// }
+/// ```
+pub struct MirrorWindow {
+    window: Window,
+    buffer: Vec<u32>,
+}
+
+impl MirrorWindow {
+    pub fn open() -> Result<Self, MirrorError> {
+        let mut window = Window::new(
+            "Push 2",
+            MIRROR_WIDTH,
+            MIRROR_HEIGHT,
+            WindowOptions::default(),
+        )?;
+        window.set_target_fps(60);
+        Ok(Self {
+            window,
+            buffer: vec![BACKGROUND; MIRROR_WIDTH * MIRROR_HEIGHT],
+        })
+    }
+
+    /// False once the user closed the window.
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    /// Redraws the window from the device. Waits for the window's frame
+    /// rate of 60 frames per second.
+    pub fn update(&mut self, push2: &Push2) -> Result<(), MirrorError> {
+        render(
+            push2.display.visible_frame(),
+            &push2.state.pads,
+            &mut self.buffer,
+        );
+        self.window
+            .update_with_buffer(&self.buffer, MIRROR_WIDTH, MIRROR_HEIGHT)?;
+        Ok(())
+    }
+}
+
+/// Draws a display frame and the pads into a 0RGB buffer of `MIRROR_WIDTH` x `MIRROR_HEIGHT`.
+pub fn render(frame: &[u16], pads: &[[PadState; 8]; 8], buffer: &mut [u32]) {
+    buffer.fill(BACKGROUND);
+    for (row, pixels) in buffer
+        .chunks_exact_mut(MIRROR_WIDTH)
+        .zip(frame.chunks_exact(DISPLAY_WIDTH))
+    {
+        for (out, pixel) in row.iter_mut().zip(pixels) {
+            *out = bgr565_to_rgb(*pixel);
+        }
+    }
+    for (y, row) in pads.iter().enumerate() {
+        for (x, pad) in row.iter().enumerate() {
+            let left = GRID_LEFT + x * (PAD_SIZE + PAD_GAP);
+            let top = GRID_TOP + y * (PAD_SIZE + PAD_GAP);
+            let color = palette_rgb(pad.shown()).map(|c| (c * 255.0).round() as u32);
+            let fill = color[0] << 16 | color[1] << 8 | color[2];
+            let held = pad.velocity > 0;
+            for py in 0..PAD_SIZE {
+                for px in 0..PAD_SIZE {
+                    let edge = py < 2 || px < 2 || py >= PAD_SIZE - 2 || px >= PAD_SIZE - 2;
+                    buffer[(top + py) * MIRROR_WIDTH + left + px] =
+                        if held && edge { PRESSED } else { fill };
+                }
+            }
+        }
+    }
+}
+
+/// A display pixel as 0RGB.
+fn bgr565_to_rgb(pixel: u16) -> u32 {
+    let r = (pixel & 0x1f) as u32;
+    let g = ((pixel >> 5) & 0x3f) as u32;
+    let b = (pixel >> 11) as u32;
+    (r << 3 | r >> 2) << 16 | (g << 2 | g >> 4) << 8 | (b << 3 | b >> 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::{Bgr565, IntoStorage, RgbColor};
+
+    #[test]
+    fn converts_display_pixels() {
+        assert_eq!(bgr565_to_rgb(Bgr565::RED.into_storage()), 0xff0000);
+        assert_eq!(bgr565_to_rgb(Bgr565::GREEN.into_storage()), 0x00ff00);
+        assert_eq!(bgr565_to_rgb(Bgr565::BLUE.into_storage()), 0x0000ff);
+    }
+
+    #[test]
+    fn renders_held_pads_outlined() {
+        let frame = vec![Bgr565::WHITE.into_storage(); DISPLAY_WIDTH * DISPLAY_HEIGHT];
+        let mut pads = [[PadState::default(); 8]; 8];
+        pads[0][0].velocity = 100;
+        let mut buffer = vec![0; MIRROR_WIDTH * MIRROR_HEIGHT];
+        render(&frame, &pads, &mut buffer);
+        assert_eq!(buffer[0], 0xffffff);
+        let corner = GRID_TOP * MIRROR_WIDTH + GRID_LEFT;
+        assert_eq!(buffer[corner], PRESSED);
+        assert_eq!(buffer[corner + (PAD_SIZE + PAD_GAP)], 0);
+        assert_eq!(buffer[corner + MIRROR_WIDTH * 10 + 10], 0);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::button_map::decode_delta;
+use crate::display::Push2DisplayError;
+use crate::gui::{ENCODER_LABEL_Y_POS, ENCODER_VALUE_Y_POS, GuiApi};
+use crate::{ControlName, Push2, Push2Colors, Push2Display, Push2Error, Push2Event};
+use embedded_graphics::pixelcolor::{Bgr565, RgbColor};
+
+/// The tracks of an application's mixer, as controlled by `Mixer`.
+pub trait MixerModel {
+    fn track_count(&self) -> usize;
+
+    fn track_name(&self, track: usize) -> String;
+
+    /// The volume of a track (0.0-1.0).
+    fn volume(&self, track: usize) -> f32;
+
+    fn set_volume(&mut self, track: usize, volume: f32);
+
+    fn is_muted(&self, track: usize) -> bool;
+
+    fn set_muted(&mut self, track: usize, muted: bool);
+
+    fn is_soloed(&self, track: usize) -> bool;
+
+    fn set_soloed(&mut self, track: usize, soloed: bool);
+
+    fn selected_track(&self) -> Option<usize>;
+
+    fn select_track(&mut self, track: usize);
+
+    /// The current output level (0.0-1.0) of a track, shown on its VU meter.
+    fn meter_level(&self, _track: usize) -> f32 {
+        0.0
+    }
+}
+
+/// What the lower row buttons toggle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LowerRowMode {
+    Mute,
+    Solo,
+}
+
+/// The colors of the channel strips and the button lights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MixerColors {
+    pub background: Bgr565,
+    pub label: Bgr565,
+    pub selected_label: Bgr565,
+    pub volume: Bgr565,
+    pub meter: Bgr565,
+    /// The meter color at levels above 0.9.
+    pub meter_hot: Bgr565,
+    pub meter_background: Bgr565,
+    pub select_light: u8,
+    pub mute_light: u8,
+    pub solo_light: u8,
+    /// Light of a row button whose track is not selected, muted or soloed.
+    pub idle_light: u8,
+}
+
+impl Default for MixerColors {
+    fn default() -> Self {
+        Self {
+            background: Bgr565::BLACK,
+            label: Bgr565::WHITE,
+            selected_label: Bgr565::YELLOW,
+            volume: Bgr565::GREEN,
+            meter: Bgr565::GREEN,
+            meter_hot: Bgr565::RED,
+            meter_background: Bgr565::new(4, 8, 4),
+            select_light: Push2Colors::WHITE_BRIGHT,
+            mute_light: Push2Colors::ORANGE,
+            solo_light: Push2Colors::BLUE_SKY,
+            idle_light: Push2Colors::WHITE_LOW,
+        }
+    }
+}
+
+/// What a channel strip showed when it was last drawn.
+#[derive(Debug, Clone, PartialEq)]
+struct StripSnapshot {
+    name: String,
+    volume: f32,
+    level: f32,
+    selected: bool,
+    muted: bool,
+    soloed: bool,
+}
+
+/// A mixer mode for 8 tracks at a time of a `MixerModel`.
+///
+/// The track encoders set the volumes, the upper row buttons select a
+/// track and the lower row buttons mute or solo it (Mute and Solo switch
+/// between the two). PageLeft/PageRight move between banks of 8 tracks.
+pub struct Mixer {
+    pub colors: MixerColors,
+    /// The volume change per encoder tick.
+    pub volume_step: f32,
+    lower_row: LowerRowMode,
+    bank: usize,
+    drawn: [Option<StripSnapshot>; 8],
+    dirty: [bool; 8],
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Self {
+            colors: MixerColors::default(),
+            volume_step: 0.01,
+            lower_row: LowerRowMode::Mute,
+            bank: 0,
+            drawn: Default::default(),
+            dirty: [true; 8],
+        }
+    }
+
+    pub fn lower_row_mode(&self) -> LowerRowMode {
+        self.lower_row
+    }
+
+    pub fn set_lower_row_mode(&mut self, mode: LowerRowMode) {
+        self.lower_row = mode;
+    }
+
+    /// The first track shown, a multiple of 8.
+    pub fn bank_offset(&self) -> usize {
+        self.bank * 8
+    }
+
+    /// Forces every strip to be redrawn on the next `render`.
+    pub fn invalidate(&mut self) {
+        self.dirty = [true; 8];
+    }
+
+    /// The track shown on a strip (0-7), if there is one.
+    fn track_at<M: MixerModel>(&self, model: &M, strip: usize) -> Option<usize> {
+        let track = self.bank_offset() + strip;
+        (strip < 8 && track < model.track_count()).then_some(track)
+    }
+
+    /// Applies encoder turns and row button presses to the model.
+    /// Returns true if the event was consumed.
+    pub fn handle_event<M: MixerModel>(&mut self, model: &mut M, event: &Push2Event) -> bool {
+        match *event {
+            Push2Event::EncoderTwisted {
+                name, raw_delta, ..
+            } => {
+                let Some(track) = name
+                    .track_index()
+                    .and_then(|strip| self.track_at(model, strip as usize))
+                else {
+                    return false;
+                };
+                let volume =
+                    model.volume(track) + decode_delta(raw_delta) as f32 * self.volume_step;
+                model.set_volume(track, volume.clamp(0.0, 1.0));
+                true
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::Mute,
+                ..
+            } => {
+                self.lower_row = LowerRowMode::Mute;
+                true
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::Solo,
+                ..
+            } => {
+                self.lower_row = LowerRowMode::Solo;
+                true
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::PageLeft,
+                ..
+            } => {
+                if self.bank > 0 {
+                    self.bank -= 1;
+                    self.invalidate();
+                }
+                true
+            }
+            Push2Event::ButtonPressed {
+                name: ControlName::PageRight,
+                ..
+            } => {
+                if self.bank_offset() + 8 < model.track_count() {
+                    self.bank += 1;
+                    self.invalidate();
+                }
+                true
+            }
+            Push2Event::ButtonPressed { name, .. } => {
+                if let Some(strip) = ControlName::UPPER_ROW.iter().position(|b| *b == name) {
+                    if let Some(track) = self.track_at(model, strip) {
+                        model.select_track(track);
+                    }
+                    true
+                } else if let Some(strip) = ControlName::LOWER_ROW.iter().position(|b| *b == name) {
+                    if let Some(track) = self.track_at(model, strip) {
+                        match self.lower_row {
+                            LowerRowMode::Mute => model.set_muted(track, !model.is_muted(track)),
+                            LowerRowMode::Solo => model.set_soloed(track, !model.is_soloed(track)),
+                        }
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Redraws the strips that changed and updates the button lights.
+    /// Returns true if the display was drawn to and needs a flush.
+    pub fn render<M: MixerModel>(
+        &mut self,
+        push2: &mut Push2,
+        model: &M,
+    ) -> Result<bool, Push2Error> {
+        let mut drawn = false;
+        for strip in 0..8 {
+            let snapshot = self.track_at(model, strip).map(|track| StripSnapshot {
+                name: model.track_name(track),
+                volume: model.volume(track),
+                // Quantized, so a meter only redraws when it moves a pixel or so.
+                level: (model.meter_level(track).clamp(0.0, 1.0) * 96.0).round() / 96.0,
+                selected: model.selected_track() == Some(track),
+                muted: model.is_muted(track),
+                soloed: model.is_soloed(track),
+            });
+            if !self.dirty[strip] && self.drawn[strip] == snapshot {
+                continue;
+            }
+            self.draw_strip(&mut push2.display, strip as u8, snapshot.as_ref())?;
+            self.drawn[strip] = snapshot;
+            self.dirty[strip] = false;
+            drawn = true;
+        }
+        self.update_lights(push2, model)?;
+        Ok(drawn)
+    }
+
+    fn draw_strip(
+        &self,
+        display: &mut Push2Display,
+        strip: u8,
+        snapshot: Option<&StripSnapshot>,
+    ) -> Result<(), Push2DisplayError> {
+        display.clear_encoder_area(strip, self.colors.background)?;
+        let Some(snapshot) = snapshot else {
+            return display.draw_vu_meter(
+                strip,
+                0.0,
+                self.colors.background,
+                self.colors.background,
+            );
+        };
+        let bar_value = (snapshot.volume * 127.0).round() as i32;
+        display.draw_encoder_outline(strip, self.colors.volume)?;
+        display.draw_encoder_bar(strip, bar_value, self.colors.volume)?;
+        let label = if snapshot.selected {
+            self.colors.selected_label
+        } else {
+            self.colors.label
+        };
+        display.draw_encoder_label(strip, ENCODER_LABEL_Y_POS, &snapshot.name, label)?;
+        let status = match (snapshot.muted, snapshot.soloed) {
+            (true, true) => "M S",
+            (true, false) => "M",
+            (false, true) => "S",
+            (false, false) => "",
+        };
+        display.draw_encoder_label(strip, ENCODER_VALUE_Y_POS, status, self.colors.label)?;
+        let meter = if snapshot.level > 0.9 {
+            self.colors.meter_hot
+        } else {
+            self.colors.meter
+        };
+        display.draw_vu_meter(strip, snapshot.level, meter, self.colors.meter_background)
+    }
+
+    /// Lights the row, Mute and Solo buttons, if they changed.
+    fn update_lights<M: MixerModel>(&self, push2: &mut Push2, model: &M) -> Result<(), Push2Error> {
+        let mut lights = Vec::with_capacity(18);
+        for strip in 0..8 {
+            let (upper, lower) = match self.track_at(model, strip) {
+                Some(track) => {
+                    let upper = if model.selected_track() == Some(track) {
+                        self.colors.select_light
+                    } else {
+                        self.colors.idle_light
+                    };
+                    let lower = match self.lower_row {
+                        LowerRowMode::Mute if model.is_muted(track) => self.colors.mute_light,
+                        LowerRowMode::Solo if model.is_soloed(track) => self.colors.solo_light,
+                        _ => self.colors.idle_light,
+                    };
+                    (upper, lower)
+                }
+                None => (Push2Colors::BLACK, Push2Colors::BLACK),
+            };
+            lights.push((ControlName::UPPER_ROW[strip], upper));
+            lights.push((ControlName::LOWER_ROW[strip], lower));
+        }
+        let (mute, solo) = match self.lower_row {
+            LowerRowMode::Mute => (self.colors.select_light, self.colors.idle_light),
+            LowerRowMode::Solo => (self.colors.idle_light, self.colors.select_light),
+        };
+        lights.push((ControlName::Mute, mute));
+        lights.push((ControlName::Solo, solo));
+        for (name, light) in lights {
+            if push2.state.buttons.get(&name).map(|b| b.light) != Some(light) {
+                push2.set_button_light(name, light)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{NoteLayout, PadCoord};
+
+/// The musical scales available to keys and note layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scale {
+    Major,
+    Minor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    HarmonicMinor,
+    MelodicMinor,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+    WholeTone,
+    Chromatic,
+}
+
+impl Scale {
+    /// The semitone offsets of each degree, relative to the root.
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Scale::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            Scale::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            Scale::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+            Scale::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+            Scale::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            Scale::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+            Scale::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            Scale::MelodicMinor => &[0, 2, 3, 5, 7, 9, 11],
+            Scale::MajorPentatonic => &[0, 2, 4, 7, 9],
+            Scale::MinorPentatonic => &[0, 3, 5, 7, 10],
+            Scale::Blues => &[0, 3, 5, 6, 7, 10],
+            Scale::WholeTone => &[0, 2, 4, 6, 8, 10],
+            Scale::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+
+    /// Returns true if the pitch class (0-11, relative to the root) is part of the scale.
+    pub fn contains_interval(&self, interval: u8) -> bool {
+        self.intervals().contains(&(interval % 12))
+    }
+}
+
+/// Note names of the 12 pitch classes, starting at C.
+pub const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Returns the name of a MIDI note using Ableton's octave numbering (60 = "C3").
+pub fn note_name(note: u8) -> String {
+    let octave = (note / 12) as i32 - 2;
+    format!("{}{}", NOTE_NAMES[(note % 12) as usize], octave)
+}
+
+/// A root pitch class combined with a scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    /// The root pitch class (0 = C, 11 = B).
+    pub root: u8,
+    pub scale: Scale,
+}
+
+impl Key {
+    pub fn new(root: u8, scale: Scale) -> Self {
+        Self {
+            root: root % 12,
+            scale,
+        }
+    }
+
+    /// Returns true if the note belongs to the key.
+    pub fn contains(&self, note: u8) -> bool {
+        self.scale.contains_interval(self.interval_of(note))
+    }
+
+    /// Returns the scale degree (0-based) of a note, if it belongs to the key.
+    pub fn degree_of(&self, note: u8) -> Option<usize> {
+        let interval = self.interval_of(note);
+        self.scale.intervals().iter().position(|i| *i == interval)
+    }
+
+    /// Returns the nearest note that belongs to the key.
+    /// Ties are resolved downwards.
+    pub fn quantize(&self, note: u8) -> u8 {
+        (0..12u8)
+            .flat_map(|distance| [note.checked_sub(distance), note.checked_add(distance)])
+            .flatten()
+            .find(|candidate| *candidate <= 127 && self.contains(*candidate))
+            .unwrap_or(note)
+    }
+
+    /// Returns the note `steps` scale degrees above (or below, if negative) `note`.
+    /// The starting note is quantized to the key first.
+    pub fn transpose_degrees(&self, note: u8, steps: i32) -> Option<u8> {
+        let start = self.quantize(note);
+        let intervals = self.scale.intervals();
+        let len = intervals.len() as i32;
+        let degree = self.degree_of(start)? as i32;
+        let octave_start = start as i32 - intervals[degree as usize] as i32;
+        let target = degree + steps;
+        let note = octave_start
+            + target.div_euclid(len) * 12
+            + intervals[target.rem_euclid(len) as usize] as i32;
+        u8::try_from(note).ok().filter(|n| *n <= 127)
+    }
+
+    /// Builds a chord by stacking thirds inside the key, starting on `note`.
+    ///
+    /// * `voices` - The number of notes in the chord (3 for a triad, 4 for a seventh chord).
+    pub fn diatonic_chord(&self, note: u8, voices: usize) -> Vec<u8> {
+        (0..voices as i32)
+            .filter_map(|voice| self.transpose_degrees(note, voice * 2))
+            .collect()
+    }
+
+    fn interval_of(&self, note: u8) -> u8 {
+        (note as i16 - self.root as i16).rem_euclid(12) as u8
+    }
+}
+
+/// The interval structure of a chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Major7,
+    Minor7,
+    Dominant7,
+    HalfDiminished7,
+    Power,
+}
+
+impl ChordQuality {
+    /// The semitone offsets of each chord tone, relative to the root.
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Diminished => &[0, 3, 6],
+            ChordQuality::Augmented => &[0, 4, 8],
+            ChordQuality::Sus2 => &[0, 2, 7],
+            ChordQuality::Sus4 => &[0, 5, 7],
+            ChordQuality::Major7 => &[0, 4, 7, 11],
+            ChordQuality::Minor7 => &[0, 3, 7, 10],
+            ChordQuality::Dominant7 => &[0, 4, 7, 10],
+            ChordQuality::HalfDiminished7 => &[0, 3, 6, 10],
+            ChordQuality::Power => &[0, 7],
+        }
+    }
+}
+
+/// A chord built on a MIDI root note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub root: u8,
+    pub quality: ChordQuality,
+}
+
+impl Chord {
+    pub fn new(root: u8, quality: ChordQuality) -> Self {
+        Self { root, quality }
+    }
+
+    /// Returns the MIDI notes of the chord. Notes above 127 are dropped.
+    pub fn notes(&self) -> Vec<u8> {
+        self.quality
+            .intervals()
+            .iter()
+            .filter_map(|interval| self.root.checked_add(*interval))
+            .filter(|note| *note <= 127)
+            .collect()
+    }
+
+    /// Returns the notes of the chord in the given inversion.
+    /// Each inversion moves the lowest note up an octave.
+    pub fn inversion(&self, inversion: usize) -> Vec<u8> {
+        let mut notes = self.notes();
+        for _ in 0..inversion.min(notes.len().saturating_sub(1)) {
+            let lowest = notes.remove(0);
+            match lowest.checked_add(12).filter(|n| *n <= 127) {
+                Some(raised) => notes.push(raised),
+                None => notes.insert(0, lowest),
+            }
+        }
+        notes
+    }
+}
+
+/// Quantizes a note to the nearest note of `key`.
+pub fn quantize_to_scale(note: u8, key: Key) -> u8 {
+    key.quantize(note)
+}
+
+/// Builds a chord of the given quality rooted on the note under a pad.
+pub fn chord_from_pad(
+    layout: &NoteLayout,
+    coord: PadCoord,
+    quality: ChordQuality,
+) -> Option<Chord> {
+    layout.note_for(coord).map(|root| Chord::new(root, quality))
+}
+
+/// Builds a diatonic chord (stacked thirds in the layout's key) from the note under a pad.
+pub fn diatonic_chord_from_pad(layout: &NoteLayout, coord: PadCoord, voices: usize) -> Vec<u8> {
+    layout
+        .note_for(coord)
+        .map(|root| layout.key().diatonic_chord(root, voices))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_do_not_overflow() {
+        let key = Key::new(2, Scale::Major);
+        assert_eq!(key.degree_of(1), Some(6));
+        assert!(!key.contains(0));
+        assert!(!key.contains(255));
+        // A root set past 11 is still a pitch class.
+        let key = Key {
+            root: 14,
+            scale: Scale::Major,
+        };
+        assert_eq!(key.degree_of(2), Some(0));
+        assert_eq!(key.degree_of(4), Some(1));
+        assert!(!key.contains(3));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::midi_handler::{MidiHandler, MidiHandlerError};
+use crate::{NOTE_OFF, NOTE_ON, NoteLayout, PadCoord, Push2Event};
+use log::info;
+use midir::{MidiOutputConnection, SendError};
+use std::collections::HashMap;
+
+/// A note produced by a generator such as the `Arpeggiator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteEvent {
+    NoteOn { note: u8, velocity: u8 },
+    NoteOff { note: u8 },
+}
+
+/// Re-emits pad presses as MIDI notes, translated through a `NoteLayout`,
+/// so the Push can play any synth listening on the output port.
+pub struct NoteOutput {
+    conn_out: MidiOutputConnection,
+    pub layout: NoteLayout,
+    /// The MIDI channel (0-15) notes are sent on.
+    pub channel: u8,
+    /// When false, pad events are ignored, e.g. while an arpeggiator plays the held pads.
+    pub play_pads: bool,
+    /// The note sent for each held pad, so releases match even if the layout changed.
+    held: HashMap<PadCoord, u8>,
+}
+
+impl NoteOutput {
+    /// Creates a virtual output port other applications can connect to.
+    #[cfg(unix)]
+    pub fn virtual_port(port_name: &str, layout: NoteLayout) -> Result<Self, MidiHandlerError> {
+        use midir::MidiOutput;
+        use midir::os::unix::VirtualOutput;
+
+        let midi_out =
+            MidiOutput::new("push2_note_output").map_err(MidiHandlerError::OutputInit)?;
+        info!("Creating virtual note output port: {}", port_name);
+        let conn_out = midi_out.create_virtual(port_name).map_err(|source| {
+            MidiHandlerError::OutputConnection {
+                port: port_name.to_string(),
+                source,
+            }
+        })?;
+        Ok(Self::from_connection(conn_out, layout))
+    }
+
+    /// Sends notes to an existing output port whose name contains `port_name`.
+    pub fn connect(port_name: &str, layout: NoteLayout) -> Result<Self, MidiHandlerError> {
+        let conn_out = MidiHandler::connect_output(port_name)?;
+        Ok(Self::from_connection(conn_out, layout))
+    }
+
+    pub fn from_connection(conn_out: MidiOutputConnection, layout: NoteLayout) -> Self {
+        Self {
+            conn_out,
+            layout,
+            channel: 0,
+            play_pads: true,
+            held: HashMap::new(),
+        }
+    }
+
+    /// Translates pad events into note on/off messages. Other events are ignored.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Result<(), SendError> {
+        let channel = self.channel & 0x0F;
+        match *event {
+            Push2Event::PadPressed { coord, velocity } if self.play_pads => {
+                if let Some(note) = self.layout.note_for(coord) {
+                    self.conn_out.send(&[NOTE_ON | channel, note, velocity])?;
+                    self.held.insert(coord, note);
+                }
+            }
+            Push2Event::PadReleased {
+                coord,
+                release_velocity,
+            } => {
+                if let Some(note) = self.held.remove(&coord) {
+                    self.conn_out
+                        .send(&[NOTE_OFF | channel, note, release_velocity])?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Sends a generated note on the output channel.
+    pub fn send_note(&mut self, event: &NoteEvent) -> Result<(), SendError> {
+        let channel = self.channel & 0x0F;
+        match *event {
+            NoteEvent::NoteOn { note, velocity } => {
+                self.conn_out.send(&[NOTE_ON | channel, note, velocity])
+            }
+            NoteEvent::NoteOff { note } => self.conn_out.send(&[NOTE_OFF | channel, note, 0]),
+        }
+    }
+
+    /// Releases every note that is still held.
+    pub fn all_notes_off(&mut self) -> Result<(), SendError> {
+        let channel = self.channel & 0x0F;
+        for (_, note) in self.held.drain() {
+            self.conn_out.send(&[NOTE_OFF | channel, note, 0])?;
+        }
+        Ok(())
+    }
+}
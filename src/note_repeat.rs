@@ -0,0 +1,173 @@
+use crate::clock::{ClockTick, beat_button_ticks};
+use crate::note_output::NoteEvent;
+use crate::{
+    ControlName, NoteLayout, PadCoord, Push2, Push2Colors, Push2Error, Push2Event, RadioGroup,
+};
+
+/// A pad held while note repeat is on.
+#[derive(Debug, Clone, Copy)]
+struct HeldPad {
+    coord: PadCoord,
+    note: u8,
+    velocity: u8,
+    /// The last polyphonic aftertouch pressure, once the pad reported one.
+    pressure: Option<u8>,
+}
+
+/// The classic Push "Repeat": while on, held pads retrigger at the rate
+/// chosen on the Beat1_* buttons, in time with a `Clock`.
+///
+/// Each hit takes its velocity from the current aftertouch pressure (the
+/// pad's own pressure, else the channel pressure), so pressing harder plays
+/// louder. While repeat is on, set `NoteOutput::play_pads` to false so the
+/// pads are not also played directly.
+pub struct NoteRepeat {
+    pub button: ControlName,
+    /// The Beat1_* buttons choosing the rate.
+    pub rate: RadioGroup,
+    /// The length of each hit, as a fraction (0.0-1.0) of the step.
+    pub gate: f32,
+    pub light_on: u8,
+    pub light_off: u8,
+    enabled: bool,
+    held: Vec<HeldPad>,
+    channel_pressure: Option<u8>,
+    /// The notes sounding and the tick they stop at.
+    sounding: Vec<(u8, u64)>,
+}
+
+impl Default for NoteRepeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NoteRepeat {
+    /// Creates note repeat, off, at sixteenth notes.
+    pub fn new() -> Self {
+        Self {
+            button: ControlName::Repeat,
+            rate: RadioGroup::beat_buttons(Push2Colors::WHITE_BRIGHT, Push2Colors::WHITE_LOW)
+                .with_selected(Some(4)),
+            gate: 0.5,
+            light_on: Push2Colors::WHITE_BRIGHT,
+            light_off: Push2Colors::WHITE_LOW,
+            enabled: false,
+            held: Vec::new(),
+            channel_pressure: None,
+            sounding: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns repeat on or off. Sounding hits are released on the next tick.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Handles the Repeat and Beat1_* buttons, and tracks the held pads and
+    /// their pressure. Returns true if the event was consumed.
+    pub fn handle_event(
+        &mut self,
+        push2: &mut Push2,
+        layout: &NoteLayout,
+        event: &Push2Event,
+    ) -> Result<bool, Push2Error> {
+        match *event {
+            Push2Event::ButtonPressed { name, .. } if name == self.button => {
+                self.enabled = !self.enabled;
+                self.update_lights(push2)?;
+                Ok(true)
+            }
+            Push2Event::ButtonPressed { .. } => Ok(self.rate.handle_event(push2, event)?.is_some()),
+            Push2Event::PadPressed { coord, velocity } => {
+                let Some(note) = layout.note_for(coord) else {
+                    return Ok(false);
+                };
+                self.held.retain(|pad| pad.coord != coord);
+                self.held.push(HeldPad {
+                    coord,
+                    note,
+                    velocity,
+                    pressure: None,
+                });
+                Ok(self.enabled)
+            }
+            Push2Event::PadReleased { coord, .. } => {
+                self.held.retain(|pad| pad.coord != coord);
+                if self.held.is_empty() {
+                    self.channel_pressure = None;
+                }
+                Ok(self.enabled)
+            }
+            Push2Event::PadAftertouch { coord, pressure } => {
+                if let Some(pad) = self.held.iter_mut().find(|pad| pad.coord == coord) {
+                    pad.pressure = Some(pressure);
+                }
+                Ok(self.enabled)
+            }
+            Push2Event::ChannelPressure { value } => {
+                self.channel_pressure = Some(value);
+                Ok(self.enabled)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Advances note repeat by one clock tick.
+    /// Returns the notes to start and stop on this tick.
+    pub fn on_clock_tick(&mut self, tick: &ClockTick) -> Vec<NoteEvent> {
+        let mut events = Vec::new();
+        let stopped = !self.enabled;
+        self.sounding.retain(|(note, off_tick)| {
+            let release = stopped || tick.tick >= *off_tick;
+            if release {
+                events.push(NoteEvent::NoteOff { note: *note });
+            }
+            !release
+        });
+        if !self.enabled || self.held.is_empty() {
+            return events;
+        }
+        let rate = self.rate.selected().unwrap_or(ControlName::Beat1_16);
+        let step_ticks = beat_button_ticks(rate, tick.ppqn).unwrap_or(1) as u64;
+        if !tick.tick.is_multiple_of(step_ticks) {
+            return events;
+        }
+        let length = ((step_ticks as f32 * self.gate.clamp(0.0, 1.0)).round() as u64).max(1);
+        for pad in &self.held {
+            // Retrigger a hit still sounding, e.g. after switching to a faster rate.
+            if let Some(index) = self.sounding.iter().position(|(n, _)| *n == pad.note) {
+                self.sounding.remove(index);
+                events.push(NoteEvent::NoteOff { note: pad.note });
+            }
+            let velocity = pad
+                .pressure
+                .or(self.channel_pressure)
+                .unwrap_or(pad.velocity)
+                .max(1);
+            events.push(NoteEvent::NoteOn {
+                note: pad.note,
+                velocity,
+            });
+            self.sounding.push((pad.note, tick.tick + length));
+        }
+        events
+    }
+
+    /// Lights the Repeat and rate buttons, if they changed.
+    pub fn update_lights(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let light = if self.enabled {
+            self.light_on
+        } else {
+            self.light_off
+        };
+        if push2.state.buttons.get(&self.button).map(|b| b.light) != Some(light) {
+            push2.set_button_light(self.button, light)?;
+        }
+        self.rate.update_lights(push2)
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{PadCoord, Push2Event};
+use smallvec::SmallVec;
+use std::time::{Duration, Instant};
+
+/// The pads of a `Push2Event::PadChord`, in the order they were pressed.
+pub type PadCoords = SmallVec<[PadCoord; 8]>;
+
+/// Groups pad presses that start within a short window into chords.
+///
+/// Set `Push2::chord_detector` to use it. Presses are still emitted one by
+/// one as they arrive; once the window after the first press closes, a
+/// `PadChord` follows if enough pads were pressed in it.
+#[derive(Debug, Clone)]
+pub struct ChordDetector {
+    /// How long after the first press other presses join the chord.
+    pub window: Duration,
+    /// The fewest pads that make a chord.
+    pub min_pads: usize,
+    pending: PadCoords,
+    started_at: Option<Instant>,
+}
+
+impl Default for ChordDetector {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(40))
+    }
+}
+
+impl ChordDetector {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            min_pads: 2,
+            pending: SmallVec::new(),
+            started_at: None,
+        }
+    }
+
+    pub fn with_min_pads(mut self, min_pads: usize) -> Self {
+        self.min_pads = min_pads;
+        self
+    }
+
+    /// Adds a press to the open chord, or opens one.
+    pub fn press(&mut self, coord: PadCoord, now: Instant) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+        if !self.pending.contains(&coord) {
+            self.pending.push(coord);
+        }
+    }
+
+    /// Closes the chord once its window has passed.
+    /// Returns a `PadChord` if enough pads were pressed in it.
+    pub fn poll(&mut self, now: Instant) -> Option<Push2Event> {
+        let started_at = self.started_at?;
+        if now.duration_since(started_at) < self.window {
+            return None;
+        }
+        self.started_at = None;
+        let coords = std::mem::take(&mut self.pending);
+        (coords.len() >= self.min_pads.max(2)).then_some(Push2Event::PadChord { coords })
+    }
+}
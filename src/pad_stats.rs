@@ -0,0 +1,171 @@
+use crate::region::PadRegion;
+use crate::{PadCoord, Push2Event};
+use serde::{Deserialize, Serialize};
+
+/// The hits and pressure measured on a pad, or on a region of pads.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PadStat {
+    pub hits: u32,
+    /// The softest hit, 0 before the first.
+    pub min_velocity: u8,
+    pub max_velocity: u8,
+    pub velocity_sum: u64,
+    /// The most aftertouch pressure seen, per pad or channel.
+    pub max_pressure: u8,
+}
+
+impl PadStat {
+    /// The mean velocity, or `None` before the first hit.
+    pub fn mean_velocity(&self) -> Option<f32> {
+        (self.hits > 0).then(|| self.velocity_sum as f32 / self.hits as f32)
+    }
+
+    fn hit(&mut self, velocity: u8) {
+        self.min_velocity = if self.hits == 0 {
+            velocity
+        } else {
+            self.min_velocity.min(velocity)
+        };
+        self.max_velocity = self.max_velocity.max(velocity);
+        self.velocity_sum += velocity as u64;
+        self.hits += 1;
+    }
+
+    fn merge(&mut self, other: &PadStat) {
+        if other.hits > 0 {
+            self.min_velocity = if self.hits == 0 {
+                other.min_velocity
+            } else {
+                self.min_velocity.min(other.min_velocity)
+            };
+        }
+        self.max_velocity = self.max_velocity.max(other.max_velocity);
+        self.velocity_sum += other.velocity_sum;
+        self.hits += other.hits;
+        self.max_pressure = self.max_pressure.max(other.max_pressure);
+    }
+}
+
+/// Velocity and pressure statistics for every pad.
+///
+/// Set `Push2::pad_stats` to collect them from the events returned by
+/// `poll_event`, and clear `enabled` to pause collecting without losing them.
+/// Feed `means` to `PadCalibration::from_means` to calibrate from normal playing.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PadStats {
+    pub enabled: bool,
+    /// Indexed by `[y][x]`.
+    pub pads: [[PadStat; 8]; 8],
+}
+
+impl PadStats {
+    /// An empty collector, enabled.
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            pads: Default::default(),
+        }
+    }
+
+    /// Counts a pad hit or pressure change. Other events are ignored.
+    pub fn record(&mut self, event: &Push2Event) {
+        if !self.enabled {
+            return;
+        }
+        match *event {
+            Push2Event::PadPressed { coord, velocity } => {
+                if let Some(stat) = self.pad_mut(coord) {
+                    stat.hit(velocity);
+                }
+            }
+            Push2Event::PadAftertouch { coord, pressure } => {
+                if let Some(stat) = self.pad_mut(coord) {
+                    stat.max_pressure = stat.max_pressure.max(pressure);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn pad(&self, coord: PadCoord) -> PadStat {
+        self.pads
+            .get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The pads of a region combined into one.
+    pub fn region(&self, region: &PadRegion) -> PadStat {
+        let mut total = PadStat::default();
+        for coord in region.coords() {
+            total.merge(&self.pad(coord));
+        }
+        total
+    }
+
+    /// The mean velocity of each pad, `None` for pads never hit.
+    pub fn means(&self) -> [[Option<f32>; 8]; 8] {
+        self.pads.map(|row| row.map(|stat| stat.mean_velocity()))
+    }
+
+    /// Forgets everything collected so far.
+    pub fn reset(&mut self) {
+        self.pads = Default::default();
+    }
+
+    /// The statistics as pretty-printed JSON.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    fn pad_mut(&mut self, coord: PadCoord) -> Option<&mut PadStat> {
+        self.pads
+            .get_mut(coord.y as usize)
+            .and_then(|row| row.get_mut(coord.x as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(x: u8, y: u8, velocity: u8) -> Push2Event {
+        Push2Event::PadPressed {
+            coord: PadCoord { x, y },
+            velocity,
+        }
+    }
+
+    #[test]
+    fn records_hits_per_pad() {
+        let mut stats = PadStats::new();
+        stats.record(&press(1, 2, 40));
+        stats.record(&press(1, 2, 100));
+        stats.record(&Push2Event::PadAftertouch {
+            coord: PadCoord { x: 1, y: 2 },
+            pressure: 90,
+        });
+        let stat = stats.pad(PadCoord { x: 1, y: 2 });
+        assert_eq!(stat.hits, 2);
+        assert_eq!((stat.min_velocity, stat.max_velocity), (40, 100));
+        assert_eq!(stat.mean_velocity(), Some(70.0));
+        assert_eq!(stat.max_pressure, 90);
+        assert_eq!(stats.means()[0][0], None);
+    }
+
+    #[test]
+    fn combines_regions_and_pauses() {
+        let mut stats = PadStats::new();
+        stats.record(&press(0, 7, 20));
+        stats.record(&press(7, 7, 60));
+        stats.record(&press(0, 0, 1));
+        stats.enabled = false;
+        stats.record(&press(3, 7, 127));
+        let bottom = stats.region(&PadRegion::row(7));
+        assert_eq!(bottom.hits, 2);
+        assert_eq!((bottom.min_velocity, bottom.max_velocity), (20, 60));
+        assert_eq!(bottom.mean_velocity(), Some(40.0));
+    }
+}
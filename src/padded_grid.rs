@@ -0,0 +1,207 @@
+use crate::{ControlName, PadCoord, PadRegion, Push2, Push2Colors, Push2Error, Push2Event};
+
+/// The pair of buttons used to move between pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagingButtons {
+    /// OctaveDown for the previous page, OctaveUp for the next.
+    Octave,
+    /// PageLeft for the previous page, PageRight for the next.
+    Page,
+}
+
+impl PagingButtons {
+    fn controls(&self) -> (ControlName, ControlName) {
+        match self {
+            PagingButtons::Octave => (ControlName::OctaveDown, ControlName::OctaveUp),
+            PagingButtons::Page => (ControlName::PageLeft, ControlName::PageRight),
+        }
+    }
+}
+
+/// Events produced by `PaddedGrid::handle_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaddedGridEvent {
+    PageChanged { page: usize },
+    ItemPressed { index: usize, velocity: u8 },
+    ItemReleased { index: usize },
+}
+
+/// A collection of any number of items laid out on the pads, one page at a time.
+///
+/// Item `i` always lives on page `i / region.len()`, at the same pad of
+/// that page, so resolving items to pads (and back) is stable across pages.
+pub struct PaddedGrid<T> {
+    items: Vec<T>,
+    page: usize,
+    /// The pads used for items. Defaults to the whole grid.
+    pub region: PadRegion,
+    pub paging: PagingButtons,
+    /// The color of pads with no item.
+    pub empty_color: u8,
+    /// Light of a paging button that has a page to move to.
+    pub page_light: u8,
+    needs_repaint: bool,
+}
+
+impl<T> PaddedGrid<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            items,
+            page: 0,
+            region: PadRegion::FULL,
+            paging: PagingButtons::Page,
+            empty_color: Push2Colors::BLACK,
+            page_light: Push2Colors::WHITE_BRIGHT,
+            needs_repaint: true,
+        }
+    }
+
+    /// Restricts the items to a region of the grid.
+    pub fn with_region(mut self, region: PadRegion) -> Self {
+        self.region = region;
+        self.page = 0;
+        self.needs_repaint = true;
+        self
+    }
+
+    pub fn with_paging(mut self, paging: PagingButtons) -> Self {
+        self.paging = paging;
+        self
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.needs_repaint = true;
+        self.items.get_mut(index)
+    }
+
+    /// Adds an item and returns its index.
+    pub fn push(&mut self, item: T) -> usize {
+        self.items.push(item);
+        self.needs_repaint = true;
+        self.items.len() - 1
+    }
+
+    /// Replaces an item, keeping its position.
+    pub fn set(&mut self, index: usize, item: T) {
+        if let Some(slot) = self.items.get_mut(index) {
+            *slot = item;
+            self.needs_repaint = true;
+        }
+    }
+
+    fn page_size(&self) -> usize {
+        self.region.len().max(1)
+    }
+
+    /// The number of pages needed for all items (at least one).
+    pub fn page_count(&self) -> usize {
+        self.items.len().div_ceil(self.page_size()).max(1)
+    }
+
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        if page < self.page_count() && page != self.page {
+            self.page = page;
+            self.needs_repaint = true;
+        }
+    }
+
+    /// The page an item lives on.
+    pub fn page_of(&self, index: usize) -> usize {
+        index / self.page_size()
+    }
+
+    /// Returns the index of the item under a pad on the current page.
+    pub fn index_at(&self, coord: PadCoord) -> Option<usize> {
+        let index = self.page * self.page_size() + self.region.index_of(coord)?;
+        (index < self.items.len()).then_some(index)
+    }
+
+    /// Returns the item under a pad on the current page.
+    pub fn item_at(&self, coord: PadCoord) -> Option<&T> {
+        self.index_at(coord).and_then(|index| self.items.get(index))
+    }
+
+    /// Returns the pad of an item, if it is on the current page.
+    pub fn coord_of(&self, index: usize) -> Option<PadCoord> {
+        if index >= self.items.len() || self.page_of(index) != self.page {
+            return None;
+        }
+        self.region.coord_at(index % self.page_size())
+    }
+
+    /// Handles the paging buttons and pad presses inside the region.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Option<PaddedGridEvent> {
+        let (previous, next) = self.paging.controls();
+        match *event {
+            Push2Event::ButtonPressed { name, .. } if name == previous => {
+                self.set_page(self.page.checked_sub(1)?);
+                Some(PaddedGridEvent::PageChanged { page: self.page })
+            }
+            Push2Event::ButtonPressed { name, .. } if name == next => {
+                let page = self.page + 1;
+                if page >= self.page_count() {
+                    return None;
+                }
+                self.set_page(page);
+                Some(PaddedGridEvent::PageChanged { page })
+            }
+            Push2Event::PadPressed { coord, velocity } => Some(PaddedGridEvent::ItemPressed {
+                index: self.index_at(coord)?,
+                velocity,
+            }),
+            Push2Event::PadReleased { coord, .. } => Some(PaddedGridEvent::ItemReleased {
+                index: self.index_at(coord)?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Marks the pads for repainting on the next `render`.
+    pub fn invalidate(&mut self) {
+        self.needs_repaint = true;
+    }
+
+    /// Repaints the region if the page or items changed, and lights the
+    /// paging buttons that have a page to move to.
+    pub fn render<F>(&mut self, push2: &mut Push2, color_for: F) -> Result<(), Push2Error>
+    where
+        F: Fn(usize, &T) -> u8,
+    {
+        if self.needs_repaint {
+            for (i, coord) in self.region.coords().enumerate() {
+                let index = self.page * self.page_size() + i;
+                let color = match self.items.get(index) {
+                    Some(item) => color_for(index, item),
+                    None => self.empty_color,
+                };
+                push2.set_pad_color(coord, color)?;
+            }
+            self.needs_repaint = false;
+        }
+        let (previous, next) = self.paging.controls();
+        let previous_light = if self.page > 0 { self.page_light } else { 0 };
+        let next_light = if self.page + 1 < self.page_count() {
+            self.page_light
+        } else {
+            0
+        };
+        for (name, light) in [(previous, previous_light), (next, next_light)] {
+            if push2.state.buttons.get(&name).map(|b| b.light) != Some(light) {
+                push2.set_button_light(name, light)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::display::DISPLAY_HEIGHT;
+use crate::gui::ENCODER_REGION_WIDTH;
+use crate::{PadCoord, Push2, Push2Error};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+    text::{Baseline, Text},
+};
+
+/// The number of colors in the pad palette.
+pub const PALETTE_SIZE: u8 = 128;
+/// The number of 64-pad pages needed to show the whole palette.
+pub const PALETTE_PAGES: u8 = PALETTE_SIZE / 64;
+
+/// The palette color a pad shows on a palette page: row by row from the top-left pad.
+pub fn palette_index(page: u8, coord: PadCoord) -> u8 {
+    page.min(PALETTE_PAGES - 1) * 64 + coord.y * 8 + coord.x
+}
+
+impl Push2 {
+    /// Lights the pads with 64 palette colors and writes the color indices
+    /// on the display, each pad column under its encoder and each row at
+    /// the height of its pad row. Call `display.flush()` to show the labels.
+    pub fn show_palette_page(&mut self, page: u8) -> Result<(), Push2Error> {
+        self.display.clear(Bgr565::BLACK).unwrap(); // Infallible
+        let style = MonoTextStyle::new(&FONT_6X10, Bgr565::WHITE);
+        let row_height = (DISPLAY_HEIGHT / 8) as i32;
+        for y in 0..8 {
+            for x in 0..8 {
+                let coord = PadCoord { x, y };
+                let index = palette_index(page, coord);
+                self.set_pad_color(coord, index)?;
+                let position = Point::new(
+                    x as i32 * ENCODER_REGION_WIDTH as i32 + 8,
+                    y as i32 * row_height + row_height / 2,
+                );
+                Text::with_baseline(&index.to_string(), position, style, Baseline::Middle)
+                    .draw(&mut self.display)
+                    .unwrap(); // Infallible
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use crate::Push2Event;
+use crate::button_map::decode_delta;
+use crate::display::{Push2Display, Push2DisplayError};
+use crate::gui::{ENCODER_LABEL_Y_POS, ENCODER_VALUE_Y_POS, GuiApi};
+use embedded_graphics::pixelcolor::{Bgr565, RgbColor};
+
+/// Identifies a parameter registered in `Params`.
+pub type ParamId = usize;
+
+/// Turns a parameter value into the text shown under its encoder.
+pub type Formatter = Box<dyn Fn(f32) -> String + Send>;
+
+type ChangeCallback = Box<dyn FnMut(&ParamChange) + Send>;
+
+/// A named value controlled by an encoder.
+pub struct Param {
+    pub name: String,
+    pub min: f32,
+    pub max: f32,
+    /// The amount added per encoder tick.
+    pub step: f32,
+    pub unit: String,
+    value: f32,
+    formatter: Option<Formatter>,
+}
+
+impl Param {
+    /// Creates a parameter with 128 steps over its range. Bounds given the
+    /// wrong way round are swapped, and a NaN bound is taken as the other one
+    /// (or 0.0).
+    pub fn new(name: &str, min: f32, max: f32, default: f32) -> Self {
+        let min = if min.is_nan() { max } else { min };
+        let max = if max.is_nan() { min } else { max };
+        let (min, max) = if min.is_nan() {
+            (0.0, 0.0)
+        } else {
+            (min.min(max), min.max(max))
+        };
+        Self {
+            name: name.to_string(),
+            min,
+            max,
+            step: (max - min) / 127.0,
+            unit: String::new(),
+            value: default.max(min).min(max),
+            formatter: None,
+        }
+    }
+
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn with_unit(mut self, unit: &str) -> Self {
+        self.unit = unit.to_string();
+        self
+    }
+
+    /// Replaces the default "value unit" text with a custom formatter,
+    /// such as one from `formatters`.
+    pub fn with_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(f32) -> String + Send + 'static,
+    {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Sets the value, clamped to the parameter's range.
+    pub fn set_value(&mut self, value: f32) {
+        // Unlike `clamp`, this does not panic on a range changed to be empty.
+        self.value = value.max(self.min).min(self.max);
+    }
+
+    /// The value mapped to 0.0-1.0 over the parameter's range.
+    pub fn normalized(&self) -> f32 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        }
+    }
+
+    /// The value as text, using the formatter if one was set.
+    pub fn formatted(&self) -> String {
+        match &self.formatter {
+            Some(formatter) => formatter(self.value),
+            None if self.unit.is_empty() => format!("{:.1}", self.value),
+            None => format!("{:.1} {}", self.value, self.unit),
+        }
+    }
+}
+
+/// Sent to change callbacks whenever an encoder changes a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamChange {
+    pub id: ParamId,
+    pub name: String,
+    pub value: f32,
+    /// The value before the change.
+    pub previous: f32,
+}
+
+/// The colors used to draw bound parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamColors {
+    pub background: Bgr565,
+    pub outline: Bgr565,
+    pub bar: Bgr565,
+    pub label: Bgr565,
+    pub value: Bgr565,
+}
+
+impl Default for ParamColors {
+    fn default() -> Self {
+        Self {
+            background: Bgr565::BLACK,
+            outline: Bgr565::WHITE,
+            bar: Bgr565::GREEN,
+            label: Bgr565::WHITE,
+            value: Bgr565::CYAN,
+        }
+    }
+}
+
+/// A set of named parameters bound to the 8 track encoders, one page at a time.
+///
+/// Encoder turns on the active page are accumulated into the bound
+/// parameter, clamped to its range, and reported to the change callbacks.
+pub struct Params {
+    params: Vec<Param>,
+    pages: Vec<[Option<ParamId>; 8]>,
+    page: usize,
+    callbacks: Vec<ChangeCallback>,
+    pub colors: ParamColors,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self {
+            params: Vec::new(),
+            pages: vec![[None; 8]],
+            page: 0,
+            callbacks: Vec::new(),
+            colors: ParamColors::default(),
+        }
+    }
+
+    /// Registers a parameter and returns its id.
+    pub fn add(&mut self, param: Param) -> ParamId {
+        self.params.push(param);
+        self.params.len() - 1
+    }
+
+    /// Binds a parameter to an encoder slot (0-7) of a page.
+    /// Pages are created as needed.
+    pub fn bind(&mut self, page: usize, slot: u8, id: ParamId) {
+        if slot > 7 || id >= self.params.len() {
+            return;
+        }
+        if self.pages.len() <= page {
+            self.pages.resize(page + 1, [None; 8]);
+        }
+        self.pages[page][slot as usize] = Some(id);
+    }
+
+    pub fn unbind(&mut self, page: usize, slot: u8) {
+        if let Some(slots) = self.pages.get_mut(page)
+            && let Some(binding) = slots.get_mut(slot as usize)
+        {
+            *binding = None;
+        }
+    }
+
+    /// Registers a callback called whenever an encoder changes a parameter.
+    pub fn on_change<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ParamChange) + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    pub fn get(&self, id: ParamId) -> Option<&Param> {
+        self.params.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: ParamId) -> Option<&mut Param> {
+        self.params.get_mut(id)
+    }
+
+    /// Finds a parameter by name.
+    pub fn find(&self, name: &str) -> Option<ParamId> {
+        self.params.iter().position(|p| p.name == name)
+    }
+
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        if page < self.pages.len() {
+            self.page = page;
+        }
+    }
+
+    /// Returns the parameter bound to a slot of the active page.
+    pub fn bound(&self, slot: u8) -> Option<ParamId> {
+        self.pages
+            .get(self.page)
+            .and_then(|slots| slots.get(slot as usize).copied().flatten())
+    }
+
+    /// Applies a track encoder turn to the parameter bound on the active page.
+    /// Returns the change, if a parameter's value changed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Option<ParamChange> {
+        let Push2Event::EncoderTwisted { name, raw_delta } = *event else {
+            return None;
+        };
+        let id = self.bound(name.track_index()?)?;
+        let param = &mut self.params[id];
+        let previous = param.value;
+        param.set_value(previous + decode_delta(raw_delta) as f32 * param.step);
+        if param.value == previous {
+            return None;
+        }
+        let change = ParamChange {
+            id,
+            name: param.name.clone(),
+            value: param.value,
+            previous,
+        };
+        for callback in self.callbacks.iter_mut() {
+            callback(&change);
+        }
+        Some(change)
+    }
+
+    /// Draws the bar, name and value of every parameter on the active page.
+    pub fn draw(&self, display: &mut Push2Display) -> Result<(), Push2DisplayError> {
+        for slot in 0..8u8 {
+            self.draw_slot(display, slot)?;
+        }
+        Ok(())
+    }
+
+    /// Draws a single encoder slot of the active page.
+    pub fn draw_slot(&self, display: &mut Push2Display, slot: u8) -> Result<(), Push2DisplayError> {
+        display.clear_encoder_area(slot, self.colors.background)?;
+        let Some(param) = self.bound(slot).and_then(|id| self.params.get(id)) else {
+            return Ok(());
+        };
+        let bar_value = (param.normalized() * 127.0).round() as i32;
+        display.draw_encoder_outline(slot, self.colors.outline)?;
+        display.draw_encoder_bar(slot, bar_value, self.colors.bar)?;
+        display.draw_encoder_label(slot, ENCODER_LABEL_Y_POS, &param.name, self.colors.label)?;
+        display.draw_encoder_label(
+            slot,
+            ENCODER_VALUE_Y_POS,
+            &param.formatted(),
+            self.colors.value,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_the_range() {
+        let mut param = Param::new("Cutoff", 1.0, 0.0, 2.0);
+        assert_eq!((param.min, param.max, param.value()), (0.0, 1.0, 1.0));
+        param.set_value(-1.0);
+        assert_eq!(param.value(), 0.0);
+        let param = Param::new("Gain", f32::NAN, 1.0, 0.5);
+        assert_eq!((param.min, param.max), (1.0, 1.0));
+    }
+}
@@ -0,0 +1,180 @@
+//! A highlight sweeping across the pads in time with a `Clock`.
+
+use crate::{ClockTick, HighlightLayer, PadCoord, PadRegion, Push2, Push2Error};
+
+/// What a `Playhead` highlights at each step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayheadShape {
+    /// One pad, reading the region left to right, top to bottom.
+    #[default]
+    Pad,
+    /// A whole column of the region, moving left to right.
+    Column,
+}
+
+/// Highlights the current step of a sequence or loop on the pads, e.g. a
+/// row of 8 steps or the 64 pads of a step sequencer.
+///
+/// The highlight is shown over the pad colors with `Push2::highlight_pad`,
+/// so the app keeps drawing its steps with `set_pad_color` and the playhead
+/// never has to be erased by hand. It is on the lowest `HighlightLayer`, so
+/// held pads keep showing their press highlight as it passes.
+#[derive(Debug, Clone)]
+pub struct Playhead {
+    pub region: PadRegion,
+    pub color: u8,
+    pub shape: PlayheadShape,
+    /// How many steps the playhead moves per quarter note.
+    pub steps_per_beat: u32,
+    step: Option<usize>,
+}
+
+impl Playhead {
+    /// Sweeps `region` one pad per sixteenth note.
+    pub fn new(region: PadRegion, color: u8) -> Self {
+        Self {
+            region,
+            color,
+            shape: PlayheadShape::Pad,
+            steps_per_beat: 4,
+            step: None,
+        }
+    }
+
+    pub fn with_shape(mut self, shape: PlayheadShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn with_steps_per_beat(mut self, steps_per_beat: u32) -> Self {
+        self.steps_per_beat = steps_per_beat;
+        self
+    }
+
+    /// The number of steps before the playhead wraps around.
+    pub fn len(&self) -> usize {
+        match self.shape {
+            PlayheadShape::Pad => self.region.len(),
+            PlayheadShape::Column => self.region.width as usize,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The highlighted step, if any.
+    pub fn step(&self) -> Option<usize> {
+        self.step
+    }
+
+    /// Moves to the step a clock position falls on.
+    pub fn on_clock_tick(&mut self, push2: &mut Push2, tick: &ClockTick) -> Result<(), Push2Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let step = tick.tick * self.steps_per_beat.max(1) as u64 / tick.ppqn.max(1) as u64;
+        self.set_step(push2, Some(step as usize % self.len()))
+    }
+
+    /// Moves the highlight to a step, e.g. the position of a sequencer or a
+    /// looper; `None` hides it. Steps past the end wrap around.
+    pub fn set_step(&mut self, push2: &mut Push2, step: Option<usize>) -> Result<(), Push2Error> {
+        let step = step.filter(|_| !self.is_empty()).map(|s| s % self.len());
+        if step == self.step {
+            return Ok(());
+        }
+        if let Some(old) = self.step.take() {
+            for coord in self.pads(old) {
+                push2.clear_highlight(coord, HighlightLayer::Playhead)?;
+            }
+        }
+        if let Some(new) = step {
+            for coord in self.pads(new) {
+                push2.highlight_pad(coord, HighlightLayer::Playhead, self.color)?;
+            }
+        }
+        self.step = step;
+        Ok(())
+    }
+
+    /// Hides the highlight, e.g. when the transport stops.
+    pub fn stop(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        self.set_step(push2, None)
+    }
+
+    /// The pads of a step.
+    fn pads(&self, step: usize) -> Vec<PadCoord> {
+        match self.shape {
+            PlayheadShape::Pad => self.region.coord_at(step).into_iter().collect(),
+            PlayheadShape::Column => (0..self.region.height)
+                .filter_map(|y| self.region.to_global(PadCoord::new(step as u8, y)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockBackend, Push2Colors};
+
+    #[test]
+    fn sweeps_a_row_with_the_clock() {
+        let (backend, _tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        let mut playhead = Playhead::new(PadRegion::row(7), Push2Colors::WHITE_BRIGHT);
+        push2
+            .set_pad_color(PadCoord::new(0, 7), Push2Colors::BLUE_SKY)
+            .unwrap();
+
+        let tick = |tick| ClockTick { tick, ppqn: 24 };
+        playhead.on_clock_tick(&mut push2, &tick(0)).unwrap();
+        assert_eq!(push2.state.pads[7][0].shown(), Push2Colors::WHITE_BRIGHT);
+        // A sixteenth note later.
+        playhead.on_clock_tick(&mut push2, &tick(6)).unwrap();
+        assert_eq!(playhead.step(), Some(1));
+        assert_eq!(push2.state.pads[7][0].shown(), Push2Colors::BLUE_SKY);
+        assert_eq!(push2.state.pads[7][1].shown(), Push2Colors::WHITE_BRIGHT);
+        // Two beats in, back at the start.
+        playhead.on_clock_tick(&mut push2, &tick(48)).unwrap();
+        assert_eq!(playhead.step(), Some(0));
+
+        playhead.stop(&mut push2).unwrap();
+        assert_eq!(push2.state.pads[7][0].highlight(), None);
+    }
+
+    #[test]
+    fn passes_under_held_pads() {
+        let (backend, tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.press_highlight = Some(Push2Colors::RED);
+        let mut playhead = Playhead::new(PadRegion::row(0), Push2Colors::WHITE_BRIGHT);
+        let pad = |push2: &Push2| push2.state.pads[0][0].shown();
+
+        // The top-left pad, pressed before the playhead reaches it.
+        tx.send(vec![0x90, 92, 100]).unwrap();
+        while push2.poll_event().is_some() {}
+        playhead.set_step(&mut push2, Some(0)).unwrap();
+        assert_eq!(pad(&push2), Push2Colors::RED);
+        playhead.set_step(&mut push2, Some(1)).unwrap();
+        assert_eq!(pad(&push2), Push2Colors::RED);
+
+        // Released under the playhead, which shows again.
+        playhead.set_step(&mut push2, Some(0)).unwrap();
+        tx.send(vec![0x80, 92, 0]).unwrap();
+        while push2.poll_event().is_some() {}
+        assert_eq!(pad(&push2), Push2Colors::WHITE_BRIGHT);
+    }
+
+    #[test]
+    fn column_covers_the_region_height() {
+        let playhead = Playhead::new(PadRegion::bottom_half(), Push2Colors::WHITE_BRIGHT)
+            .with_shape(PlayheadShape::Column);
+        assert_eq!(playhead.len(), 8);
+        assert_eq!(
+            playhead.pads(2),
+            (4..8).map(|y| PadCoord::new(2, y)).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Lights held pads by how hard they are pressed.
+
+use crate::{HighlightLayer, PadCoord, Push2, Push2Colors, Push2Event, Push2State};
+use log::warn;
+
+/// How the pressure on a pad is shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PressureColors {
+    /// Blue when touched, through green and yellow to red at full pressure.
+    Heatmap,
+    /// One palette color, dim when touched and at full brightness at full pressure.
+    Brightness(u8),
+    /// Blends from the first palette color to the second.
+    Blend(u8, u8),
+}
+
+impl PressureColors {
+    /// The palette color for a pressure from 0 to 127.
+    pub fn color(&self, pressure: u8) -> u8 {
+        let t = pressure.min(127) as f32 / 127.0;
+        match *self {
+            PressureColors::Heatmap => {
+                Push2Colors::hsv_to_nearest_index(240.0 * (1.0 - t), 1.0, 1.0)
+            }
+            PressureColors::Brightness(color) => {
+                let (h, s, v) = Push2Colors::palette_hsv(color);
+                Push2Colors::hsv_to_nearest_index(h, s, v * (0.15 + 0.85 * t))
+            }
+            PressureColors::Blend(from, to) => Push2Colors::blend(from, to, t),
+        }
+    }
+}
+
+/// Shows the pressure on each held pad as a highlight, as feedback for
+/// aftertouch. Released pads show their own color again, or the highlight
+/// below (see `HighlightLayer`).
+///
+/// Set `Push2::pressure_lights` to enable it. Each pad shows its own pressure
+/// in `AftertouchMode::Polyphonic`; with channel pressure every held pad
+/// shows the same.
+#[derive(Debug, Clone)]
+pub struct PressureLights {
+    colors: PressureColors,
+    /// The color for each pressure.
+    table: [u8; 128],
+}
+
+impl PressureLights {
+    pub fn new(colors: PressureColors) -> Self {
+        Self {
+            colors,
+            table: std::array::from_fn(|pressure| colors.color(pressure as u8)),
+        }
+    }
+
+    pub fn colors(&self) -> PressureColors {
+        self.colors
+    }
+
+    /// The pad highlights to set for an event, `None` clearing one, leaving
+    /// out the ones already shown. `state` has to be updated with the event.
+    pub fn update(&self, event: &Push2Event, state: &Push2State) -> Vec<(PadCoord, Option<u8>)> {
+        let mut highlights = Vec::new();
+        match *event {
+            Push2Event::PadPressed { coord, .. } => highlights.push((coord, Some(self.table[0]))),
+            Push2Event::PadAftertouch { coord, pressure } if state.is_pad_held(coord) => {
+                highlights.push((coord, Some(self.table[pressure.min(127) as usize])));
+            }
+            Push2Event::ChannelPressure { value } => {
+                let color = self.table[value.min(127) as usize];
+                highlights.extend(state.pressed_pads().map(|coord| (coord, Some(color))));
+            }
+            Push2Event::PadReleased { coord, .. } => highlights.push((coord, None)),
+            _ => {}
+        }
+        highlights.retain(|(coord, color)| {
+            state.pads[coord.y as usize][coord.x as usize].highlight_of(HighlightLayer::Pressure)
+                != *color
+        });
+        highlights
+    }
+}
+
+impl Push2 {
+    /// Shows the pressure of an event on the pads.
+    pub(crate) fn show_pressure(&mut self, event: &Push2Event) {
+        let Some(pressure_lights) = self.pressure_lights.as_ref() else {
+            return;
+        };
+        for (coord, highlight) in pressure_lights.update(event, &self.state) {
+            let result = match highlight {
+                Some(color) => self.highlight_pad(coord, HighlightLayer::Pressure, color),
+                None => self.clear_highlight(coord, HighlightLayer::Pressure),
+            };
+            if let Err(e) = result {
+                warn!("Failed to show the pad pressure: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+
+    #[test]
+    fn shows_pressure_over_the_pad_color() {
+        let (backend, tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.pressure_lights = Some(PressureLights::new(PressureColors::Brightness(
+            Push2Colors::RED,
+        )));
+        let coord = PadCoord::new(0, 0);
+        push2.set_pad_color(coord, Push2Colors::GREEN).unwrap();
+
+        // The top-left pad, pressed, then pressed hard.
+        tx.send(vec![0x90, 92, 100]).unwrap();
+        tx.send(vec![0xA0, 92, 127]).unwrap();
+        while push2.poll_event().is_some() {}
+        let pad = push2.state.pads[0][0];
+        assert_eq!(pad.shown(), Push2Colors::RED);
+        assert_eq!(pad.color, Push2Colors::GREEN);
+
+        tx.send(vec![0x80, 92, 0]).unwrap();
+        while push2.poll_event().is_some() {}
+        assert_eq!(push2.state.pads[0][0].shown(), Push2Colors::GREEN);
+    }
+
+    #[test]
+    fn heatmap_runs_from_blue_to_red() {
+        assert_eq!(PressureColors::Heatmap.color(127), Push2Colors::RED);
+        let (hue, _, _) = Push2Colors::palette_hsv(PressureColors::Heatmap.color(0));
+        assert!((200.0..=280.0).contains(&hue));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::app_config::config_dir;
+use crate::{ControlName, Push2, Push2Error, Push2Event, Push2State};
+use log::info;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The file extension of project files.
+pub const PROJECT_EXTENSION: &str = "ron";
+
+#[derive(Error, Debug)]
+pub enum ProjectError {
+    #[error("Failed to serialize project: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to parse project file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read or write project file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+    #[error("Project version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("Project version {0} needs a migration, but none was registered")]
+    NoMigration(u32),
+    #[error("Could not restore the device state: {0}")]
+    Device(#[from] Push2Error),
+}
+
+/// The crate-level state saved alongside the app's model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSnapshot {
+    /// Pad colors, button lights, encoder values and the slider.
+    pub state: Push2State,
+    /// Control buttons moved to other CC addresses.
+    #[serde(default)]
+    pub control_overrides: HashMap<ControlName, u8>,
+}
+
+impl DeviceSnapshot {
+    pub fn capture(push2: &Push2) -> Self {
+        Self {
+            state: push2.state.clone(),
+            control_overrides: push2.button_map.control_overrides().clone(),
+        }
+    }
+
+    /// Applies the overrides, then replays the lights and encoder values.
+    pub fn restore(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        for (name, address) in &self.control_overrides {
+            push2.button_map.set_control_address(*name, *address);
+        }
+        push2.apply_state(&self.state)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProjectFile<M> {
+    version: u32,
+    device: DeviceSnapshot,
+    model: M,
+}
+
+/// The part of a project file read before the model, whatever its version.
+#[derive(Deserialize)]
+struct ProjectHeader {
+    version: u32,
+    device: DeviceSnapshot,
+}
+
+/// Turns the RON text of a project saved by an older version into the current model.
+/// It receives the version the file was saved with.
+type Migration<M> = Box<dyn Fn(u32, &str) -> Result<M, ProjectError> + Send>;
+
+/// Produced by `ProjectStore::handle_event`, with the name of the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectEvent {
+    Created(String),
+    Duplicated(String),
+    Deleted(String),
+}
+
+/// Saves and loads named projects: an app-defined model `M` plus the state
+/// of the device, in versioned RON files in one directory.
+///
+/// New starts a project with a default model, Duplicate saves the current
+/// project under a new name, and Shift+Delete deletes it.
+pub struct ProjectStore<M> {
+    pub dir: PathBuf,
+    /// The version written into saved projects. Bump it when `M` changes shape.
+    pub version: u32,
+    /// The name of the project last saved or loaded.
+    pub current: Option<String>,
+    migration: Option<Migration<M>>,
+}
+
+impl<M: Serialize + DeserializeOwned> ProjectStore<M> {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            version: 1,
+            current: None,
+            migration: None,
+        }
+    }
+
+    /// A store in the push2 config directory, under "projects/<app_name>".
+    pub fn in_config_dir(app_name: &str) -> Result<Self, ProjectError> {
+        let mut dir = config_dir().ok_or(ProjectError::NoConfigDir)?;
+        dir.push("projects");
+        dir.push(app_name);
+        Ok(Self::new(dir))
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Registers the hook loading projects saved with an older version.
+    pub fn with_migration<F>(mut self, migration: F) -> Self
+    where
+        F: Fn(u32, &str) -> Result<M, ProjectError> + Send + 'static,
+    {
+        self.migration = Some(Box::new(migration));
+        self
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.{PROJECT_EXTENSION}"))
+    }
+
+    /// The names of the saved projects, sorted.
+    pub fn list(&self) -> Result<Vec<String>, ProjectError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == PROJECT_EXTENSION))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.path(name).exists()
+    }
+
+    /// Saves the model and the device state as a project, replacing any with the same name.
+    pub fn save(&mut self, name: &str, push2: &Push2, model: &M) -> Result<(), ProjectError> {
+        fs::create_dir_all(&self.dir)?;
+        let file = ProjectFile {
+            version: self.version,
+            device: DeviceSnapshot::capture(push2),
+            model,
+        };
+        let ron = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())?;
+        fs::write(self.path(name), ron)?;
+        info!("Saved project '{}'", name);
+        self.current = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Loads a project, restores the device state and returns the model.
+    /// Projects saved with an older version go through the migration hook.
+    pub fn load(&mut self, name: &str, push2: &mut Push2) -> Result<M, ProjectError> {
+        let ron = fs::read_to_string(self.path(name))?;
+        let header: ProjectHeader = ron::from_str(&ron).map_err(Box::new)?;
+        let model = if header.version == self.version {
+            let file: ProjectFile<M> = ron::from_str(&ron).map_err(Box::new)?;
+            file.model
+        } else if header.version > self.version {
+            return Err(ProjectError::UnsupportedVersion {
+                found: header.version,
+                supported: self.version,
+            });
+        } else {
+            let migration = self
+                .migration
+                .as_ref()
+                .ok_or(ProjectError::NoMigration(header.version))?;
+            migration(header.version, &ron)?
+        };
+        header.device.restore(push2)?;
+        info!("Loaded project '{}' (version {})", name, header.version);
+        self.current = Some(name.to_string());
+        Ok(model)
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), ProjectError> {
+        fs::remove_file(self.path(name))?;
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+        Ok(())
+    }
+
+    /// The first unused name made of `base` and a number, e.g. "Project 3".
+    pub fn next_free_name(&self, base: &str) -> String {
+        (1..)
+            .map(|n| format!("{base} {n}"))
+            .find(|name| !self.exists(name))
+            .unwrap_or_else(|| base.to_string())
+    }
+}
+
+impl<M: Serialize + DeserializeOwned + Default> ProjectStore<M> {
+    /// Handles New, Duplicate and Shift+Delete.
+    /// Returns what happened to which project.
+    pub fn handle_event(
+        &mut self,
+        push2: &mut Push2,
+        model: &mut M,
+        event: &Push2Event,
+    ) -> Result<Option<ProjectEvent>, ProjectError> {
+        let Push2Event::ButtonPressed { name, .. } = *event else {
+            return Ok(None);
+        };
+        match name {
+            ControlName::New => {
+                *model = M::default();
+                let name = self.next_free_name("Project");
+                self.save(&name, push2, model)?;
+                Ok(Some(ProjectEvent::Created(name)))
+            }
+            ControlName::Duplicate => {
+                let base = self
+                    .current
+                    .clone()
+                    .unwrap_or_else(|| "Project".to_string());
+                let name = self.next_free_name(&base);
+                self.save(&name, push2, model)?;
+                Ok(Some(ProjectEvent::Duplicated(name)))
+            }
+            ControlName::Delete if push2.state.is_button_held(ControlName::Shift) => {
+                let Some(name) = self.current.clone() else {
+                    return Ok(None);
+                };
+                self.delete(&name)?;
+                Ok(Some(ProjectEvent::Deleted(name)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Python bindings, built with `maturin build --features python`.
+//!
+//! ```python
+//! import push2
+//! device = push2.Push2()
+//! device.set_pad_color(0, 7, 5)
+//! while True:
+//!     event = device.poll_event()
+//!     if event and event.kind == "pad_pressed":
+//!         print(event.x, event.y, event.value)
+//! ```
+
+use crate::button_map::decode_delta;
+use crate::{ControlName, PadCoord, Push2, Push2Error, Push2Event};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::{Bgr565, Rgb888},
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::Text,
+};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+type Rgb = (u8, u8, u8);
+
+impl From<Push2Error> for PyErr {
+    fn from(e: Push2Error) -> PyErr {
+        PyRuntimeError::new_err(e.to_string())
+    }
+}
+
+fn to_bgr565((r, g, b): Rgb) -> Bgr565 {
+    Bgr565::from(Rgb888::new(r, g, b))
+}
+
+/// A device event. `kind` is the snake_case event name, as in the JSON event log;
+/// the other fields are set when they apply to that kind.
+#[pyclass(name = "Event", get_all, frozen, skip_from_py_object)]
+#[derive(Debug, Clone, Default)]
+pub struct PyEvent {
+    kind: String,
+    x: Option<u8>,
+    y: Option<u8>,
+    /// The button or encoder name, e.g. "Play" or "Track1", the CC number
+    /// of an unknown control, or the path of a reloaded config file.
+    name: Option<String>,
+    /// The velocity, pressure or slider position.
+    value: Option<u16>,
+    /// The signed turn of an encoder.
+    delta: Option<i32>,
+    /// The (x, y) of every pad in a chord.
+    pads: Vec<(u8, u8)>,
+}
+
+#[pymethods]
+impl PyEvent {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<&Push2Event> for PyEvent {
+    fn from(event: &Push2Event) -> Self {
+        let mut e = PyEvent::default();
+        let kind = match event {
+            Push2Event::PadPressed { coord, velocity } => {
+                e.pad(*coord);
+                e.value = Some(*velocity as u16);
+                "pad_pressed"
+            }
+            Push2Event::PadReleased {
+                coord,
+                release_velocity,
+            } => {
+                e.pad(*coord);
+                e.value = Some(*release_velocity as u16);
+                "pad_released"
+            }
+            Push2Event::ButtonPressed { name, velocity } => {
+                e.name = Some(format!("{:?}", name));
+                e.value = Some(*velocity as u16);
+                "button_pressed"
+            }
+            Push2Event::ButtonReleased { name } => {
+                e.name = Some(format!("{:?}", name));
+                "button_released"
+            }
+            Push2Event::EncoderTwisted { name, raw_delta } => {
+                e.name = Some(format!("{:?}", name));
+                e.delta = Some(decode_delta(*raw_delta));
+                "encoder_twisted"
+            }
+            Push2Event::SliderMoved { value } => {
+                e.value = Some(*value);
+                "slider_moved"
+            }
+            Push2Event::PadAftertouch { coord, pressure } => {
+                e.pad(*coord);
+                e.value = Some(*pressure as u16);
+                "pad_aftertouch"
+            }
+            Push2Event::ChannelPressure { value } => {
+                e.value = Some(*value as u16);
+                "channel_pressure"
+            }
+            Push2Event::PadChord { coords } => {
+                e.pads = coords.iter().map(|c| (c.x, c.y)).collect();
+                "pad_chord"
+            }
+            Push2Event::IdleEntered => "idle_entered",
+            Push2Event::IdleExited => "idle_exited",
+            Push2Event::UnknownControl { address, value } => {
+                e.name = Some(address.to_string());
+                e.value = Some(*value as u16);
+                "unknown_control"
+            }
+            Push2Event::ConfigReloaded { file } => {
+                e.name = Some(file.path().display().to_string());
+                "config_reloaded"
+            }
+        };
+        e.kind = kind.to_string();
+        e
+    }
+}
+
+impl PyEvent {
+    fn pad(&mut self, coord: PadCoord) {
+        self.x = Some(coord.x);
+        self.y = Some(coord.y);
+    }
+}
+
+/// A connected Push 2.
+#[pyclass(name = "Push2", unsendable)]
+pub struct PyPush2 {
+    push2: Push2,
+}
+
+#[pymethods]
+impl PyPush2 {
+    /// Connects to the device, as configured in the push2 config directory.
+    #[new]
+    fn new() -> PyResult<Self> {
+        Ok(Self {
+            push2: Push2::new()?,
+        })
+    }
+
+    /// The next event, or None.
+    fn poll_event(&mut self) -> Option<PyEvent> {
+        self.push2.poll_event().map(|e| PyEvent::from(&e))
+    }
+
+    /// Sets the palette color (0-127) of the pad at column x and row y (0 is the top row).
+    fn set_pad_color(&mut self, x: u8, y: u8, color: u8) -> PyResult<()> {
+        if x > 7 || y > 7 {
+            return Err(PyValueError::new_err(format!("No pad at ({}, {})", x, y)));
+        }
+        Ok(self.push2.set_pad_color(PadCoord { x, y }, color)?)
+    }
+
+    /// Lights a button by name, e.g. "Play".
+    fn set_button_light(&mut self, name: &str, light: u8) -> PyResult<()> {
+        let name: ControlName = ron::from_str(name)
+            .map_err(|_| PyValueError::new_err(format!("Unknown button '{}'", name)))?;
+        Ok(self.push2.set_button_light(name, light)?)
+    }
+
+    /// Fills the frame buffer with an (r, g, b) color.
+    #[pyo3(signature = (color = (0, 0, 0)))]
+    fn clear(&mut self, color: Rgb) {
+        self.push2.display.clear(to_bgr565(color)).unwrap(); // Infallible
+    }
+
+    /// Fills a rectangle of the frame buffer.
+    fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Rgb) {
+        Rectangle::new(Point::new(x, y), Size::new(width, height))
+            .into_styled(PrimitiveStyle::with_fill(to_bgr565(color)))
+            .draw(&mut self.push2.display)
+            .unwrap(); // Infallible
+    }
+
+    /// Draws text into the frame buffer, with its baseline at y.
+    #[pyo3(signature = (x, y, text, color = (255, 255, 255)))]
+    fn draw_text(&mut self, x: i32, y: i32, text: &str, color: Rgb) {
+        let style = MonoTextStyle::new(&FONT_6X10, to_bgr565(color));
+        Text::new(text, Point::new(x, y), style)
+            .draw(&mut self.push2.display)
+            .unwrap(); // Infallible
+    }
+
+    /// Sends the frame buffer to the display.
+    fn flush(&mut self) -> PyResult<()> {
+        self.push2.display.flush().map_err(Push2Error::from)?;
+        Ok(())
+    }
+}
+
+#[pymodule]
+fn push2(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyPush2>()?;
+    module.add_class::<PyEvent>()?;
+    Ok(())
+}
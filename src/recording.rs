@@ -0,0 +1,192 @@
+//! Records the frames sent to the display as an animated GIF or an MP4.
+
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use embedded_graphics_core::pixelcolor::{Bgr565, Rgb888, RgbColor, raw::RawU16};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// The NeuQuant speed for GIF frames: 1 is best, 30 fastest.
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+#[derive(Error, Debug)]
+pub enum RecordingError {
+    #[error("Could not write the recording: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to encode a GIF frame: {0}")]
+    Gif(#[from] gif::EncodingError),
+    #[error("Cannot record to {0:?}; use a .gif or .mp4 file")]
+    Format(PathBuf),
+    #[error("ffmpeg exited with {0}")]
+    Ffmpeg(ExitStatus),
+}
+
+enum Output {
+    Gif(gif::Encoder<BufWriter<File>>),
+    /// An `ffmpeg` process reading raw RGB frames from stdin.
+    Ffmpeg(Child),
+}
+
+/// Encodes display frames to a file, keeping the timing they were flushed with.
+///
+/// The format follows the file extension: ".gif" is encoded in-process, and
+/// ".mp4" is piped to `ffmpeg`, which has to be installed. Frames flushed
+/// faster than `fps` are skipped; each recorded frame is shown until the next.
+/// Usually driven by `Push2Display::start_recording`.
+pub struct FrameRecorder {
+    output: Output,
+    fps: u32,
+    /// The last recorded frame as RGB, with when it was flushed; written
+    /// once the next one shows how long it lasted.
+    held: Option<(Vec<u8>, Instant)>,
+}
+
+impl FrameRecorder {
+    pub fn create(path: impl AsRef<Path>, fps: u32) -> Result<Self, RecordingError> {
+        let path = path.as_ref();
+        let fps = fps.clamp(1, 100);
+        let extension = path.extension().and_then(|e| e.to_str());
+        let output = match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("gif") => {
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder =
+                    gif::Encoder::new(file, DISPLAY_WIDTH as u16, DISPLAY_HEIGHT as u16, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                Output::Gif(encoder)
+            }
+            Some("mp4") => Output::Ffmpeg(
+                Command::new("ffmpeg")
+                    .args(["-loglevel", "error", "-y", "-f", "rawvideo"])
+                    .args(["-pix_fmt", "rgb24", "-s"])
+                    .arg(format!("{DISPLAY_WIDTH}x{DISPLAY_HEIGHT}"))
+                    .args(["-r", &fps.to_string(), "-i", "-"])
+                    .args(["-pix_fmt", "yuv420p"])
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .spawn()?,
+            ),
+            _ => return Err(RecordingError::Format(path.to_path_buf())),
+        };
+        Ok(Self {
+            output,
+            fps,
+            held: None,
+        })
+    }
+
+    /// Adds a frame of 960x160 raw Bgr565 pixels, flushed now.
+    pub fn push(&mut self, frame: &[u16]) -> Result<(), RecordingError> {
+        self.push_at(frame, Instant::now())
+    }
+
+    /// Adds a frame flushed at `now`.
+    pub fn push_at(&mut self, frame: &[u16], now: Instant) -> Result<(), RecordingError> {
+        if let Some((_, at)) = &self.held {
+            let elapsed = now.saturating_duration_since(*at);
+            if elapsed < self.frame_interval() {
+                return Ok(());
+            }
+            if let Some((rgb, _)) = self.held.take() {
+                self.write(&rgb, elapsed)?;
+            }
+        }
+        self.held = Some((to_rgb(frame), now));
+        Ok(())
+    }
+
+    /// Writes the last frame and closes the file, waiting for `ffmpeg` to finish.
+    pub fn finish(mut self) -> Result<(), RecordingError> {
+        if let Some((rgb, _)) = self.held.take() {
+            self.write(&rgb, self.frame_interval())?;
+        }
+        match self.output {
+            Output::Gif(encoder) => encoder.into_inner()?.flush()?,
+            Output::Ffmpeg(mut child) => {
+                drop(child.stdin.take());
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(RecordingError::Ffmpeg(status));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.fps
+    }
+
+    fn write(&mut self, rgb: &[u8], duration: Duration) -> Result<(), RecordingError> {
+        match &mut self.output {
+            Output::Gif(encoder) => {
+                let mut frame = gif::Frame::from_rgb_speed(
+                    DISPLAY_WIDTH as u16,
+                    DISPLAY_HEIGHT as u16,
+                    rgb,
+                    GIF_QUANTIZE_SPEED,
+                );
+                // In hundredths of a second.
+                frame.delay = (duration.as_millis() / 10).clamp(1, u16::MAX as u128) as u16;
+                encoder.write_frame(&frame)?;
+            }
+            Output::Ffmpeg(child) => {
+                let Some(stdin) = child.stdin.as_mut() else {
+                    return Ok(());
+                };
+                // The video has a fixed frame rate, so long frames are repeated.
+                let repeats = (duration.as_secs_f64() * self.fps as f64).round().max(1.0);
+                for _ in 0..repeats as usize {
+                    stdin.write_all(rgb)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_rgb(frame: &[u16]) -> Vec<u8> {
+    frame
+        .iter()
+        .flat_map(|pixel| {
+            let color = Rgb888::from(Bgr565::from(RawU16::new(*pixel)));
+            [color.r(), color.g(), color.b()]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn records_a_gif() {
+        let path = std::env::temp_dir().join("push2_recording_test.gif");
+        let mut recorder = FrameRecorder::create(&path, 10).unwrap();
+        let start = Instant::now();
+        let frame = vec![0xF800; DISPLAY_WIDTH * DISPLAY_HEIGHT];
+        recorder.push_at(&frame, start).unwrap();
+        // Too soon for 10 fps; skipped.
+        recorder
+            .push_at(&frame, start + Duration::from_millis(20))
+            .unwrap();
+        recorder
+            .push_at(&frame, start + Duration::from_millis(300))
+            .unwrap();
+        recorder.finish().unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(bytes.starts_with(b"GIF89a"));
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(matches!(
+            FrameRecorder::create("frames.avi", 30),
+            Err(RecordingError::Format(_))
+        ));
+    }
+}
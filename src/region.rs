@@ -0,0 +1,170 @@
+use crate::grid::GridController;
+use crate::{PadCoord, Push2Error, Push2Event};
+
+/// A rectangular part of the 8x8 pad grid with its own coordinate space.
+///
+/// Local coordinates start at (0, 0) on the region's top-left pad, so an
+/// app can treat e.g. the bottom four rows as a keyboard without offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PadRegion {
+    pub x: u8,
+    pub y: u8,
+    pub width: u8,
+    pub height: u8,
+}
+
+impl PadRegion {
+    /// The whole grid.
+    pub const FULL: PadRegion = PadRegion {
+        x: 0,
+        y: 0,
+        width: 8,
+        height: 8,
+    };
+
+    /// Creates a region, clipped to the grid.
+    pub fn new(x: u8, y: u8, width: u8, height: u8) -> Self {
+        let x = x.min(8);
+        let y = y.min(8);
+        Self {
+            x,
+            y,
+            width: width.min(8 - x),
+            height: height.min(8 - y),
+        }
+    }
+
+    pub fn top_half() -> Self {
+        Self::new(0, 0, 8, 4)
+    }
+
+    pub fn bottom_half() -> Self {
+        Self::new(0, 4, 8, 4)
+    }
+
+    pub fn left_half() -> Self {
+        Self::new(0, 0, 4, 8)
+    }
+
+    pub fn right_half() -> Self {
+        Self::new(4, 0, 4, 8)
+    }
+
+    /// A single row of the grid (0 = top).
+    pub fn row(y: u8) -> Self {
+        Self::new(0, y, 8, 1)
+    }
+
+    /// A single column of the grid (0 = left).
+    pub fn column(x: u8) -> Self {
+        Self::new(x, 0, 1, 8)
+    }
+
+    /// The number of pads in the region.
+    pub fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if a grid coordinate lies inside the region.
+    pub fn contains_coord(&self, coord: PadCoord) -> bool {
+        coord.x >= self.x
+            && coord.x < self.x + self.width
+            && coord.y >= self.y
+            && coord.y < self.y + self.height
+    }
+
+    /// Returns true if the event is a pad event inside the region.
+    pub fn contains(&self, event: &Push2Event) -> bool {
+        match event {
+            Push2Event::PadPressed { coord, .. } | Push2Event::PadReleased { coord, .. } => {
+                self.contains_coord(*coord)
+            }
+            _ => false,
+        }
+    }
+
+    /// Converts a grid coordinate into region coordinates.
+    pub fn to_local(&self, coord: PadCoord) -> Option<PadCoord> {
+        self.contains_coord(coord).then(|| PadCoord {
+            x: coord.x - self.x,
+            y: coord.y - self.y,
+        })
+    }
+
+    /// Converts region coordinates into a grid coordinate.
+    pub fn to_global(&self, local: PadCoord) -> Option<PadCoord> {
+        (local.x < self.width && local.y < self.height).then(|| PadCoord {
+            x: local.x + self.x,
+            y: local.y + self.y,
+        })
+    }
+
+    /// Returns the pad at a local index, reading left to right, top to bottom.
+    pub fn coord_at(&self, index: usize) -> Option<PadCoord> {
+        if self.width == 0 || index >= self.len() {
+            return None;
+        }
+        self.to_global(PadCoord {
+            x: (index % self.width as usize) as u8,
+            y: (index / self.width as usize) as u8,
+        })
+    }
+
+    /// Returns the local index of a grid coordinate, reading left to right, top to bottom.
+    pub fn index_of(&self, coord: PadCoord) -> Option<usize> {
+        self.to_local(coord)
+            .map(|local| local.y as usize * self.width as usize + local.x as usize)
+    }
+
+    /// Iterates over the grid coordinates of the region, reading left to right, top to bottom.
+    pub fn coords(&self) -> impl Iterator<Item = PadCoord> + '_ {
+        (0..self.len()).filter_map(|index| self.coord_at(index))
+    }
+
+    /// Returns a copy of a pad event with its coordinate converted into
+    /// region coordinates, or `None` if it is not a pad event inside the region.
+    pub fn localize(&self, event: &Push2Event) -> Option<Push2Event> {
+        match *event {
+            Push2Event::PadPressed { coord, velocity } => Some(Push2Event::PadPressed {
+                coord: self.to_local(coord)?,
+                velocity,
+            }),
+            Push2Event::PadReleased {
+                coord,
+                release_velocity,
+            } => Some(Push2Event::PadReleased {
+                coord: self.to_local(coord)?,
+                release_velocity,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Sets every pad of the region to the same color.
+    pub fn fill<G: GridController>(&self, push2: &mut G, color: u8) -> Result<(), Push2Error> {
+        for coord in self.coords() {
+            push2.set_pad_color(coord, color)?;
+        }
+        Ok(())
+    }
+
+    /// Colors every pad of the region from a function of its local coordinate.
+    pub fn paint<G, F>(&self, push2: &mut G, mut color_for: F) -> Result<(), Push2Error>
+    where
+        G: GridController,
+        F: FnMut(PadCoord) -> u8,
+    {
+        for coord in self.coords() {
+            let local = PadCoord {
+                x: coord.x - self.x,
+                y: coord.y - self.y,
+            };
+            push2.set_pad_color(coord, color_for(local))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH, Push2Display};
+use crate::frame_codec::FrameEncoder;
+use crate::websocket;
+use crate::{ControlName, PadCoord, Push2, Push2Error, Push2Event};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::{Bgr565, Rgb888},
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle},
+    text::Text,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use thiserror::Error;
+use tungstenite::Message;
+
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    #[error("Failed to start the remote server: {0}")]
+    Bind(#[from] io::Error),
+    #[error("Failed to encode a remote message: {0}")]
+    Encode(#[from] serde_json::Error),
+}
+
+/// Commands accepted from remote clients, as JSON objects tagged by `type`,
+/// e.g. `{"type": "set_pad_color", "x": 0, "y": 7, "color": 5}`.
+///
+/// Display colors are `[r, g, b]` triples of 0-255.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    SetPadColor {
+        x: u8,
+        y: u8,
+        color: u8,
+    },
+    SetButtonLight {
+        name: ControlName,
+        light: u8,
+    },
+    Clear {
+        color: [u8; 3],
+    },
+    FillRect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: [u8; 3],
+    },
+    DrawLine {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        color: [u8; 3],
+    },
+    DrawCircle {
+        x: i32,
+        y: i32,
+        diameter: u32,
+        color: [u8; 3],
+    },
+    DrawText {
+        x: i32,
+        y: i32,
+        text: String,
+        color: [u8; 3],
+    },
+    /// Sends the drawn frame to the display.
+    Flush,
+}
+
+impl RemoteCommand {
+    /// Executes the command on the device.
+    /// Drawing to the display is infallible, so only MIDI and USB errors are returned.
+    pub fn apply(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        match self {
+            RemoteCommand::SetPadColor { x, y, color } => {
+                push2.set_pad_color(PadCoord { x: *x, y: *y }, *color)?;
+            }
+            RemoteCommand::SetButtonLight { name, light } => {
+                push2.set_button_light(*name, *light)?;
+            }
+            RemoteCommand::Clear { color } => {
+                push2.display.clear(to_bgr565(*color)).unwrap();
+            }
+            RemoteCommand::FillRect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => {
+                Rectangle::new(Point::new(*x, *y), Size::new(*width, *height))
+                    .into_styled(PrimitiveStyle::with_fill(to_bgr565(*color)))
+                    .draw(&mut push2.display)
+                    .unwrap();
+            }
+            RemoteCommand::DrawLine {
+                x1,
+                y1,
+                x2,
+                y2,
+                color,
+            } => {
+                Line::new(Point::new(*x1, *y1), Point::new(*x2, *y2))
+                    .into_styled(PrimitiveStyle::with_stroke(to_bgr565(*color), 1))
+                    .draw(&mut push2.display)
+                    .unwrap();
+            }
+            RemoteCommand::DrawCircle {
+                x,
+                y,
+                diameter,
+                color,
+            } => {
+                Circle::new(Point::new(*x, *y), *diameter)
+                    .into_styled(PrimitiveStyle::with_stroke(to_bgr565(*color), 1))
+                    .draw(&mut push2.display)
+                    .unwrap();
+            }
+            RemoteCommand::DrawText { x, y, text, color } => {
+                let style = MonoTextStyle::new(&FONT_6X10, to_bgr565(*color));
+                Text::new(text, Point::new(*x, *y), style)
+                    .draw(&mut push2.display)
+                    .unwrap();
+            }
+            RemoteCommand::Flush => {
+                push2.display.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Messages streamed to remote clients, as JSON objects tagged by `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteMessage {
+    Event {
+        event: Push2Event,
+    },
+    /// A downscaled copy of the display. `pixels` holds `width * height`
+    /// raw Bgr565 values, row by row.
+    Frame {
+        width: usize,
+        height: usize,
+        pixels: Vec<u16>,
+    },
+}
+
+/// A WebSocket server mirroring the device to browsers or other remote clients.
+///
+/// Every connected client receives the broadcast events and frames, and can
+/// send `RemoteCommand`s, which are queued until `poll_command` is called.
+pub struct RemoteServer {
+    clients: Arc<Mutex<Vec<Sender<Message>>>>,
+    command_rx: Receiver<RemoteCommand>,
+    frame_encoder: Mutex<FrameEncoder>,
+    /// Set when a client connects, so the next compressed frame is a key frame.
+    client_joined: Arc<AtomicBool>,
+}
+
+impl RemoteServer {
+    /// Starts listening for WebSocket clients, e.g. on "0.0.0.0:9001".
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, RemoteError> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Remote server listening on {}", listener.local_addr()?);
+        let clients: Arc<Mutex<Vec<Sender<Message>>>> = Arc::new(Mutex::new(Vec::new()));
+        let (command_tx, command_rx) = mpsc::channel();
+        let accept_clients = Arc::clone(&clients);
+        let client_joined = Arc::new(AtomicBool::new(false));
+        let accept_joined = Arc::clone(&client_joined);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept remote client: {}", e);
+                        continue;
+                    }
+                };
+                let (message_tx, message_rx) = mpsc::channel();
+                if let Ok(mut clients) = accept_clients.lock() {
+                    clients.push(message_tx);
+                }
+                accept_joined.store(true, Ordering::Relaxed);
+                let command_tx = command_tx.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_client(stream, message_rx, command_tx) {
+                        warn!("Remote client disconnected: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            clients,
+            command_rx,
+            frame_encoder: Mutex::new(FrameEncoder::new()),
+            client_joined,
+        })
+    }
+
+    /// Sets the encoder used by `broadcast_compressed_frame`, e.g. to cap its bandwidth.
+    pub fn with_frame_encoder(mut self, encoder: FrameEncoder) -> Self {
+        self.frame_encoder = Mutex::new(encoder);
+        self
+    }
+
+    /// The number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().map(|c| c.len()).unwrap_or(0)
+    }
+
+    /// Returns the next command received from a client. This is non-blocking.
+    pub fn poll_command(&self) -> Option<RemoteCommand> {
+        self.command_rx.try_recv().ok()
+    }
+
+    /// Sends a message to every connected client.
+    pub fn broadcast(&self, message: &RemoteMessage) -> Result<(), RemoteError> {
+        let text = serde_json::to_string(message)?;
+        self.send_to_all(Message::text(text));
+        Ok(())
+    }
+
+    fn send_to_all(&self, message: Message) {
+        if let Ok(mut clients) = self.clients.lock() {
+            // Clients whose thread ended have dropped their receiver.
+            clients.retain(|client| client.send(message.clone()).is_ok());
+        }
+    }
+
+    pub fn broadcast_event(&self, event: &Push2Event) -> Result<(), RemoteError> {
+        self.broadcast(&RemoteMessage::Event {
+            event: event.clone(),
+        })
+    }
+
+    /// Sends the display's frame buffer, keeping one pixel in every
+    /// `scale` x `scale` block (1 = full resolution).
+    pub fn broadcast_frame(&self, display: &Push2Display, scale: usize) -> Result<(), RemoteError> {
+        if self.client_count() == 0 {
+            return Ok(());
+        }
+        let scale = scale.max(1);
+        let width = DISPLAY_WIDTH / scale;
+        let height = DISPLAY_HEIGHT / scale;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(display.frame_buffer[y * scale * DISPLAY_WIDTH + x * scale]);
+            }
+        }
+        self.broadcast(&RemoteMessage::Frame {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Sends the full-resolution display as a binary message compressed by
+    /// the `FrameEncoder` (see its docs for the format). Unchanged frames are
+    /// not sent, and frames over the encoder's bandwidth cap are dropped.
+    /// Returns whether a frame was sent.
+    pub fn broadcast_compressed_frame(&self, display: &Push2Display) -> bool {
+        if self.client_count() == 0 {
+            return false;
+        }
+        let Ok(mut encoder) = self.frame_encoder.lock() else {
+            return false;
+        };
+        if self.client_joined.swap(false, Ordering::Relaxed) {
+            encoder.request_key_frame();
+        }
+        let Some(bytes) = encoder.encode(DISPLAY_WIDTH, DISPLAY_HEIGHT, &display.frame_buffer)
+        else {
+            return false;
+        };
+        drop(encoder);
+        self.send_to_all(Message::binary(bytes));
+        true
+    }
+
+    /// The number of compressed frames dropped to stay under the bandwidth cap.
+    pub fn dropped_frames(&self) -> usize {
+        self.frame_encoder.lock().map(|e| e.dropped).unwrap_or(0)
+    }
+}
+
+fn to_bgr565(color: [u8; 3]) -> Bgr565 {
+    Rgb888::new(color[0], color[1], color[2]).into()
+}
+
+/// Runs a single client connection: forwards queued messages to it and
+/// parses the commands it sends.
+fn serve_client(
+    stream: TcpStream,
+    message_rx: Receiver<Message>,
+    command_tx: Sender<RemoteCommand>,
+) -> Result<(), tungstenite::Error> {
+    let peer = stream.peer_addr().ok();
+    let mut socket = websocket::accept(stream)?;
+    info!("Remote client connected: {:?}", peer);
+    websocket::run_client(&mut socket, &message_rx, |text| {
+        match serde_json::from_str::<RemoteCommand>(text) {
+            Ok(command) => return command_tx.send(command).is_ok(),
+            Err(e) => warn!("Ignoring invalid remote command: {}", e),
+        }
+        true
+    })
+}
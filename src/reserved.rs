@@ -0,0 +1,90 @@
+use crate::{ControlName, Push2, Push2Event};
+use std::collections::HashMap;
+
+type ReservedFn = Box<dyn FnMut(&mut Push2) + Send>;
+
+/// What the crate does with a press of a reserved button.
+pub enum ReservedAction {
+    /// Drops the press, so apps never act on the button by accident.
+    Swallow,
+    /// Opens and closes `Push2::settings_menu`.
+    Settings,
+    /// Calls a function with the device, in place of returning the press.
+    Callback(ReservedFn),
+}
+
+/// Buttons handled by the crate itself instead of being returned by `poll_event`.
+///
+/// Set `Push2::reserved_controls` to enable it. Presses and releases of a
+/// reserved button are not returned to the app and do not change
+/// `Push2::state`; presses run the button's `ReservedAction`.
+///
+/// ```ignore
+/// push2.reserved_controls = Some(
+///     ReservedControls::default().with_callback(ControlName::User, |push2| {
+///         let _ = push2.set_button_light(ControlName::User, Push2Colors::WHITE_BRIGHT);
+///     }),
+/// );
+/// ```
+pub struct ReservedControls {
+    actions: HashMap<ControlName, ReservedAction>,
+}
+
+impl Default for ReservedControls {
+    /// Reserves Setup for the settings menu, and User.
+    fn default() -> Self {
+        Self::new()
+            .with_action(ControlName::Setup, ReservedAction::Settings)
+            .with_action(ControlName::User, ReservedAction::Swallow)
+    }
+}
+
+impl ReservedControls {
+    /// No reserved buttons.
+    pub fn new() -> Self {
+        Self {
+            actions: HashMap::new(),
+        }
+    }
+
+    pub fn with_action(mut self, name: ControlName, action: ReservedAction) -> Self {
+        self.reserve(name, action);
+        self
+    }
+
+    pub fn with_callback<F>(self, name: ControlName, callback: F) -> Self
+    where
+        F: FnMut(&mut Push2) + Send + 'static,
+    {
+        self.with_action(name, ReservedAction::Callback(Box::new(callback)))
+    }
+
+    pub fn reserve(&mut self, name: ControlName, action: ReservedAction) {
+        self.actions.insert(name, action);
+    }
+
+    /// Returns a button to the app.
+    pub fn unreserve(&mut self, name: ControlName) {
+        self.actions.remove(&name);
+    }
+
+    pub fn is_reserved(&self, name: ControlName) -> bool {
+        self.actions.contains_key(&name)
+    }
+
+    /// The reserved button an event is about, if any.
+    pub(crate) fn reserved_control(&self, event: &Push2Event) -> Option<ControlName> {
+        match *event {
+            Push2Event::ButtonPressed { name, .. } | Push2Event::ButtonReleased { name }
+                if self.is_reserved(name) =>
+            {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn action_mut(&mut self, name: ControlName) -> Option<&mut ReservedAction> {
+        self.actions.get_mut(&name)
+    }
+}
@@ -0,0 +1,480 @@
+//! User scripts in Rhai that react to events and set the lights and display,
+//! reloaded whenever a script file changes.
+//!
+//! Scripts are the ".rhai" files in the "scripts" folder of the push2 config
+//! directory. A script handles an event by defining its function, e.g.:
+//!
+//! ```text
+//! fn on_pad_pressed(x, y, velocity) {
+//!     if pad_color(x, y) == 0 { set_pad(x, y, 11); } else { set_pad(x, y, 0); }
+//! }
+//!
+//! fn on_button_pressed(name) {
+//!     this.presses = (this.presses ?? 0) + 1;
+//!     text(0, `${name} pressed ${this.presses} times`);
+//! }
+//! ```
+//!
+//! The handlers are `on_pad_pressed(x, y, velocity)`, `on_pad_released(x, y)`,
+//! `on_button_pressed(name)`, `on_button_released(name)`,
+//! `on_encoder(name, delta)` and `on_slider(value)`. `this` is an object map
+//! each script keeps between calls. Scripts can call `set_pad(x, y, color)`,
+//! `pad_color(x, y)`, `clear_pads()`, `set_button(name, light)` and
+//! `text(line, text)`; `print` writes to the log.
+
+use crate::app_config::config_dir;
+use crate::button_map::decode_delta;
+use crate::{ControlName, PadCoord, Push2, Push2Error, Push2Event, PushApp};
+use log::{info, warn};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// Stops scripts stuck in a loop instead of freezing the main loop.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    #[error("Could not read the scripts: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+    #[error("Failed to compile {path:?}: {source}")]
+    Compile {
+        path: PathBuf,
+        source: Box<EvalAltResult>,
+    },
+    #[error("Error in {path:?}: {source}")]
+    Runtime {
+        path: PathBuf,
+        source: Box<EvalAltResult>,
+    },
+    #[error(transparent)]
+    Push2(#[from] Push2Error),
+}
+
+/// A call from a script, applied to the device once the handler returns.
+#[derive(Debug, Clone, PartialEq)]
+enum ScriptCommand {
+    Pad(PadCoord, u8),
+    ClearPads,
+    Button(ControlName, u8),
+    Text(usize, String),
+}
+
+/// What the script functions share with the engine.
+#[derive(Default)]
+struct Shared {
+    commands: RefCell<Vec<ScriptCommand>>,
+    /// The pad colors when the handler was called.
+    pads: RefCell<[[u8; 8]; 8]>,
+}
+
+struct Script {
+    path: PathBuf,
+    ast: AST,
+    modified: Option<SystemTime>,
+    /// False for a script added with `load_str`, which has no file.
+    from_file: bool,
+    /// The script's `this`.
+    this: Dynamic,
+}
+
+/// Runs the scripts in a directory for every event passed to `handle_event`.
+///
+/// Also a `PushApp`, so an `AppHost` can run scripts as one of its modes.
+pub struct ScriptEngine {
+    engine: Engine,
+    dir: PathBuf,
+    scripts: Vec<Script>,
+    /// The files whose last version failed to load, with its modification
+    /// time, so each version is only reported once.
+    failed: HashMap<PathBuf, Option<SystemTime>>,
+    shared: Rc<Shared>,
+}
+
+impl ScriptEngine {
+    /// Loads every ".rhai" file in `dir`. A missing directory has no scripts.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, ScriptError> {
+        let shared = Rc::new(Shared::default());
+        let mut script_engine = Self {
+            engine: Self::engine(&shared),
+            dir: dir.into(),
+            scripts: Vec::new(),
+            failed: HashMap::new(),
+            shared,
+        };
+        script_engine.reload()?;
+        Ok(script_engine)
+    }
+
+    /// Loads the scripts in the "scripts" folder of the push2 config directory.
+    pub fn in_config_dir() -> Result<Self, ScriptError> {
+        let dir = config_dir().ok_or(ScriptError::NoConfigDir)?;
+        Self::new(dir.join("scripts"))
+    }
+
+    fn engine(shared: &Rc<Shared>) -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| info!("script: {}", text));
+
+        let s = Rc::clone(shared);
+        engine.register_fn("set_pad", move |x: i64, y: i64, color: i64| {
+            if let Some(coord) = pad_coord(x, y) {
+                s.commands
+                    .borrow_mut()
+                    .push(ScriptCommand::Pad(coord, color.clamp(0, 127) as u8));
+            }
+        });
+        let s = Rc::clone(shared);
+        engine.register_fn("pad_color", move |x: i64, y: i64| -> i64 {
+            pad_coord(x, y).map_or(0, |c| s.pads.borrow()[c.y as usize][c.x as usize] as i64)
+        });
+        let s = Rc::clone(shared);
+        engine.register_fn("clear_pads", move || {
+            s.commands.borrow_mut().push(ScriptCommand::ClearPads);
+        });
+        let s = Rc::clone(shared);
+        engine.register_fn(
+            "set_button",
+            move |name: &str, light: i64| match ron::from_str::<ControlName>(name) {
+                Ok(name) => s
+                    .commands
+                    .borrow_mut()
+                    .push(ScriptCommand::Button(name, light.clamp(0, 127) as u8)),
+                Err(_) => warn!("script: no button named {:?}", name),
+            },
+        );
+        let s = Rc::clone(shared);
+        engine.register_fn("text", move |line: i64, text: &str| {
+            s.commands
+                .borrow_mut()
+                .push(ScriptCommand::Text(line.max(0) as usize, text.to_string()));
+        });
+        engine
+    }
+
+    /// The directory the scripts are loaded from.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The files of the loaded scripts.
+    pub fn script_paths(&self) -> impl Iterator<Item = &Path> {
+        self.scripts.iter().map(|script| script.path.as_path())
+    }
+
+    /// Loads every script file again, dropping their `this` state. Scripts
+    /// added with `load_str` are kept.
+    pub fn reload(&mut self) -> Result<(), ScriptError> {
+        self.scripts.retain(|script| !script.from_file);
+        self.failed.clear();
+        for path in self.script_files()? {
+            let script = self.compile(path)?;
+            self.scripts.push(script);
+        }
+        Ok(())
+    }
+
+    /// Reloads the scripts whose file changed, was added or was removed.
+    /// A script that fails to compile keeps running its last version; the
+    /// error is logged once per version. Returns true if anything was
+    /// reloaded.
+    pub fn reload_changed(&mut self) -> Result<bool, ScriptError> {
+        let files = self.script_files()?;
+        let before = self.scripts.len();
+        self.scripts
+            .retain(|script| !script.from_file || files.contains(&script.path));
+        self.failed.retain(|path, _| files.contains(path));
+        let mut changed = self.scripts.len() != before;
+        for path in files {
+            let modified = modified(&path);
+            let index = self
+                .scripts
+                .iter()
+                .position(|s| s.from_file && s.path == path);
+            if index.is_some_and(|index| self.scripts[index].modified == modified)
+                || self.failed.get(&path) == Some(&modified)
+            {
+                continue;
+            }
+            match self.compile(path.clone()) {
+                Ok(script) => {
+                    info!("Loaded script {:?}", script.path);
+                    self.failed.remove(&path);
+                    match index {
+                        Some(index) => self.scripts[index] = script,
+                        None => self.scripts.push(script),
+                    }
+                    changed = true;
+                }
+                Err(e) => {
+                    warn!("{}", e);
+                    self.failed.insert(path, modified);
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    fn script_files(&self) -> Result<Vec<PathBuf>, ScriptError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "rhai") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn compile(&self, path: PathBuf) -> Result<Script, ScriptError> {
+        let modified = modified(&path);
+        match self.engine.compile_file(path.clone()) {
+            Ok(ast) => self.start(path, ast, modified, true),
+            Err(source) => Err(ScriptError::Compile { path, source }),
+        }
+    }
+
+    /// Adds a script from source, e.g. one that is not in a file.
+    pub fn load_str(&mut self, name: &str, source: &str) -> Result<(), ScriptError> {
+        let path = PathBuf::from(name);
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|e| ScriptError::Compile {
+                path: path.clone(),
+                source: e.into(),
+            })?;
+        let script = self.start(path, ast, None, false)?;
+        self.scripts.push(script);
+        Ok(())
+    }
+
+    /// Runs the top level of a script once.
+    fn start(
+        &self,
+        path: PathBuf,
+        ast: AST,
+        modified: Option<SystemTime>,
+        from_file: bool,
+    ) -> Result<Script, ScriptError> {
+        if let Err(source) = self.engine.run_ast(&ast) {
+            return Err(ScriptError::Runtime { path, source });
+        }
+        Ok(Script {
+            path,
+            ast,
+            modified,
+            from_file,
+            this: Dynamic::from_map(Map::new()),
+        })
+    }
+
+    /// Calls the scripts' handler for an event, then applies what they set.
+    /// Every script runs even if one fails; the first error is returned.
+    pub fn handle_event(
+        &mut self,
+        push2: &mut Push2,
+        event: &Push2Event,
+    ) -> Result<(), ScriptError> {
+        let (handler, args): (&str, Vec<Dynamic>) = match *event {
+            Push2Event::PadPressed { coord, velocity } => (
+                "on_pad_pressed",
+                vec![
+                    (coord.x as i64).into(),
+                    (coord.y as i64).into(),
+                    (velocity as i64).into(),
+                ],
+            ),
+            Push2Event::PadReleased { coord, .. } => (
+                "on_pad_released",
+                vec![(coord.x as i64).into(), (coord.y as i64).into()],
+            ),
+            Push2Event::ButtonPressed { name, .. } => {
+                ("on_button_pressed", vec![format!("{:?}", name).into()])
+            }
+            Push2Event::ButtonReleased { name } => {
+                ("on_button_released", vec![format!("{:?}", name).into()])
+            }
+            Push2Event::EncoderTwisted { name, raw_delta } => (
+                "on_encoder",
+                vec![
+                    format!("{:?}", name).into(),
+                    (decode_delta(raw_delta) as i64).into(),
+                ],
+            ),
+            Push2Event::SliderMoved { value } => ("on_slider", vec![(value as i64).into()]),
+            _ => return Ok(()),
+        };
+        let result = self.call(push2, handler, args);
+        let applied = self.apply(push2);
+        result.and(applied)
+    }
+
+    fn call(
+        &mut self,
+        push2: &Push2,
+        handler: &str,
+        args: Vec<Dynamic>,
+    ) -> Result<(), ScriptError> {
+        *self.shared.pads.borrow_mut() = push2.state.pads.map(|row| row.map(|pad| pad.color));
+        let mut first_error = None;
+        for script in &mut self.scripts {
+            let defined = script
+                .ast
+                .iter_functions()
+                .any(|f| f.name == handler && f.params.len() == args.len());
+            if !defined {
+                continue;
+            }
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut script.this);
+            let result = self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                &script.ast,
+                handler,
+                args.clone(),
+            );
+            if let Err(source) = result
+                && first_error.is_none()
+            {
+                first_error = Some(ScriptError::Runtime {
+                    path: script.path.clone(),
+                    source,
+                });
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    fn apply(&mut self, push2: &mut Push2) -> Result<(), ScriptError> {
+        let commands = self.shared.commands.take();
+        for command in commands {
+            match command {
+                ScriptCommand::Pad(coord, color) => push2.set_pad_color(coord, color)?,
+                ScriptCommand::ClearPads => push2.clear_pads().into_result()?,
+                ScriptCommand::Button(name, light) => push2.set_button_light(name, light)?,
+                ScriptCommand::Text(line, text) => push2.set_text_line(line, &text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Script errors are logged so one broken script does not stop the host.
+impl PushApp for ScriptEngine {
+    fn name(&self) -> &str {
+        "Scripts"
+    }
+
+    fn on_event(&mut self, push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+        match self.handle_event(push2, event) {
+            Err(ScriptError::Push2(e)) => Err(e),
+            Err(e) => {
+                warn!("{}", e);
+                Ok(())
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    fn draw(&mut self, _push2: &mut Push2) -> Result<(), Push2Error> {
+        if let Err(e) = self.reload_changed() {
+            warn!("{}", e);
+        }
+        Ok(())
+    }
+}
+
+fn pad_coord(x: i64, y: i64) -> Option<PadCoord> {
+    ((0..8).contains(&x) && (0..8).contains(&y)).then(|| PadCoord::new(x as u8, y as u8))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+
+    #[test]
+    fn handlers_set_lights_and_keep_state() {
+        let (backend, _tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        let mut scripts = ScriptEngine::new(std::env::temp_dir().join("push2-no-scripts")).unwrap();
+        scripts
+            .load_str(
+                "toggle",
+                r#"
+                fn on_pad_pressed(x, y, velocity) {
+                    this.count = (this.count ?? 0) + 1;
+                    set_pad(x, y, if pad_color(x, y) == 0 { this.count } else { 0 });
+                }
+                "#,
+            )
+            .unwrap();
+        let press = Push2Event::PadPressed {
+            coord: PadCoord::new(2, 3),
+            velocity: 100,
+        };
+        scripts.handle_event(&mut push2, &press).unwrap();
+        assert_eq!(push2.state.pads[3][2].color, 1);
+        scripts.handle_event(&mut push2, &press).unwrap();
+        assert_eq!(push2.state.pads[3][2].color, 0);
+        scripts.handle_event(&mut push2, &press).unwrap();
+        assert_eq!(push2.state.pads[3][2].color, 3);
+    }
+
+    #[test]
+    fn reports_compile_errors() {
+        let mut scripts = ScriptEngine::new(std::env::temp_dir().join("push2-no-scripts")).unwrap();
+        assert!(matches!(
+            scripts.load_str("broken", "fn on_slider(value) {"),
+            Err(ScriptError::Compile { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_broken_files_once_and_keeps_loaded_strings() {
+        let dir = std::env::temp_dir().join("push2_scripting_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut scripts = ScriptEngine::new(&dir).unwrap();
+        scripts
+            .load_str("inline", "fn on_pad_pressed(x, y, v) {}")
+            .unwrap();
+
+        let broken = dir.join("broken.rhai");
+        fs::write(&broken, "fn on_pad_pressed(").unwrap();
+        assert!(!scripts.reload_changed().unwrap());
+        assert_eq!(scripts.failed.get(&broken), Some(&modified(&broken)));
+        assert!(!scripts.reload_changed().unwrap());
+        assert_eq!(scripts.script_paths().count(), 1);
+
+        fs::write(&broken, "fn on_pad_pressed(x, y, v) {}").unwrap();
+        // The rewrite may land within the same modification time.
+        scripts.failed.insert(broken.clone(), None);
+        assert!(scripts.reload_changed().unwrap());
+        assert!(scripts.failed.is_empty());
+        assert_eq!(scripts.script_paths().count(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
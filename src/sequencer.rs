@@ -0,0 +1,388 @@
+use crate::PushApp;
+use crate::clock::ClockTick;
+use crate::{PadCoord, Push2, Push2Colors, Push2Error, Push2Event};
+use std::time::{Duration, Instant};
+
+/// The maximum number of steps a pattern can hold (one per pad).
+pub const MAX_STEPS: usize = 64;
+
+/// A single step of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub active: bool,
+    pub velocity: u8,
+    /// The chance (0.0-1.0) that an active step actually triggers.
+    pub probability: f32,
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Self {
+            active: false,
+            velocity: 100,
+            probability: 1.0,
+        }
+    }
+}
+
+/// A sequence of steps triggering a single note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub note: u8,
+    pub steps: Vec<Step>,
+}
+
+impl Pattern {
+    /// Creates an empty pattern. The length is clamped to `MAX_STEPS`.
+    pub fn new(note: u8, length: usize) -> Self {
+        Self {
+            note,
+            steps: vec![Step::default(); length.clamp(1, MAX_STEPS)],
+        }
+    }
+
+    /// Changes the number of steps, keeping existing steps where possible.
+    pub fn set_length(&mut self, length: usize) {
+        self.steps
+            .resize(length.clamp(1, MAX_STEPS), Step::default());
+    }
+}
+
+/// Passed to the trigger callback whenever a step fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepTrigger {
+    /// The index of the pattern that fired.
+    pub pattern: usize,
+    /// The step index inside that pattern.
+    pub step: usize,
+    pub note: u8,
+    pub velocity: u8,
+}
+
+/// The pad colors used to render the edited pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequencerColors {
+    pub active: u8,
+    pub inactive: u8,
+    pub playhead: u8,
+}
+
+impl Default for SequencerColors {
+    fn default() -> Self {
+        Self {
+            active: Push2Colors::BLUE,
+            inactive: Push2Colors::WHITE_LOW,
+            playhead: Push2Colors::GREEN,
+        }
+    }
+}
+
+/// A step sequencer playing several patterns in parallel.
+///
+/// The selected pattern is shown on the grid (step 0 on the top-left pad,
+/// reading left to right) and edited by pressing pads. All patterns play
+/// at the same time, each looping over its own length.
+pub struct StepSequencer {
+    patterns: Vec<Pattern>,
+    selected: usize,
+    /// The tempo. Changing it while playing carries on from the current step.
+    pub bpm: f64,
+    /// Steps per quarter note (4 = sixteenth notes).
+    pub steps_per_beat: u32,
+    /// Delay applied to every odd step, as a fraction of a step (0.0-0.75).
+    pub swing: f32,
+    pub colors: SequencerColors,
+    playing: bool,
+    /// Total number of steps played since `start`.
+    step_count: u64,
+    /// The time of step `anchor_step`, from which the later steps are timed.
+    started_at: Option<Instant>,
+    anchor_step: u64,
+    /// The step duration the steps are timed with, to notice tempo changes.
+    anchor_duration: Duration,
+    on_trigger: Option<Box<dyn FnMut(StepTrigger) + Send>>,
+    rng_state: u32,
+}
+
+impl StepSequencer {
+    /// Creates a sequencer with a single empty pattern.
+    pub fn new(note: u8, length: usize) -> Self {
+        Self {
+            patterns: vec![Pattern::new(note, length)],
+            selected: 0,
+            bpm: 120.0,
+            steps_per_beat: 4,
+            swing: 0.0,
+            colors: SequencerColors::default(),
+            playing: false,
+            step_count: 0,
+            started_at: None,
+            anchor_step: 0,
+            anchor_duration: Duration::ZERO,
+            on_trigger: None,
+            rng_state: 0x9E37_79B9,
+        }
+    }
+
+    /// Registers the callback called for every step that fires.
+    pub fn on_trigger<F>(&mut self, callback: F)
+    where
+        F: FnMut(StepTrigger) + Send + 'static,
+    {
+        self.on_trigger = Some(Box::new(callback));
+    }
+
+    /// Adds a pattern and returns its index.
+    pub fn add_pattern(&mut self, pattern: Pattern) -> usize {
+        self.patterns.push(pattern);
+        self.patterns.len() - 1
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    pub fn pattern_mut(&mut self, index: usize) -> Option<&mut Pattern> {
+        self.patterns.get_mut(index)
+    }
+
+    /// The index of the pattern shown on (and edited from) the grid.
+    pub fn selected_pattern(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select_pattern(&mut self, index: usize) {
+        if index < self.patterns.len() {
+            self.selected = index;
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Starts playback from the first step.
+    pub fn start(&mut self) {
+        self.playing = true;
+        self.step_count = 0;
+        self.started_at = Some(Instant::now());
+        self.anchor_step = 0;
+        self.anchor_duration = self.step_duration();
+    }
+
+    /// Changes the tempo, carrying on from the current step.
+    pub fn set_bpm(&mut self, bpm: f64) {
+        self.bpm = bpm;
+        self.reanchor(Instant::now());
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.started_at = None;
+    }
+
+    /// The step of the selected pattern that was played last.
+    pub fn position(&self) -> Option<usize> {
+        if !self.playing || self.step_count == 0 {
+            return None;
+        }
+        let len = self.patterns[self.selected].steps.len() as u64;
+        Some(((self.step_count - 1) % len) as usize)
+    }
+
+    /// The duration of a single step at the current tempo.
+    pub fn step_duration(&self) -> Duration {
+        Duration::from_secs_f64(60.0 / self.bpm.max(1.0) / self.steps_per_beat.max(1) as f64)
+    }
+
+    /// Fires every step that is due. Call this regularly from the main loop.
+    pub fn update(&mut self) {
+        self.update_at(Instant::now());
+    }
+
+    /// Fires every step that is due at `now`.
+    pub fn update_at(&mut self, now: Instant) {
+        self.reanchor(now);
+        let Some(started_at) = self.started_at else {
+            return;
+        };
+        let step = self.step_duration();
+        let anchor = self.step_offset(self.anchor_step, step);
+        while self.playing && now >= started_at + self.step_offset(self.step_count, step) - anchor {
+            self.fire_step(self.step_count);
+            self.step_count += 1;
+        }
+    }
+
+    /// Advances the sequencer by exactly one step, ignoring its own timing.
+    /// Use this to drive the sequencer from an external clock.
+    pub fn advance(&mut self) {
+        self.fire_step(self.step_count);
+        self.step_count += 1;
+    }
+
+    /// Drives the sequencer from a `Clock` tick instead of its own timing.
+    /// Swing is applied by delaying odd steps by whole ticks.
+    pub fn on_clock_tick(&mut self, tick: &ClockTick) {
+        if tick.tick == 0 {
+            self.playing = true;
+            self.started_at = None;
+            self.step_count = 0;
+        }
+        let ticks_per_step = (tick.ppqn / self.steps_per_beat.max(1)).max(1) as u64;
+        let mut due = self.step_count * ticks_per_step;
+        if self.step_count % 2 == 1 {
+            due += (ticks_per_step as f32 * self.swing.clamp(0.0, 0.75)).round() as u64;
+        }
+        if self.playing && tick.tick >= due {
+            self.advance();
+        }
+    }
+
+    /// Applies pad presses to the selected pattern.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&mut self, event: &Push2Event) -> bool {
+        if let Push2Event::PadPressed { coord, velocity } = *event {
+            let index = (coord.y * 8 + coord.x) as usize;
+            let pattern = &mut self.patterns[self.selected];
+            if let Some(step) = pattern.steps.get_mut(index) {
+                step.active = !step.active;
+                if step.active {
+                    step.velocity = velocity;
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Draws the selected pattern onto the grid.
+    /// Only pads whose color changed are sent to the hardware.
+    pub fn render(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let pattern = &self.patterns[self.selected];
+        let playhead = self.position();
+        for index in 0..MAX_STEPS {
+            let coord = PadCoord {
+                x: (index % 8) as u8,
+                y: (index / 8) as u8,
+            };
+            let color = match pattern.steps.get(index) {
+                None => Push2Colors::BLACK,
+                Some(_) if playhead == Some(index) => self.colors.playhead,
+                Some(step) if step.active => self.colors.active,
+                Some(_) => self.colors.inactive,
+            };
+            if push2.state.pads[coord.y as usize][coord.x as usize].color != color {
+                push2.set_pad_color(coord, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Times the coming steps with the current tempo, keeping how far the
+    /// sequencer is between the last step and the next.
+    fn reanchor(&mut self, now: Instant) {
+        let step = self.step_duration();
+        if step == self.anchor_duration {
+            return;
+        }
+        let old = self.anchor_duration;
+        if let Some(started_at) = self.started_at
+            && self.step_count > 0
+        {
+            let last = self.step_count - 1;
+            let anchor = self.step_offset(self.anchor_step, old);
+            let last_at = started_at + self.step_offset(last, old) - anchor;
+            let old_gap = self.step_offset(last + 1, old) - self.step_offset(last, old);
+            let new_gap = self.step_offset(last + 1, step) - self.step_offset(last, step);
+            let progress = (now.saturating_duration_since(last_at).as_secs_f64()
+                / old_gap.as_secs_f64().max(f64::EPSILON))
+            .min(1.0);
+            self.started_at = Some(now.checked_sub(new_gap.mul_f64(progress)).unwrap_or(now));
+            self.anchor_step = last;
+        }
+        self.anchor_duration = step;
+    }
+
+    /// The time of step `n` relative to step 0 with a step duration of
+    /// `step`, including swing.
+    fn step_offset(&self, n: u64, step: Duration) -> Duration {
+        let mut offset = step.mul_f64(n as f64);
+        if n % 2 == 1 {
+            offset += step.mul_f32(self.swing.clamp(0.0, 0.75));
+        }
+        offset
+    }
+
+    fn fire_step(&mut self, n: u64) {
+        for pattern_index in 0..self.patterns.len() {
+            let pattern = &self.patterns[pattern_index];
+            let step_index = (n % pattern.steps.len() as u64) as usize;
+            let step = pattern.steps[step_index];
+            let note = pattern.note;
+            if !step.active || self.next_random() > step.probability {
+                continue;
+            }
+            if let Some(callback) = self.on_trigger.as_mut() {
+                callback(StepTrigger {
+                    pattern: pattern_index,
+                    step: step_index,
+                    note,
+                    velocity: step.velocity,
+                });
+            }
+        }
+    }
+
+    /// A small xorshift generator for step probability, returning 0.0-1.0.
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+impl PushApp for StepSequencer {
+    fn name(&self) -> &str {
+        "Sequencer"
+    }
+
+    fn on_event(&mut self, _push2: &mut Push2, event: &Push2Event) -> Result<(), Push2Error> {
+        self.handle_event(event);
+        Ok(())
+    }
+
+    fn draw(&mut self, push2: &mut Push2) -> Result<(), Push2Error> {
+        self.update();
+        self.render(push2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tempo_changes_keep_the_position() {
+        let mut sequencer = StepSequencer::new(36, 16);
+        sequencer.start();
+        let start = sequencer.started_at.unwrap();
+        // Sixteenth notes at 120 BPM, 8 steps a second.
+        let second = start + Duration::from_secs(1);
+        sequencer.update_at(second);
+        assert_eq!(sequencer.step_count, 9);
+
+        sequencer.bpm = 480.0;
+        sequencer.update_at(second);
+        assert_eq!(sequencer.step_count, 9);
+        sequencer.update_at(second + Duration::from_millis(32));
+        assert_eq!(sequencer.step_count, 10);
+
+        sequencer.bpm = 60.0;
+        sequencer.update_at(second + Duration::from_millis(40));
+        assert_eq!(sequencer.step_count, 10);
+    }
+}
@@ -0,0 +1,220 @@
+//! Keeps the encoder values and the slider position between runs.
+
+use crate::app_config::config_dir;
+use crate::{EncoderName, Push2, Push2Event, Push2State};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long the values have to stay unchanged before they are saved, so a
+/// twist is written once rather than on every tick.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("Could not read or write the session file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to parse the session file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Failed to serialize the session: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+}
+
+/// The encoder values and slider position saved by a `SessionStore`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Session {
+    pub encoders: HashMap<EncoderName, i32>,
+    pub slider: u16,
+}
+
+impl Session {
+    /// The values of `state`, leaving out the relative only encoders.
+    pub fn capture(state: &Push2State) -> Self {
+        Self {
+            encoders: state
+                .encoders
+                .iter()
+                .filter(|(_, encoder)| !encoder.relative_only)
+                .map(|(name, encoder)| (*name, encoder.value))
+                .collect(),
+            slider: state.slider,
+        }
+    }
+
+    /// Puts the values into `state`, each clamped to its encoder's range.
+    pub fn restore(&self, state: &mut Push2State) {
+        for (name, value) in &self.encoders {
+            state.set_encoder_value(*name, *value);
+        }
+        state.slider = self.slider;
+    }
+}
+
+/// Saves the session to a RON file a moment after it changes.
+///
+/// Set with `Push2::persist_session`; `poll_event` then does the saving, and
+/// a change not saved yet is saved when the store is dropped with its `Push2`.
+pub struct SessionStore {
+    pub path: PathBuf,
+    /// When the values last changed and what they changed to, if they were
+    /// not saved since.
+    pending: Option<(Instant, Session)>,
+}
+
+impl SessionStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            pending: None,
+        }
+    }
+
+    /// A store in the push2 config directory, at "sessions/<app_name>.ron".
+    pub fn in_config_dir(app_name: &str) -> Result<Self, SessionError> {
+        let mut path = config_dir().ok_or(SessionError::NoConfigDir)?;
+        path.push("sessions");
+        path.push(format!("{app_name}.ron"));
+        Ok(Self::new(path))
+    }
+
+    /// The saved session, or `None` before the first save.
+    pub fn load(&self) -> Result<Option<Session>, SessionError> {
+        let ron = match fs::read_to_string(&self.path) {
+            Ok(ron) => ron,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(ron::from_str(&ron).map_err(Box::new)?))
+    }
+
+    pub fn save(&self, session: &Session) -> Result<(), SessionError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let ron = ron::ser::to_string_pretty(session, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, ron)?;
+        Ok(())
+    }
+}
+
+impl Push2 {
+    /// Restores the encoder values and slider position from the last run of
+    /// `app_name`, and saves them from now on as they change.
+    ///
+    /// Set the encoder ranges first: the restored values are clamped to them.
+    /// The last change is saved when `self` is dropped; call `save_session`
+    /// before leaving in a way that skips it, such as `std::process::exit`.
+    pub fn persist_session(&mut self, app_name: &str) -> Result<(), SessionError> {
+        self.persist_session_to(SessionStore::in_config_dir(app_name)?)
+    }
+
+    /// Like `persist_session`, with a store of the caller's choosing.
+    pub fn persist_session_to(&mut self, store: SessionStore) -> Result<(), SessionError> {
+        if let Some(session) = store.load()? {
+            info!("Restoring the session from: {:?}", store.path);
+            session.restore(&mut self.state);
+        }
+        self.session = Some(store);
+        Ok(())
+    }
+
+    /// Saves the session now instead of waiting for `poll_event`.
+    pub fn save_session(&mut self) -> Result<(), SessionError> {
+        let Some(store) = self.session.as_mut() else {
+            return Ok(());
+        };
+        store.pending = None;
+        store.save(&Session::capture(&self.state))
+    }
+
+    /// Notes a change to the session.
+    pub(crate) fn track_session(&mut self, event: &Push2Event) {
+        if let Some(store) = self.session.as_mut()
+            && matches!(
+                event,
+                Push2Event::EncoderTwisted { .. } | Push2Event::SliderMoved { .. }
+            )
+        {
+            store.pending = Some((Instant::now(), Session::capture(&self.state)));
+        }
+    }
+
+    /// Saves the session once it stopped changing.
+    pub(crate) fn update_session(&mut self) {
+        if self
+            .session
+            .as_ref()
+            .and_then(|store| store.pending.as_ref())
+            .is_some_and(|(at, _)| at.elapsed() >= SAVE_DELAY)
+            && let Err(e) = self.save_session()
+        {
+            warn!("Failed to save the session: {}", e);
+        }
+    }
+}
+
+impl Drop for SessionStore {
+    /// Saves a session change still waiting for `SAVE_DELAY`.
+    fn drop(&mut self) {
+        if let Some((_, session)) = self.pending.take()
+            && let Err(e) = self.save(&session)
+        {
+            warn!("Failed to save the session: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+
+    #[test]
+    fn restores_the_saved_values() {
+        let path = std::env::temp_dir().join("push2_session_test.ron");
+        let _ = fs::remove_file(&path);
+        let (backend, tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.persist_session_to(SessionStore::new(&path)).unwrap();
+        let track1 = push2
+            .button_map
+            .get_encoder_address(EncoderName::Track1)
+            .unwrap();
+        // Three ticks clockwise, then the slider.
+        tx.send(vec![0xB0, track1, 3]).unwrap();
+        tx.send(vec![0xE0, 0, 64]).unwrap();
+        while push2.poll_event().is_some() {}
+        push2.save_session().unwrap();
+
+        let mut state = Push2State::new();
+        let session = SessionStore::new(&path).load().unwrap().unwrap();
+        let _ = fs::remove_file(&path);
+        session.restore(&mut state);
+        assert_eq!(state.encoders.values().map(|e| e.value).sum::<i32>(), 3);
+        assert_eq!(state.slider, 64 << 7);
+    }
+
+    #[test]
+    fn saves_pending_changes_on_drop() {
+        let path = std::env::temp_dir().join("push2_session_drop_test.ron");
+        let _ = fs::remove_file(&path);
+        let (backend, tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.persist_session_to(SessionStore::new(&path)).unwrap();
+        tx.send(vec![0xE0, 0, 32]).unwrap();
+        while push2.poll_event().is_some() {}
+        drop(push2);
+
+        let session = SessionStore::new(&path).load().unwrap().unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(session.slider, 32 << 7);
+    }
+}
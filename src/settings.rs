@@ -0,0 +1,370 @@
+use crate::app_config::config_dir;
+use crate::button_map::decode_delta;
+use crate::display::{Push2Display, Push2DisplayError};
+use crate::fonts::{FontSize, TextStyle};
+use crate::formatters;
+use crate::gui::{ENCODER_LABEL_Y_POS, ENCODER_VALUE_Y_POS, GuiApi};
+use crate::params::ParamColors;
+use crate::reserved::{ReservedAction, ReservedControls};
+use crate::touch_strip::TouchStripMode;
+use crate::velocity::VelocityCurve;
+use crate::{ControlName, Push2, Push2Error, Push2Event};
+use embedded_graphics::{
+    prelude::*,
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+const SET_LED_BRIGHTNESS: u8 = 0x06;
+const SET_DISPLAY_BRIGHTNESS: u8 = 0x08;
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Failed to serialize settings: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to parse settings file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read or write settings file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not find the config directory")]
+    NoConfigDir,
+}
+
+/// How hard the pads have to be hit for a loud note.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PadSensitivity {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl PadSensitivity {
+    pub fn curve(self) -> VelocityCurve {
+        match self {
+            PadSensitivity::Low => VelocityCurve::Hard,
+            PadSensitivity::Medium => VelocityCurve::Linear,
+            PadSensitivity::High => VelocityCurve::Soft,
+        }
+    }
+
+    fn step(self, delta: i32) -> Self {
+        const ORDER: [PadSensitivity; 3] = [
+            PadSensitivity::Low,
+            PadSensitivity::Medium,
+            PadSensitivity::High,
+        ];
+        let index = ORDER.iter().position(|s| *s == self).unwrap_or(1) as i32;
+        ORDER[(index + delta.signum()).clamp(0, 2) as usize]
+    }
+}
+
+/// The hardware settings shared by every app, kept in "settings.ron" in the
+/// push2 config directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct HardwareSettings {
+    /// 0-255.
+    pub display_brightness: u8,
+    /// 0-127.
+    pub led_brightness: u8,
+    pub pad_sensitivity: PadSensitivity,
+    pub touch_strip_mode: TouchStripMode,
+}
+
+impl Default for HardwareSettings {
+    fn default() -> Self {
+        Self {
+            display_brightness: 255,
+            led_brightness: 127,
+            pad_sensitivity: PadSensitivity::default(),
+            touch_strip_mode: TouchStripMode::default(),
+        }
+    }
+}
+
+/// An overlay for the hardware settings, drawn over the app while open.
+///
+/// While it is open, `poll_event` gives every event to the menu instead of
+/// returning it: the first four track encoders change the display
+/// brightness, LED brightness, pad sensitivity and touch strip mode, and
+/// Setup closes it. The settings are applied as they change and saved on close.
+///
+/// `Push2::enable_settings_menu` loads the saved settings and opens the menu
+/// with Setup.
+pub struct SettingsMenu {
+    pub settings: HardwareSettings,
+    /// Where the settings are saved, if anywhere.
+    pub path: Option<PathBuf>,
+    pub colors: ParamColors,
+    open: bool,
+}
+
+impl SettingsMenu {
+    /// Settings kept in memory only.
+    pub fn new(settings: HardwareSettings) -> Self {
+        Self {
+            settings,
+            path: None,
+            colors: ParamColors::default(),
+            open: false,
+        }
+    }
+
+    /// Loads "settings.ron" from the push2 config directory, where the
+    /// settings are saved from then on.
+    pub fn in_config_dir() -> Result<Self, SettingsError> {
+        let path = config_dir()
+            .ok_or(SettingsError::NoConfigDir)?
+            .join("settings.ron");
+        let mut menu = Self::new(HardwareSettings::default());
+        if path.exists() {
+            info!("Loading hardware settings from: {:?}", path);
+            let ron = fs::read_to_string(&path)?;
+            menu.settings = ron::from_str(&ron).map_err(Box::new)?;
+        }
+        menu.path = Some(path);
+        Ok(menu)
+    }
+
+    /// Writes the settings to `path`, if set.
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let ron = ron::ser::to_string_pretty(&self.settings, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Applies an encoder turn to a setting. Returns true if a setting changed.
+    fn adjust(&mut self, slot: u8, delta: i32) -> bool {
+        let before = self.settings;
+        let settings = &mut self.settings;
+        match slot {
+            0 => {
+                settings.display_brightness =
+                    (settings.display_brightness as i32 + delta * 4).clamp(0, 255) as u8
+            }
+            1 => {
+                settings.led_brightness =
+                    (settings.led_brightness as i32 + delta * 2).clamp(0, 127) as u8
+            }
+            2 => settings.pad_sensitivity = settings.pad_sensitivity.step(delta),
+            3 => {
+                settings.touch_strip_mode = if delta > 0 {
+                    TouchStripMode::ModWheel
+                } else {
+                    TouchStripMode::PitchBend
+                }
+            }
+            _ => {}
+        }
+        self.settings != before
+    }
+
+    fn draw(&self, display: &mut Push2Display) -> Result<(), Push2DisplayError> {
+        let settings = &self.settings;
+        let slots = [
+            (
+                "Display",
+                settings.display_brightness as f32 / 255.0,
+                formatters::percent(settings.display_brightness as f32 / 255.0),
+            ),
+            (
+                "LEDs",
+                settings.led_brightness as f32 / 127.0,
+                formatters::percent(settings.led_brightness as f32 / 127.0),
+            ),
+            (
+                "Pads",
+                match settings.pad_sensitivity {
+                    PadSensitivity::Low => 0.0,
+                    PadSensitivity::Medium => 0.5,
+                    PadSensitivity::High => 1.0,
+                },
+                format!("{:?}", settings.pad_sensitivity),
+            ),
+            (
+                "Touch strip",
+                match settings.touch_strip_mode {
+                    TouchStripMode::PitchBend => 0.0,
+                    TouchStripMode::ModWheel => 1.0,
+                },
+                match settings.touch_strip_mode {
+                    TouchStripMode::PitchBend => "Pitch bend".to_string(),
+                    TouchStripMode::ModWheel => "Mod wheel".to_string(),
+                },
+            ),
+        ];
+        display.clear(self.colors.background).unwrap(); // Infallible
+        for (slot, (name, normalized, value)) in slots.iter().enumerate() {
+            let slot = slot as u8;
+            display.draw_encoder_outline(slot, self.colors.outline)?;
+            display.draw_encoder_bar(slot, (normalized * 127.0).round() as i32, self.colors.bar)?;
+            display.draw_encoder_label(slot, ENCODER_LABEL_Y_POS, name, self.colors.label)?;
+            display.draw_encoder_label(slot, ENCODER_VALUE_Y_POS, value, self.colors.value)?;
+        }
+        let centered = TextStyleBuilder::new()
+            .alignment(Alignment::Center)
+            .baseline(Baseline::Middle)
+            .build();
+        Text::with_text_style(
+            "Settings - press Setup to close",
+            Point::new(480, 104),
+            TextStyle::new(FontSize::Large).with_color(self.colors.label),
+            centered,
+        )
+        .draw(display)
+        .unwrap(); // Infallible
+        Ok(())
+    }
+}
+
+impl Push2 {
+    /// Sends the display and LED brightness and the touch strip mode, and
+    /// sets the pad sensitivity as the curve of `velocity_map` (Push 2 only).
+    pub fn apply_settings(&mut self, settings: &HardwareSettings) -> Result<(), Push2Error> {
+        let brightness = settings.display_brightness;
+        self.midi_out.send(&[
+            0xF0,
+            0x00,
+            0x21,
+            0x1D,
+            0x01,
+            0x01,
+            SET_DISPLAY_BRIGHTNESS,
+            brightness & 0x7F,
+            brightness >> 7,
+            0xF7,
+        ])?;
+        self.midi_out.send(&[
+            0xF0,
+            0x00,
+            0x21,
+            0x1D,
+            0x01,
+            0x01,
+            SET_LED_BRIGHTNESS,
+            settings.led_brightness.min(127),
+            0xF7,
+        ])?;
+        self.set_touch_strip_mode(settings.touch_strip_mode)?;
+        let mut velocity_map = self.velocity_map.unwrap_or_default();
+        velocity_map.curve = settings.pad_sensitivity.curve();
+        self.velocity_map = Some(velocity_map);
+        Ok(())
+    }
+
+    /// Loads and applies the saved hardware settings, and reserves Setup to
+    /// open the settings menu.
+    pub fn enable_settings_menu(&mut self) -> Result<(), Push2Error> {
+        let menu = SettingsMenu::in_config_dir()?;
+        self.apply_settings(&menu.settings)?;
+        self.settings_menu = Some(menu);
+        self.reserved_controls
+            .get_or_insert_with(ReservedControls::new)
+            .reserve(ControlName::Setup, ReservedAction::Settings);
+        Ok(())
+    }
+
+    /// Opens the settings menu if it is closed, and closes it otherwise.
+    pub(crate) fn toggle_settings(&mut self) {
+        let Some(mut menu) = self.settings_menu.take() else {
+            return;
+        };
+        menu.open = !menu.open;
+        if menu.open {
+            let mut result = Ok(());
+            self.display
+                .draw_overlay(|display| result = menu.draw(display));
+            if let Err(e) = result.and_then(|_| self.display.flush()) {
+                warn!("Failed to draw the settings menu: {}", e);
+            }
+        } else {
+            self.display.clear_overlay();
+            if let Err(e) = menu.save() {
+                warn!("Failed to save the hardware settings: {}", e);
+            }
+        }
+        self.settings_menu = Some(menu);
+    }
+
+    /// Gives an event to the settings menu while it is open.
+    /// Returns true if the event was taken by the menu. Only presses and
+    /// encoder turns are taken; releases go through, so pads held when the
+    /// menu opened are released in `Push2State` and the note output.
+    pub(crate) fn handle_settings(&mut self, event: &Push2Event) -> bool {
+        if !self.settings_menu.as_ref().is_some_and(|m| m.open) {
+            return false;
+        }
+        if let Push2Event::ButtonPressed {
+            name: ControlName::Setup,
+            ..
+        } = event
+        {
+            self.toggle_settings();
+            return true;
+        }
+        let (name, raw_delta) = match *event {
+            Push2Event::EncoderTwisted { name, raw_delta } => (name, raw_delta),
+            Push2Event::ButtonPressed { .. } | Push2Event::PadPressed { .. } => return true,
+            _ => return false,
+        };
+        let Some(mut menu) = self.settings_menu.take() else {
+            return true;
+        };
+        if let Some(slot) = name.track_index()
+            && menu.adjust(slot, decode_delta(raw_delta))
+        {
+            if let Err(e) = self.apply_settings(&menu.settings) {
+                warn!("Failed to apply the hardware settings: {}", e);
+            }
+            let mut result = Ok(());
+            self.display
+                .draw_overlay(|display| result = menu.draw(display));
+            if let Err(e) = result.and_then(|_| self.display.flush()) {
+                warn!("Failed to draw the settings menu: {}", e);
+            }
+        }
+        self.settings_menu = Some(menu);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockBackend, PadCoord};
+
+    #[test]
+    fn lets_releases_through_while_open() {
+        let (backend, tx) = MockBackend::new();
+        let mut push2 = Push2::with_backend(backend).unwrap();
+        push2.settings_menu = Some(SettingsMenu::new(HardwareSettings::default()));
+        // The top-left pad, held while the menu opens.
+        tx.send(vec![0x90, 92, 100]).unwrap();
+        while push2.poll_event().is_some() {}
+        push2.toggle_settings();
+
+        tx.send(vec![0x90, 93, 100]).unwrap();
+        tx.send(vec![0x80, 92, 0]).unwrap();
+        assert!(matches!(
+            push2.poll_event(),
+            Some(Push2Event::PadReleased { .. })
+        ));
+        assert!(!push2.state.is_pad_held(PadCoord::new(0, 0)));
+        assert!(!push2.state.is_pad_held(PadCoord::new(1, 0)));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::button_map::decode_delta;
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH, Push2Display};
+use crate::gui::ENCODER_REGION_WIDTH;
+use crate::{ControlName, EncoderName, Push2Event};
+use slint::platform::software_renderer::{MinimalSoftwareWindow, RepaintBufferType, Rgb565Pixel};
+use slint::platform::{
+    Key, Platform, PointerEventButton, SetPlatformError, WindowAdapter, WindowEvent,
+};
+use slint::{LogicalPosition, PhysicalSize, PlatformError};
+use std::rc::Rc;
+
+/// The Slint platform handing out the display window.
+struct Push2Platform {
+    window: Rc<MinimalSoftwareWindow>,
+}
+
+impl Platform for Push2Platform {
+    fn create_window_adapter(&self) -> Result<Rc<dyn WindowAdapter>, PlatformError> {
+        Ok(self.window.clone())
+    }
+}
+
+/// Makes the 960x160 display the window of Slint components.
+///
+/// `install` registers it as Slint's platform, so it has to be called before
+/// any component is created. Components are then drawn with Slint's software
+/// renderer by `render`, and `handle_event` feeds the device in as input:
+/// the buttons above and below the display click the top and bottom of
+/// their column, the arrows and Select are arrow and Return keys, and the
+/// first track encoder moves the keyboard focus.
+pub struct SlintDisplay {
+    window: Rc<MinimalSoftwareWindow>,
+    buffer: Vec<Rgb565Pixel>,
+}
+
+impl SlintDisplay {
+    pub fn install() -> Result<Self, SetPlatformError> {
+        let window = MinimalSoftwareWindow::new(RepaintBufferType::ReusedBuffer);
+        window.set_size(PhysicalSize::new(
+            DISPLAY_WIDTH as u32,
+            DISPLAY_HEIGHT as u32,
+        ));
+        slint::platform::set_platform(Box::new(Push2Platform {
+            window: window.clone(),
+        }))?;
+        Ok(Self {
+            window,
+            buffer: vec![Rgb565Pixel::default(); DISPLAY_WIDTH * DISPLAY_HEIGHT],
+        })
+    }
+
+    fn key(&self, key: Key) {
+        self.window
+            .dispatch_event(WindowEvent::KeyPressed { text: key.into() });
+        self.window
+            .dispatch_event(WindowEvent::KeyReleased { text: key.into() });
+    }
+
+    fn click(&self, position: LogicalPosition) {
+        let button = PointerEventButton::Left;
+        self.window
+            .dispatch_event(WindowEvent::PointerPressed { position, button });
+        self.window
+            .dispatch_event(WindowEvent::PointerReleased { position, button });
+    }
+
+    /// Turns a device event into window input.
+    /// Returns true if the event was consumed.
+    pub fn handle_event(&self, event: &Push2Event) -> bool {
+        match *event {
+            Push2Event::ButtonPressed { name, .. } => {
+                if let Some((slot, upper)) = row_button_slot(name) {
+                    let x = (slot as u32 * ENCODER_REGION_WIDTH + ENCODER_REGION_WIDTH / 2) as f32;
+                    let y = if upper {
+                        8.0
+                    } else {
+                        DISPLAY_HEIGHT as f32 - 8.0
+                    };
+                    self.click(LogicalPosition::new(x, y));
+                    return true;
+                }
+                let key = match name {
+                    ControlName::Up => Key::UpArrow,
+                    ControlName::Down => Key::DownArrow,
+                    ControlName::Left => Key::LeftArrow,
+                    ControlName::Right => Key::RightArrow,
+                    ControlName::Select => Key::Return,
+                    ControlName::Shift => {
+                        self.window.dispatch_event(WindowEvent::KeyPressed {
+                            text: Key::Shift.into(),
+                        });
+                        return true;
+                    }
+                    _ => return false,
+                };
+                self.key(key);
+                true
+            }
+            Push2Event::ButtonReleased {
+                name: ControlName::Shift,
+            } => {
+                self.window.dispatch_event(WindowEvent::KeyReleased {
+                    text: Key::Shift.into(),
+                });
+                true
+            }
+            Push2Event::EncoderTwisted {
+                name: EncoderName::Track1,
+                raw_delta,
+            } => {
+                if decode_delta(raw_delta) > 0 {
+                    self.key(Key::Tab);
+                } else {
+                    self.key(Key::Backtab);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs Slint's timers and animations, and draws the window into the
+    /// frame buffer if it changed. Returns true if it was redrawn; call
+    /// `display.flush()` to show it.
+    pub fn render(&mut self, display: &mut Push2Display) -> bool {
+        slint::platform::update_timers_and_animations();
+        let buffer = &mut self.buffer;
+        let redrawn = self.window.draw_if_needed(|renderer| {
+            renderer.render(buffer, DISPLAY_WIDTH);
+        });
+        if redrawn {
+            // Slint packs red in the high bits, the frame buffer blue.
+            for (target, pixel) in display.frame_buffer.iter_mut().zip(buffer.iter()) {
+                let p = pixel.0;
+                *target = (p >> 11) | (p & 0x07E0) | ((p & 0x1F) << 11);
+            }
+        }
+        redrawn
+    }
+}
+
+/// The display column (0-7) of an upper or lower row button, and whether it is in the upper row.
+fn row_button_slot(name: ControlName) -> Option<(u8, bool)> {
+    if let Some(slot) = ControlName::UPPER_ROW.iter().position(|n| *n == name) {
+        return Some((slot as u8, true));
+    }
+    ControlName::LOWER_ROW
+        .iter()
+        .position(|n| *n == name)
+        .map(|slot| (slot as u8, false))
+}
@@ -1,26 +1,167 @@
-use crate::ControlName;
+use crate::button_map::decode_delta;
+use crate::{ControlName, EncoderName, PadCoord};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StateError {
+    #[error("Failed to serialize state: {0}")]
+    SerializeError(#[from] ron::Error),
+    #[error("Failed to parse state file: {0}")]
+    ParseError(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read or write state file: {0}")]
+    StateFileError(#[from] std::io::Error),
+}
 /// Holds the state of a single 8x8 grid pad
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct PadState {
     /// The last recorded velocity (0 = released)
     pub velocity: u8,
-    /// The currently set color (0 = off)
+    /// The base color (0 = off), shown unless the pad is highlighted
     pub color: u8,
+    /// The release velocity of the last NoteOff
+    #[serde(default)]
+    pub release_velocity: u8,
+    /// When the pad was pressed, if it is held
+    #[serde(skip)]
+    pub pressed_at: Option<Instant>,
+    /// The last aftertouch pressure while held (0 = none)
+    #[serde(skip)]
+    pub pressure: u8,
+    /// Colors shown over `color` for a while, one per `HighlightLayer`, such
+    /// as a press flash or a playhead (see `Push2::highlight_pad`)
+    #[serde(skip)]
+    pub highlights: [Option<u8>; HighlightLayer::COUNT],
+}
+impl PadState {
+    /// The highlight shown, from the highest layer that has one.
+    pub fn highlight(&self) -> Option<u8> {
+        self.highlights.iter().rev().find_map(|h| *h)
+    }
+    pub fn highlight_of(&self, layer: HighlightLayer) -> Option<u8> {
+        self.highlights[layer as usize]
+    }
+    /// The color the pad shows.
+    pub fn shown(&self) -> u8 {
+        self.highlight().unwrap_or(self.color)
+    }
+}
+/// Who set a pad highlight, from the lowest layer to the highest. A pad shows
+/// the highlight of the highest layer, and the one below once it is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HighlightLayer {
+    /// A `Playhead`
+    Playhead,
+    /// Highlights of the app itself
+    App,
+    /// `Push2::press_highlight`
+    Press,
+    /// `Push2::pressure_lights`
+    Pressure,
+}
+impl HighlightLayer {
+    /// Every layer, from the lowest to the highest.
+    pub const ALL: [HighlightLayer; 4] = [
+        HighlightLayer::Playhead,
+        HighlightLayer::App,
+        HighlightLayer::Press,
+        HighlightLayer::Pressure,
+    ];
+    pub const COUNT: usize = Self::ALL.len();
 }
 /// Holds the state of a single control button
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct ButtonState {
     /// The last recorded velocity (0 = released)
     pub velocity: u8,
     /// The currently set brightness/color (0 = off)
     pub light: u8,
+    /// When the button was pressed, if it is held
+    #[serde(skip)]
+    pub pressed_at: Option<Instant>,
+}
+/// Holds the state of a single encoder
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncoderState {
+    /// The value accumulated from relative turns, kept within `min..=max`
+    pub value: i32,
+    pub min: i32,
+    pub max: i32,
+    /// The value restored by `reset_encoder`
+    pub default: i32,
+    /// The raw ticks that make one step of `value`. Tempo and Swing send a
+    /// tick per detent; the others send many per turn and can be slowed down
+    /// with a higher count.
+    pub ticks_per_step: u32,
+    /// When true, turns are not accumulated and `value` stays at `default`;
+    /// only the `EncoderTwisted` events count. The default for Tempo, whose
+    /// detents mean nothing as a 0-127 value.
+    pub relative_only: bool,
+    /// Ticks short of a whole step.
+    #[serde(skip)]
+    remainder: i32,
+}
+impl Default for EncoderState {
+    fn default() -> Self {
+        Self {
+            value: 0,
+            min: 0,
+            max: 127,
+            default: 0,
+            ticks_per_step: 1,
+            relative_only: false,
+            remainder: 0,
+        }
+    }
 }
-#[derive(Debug)]
+impl EncoderState {
+    /// The state an encoder starts with: Tempo is relative only.
+    pub fn new(name: EncoderName) -> Self {
+        Self {
+            relative_only: name == EncoderName::Tempo,
+            ..Self::default()
+        }
+    }
+    /// Swaps `min` and `max` if they are the wrong way round, and clamps the
+    /// values to the range.
+    fn fix_range(&mut self) {
+        if self.min > self.max {
+            std::mem::swap(&mut self.min, &mut self.max);
+        }
+        self.default = self.default.clamp(self.min, self.max);
+        self.value = self.value.clamp(self.min, self.max);
+    }
+    /// Adds a turn of `delta` raw ticks. Returns the steps `value` moved.
+    pub fn turn(&mut self, delta: i32) -> i32 {
+        if self.relative_only {
+            return 0;
+        }
+        let ticks_per_step = self.ticks_per_step.max(1) as i32;
+        // A change of direction drops the ticks of the other way.
+        if self.remainder.signum() == -delta.signum() {
+            self.remainder = 0;
+        }
+        self.remainder += delta;
+        let steps = self.remainder / ticks_per_step;
+        self.remainder %= ticks_per_step;
+        let old = self.value;
+        self.value = (self.value + steps).clamp(self.min, self.max);
+        self.value - old
+    }
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Push2State {
     pub pads: [[PadState; 8]; 8],
     pub buttons: HashMap<ControlName, ButtonState>,
+    #[serde(default)]
+    pub encoders: HashMap<EncoderName, EncoderState>,
     pub slider: u16,
+    /// The last channel aftertouch pressure
+    #[serde(skip)]
+    pub channel_pressure: u8,
 }
 impl Push2State {
     /// Creates a new, default state.
@@ -28,7 +169,116 @@ impl Push2State {
         Self {
             pads: [[PadState::default(); 8]; 8],
             buttons: HashMap::new(),
+            encoders: HashMap::new(),
             slider: 0,
+            channel_pressure: 0,
+        }
+    }
+    /// Serializes the state to RON.
+    pub fn to_ron(&self) -> Result<String, StateError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+    /// Reads a state written by `to_ron`. Encoder ranges written the wrong
+    /// way round, e.g. by hand, are swapped.
+    pub fn from_ron(s: &str) -> Result<Self, StateError> {
+        let mut state: Self = ron::from_str(s).map_err(Box::new)?;
+        for encoder in state.encoders.values_mut() {
+            encoder.fix_range();
+        }
+        Ok(state)
+    }
+    /// Iterates over the pads that are currently held, row by row from the top.
+    pub fn pressed_pads(&self) -> impl Iterator<Item = PadCoord> + '_ {
+        self.pads.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, pad)| pad.pressed_at.is_some())
+                .map(move |(x, _)| PadCoord {
+                    x: x as u8,
+                    y: y as u8,
+                })
+        })
+    }
+    /// Returns true if the pad is currently held.
+    pub fn is_pad_held(&self, coord: PadCoord) -> bool {
+        self.pad_held_duration(coord).is_some()
+    }
+    /// How long the pad has been held, or `None` if it is released.
+    pub fn pad_held_duration(&self, coord: PadCoord) -> Option<Duration> {
+        self.pads
+            .get(coord.y as usize)?
+            .get(coord.x as usize)?
+            .pressed_at
+            .map(|at| at.elapsed())
+    }
+    /// Iterates over the buttons that are currently held.
+    pub fn held_buttons(&self) -> impl Iterator<Item = ControlName> + '_ {
+        self.buttons
+            .iter()
+            .filter(|(_, button)| button.pressed_at.is_some())
+            .map(|(name, _)| *name)
+    }
+    /// Returns true if the button is currently held.
+    pub fn is_button_held(&self, name: ControlName) -> bool {
+        self.held_duration(name).is_some()
+    }
+    /// How long the button has been held, or `None` if it is released.
+    pub fn held_duration(&self, name: ControlName) -> Option<Duration> {
+        self.buttons.get(&name)?.pressed_at.map(|at| at.elapsed())
+    }
+    /// The accumulated value of an encoder.
+    pub fn encoder_value(&self, name: EncoderName) -> i32 {
+        self.encoders
+            .get(&name)
+            .map_or(EncoderState::new(name).value, |encoder| encoder.value)
+    }
+    fn encoder_mut(&mut self, name: EncoderName) -> &mut EncoderState {
+        self.encoders
+            .entry(name)
+            .or_insert_with(|| EncoderState::new(name))
+    }
+    /// Sets how many raw ticks make one step of an encoder's value.
+    pub fn set_encoder_ticks_per_step(&mut self, name: EncoderName, ticks_per_step: u32) {
+        self.encoder_mut(name).ticks_per_step = ticks_per_step.max(1);
+    }
+    /// Stops or starts accumulating an encoder's turns. A relative only
+    /// encoder goes back to its default value.
+    pub fn set_encoder_relative_only(&mut self, name: EncoderName, relative_only: bool) {
+        let encoder = self.encoder_mut(name);
+        encoder.relative_only = relative_only;
+        if relative_only {
+            encoder.value = encoder.default;
+        }
+    }
+    /// Seeds the value of an encoder, clamped to its range.
+    pub fn set_encoder_value(&mut self, name: EncoderName, value: i32) {
+        let encoder = self.encoder_mut(name);
+        encoder.value = value.clamp(encoder.min, encoder.max);
+    }
+    /// Sets the range turns are accumulated in. The value is clamped to the new range.
+    pub fn set_encoder_range(&mut self, name: EncoderName, min: i32, max: i32) {
+        let encoder = self.encoder_mut(name);
+        encoder.min = min;
+        encoder.max = max;
+        encoder.fix_range();
+    }
+    /// Sets the value an encoder returns to on reset.
+    pub fn set_encoder_default(&mut self, name: EncoderName, default: i32) {
+        let encoder = self.encoder_mut(name);
+        encoder.default = default.clamp(encoder.min, encoder.max);
+    }
+    /// Returns an encoder to its default value.
+    pub fn reset_encoder(&mut self, name: EncoderName) {
+        let encoder = self.encoder_mut(name);
+        encoder.value = encoder.default;
+    }
+    /// Returns every known encoder to its default value.
+    pub fn reset_encoders(&mut self) {
+        for encoder in self.encoders.values_mut() {
+            encoder.value = encoder.default;
         }
     }
     /// Updates the state based on an incoming event.
@@ -38,23 +288,45 @@ impl Push2State {
             crate::Push2Event::PadPressed { coord, velocity } => {
                 let pad = &mut self.pads[coord.y as usize][coord.x as usize];
                 pad.velocity = *velocity;
+                pad.pressed_at = Some(Instant::now());
             }
-            crate::Push2Event::PadReleased { coord } => {
+            crate::Push2Event::PadReleased {
+                coord,
+                release_velocity,
+            } => {
                 let pad = &mut self.pads[coord.y as usize][coord.x as usize];
                 pad.velocity = 0;
+                pad.release_velocity = *release_velocity;
+                pad.pressed_at = None;
+                pad.pressure = 0;
+            }
+            crate::Push2Event::PadAftertouch { coord, pressure } => {
+                self.pads[coord.y as usize][coord.x as usize].pressure = *pressure;
+            }
+            crate::Push2Event::ChannelPressure { value } => {
+                self.channel_pressure = *value;
             }
             crate::Push2Event::ButtonPressed { name, velocity } => {
                 let button = self.buttons.entry(*name).or_default();
                 button.velocity = *velocity;
+                button.pressed_at = Some(Instant::now());
             }
             crate::Push2Event::ButtonReleased { name } => {
                 let button = self.buttons.entry(*name).or_default();
                 button.velocity = 0;
+                button.pressed_at = None;
+            }
+            crate::Push2Event::EncoderTwisted { name, raw_delta } => {
+                self.encoder_mut(*name).turn(decode_delta(*raw_delta));
             }
             crate::Push2Event::SliderMoved { value } => {
                 self.slider = *value;
             }
-            _ => {}
+            crate::Push2Event::PadChord { .. }
+            | crate::Push2Event::IdleEntered
+            | crate::Push2Event::IdleExited
+            | crate::Push2Event::UnknownControl { .. }
+            | crate::Push2Event::ConfigReloaded { .. } => {}
         }
     }
 }
@@ -63,3 +335,111 @@ impl Default for Push2State {
         Self::new()
     }
 }
+/// Application data attached to pads (file paths, clip ids, ...),
+/// keyed by grid coordinate instead of raw MIDI addresses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PadDataMap<T> {
+    cells: Vec<Option<T>>,
+}
+impl<T> PadDataMap<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: (0..64).map(|_| None).collect(),
+        }
+    }
+    fn index(coord: PadCoord) -> Option<usize> {
+        (coord.x < 8 && coord.y < 8).then(|| coord.y as usize * 8 + coord.x as usize)
+    }
+    pub fn get(&self, coord: PadCoord) -> Option<&T> {
+        self.cells.get(Self::index(coord)?)?.as_ref()
+    }
+    pub fn get_mut(&mut self, coord: PadCoord) -> Option<&mut T> {
+        self.cells.get_mut(Self::index(coord)?)?.as_mut()
+    }
+    /// Attaches data to a pad, returning what was attached before.
+    pub fn insert(&mut self, coord: PadCoord, value: T) -> Option<T> {
+        let cell = self.cells.get_mut(Self::index(coord)?)?;
+        cell.replace(value)
+    }
+    pub fn remove(&mut self, coord: PadCoord) -> Option<T> {
+        self.cells.get_mut(Self::index(coord)?)?.take()
+    }
+    pub fn contains(&self, coord: PadCoord) -> bool {
+        self.get(coord).is_some()
+    }
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = None);
+    }
+    /// The number of pads with data attached.
+    pub fn len(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.is_some()).count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Iterates over the pads with data attached, row by row from the top.
+    pub fn iter(&self) -> impl Iterator<Item = (PadCoord, &T)> {
+        self.cells.iter().enumerate().filter_map(|(i, cell)| {
+            cell.as_ref().map(|value| {
+                let coord = PadCoord {
+                    x: (i % 8) as u8,
+                    y: (i / 8) as u8,
+                };
+                (coord, value)
+            })
+        })
+    }
+    /// Returns the first pad whose data matches the predicate.
+    pub fn find<F>(&self, mut predicate: F) -> Option<PadCoord>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.iter()
+            .find(|(_, value)| predicate(value))
+            .map(|(coord, _)| coord)
+    }
+}
+impl<T> Default for PadDataMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_and_skips_encoder_turns() {
+        let mut state = Push2State::new();
+        state.set_encoder_ticks_per_step(EncoderName::Track1, 4);
+        let twist = |name, raw_delta| crate::Push2Event::EncoderTwisted { name, raw_delta };
+        for _ in 0..3 {
+            state.update_from_event(&twist(EncoderName::Track1, 3));
+        }
+        // Nine ticks at four per step, one tick left over.
+        assert_eq!(state.encoder_value(EncoderName::Track1), 2);
+        // Turning back drops the leftover tick.
+        state.update_from_event(&twist(EncoderName::Track1, 124));
+        assert_eq!(state.encoder_value(EncoderName::Track1), 1);
+
+        state.update_from_event(&twist(EncoderName::Tempo, 5));
+        assert_eq!(state.encoder_value(EncoderName::Tempo), 0);
+        assert!(EncoderName::Tempo.has_detents());
+    }
+
+    #[test]
+    fn swaps_reversed_ranges_on_load() {
+        let mut state = Push2State::new();
+        state.set_encoder_range(EncoderName::Track1, 0, 10);
+        let ron = state.to_ron().unwrap().replace("min: 0,", "min: 50,");
+        let mut state = Push2State::from_ron(&ron).unwrap();
+        let encoder = state.encoders[&EncoderName::Track1];
+        assert_eq!((encoder.min, encoder.max, encoder.value), (10, 50, 10));
+        state.update_from_event(&crate::Push2Event::EncoderTwisted {
+            name: EncoderName::Track1,
+            raw_delta: 3,
+        });
+        assert_eq!(state.encoder_value(EncoderName::Track1), 13);
+    }
+}
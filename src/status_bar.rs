@@ -0,0 +1,287 @@
+use crate::clock::{Clock, ClockSource};
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH, Push2Display, Push2DisplayError};
+use crate::fonts::{FontSize, TextStyle};
+use crate::transport::{Transport, TransportState};
+use embedded_graphics::{
+    pixelcolor::{Bgr565, RgbColor},
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle, Triangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use std::time::{Duration, Instant};
+
+/// The height of the strip reserved at the bottom of the display.
+pub const STATUS_BAR_HEIGHT: u32 = 16;
+/// The Y-position (from top) of the status bar.
+pub const STATUS_BAR_Y_POS: i32 = DISPLAY_HEIGHT as i32 - STATUS_BAR_HEIGHT as i32;
+/// The height of the progress line along the top of the status bar.
+const PROGRESS_HEIGHT: u32 = 2;
+
+/// An indicator shown at the right end of the status bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusIcon {
+    Metronome,
+    /// The clock follows an external MIDI clock.
+    ExternalSync,
+    /// An app-defined indicator, shown as its short text.
+    Custom(&'static str),
+}
+
+impl StatusIcon {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusIcon::Metronome => "MET",
+            StatusIcon::ExternalSync => "EXT",
+            StatusIcon::Custom(label) => label,
+        }
+    }
+}
+
+/// The colors of the status bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusBarColors {
+    pub background: Bgr565,
+    pub text: Bgr565,
+    pub playing: Bgr565,
+    pub recording: Bgr565,
+    pub progress: Bgr565,
+    pub icon: Bgr565,
+}
+
+impl Default for StatusBarColors {
+    fn default() -> Self {
+        Self {
+            background: Bgr565::new(2, 4, 2),
+            text: Bgr565::WHITE,
+            playing: Bgr565::GREEN,
+            recording: Bgr565::RED,
+            progress: Bgr565::CYAN,
+            icon: Bgr565::YELLOW,
+        }
+    }
+}
+
+/// A strip along the bottom of the display with the transport state, song
+/// position, tempo, a progress line, a status message and status icons.
+///
+/// Update it from the clock and transport, then draw it after the app's own
+/// content each frame so it stays on top:
+///
+/// ```ignore
+/// status.update_from_clock(&clock);
+/// status.update_from_transport(&transport);
+/// app.draw(&mut push2.display)?;
+/// status.draw(&mut push2.display)?;
+/// push2.display.flush()?;
+/// ```
+pub struct StatusBar {
+    pub colors: StatusBarColors,
+    pub beats_per_bar: u32,
+    transport: TransportState,
+    position: u64,
+    ppqn: u32,
+    bpm: f64,
+    progress: Option<f32>,
+    message: Option<(String, Option<Instant>)>,
+    icons: Vec<StatusIcon>,
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatusBar {
+    pub fn new() -> Self {
+        Self {
+            colors: StatusBarColors::default(),
+            beats_per_bar: 4,
+            transport: TransportState::Stopped,
+            position: 0,
+            ppqn: 24,
+            bpm: 120.0,
+            progress: None,
+            message: None,
+            icons: Vec::new(),
+        }
+    }
+
+    /// The area of the display the status bar covers.
+    pub fn area() -> Rectangle {
+        Rectangle::new(
+            Point::new(0, STATUS_BAR_Y_POS),
+            Size::new(DISPLAY_WIDTH as u32, STATUS_BAR_HEIGHT),
+        )
+    }
+
+    /// Takes the position, tempo, metronome and sync source from the clock.
+    pub fn update_from_clock(&mut self, clock: &Clock) {
+        self.position = clock.position();
+        self.ppqn = clock.ppqn.max(1);
+        self.bpm = clock.bpm();
+        self.set_icon(StatusIcon::Metronome, clock.metronome_enabled());
+        self.set_icon(
+            StatusIcon::ExternalSync,
+            clock.source() == ClockSource::External,
+        );
+    }
+
+    pub fn update_from_transport(&mut self, transport: &Transport) {
+        self.transport = transport.state();
+    }
+
+    /// Shows or hides an icon. Icons are shown in the order they were first set.
+    pub fn set_icon(&mut self, icon: StatusIcon, shown: bool) {
+        let index = self.icons.iter().position(|i| *i == icon);
+        match (index, shown) {
+            (None, true) => self.icons.push(icon),
+            (Some(index), false) => {
+                self.icons.remove(index);
+            }
+            _ => {}
+        }
+    }
+
+    /// Sets the progress line (0.0-1.0), e.g. for a loading sample. `None` hides it.
+    pub fn set_progress(&mut self, progress: Option<f32>) {
+        self.progress = progress.map(|p| p.clamp(0.0, 1.0));
+    }
+
+    /// Shows a message in the middle of the bar until it is replaced.
+    pub fn set_message(&mut self, message: &str) {
+        self.message = Some((message.to_string(), None));
+    }
+
+    /// Shows a message for `duration`.
+    pub fn flash_message(&mut self, message: &str, duration: Duration) {
+        self.message = Some((message.to_string(), Some(Instant::now() + duration)));
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
+    /// The song position as bar.beat.sixteenth, counting from 1.
+    pub fn position_text(&self) -> String {
+        let ppqn = self.ppqn.max(1) as u64;
+        let beats_per_bar = self.beats_per_bar.max(1) as u64;
+        // The clock counts ticks fired, so the current tick is one before.
+        let tick = self.position.saturating_sub(1);
+        let beat = tick / ppqn;
+        format!(
+            "{}.{}.{}",
+            beat / beats_per_bar + 1,
+            beat % beats_per_bar + 1,
+            (tick % ppqn) * 4 / ppqn + 1
+        )
+    }
+
+    /// Draws the status bar over whatever is on the bottom of the display.
+    pub fn draw(&mut self, display: &mut Push2Display) -> Result<(), Push2DisplayError> {
+        self.draw_at(display, Instant::now())
+    }
+
+    /// Draws the status bar, dropping a flashed message that expired before `now`.
+    pub fn draw_at(
+        &mut self,
+        display: &mut Push2Display,
+        now: Instant,
+    ) -> Result<(), Push2DisplayError> {
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|(_, until)| until.is_some_and(|until| now >= until))
+        {
+            self.message = None;
+        }
+        let area = Self::area();
+        area.into_styled(PrimitiveStyle::with_fill(self.colors.background))
+            .draw(display)
+            .unwrap(); // Infallible
+
+        if let Some(progress) = self.progress {
+            let width = (area.size.width as f32 * progress).round() as u32;
+            Rectangle::new(area.top_left, Size::new(width, PROGRESS_HEIGHT))
+                .into_styled(PrimitiveStyle::with_fill(self.colors.progress))
+                .draw(display)
+                .unwrap(); // Infallible
+        }
+
+        self.draw_transport_icon(display);
+        let middle = STATUS_BAR_Y_POS + STATUS_BAR_HEIGHT as i32 / 2 + 1;
+        let text = TextStyle::new(FontSize::Small).with_color(self.colors.text);
+        let left = TextStyleBuilder::new().baseline(Baseline::Middle).build();
+        Text::with_text_style(
+            &self.position_text(),
+            Point::new(20, middle),
+            text.clone(),
+            left,
+        )
+        .draw(display)
+        .unwrap(); // Infallible
+        Text::with_text_style(
+            &format!("{:.1} BPM", self.bpm),
+            Point::new(100, middle),
+            text.clone(),
+            left,
+        )
+        .draw(display)
+        .unwrap(); // Infallible
+
+        if let Some((message, _)) = &self.message {
+            let centered = TextStyleBuilder::new()
+                .baseline(Baseline::Middle)
+                .alignment(Alignment::Center)
+                .build();
+            Text::with_text_style(
+                message,
+                Point::new(DISPLAY_WIDTH as i32 / 2, middle),
+                text.clone(),
+                centered,
+            )
+            .draw(display)
+            .unwrap(); // Infallible
+        }
+
+        let right = TextStyleBuilder::new()
+            .baseline(Baseline::Middle)
+            .alignment(Alignment::Right)
+            .build();
+        let icon_text = text.with_color(self.colors.icon);
+        let mut x = DISPLAY_WIDTH as i32 - 4;
+        for icon in self.icons.iter().rev() {
+            Text::with_text_style(
+                icon.label(),
+                Point::new(x, middle),
+                icon_text.clone(),
+                right,
+            )
+            .draw(display)
+            .unwrap(); // Infallible
+            x -= icon.label().chars().count() as i32 * 6 + 8;
+        }
+        Ok(())
+    }
+
+    /// A triangle while playing, a circle while recording and a square when stopped.
+    fn draw_transport_icon(&self, display: &mut Push2Display) {
+        let top = STATUS_BAR_Y_POS + 4;
+        let result = match self.transport {
+            TransportState::Playing => Triangle::new(
+                Point::new(6, top),
+                Point::new(6, top + 8),
+                Point::new(13, top + 4),
+            )
+            .into_styled(PrimitiveStyle::with_fill(self.colors.playing))
+            .draw(display),
+            TransportState::Recording => Circle::new(Point::new(5, top), 9)
+                .into_styled(PrimitiveStyle::with_fill(self.colors.recording))
+                .draw(display),
+            TransportState::Stopped => Rectangle::new(Point::new(6, top + 1), Size::new(7, 7))
+                .into_styled(PrimitiveStyle::with_fill(self.colors.text))
+                .draw(display),
+        };
+        result.unwrap(); // Infallible
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{Push2, Push2Event};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::time::Duration;
+
+/// The number of events a subscription buffers unless told otherwise.
+pub const DEFAULT_SUBSCRIPTION_CAPACITY: usize = 256;
+
+/// The sending side of an `EventSubscription`, kept by `Push2`.
+pub(crate) struct Subscriber {
+    tx: SyncSender<Push2Event>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Subscriber {
+    /// Queues an event. Returns false once the subscription was dropped.
+    pub(crate) fn send(&self, event: &Push2Event) -> bool {
+        match self.tx.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// A copy of the event stream of a `Push2`, for use on another thread.
+///
+/// Every event returned by `poll_event` is also queued to each subscription.
+/// A subscription that falls behind by more than its capacity loses the
+/// newest events instead of slowing the device down; `dropped` counts them.
+pub struct EventSubscription {
+    rx: Receiver<Push2Event>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl EventSubscription {
+    /// Returns the next event, if one is queued. This is non-blocking.
+    pub fn try_recv(&self) -> Option<Push2Event> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Waits for the next event. Returns `None` once the `Push2` is dropped.
+    pub fn recv(&self) -> Option<Push2Event> {
+        self.rx.recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next event.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Push2Event> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    /// Iterates over the events until the `Push2` is dropped.
+    pub fn iter(&self) -> impl Iterator<Item = Push2Event> + '_ {
+        self.rx.iter()
+    }
+
+    /// The number of events lost because the buffer was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Push2 {
+    /// Subscribes to the events returned by `poll_event`, buffering up to
+    /// `DEFAULT_SUBSCRIPTION_CAPACITY` of them.
+    pub fn subscribe(&mut self) -> EventSubscription {
+        self.subscribe_with_capacity(DEFAULT_SUBSCRIPTION_CAPACITY)
+    }
+
+    /// Subscribes to the events returned by `poll_event`, buffering up to `capacity` of them.
+    pub fn subscribe_with_capacity(&mut self, capacity: usize) -> EventSubscription {
+        let (tx, rx) = std::sync::mpsc::sync_channel(capacity.max(1));
+        let dropped = Arc::new(AtomicUsize::new(0));
+        self.subscribers.push(Subscriber {
+            tx,
+            dropped: Arc::clone(&dropped),
+        });
+        EventSubscription { rx, dropped }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::app_config::config_dir;
+use crate::{ClipColors, ListColors, MixerColors, Push2Colors, TransportColors};
+use embedded_graphics::pixelcolor::{Bgr565, RgbColor};
+use log::info;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ThemeError {
+    #[error("Failed to parse theme file: {0}")]
+    Parse(#[from] Box<ron::error::SpannedError>),
+    #[error("Could not read theme file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No built-in or user theme named '{0}'")]
+    NotFound(String),
+}
+
+/// Writes a display color as 8-bit `(red, green, blue)` in RON.
+mod rgb {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Bgr565, serializer: S) -> Result<S::Ok, S::Error> {
+        let rgb = (color.r() << 3, color.g() << 2, color.b() << 3);
+        rgb.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bgr565, D::Error> {
+        let (r, g, b) = <(u8, u8, u8)>::deserialize(deserializer)?;
+        Ok(Bgr565::new(r >> 3, g >> 2, b >> 3))
+    }
+}
+
+/// A theme color, as a pad palette index and as a display color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeColor {
+    pub pad: u8,
+    #[serde(with = "rgb")]
+    pub display: Bgr565,
+}
+
+impl ThemeColor {
+    pub const fn new(pad: u8, display: Bgr565) -> Self {
+        Self { pad, display }
+    }
+}
+
+/// The colors shared by the lights and the display.
+///
+/// Widgets, the transport and the clip launcher take their colors from a
+/// theme through `From<&Theme>`, e.g. `TransportColors::from(&theme)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    /// What is active: playing clips, the selection.
+    pub primary: ThemeColor,
+    /// What stands out: queued clips, highlighted text.
+    pub accent: ThemeColor,
+    /// What needs attention: recording, clipping meters.
+    pub warning: ThemeColor,
+    /// Lights of available but inactive controls, and dimmed text.
+    pub idle: ThemeColor,
+    #[serde(with = "rgb")]
+    pub background: Bgr565,
+    #[serde(with = "rgb")]
+    pub text: Bgr565,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl Theme {
+    /// Green for active, orange for accents and red for recording, as in Live.
+    pub fn classic() -> Self {
+        Self {
+            name: "classic".to_string(),
+            primary: ThemeColor::new(Push2Colors::GREEN_BRIGHT, Bgr565::GREEN),
+            accent: ThemeColor::new(Push2Colors::ORANGE, Bgr565::new(31, 40, 0)),
+            warning: ThemeColor::new(Push2Colors::RED_BRIGHT, Bgr565::RED),
+            idle: ThemeColor::new(Push2Colors::WHITE_LOW, Bgr565::new(12, 24, 12)),
+            background: Bgr565::BLACK,
+            text: Bgr565::WHITE,
+        }
+    }
+
+    /// Blues and cyans, with magenta for recording.
+    pub fn ocean() -> Self {
+        Self {
+            name: "ocean".to_string(),
+            primary: ThemeColor::new(Push2Colors::AZURE_BRIGHT, Bgr565::new(0, 40, 31)),
+            accent: ThemeColor::new(Push2Colors::CYAN_BRIGHT, Bgr565::CYAN),
+            warning: ThemeColor::new(Push2Colors::MAGENTA_BRIGHT, Bgr565::MAGENTA),
+            idle: ThemeColor::new(Push2Colors::BLUE_LOW, Bgr565::new(4, 10, 14)),
+            background: Bgr565::new(0, 4, 4),
+            text: Bgr565::new(24, 60, 31),
+        }
+    }
+
+    /// The built-in theme with this name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Self::classic()),
+            "ocean" => Some(Self::ocean()),
+            _ => None,
+        }
+    }
+
+    /// Loads a theme from a RON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThemeError> {
+        let ron = fs::read_to_string(path)?;
+        Ok(ron::from_str(&ron).map_err(Box::new)?)
+    }
+
+    /// Loads "themes/<name>.ron" from the push2 config directory, falling
+    /// back to the built-in theme with that name.
+    pub fn named(name: &str) -> Result<Self, ThemeError> {
+        if let Some(mut path) = config_dir() {
+            path.push("themes");
+            path.push(format!("{name}.ron"));
+            if path.exists() {
+                info!("Loading theme from: {:?}", path);
+                return Self::load(path);
+            }
+        }
+        Self::builtin(name).ok_or_else(|| ThemeError::NotFound(name.to_string()))
+    }
+}
+
+impl From<&Theme> for TransportColors {
+    fn from(theme: &Theme) -> Self {
+        Self {
+            playing: theme.primary.pad,
+            recording: theme.warning.pad,
+            recording_pulse: Push2Colors::dimmed(theme.warning.pad),
+            idle: theme.idle.pad,
+        }
+    }
+}
+
+impl From<&Theme> for ClipColors {
+    fn from(theme: &Theme) -> Self {
+        Self {
+            empty: Push2Colors::BLACK,
+            loaded: theme.idle.pad,
+            playing: theme.primary.pad,
+            queued: theme.accent.pad,
+            recording: theme.warning.pad,
+            scene: theme.primary.pad,
+            scene_empty: theme.idle.pad,
+        }
+    }
+}
+
+impl From<&Theme> for MixerColors {
+    fn from(theme: &Theme) -> Self {
+        Self {
+            background: theme.background,
+            label: theme.text,
+            selected_label: theme.accent.display,
+            volume: theme.primary.display,
+            meter: theme.primary.display,
+            meter_hot: theme.warning.display,
+            meter_background: theme.idle.display,
+            select_light: theme.primary.pad,
+            mute_light: theme.accent.pad,
+            solo_light: theme.warning.pad,
+            idle_light: theme.idle.pad,
+        }
+    }
+}
+
+impl From<&Theme> for ListColors {
+    fn from(theme: &Theme) -> Self {
+        Self {
+            background: theme.background,
+            text: theme.text,
+            highlight: theme.primary.display,
+            highlight_text: theme.background,
+        }
+    }
+}
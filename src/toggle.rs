@@ -0,0 +1,147 @@
+use crate::{ControlName, Push2, Push2Error, Push2Event};
+
+/// A button that flips between on and off on every press, with its LED
+/// following the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToggleButton {
+    pub name: ControlName,
+    pub on_color: u8,
+    pub off_color: u8,
+    on: bool,
+}
+
+impl ToggleButton {
+    pub fn new(name: ControlName, on_color: u8, off_color: u8) -> Self {
+        Self {
+            name,
+            on_color,
+            off_color,
+            on: false,
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Sets the state and updates the LED.
+    pub fn set(&mut self, push2: &mut Push2, on: bool) -> Result<(), Push2Error> {
+        self.on = on;
+        self.update_light(push2)
+    }
+
+    /// Sends the LED color for the current state.
+    pub fn update_light(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let light = if self.on {
+            self.on_color
+        } else {
+            self.off_color
+        };
+        push2.set_button_light(self.name, light)
+    }
+
+    /// Flips the state when the button is pressed.
+    /// Returns the new state if the event toggled the button.
+    pub fn handle_event(
+        &mut self,
+        push2: &mut Push2,
+        event: &Push2Event,
+    ) -> Result<Option<bool>, Push2Error> {
+        match event {
+            Push2Event::ButtonPressed { name, .. } if *name == self.name => {
+                self.set(push2, !self.on)?;
+                Ok(Some(self.on))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A set of mutually exclusive buttons, like the Beat1_* row.
+/// Pressing a button selects it and deselects the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RadioGroup {
+    pub buttons: Vec<ControlName>,
+    pub on_color: u8,
+    pub off_color: u8,
+    selected: Option<usize>,
+}
+
+impl RadioGroup {
+    pub fn new(buttons: Vec<ControlName>, on_color: u8, off_color: u8) -> Self {
+        Self {
+            buttons,
+            on_color,
+            off_color,
+            selected: None,
+        }
+    }
+
+    /// Preselects a button by index, without sending any light.
+    pub fn with_selected(mut self, index: Option<usize>) -> Self {
+        self.selected = index.filter(|i| *i < self.buttons.len());
+        self
+    }
+
+    /// The eight note repeat rate buttons, from 1/4 to 1/32t.
+    pub fn beat_buttons(on_color: u8, off_color: u8) -> Self {
+        Self::new(
+            vec![
+                ControlName::Beat1_4,
+                ControlName::Beat1_4t,
+                ControlName::Beat1_8,
+                ControlName::Beat1_8t,
+                ControlName::Beat1_16,
+                ControlName::Beat1_16t,
+                ControlName::Beat1_32,
+                ControlName::Beat1_32t,
+            ],
+            on_color,
+            off_color,
+        )
+    }
+
+    /// The index of the selected button.
+    pub fn selected_index(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn selected(&self) -> Option<ControlName> {
+        self.selected.map(|i| self.buttons[i])
+    }
+
+    /// Selects a button by index (or none) and updates every LED of the group.
+    pub fn select(&mut self, push2: &mut Push2, index: Option<usize>) -> Result<(), Push2Error> {
+        self.selected = index.filter(|i| *i < self.buttons.len());
+        self.update_lights(push2)
+    }
+
+    /// Sends the LED color of every button of the group.
+    pub fn update_lights(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        for (i, name) in self.buttons.iter().enumerate() {
+            let light = if self.selected == Some(i) {
+                self.on_color
+            } else {
+                self.off_color
+            };
+            push2.set_button_light(*name, light)?;
+        }
+        Ok(())
+    }
+
+    /// Selects a button of the group when it is pressed.
+    /// Returns the newly selected button.
+    pub fn handle_event(
+        &mut self,
+        push2: &mut Push2,
+        event: &Push2Event,
+    ) -> Result<Option<ControlName>, Push2Error> {
+        if let Push2Event::ButtonPressed { name, .. } = event
+            && let Some(index) = self.buttons.iter().position(|b| b == name)
+        {
+            self.select(push2, Some(index))?;
+            return Ok(Some(*name));
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,126 @@
+//! The LEDs of the Push 2 touch strip, driven over SysEx.
+
+use serde::{Deserialize, Serialize};
+
+/// The number of LEDs on the touch strip, from the bottom up.
+pub const TOUCH_STRIP_LEDS: usize = 31;
+/// The brightest an LED can be; 0 is off.
+pub const MAX_LED_BRIGHTNESS: u8 = 7;
+
+const SYSEX_HEADER: [u8; 6] = [0xF0, 0x00, 0x21, 0x1D, 0x01, 0x01];
+const SET_CONFIGURATION: u8 = 0x17;
+const SET_LEDS: u8 = 0x19;
+/// LEDs controlled by the host, with their values sent as SysEx.
+const HOST_CONFIGURATION: u8 = 0b0000_0011;
+
+/// What the touch strip sends and shows while it lights itself.
+/// Both are reported as `Push2Event::SliderMoved`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TouchStripMode {
+    /// The power-on mode: pitch bend, with a point where the strip is
+    /// touched, returning to the center when released.
+    #[default]
+    PitchBend,
+    /// The mod wheel (CC 1), with a bar from the bottom that stays where it is left.
+    ModWheel,
+}
+
+impl TouchStripMode {
+    fn configuration(self) -> u8 {
+        match self {
+            TouchStripMode::PitchBend => 0b0110_1000,
+            TouchStripMode::ModWheel => 0b0000_0100,
+        }
+    }
+}
+
+fn configuration_message(configuration: u8) -> [u8; 9] {
+    let mut message = [0; 9];
+    message[..6].copy_from_slice(&SYSEX_HEADER);
+    message[6] = SET_CONFIGURATION;
+    message[7] = configuration;
+    message[8] = 0xF7;
+    message
+}
+
+/// Hands the LEDs over to the host, so `leds_message` takes effect.
+pub(crate) fn host_control_message() -> [u8; 9] {
+    configuration_message(HOST_CONFIGURATION)
+}
+
+/// Hands the LEDs back to the strip itself, in `mode`.
+pub(crate) fn mode_message(mode: TouchStripMode) -> [u8; 9] {
+    configuration_message(mode.configuration())
+}
+
+/// Sets every LED, 3 bits of brightness each, two LEDs per data byte.
+pub(crate) fn leds_message(leds: &[u8; TOUCH_STRIP_LEDS]) -> Vec<u8> {
+    let mut message = SYSEX_HEADER.to_vec();
+    message.push(SET_LEDS);
+    for pair in leds.chunks(2) {
+        let low = pair[0].min(MAX_LED_BRIGHTNESS);
+        let high = pair.get(1).map_or(0, |b| (*b).min(MAX_LED_BRIGHTNESS));
+        message.push(low | high << 3);
+    }
+    message.push(0xF7);
+    message
+}
+
+/// A bar from the bottom up to `level` (0.0-1.0), as for a level meter.
+pub fn level_leds(level: f32) -> [u8; TOUCH_STRIP_LEDS] {
+    let lit = (level.clamp(0.0, 1.0) * TOUCH_STRIP_LEDS as f32).round() as usize;
+    let mut leds = [0; TOUCH_STRIP_LEDS];
+    leds[..lit].fill(MAX_LED_BRIGHTNESS);
+    leds
+}
+
+/// A bar from the center to a 14-bit pitch bend (-8192 to 8191), with the
+/// center LED always lit dimly.
+pub fn pitch_bend_leds(bend: i16) -> [u8; TOUCH_STRIP_LEDS] {
+    let center = TOUCH_STRIP_LEDS / 2;
+    let reach = (bend.clamp(-8192, 8191) as f32 / 8192.0 * center as f32).round() as isize;
+    let end = (center as isize + reach) as usize;
+    let mut leds = [0; TOUCH_STRIP_LEDS];
+    leds[center.min(end)..=center.max(end)].fill(MAX_LED_BRIGHTNESS);
+    if reach == 0 {
+        leds[center] = MAX_LED_BRIGHTNESS / 2;
+    }
+    leds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leds_are_packed_in_pairs() {
+        let mut leds = [0; TOUCH_STRIP_LEDS];
+        leds[0] = 7;
+        leds[1] = 2;
+        leds[30] = 5;
+        let message = leds_message(&leds);
+        assert_eq!(message.len(), 6 + 1 + 16 + 1);
+        assert_eq!(message[7], 7 | 2 << 3);
+        assert_eq!(message[22], 5);
+        assert_eq!(message[23], 0xF7);
+    }
+
+    #[test]
+    fn level_fills_from_the_bottom() {
+        assert_eq!(level_leds(0.0), [0; TOUCH_STRIP_LEDS]);
+        let half = level_leds(0.5);
+        assert_eq!(half.iter().filter(|b| **b > 0).count(), 16);
+        assert_eq!(half[0], MAX_LED_BRIGHTNESS);
+        assert_eq!(level_leds(2.0), [MAX_LED_BRIGHTNESS; TOUCH_STRIP_LEDS]);
+    }
+
+    #[test]
+    fn pitch_bend_fills_from_the_center() {
+        let up = pitch_bend_leds(8191);
+        assert!(up[15..].iter().all(|b| *b == MAX_LED_BRIGHTNESS));
+        assert!(up[..15].iter().all(|b| *b == 0));
+        let down = pitch_bend_leds(-8192);
+        assert!(down[..=15].iter().all(|b| *b == MAX_LED_BRIGHTNESS));
+        assert_eq!(pitch_bend_leds(0)[15], MAX_LED_BRIGHTNESS / 2);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{ControlName, Push2, Push2Colors, Push2Error, Push2Event};
+use std::time::{Duration, Instant};
+
+/// Where the transport is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransportState {
+    #[default]
+    Stopped,
+    Playing,
+    /// Playing and recording.
+    Recording,
+}
+
+impl TransportState {
+    pub fn is_playing(&self) -> bool {
+        *self != TransportState::Stopped
+    }
+
+    pub fn is_recording(&self) -> bool {
+        *self == TransportState::Recording
+    }
+}
+
+/// Events produced by `Transport::handle_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportEvent {
+    PlayToggled {
+        playing: bool,
+    },
+    RecordToggled {
+        recording: bool,
+    },
+    /// Stop was pressed while playing.
+    Stopped,
+    New,
+    Duplicate,
+}
+
+/// The button lights used by the transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportColors {
+    pub playing: u8,
+    pub recording: u8,
+    /// The dimmer shade the record light pulses to.
+    pub recording_pulse: u8,
+    /// Light of a button that is available but not active.
+    pub idle: u8,
+}
+
+impl Default for TransportColors {
+    fn default() -> Self {
+        Self {
+            playing: Push2Colors::GREEN_BRIGHT,
+            recording: Push2Colors::RED_BRIGHT,
+            recording_pulse: Push2Colors::RED,
+            idle: Push2Colors::WHITE_LOW,
+        }
+    }
+}
+
+/// The Play, Record, Stop, New and Duplicate buttons as a small state machine.
+///
+/// Play starts and stops, Record toggles recording (starting playback if
+/// needed), and Stop always stops. The lights follow the state: green Play
+/// while playing and a pulsing red Record while recording.
+pub struct Transport {
+    pub colors: TransportColors,
+    /// How long the record light stays on each shade while pulsing.
+    pub pulse_interval: Duration,
+    state: TransportState,
+    epoch: Instant,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport {
+    pub fn new() -> Self {
+        Self {
+            colors: TransportColors::default(),
+            pulse_interval: Duration::from_millis(500),
+            state: TransportState::Stopped,
+            epoch: Instant::now(),
+        }
+    }
+
+    pub fn state(&self) -> TransportState {
+        self.state
+    }
+
+    /// Sets the state without producing an event, e.g. to follow an external clock.
+    pub fn set_state(&mut self, state: TransportState) {
+        self.state = state;
+    }
+
+    /// Applies a transport button press.
+    /// Returns the resulting event, if the press changed anything.
+    pub fn handle_event(&mut self, event: &Push2Event) -> Option<TransportEvent> {
+        let Push2Event::ButtonPressed { name, .. } = *event else {
+            return None;
+        };
+        match name {
+            ControlName::Play => {
+                let playing = !self.state.is_playing();
+                self.state = if playing {
+                    TransportState::Playing
+                } else {
+                    TransportState::Stopped
+                };
+                Some(TransportEvent::PlayToggled { playing })
+            }
+            ControlName::Record => {
+                let recording = !self.state.is_recording();
+                self.state = if recording {
+                    TransportState::Recording
+                } else {
+                    TransportState::Playing
+                };
+                Some(TransportEvent::RecordToggled { recording })
+            }
+            ControlName::Stop if self.state.is_playing() => {
+                self.state = TransportState::Stopped;
+                Some(TransportEvent::Stopped)
+            }
+            ControlName::New => Some(TransportEvent::New),
+            ControlName::Duplicate => Some(TransportEvent::Duplicate),
+            _ => None,
+        }
+    }
+
+    /// Lights the transport buttons, if they changed.
+    /// Call this regularly so the record light pulses.
+    pub fn render(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        self.render_at(push2, Instant::now())
+    }
+
+    /// Lights the transport buttons, with the record light in its phase at `now`.
+    pub fn render_at(&self, push2: &mut Push2, now: Instant) -> Result<(), Push2Error> {
+        let interval = self.pulse_interval.as_millis().max(1);
+        let pulse_on =
+            (now.saturating_duration_since(self.epoch).as_millis() / interval).is_multiple_of(2);
+        let play = if self.state.is_playing() {
+            self.colors.playing
+        } else {
+            self.colors.idle
+        };
+        let record = match self.state {
+            TransportState::Recording if pulse_on => self.colors.recording,
+            TransportState::Recording => self.colors.recording_pulse,
+            _ => self.colors.idle,
+        };
+        for (name, light) in [
+            (ControlName::Play, play),
+            (ControlName::Record, record),
+            (ControlName::Stop, self.colors.idle),
+            (ControlName::New, self.colors.idle),
+            (ControlName::Duplicate, self.colors.idle),
+        ] {
+            if push2.state.buttons.get(&name).map(|b| b.light) != Some(light) {
+                push2.set_button_light(name, light)?;
+            }
+        }
+        Ok(())
+    }
+}
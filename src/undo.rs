@@ -0,0 +1,237 @@
+use crate::display::Push2DisplayError;
+use crate::gui::{ENCODER_LABEL_Y_POS, ENCODER_VALUE_Y_POS, GuiApi};
+use crate::params::{ParamChange, ParamId, Params};
+use crate::{ControlName, Push2, Push2Colors, Push2Display, Push2Error, Push2Event};
+use embedded_graphics::pixelcolor::{Bgr565, RgbColor};
+
+/// A reversible operation on an app's state `S`.
+pub trait Command<S>: Send {
+    /// A short name shown on the display, e.g. "Assign sample".
+    fn name(&self) -> &str;
+
+    fn apply(&mut self, state: &mut S);
+
+    fn revert(&mut self, state: &mut S);
+}
+
+/// A command made of two closures, for operations without their own type.
+pub struct FnCommand<S> {
+    name: String,
+    apply: Box<dyn FnMut(&mut S) + Send>,
+    revert: Box<dyn FnMut(&mut S) + Send>,
+}
+
+impl<S> FnCommand<S> {
+    pub fn new<A, R>(name: &str, apply: A, revert: R) -> Self
+    where
+        A: FnMut(&mut S) + Send + 'static,
+        R: FnMut(&mut S) + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            apply: Box::new(apply),
+            revert: Box::new(revert),
+        }
+    }
+}
+
+impl<S> Command<S> for FnCommand<S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, state: &mut S) {
+        (self.apply)(state)
+    }
+
+    fn revert(&mut self, state: &mut S) {
+        (self.revert)(state)
+    }
+}
+
+/// Sets a parameter of a `Params` set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetParam {
+    pub id: ParamId,
+    pub name: String,
+    pub from: f32,
+    pub to: f32,
+}
+
+impl SetParam {
+    /// The command for a change already made by `Params::handle_event`.
+    /// Record it with `UndoStack::record`.
+    pub fn from_change(change: &ParamChange) -> Self {
+        Self {
+            id: change.id,
+            name: format!("Set {}", change.name),
+            from: change.previous,
+            to: change.value,
+        }
+    }
+}
+
+impl Command<Params> for SetParam {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, params: &mut Params) {
+        if let Some(param) = params.get_mut(self.id) {
+            param.set_value(self.to);
+        }
+    }
+
+    fn revert(&mut self, params: &mut Params) {
+        if let Some(param) = params.get_mut(self.id) {
+            param.set_value(self.from);
+        }
+    }
+}
+
+/// Produced by `UndoStack::handle_event`, with the name of the command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoEvent {
+    Undone(String),
+    Redone(String),
+}
+
+/// The undo and redo history of an app's state `S`.
+///
+/// Undo undoes the last command and Shift+Undo redoes it. The Undo light
+/// is bright while there is something to undo and dim while there is only
+/// something to redo.
+pub struct UndoStack<S> {
+    undo: Vec<Box<dyn Command<S>>>,
+    redo: Vec<Box<dyn Command<S>>>,
+    /// The maximum number of commands kept; the oldest are dropped first.
+    pub limit: usize,
+    pub light_undo: u8,
+    pub light_redo: u8,
+}
+
+impl<S> Default for UndoStack<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> UndoStack<S> {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit: 100,
+            light_undo: Push2Colors::WHITE_BRIGHT,
+            light_redo: Push2Colors::WHITE_LOW,
+        }
+    }
+
+    /// Applies a command and adds it to the history. This clears the redo history.
+    pub fn push(&mut self, mut command: impl Command<S> + 'static, state: &mut S) {
+        command.apply(state);
+        self.record(command);
+    }
+
+    /// Adds a command that was already applied to the history.
+    pub fn record(&mut self, command: impl Command<S> + 'static) {
+        self.undo.push(Box::new(command));
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// The number of commands that can be undone.
+    pub fn undo_depth(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// The number of commands that can be redone.
+    pub fn redo_depth(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// The name of the command the next `undo` reverts.
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|c| c.name())
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Reverts the last command. Returns its name.
+    pub fn undo(&mut self, state: &mut S) -> Option<String> {
+        let mut command = self.undo.pop()?;
+        command.revert(state);
+        let name = command.name().to_string();
+        self.redo.push(command);
+        Some(name)
+    }
+
+    /// Applies the last undone command again. Returns its name.
+    pub fn redo(&mut self, state: &mut S) -> Option<String> {
+        let mut command = self.redo.pop()?;
+        command.apply(state);
+        let name = command.name().to_string();
+        self.undo.push(command);
+        Some(name)
+    }
+
+    /// Undoes on Undo, or redoes on Shift+Undo.
+    /// Returns what was undone or redone.
+    pub fn handle_event(
+        &mut self,
+        push2: &Push2,
+        state: &mut S,
+        event: &Push2Event,
+    ) -> Option<UndoEvent> {
+        let Push2Event::ButtonPressed {
+            name: ControlName::Undo,
+            ..
+        } = event
+        else {
+            return None;
+        };
+        let shift = push2
+            .state
+            .buttons
+            .get(&ControlName::Shift)
+            .is_some_and(|b| b.pressed_at.is_some());
+        if shift {
+            self.redo(state).map(UndoEvent::Redone)
+        } else {
+            self.undo(state).map(UndoEvent::Undone)
+        }
+    }
+
+    /// Lights the Undo button, if it changed.
+    pub fn update_light(&self, push2: &mut Push2) -> Result<(), Push2Error> {
+        let light = if !self.undo.is_empty() {
+            self.light_undo
+        } else if !self.redo.is_empty() {
+            self.light_redo
+        } else {
+            Push2Colors::BLACK
+        };
+        if push2.state.buttons.get(&ControlName::Undo).map(|b| b.light) != Some(light) {
+            push2.set_button_light(ControlName::Undo, light)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the next command to undo and the undo/redo depth in an encoder slot (0-7).
+    pub fn draw_status(
+        &self,
+        display: &mut Push2Display,
+        slot: u8,
+    ) -> Result<(), Push2DisplayError> {
+        display.clear_encoder_area(slot, Bgr565::BLACK)?;
+        let name = self.next_undo().unwrap_or("Nothing to undo");
+        display.draw_encoder_label(slot, ENCODER_LABEL_Y_POS, name, Bgr565::WHITE)?;
+        let depth = format!("Undo {} / Redo {}", self.undo.len(), self.redo.len());
+        display.draw_encoder_label(slot, ENCODER_VALUE_Y_POS, &depth, Bgr565::CYAN)
+    }
+}
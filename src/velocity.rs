@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// The shape of the velocity response.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VelocityCurve {
+    Linear,
+    /// Light hits come out louder.
+    Soft,
+    /// Light hits come out quieter.
+    Hard,
+    /// `velocity ^ exponent` on the 0.0-1.0 range; below 1.0 is softer, above is harder.
+    Power(f32),
+    /// Every hit has the same velocity.
+    Fixed(u8),
+}
+
+/// Remaps the velocity of pad presses in software.
+///
+/// Set `Push2::velocity_map` to apply it to every `PadPressed` event
+/// before it is returned by `poll_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VelocityMap {
+    pub curve: VelocityCurve,
+    /// The lowest velocity produced (1-127).
+    pub min: u8,
+    /// The highest velocity produced (1-127).
+    pub max: u8,
+    /// When true, holding Accent plays every pad at 127.
+    pub accent_button: bool,
+}
+
+impl Default for VelocityMap {
+    fn default() -> Self {
+        Self {
+            curve: VelocityCurve::Linear,
+            min: 1,
+            max: 127,
+            accent_button: true,
+        }
+    }
+}
+
+impl VelocityMap {
+    pub fn new(curve: VelocityCurve) -> Self {
+        Self {
+            curve,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_range(mut self, min: u8, max: u8) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn with_accent_button(mut self, accent_button: bool) -> Self {
+        self.accent_button = accent_button;
+        self
+    }
+
+    /// The remapped velocity of a hit. `accent` is whether Accent is held.
+    pub fn apply(&self, velocity: u8, accent: bool) -> u8 {
+        if accent && self.accent_button {
+            return 127;
+        }
+        let min = self.min.clamp(1, 127);
+        let max = self.max.clamp(min, 127);
+        let x = velocity.min(127) as f32 / 127.0;
+        let shaped = match self.curve {
+            VelocityCurve::Linear => x,
+            VelocityCurve::Soft => x.sqrt(),
+            VelocityCurve::Hard => x * x,
+            VelocityCurve::Power(exponent) => x.powf(exponent.max(0.01)),
+            VelocityCurve::Fixed(fixed) => return fixed.clamp(min, max),
+        };
+        let range = (max - min) as f32;
+        min + (shaped * range).round() as u8
+    }
+}
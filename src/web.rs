@@ -0,0 +1,220 @@
+//! Runs `Push2` in the browser, with MIDI over WebMIDI and the display over WebUSB.
+//!
+//! Build for `wasm32-unknown-unknown` with the `wasm` feature. WebUSB is still an
+//! unstable web-sys API, enabled by the `web_sys_unstable_apis` cfg in `.cargo/config.toml`.
+//!
+//! ```ignore
+//! // In a click handler: the browser only shows the USB device picker after a user gesture.
+//! let backend = WebBackend::request(DEFAULT_WEB_PORT).await?;
+//! let mut push2 = Push2::with_backend(backend)?;
+//! ```
+
+use crate::Push2Error;
+use crate::backend::{Backend, BackendParts, FrameSink, MidiOut};
+use crate::device::Push2Model;
+use crate::display::{
+    FRAME_BYTES, HEADER, PUSH_2_PRODUCT_ID, PUSH_2_VENDOR_ID, PUSH2_BULK_EP_OUT, Push2DisplayError,
+    encode_frame,
+};
+use js_sys::{Array, Uint8Array};
+use log::warn;
+use midir::SendError;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use thiserror::Error;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{
+    MidiAccess, MidiInput, MidiMessageEvent, MidiOptions, MidiOutput, UsbDevice, UsbDeviceFilter,
+    UsbDeviceRequestOptions,
+};
+
+/// The part of the port name browsers give the Push 2 live port, on every OS.
+pub const DEFAULT_WEB_PORT: &str = "Ableton Push 2";
+
+#[derive(Error, Debug)]
+pub enum WebError {
+    #[error("Not running in a browser window")]
+    NoWindow,
+    #[error("MIDI access was denied: {0}")]
+    MidiDenied(String),
+    #[error("USB access was denied: {0}")]
+    UsbDenied(String),
+    #[error("No MIDI port matching '{0}' found")]
+    PortNotFound(String),
+}
+
+fn js_message(value: &JsValue) -> String {
+    value
+        .dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .unwrap_or_else(|| format!("{:?}", value))
+}
+
+/// Sends MIDI to a WebMIDI output, and keeps the input handler alive.
+pub struct WebMidiOut {
+    output: MidiOutput,
+    input: MidiInput,
+    _on_message: Closure<dyn FnMut(MidiMessageEvent)>,
+}
+
+// SAFETY: wasm32 without the atomics feature has a single thread, so the JS
+// handles can never be used from another one.
+unsafe impl Send for WebMidiOut {}
+
+impl MidiOut for WebMidiOut {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        self.output
+            .send(&Uint8Array::from(message))
+            .map_err(|_| SendError::Other("WebMIDI send failed"))
+    }
+}
+
+impl Drop for WebMidiOut {
+    fn drop(&mut self) {
+        self.input.set_onmidimessage(None);
+    }
+}
+
+/// The Push 2 display, written to over WebUSB.
+///
+/// Transfers are asynchronous: while one frame is still being sent, newer
+/// frames are dropped rather than queued.
+pub struct WebUsbDisplay {
+    device: UsbDevice,
+    transfer_buffer: Vec<u8>,
+    in_flight: Rc<Cell<bool>>,
+}
+
+// SAFETY: see `WebMidiOut`.
+unsafe impl Send for WebUsbDisplay {}
+
+impl WebUsbDisplay {
+    /// Asks the user to pick the Push 2 in the browser's USB device dialog, and opens it.
+    pub async fn request() -> Result<Self, WebError> {
+        let window = web_sys::window().ok_or(WebError::NoWindow)?;
+        let filter = UsbDeviceFilter::new();
+        filter.set_vendor_id(PUSH_2_VENDOR_ID);
+        filter.set_product_id(PUSH_2_PRODUCT_ID);
+        let options = UsbDeviceRequestOptions::new(&[filter]);
+        let usb_error = |e: JsValue| WebError::UsbDenied(js_message(&e));
+
+        let device = window
+            .navigator()
+            .usb()
+            .request_device(&options)
+            .await
+            .map_err(usb_error)?;
+        device.open().await.map_err(usb_error)?;
+        if device.configuration().is_none() {
+            device.select_configuration(1).await.map_err(usb_error)?;
+        }
+        device.claim_interface(0).await.map_err(usb_error)?;
+        Ok(Self {
+            device,
+            transfer_buffer: vec![0u8; FRAME_BYTES],
+            in_flight: Rc::new(Cell::new(false)),
+        })
+    }
+}
+
+impl FrameSink for WebUsbDisplay {
+    fn write_frame(&mut self, frame: &[u16]) -> Result<(), Push2DisplayError> {
+        if self.in_flight.get() {
+            return Ok(());
+        }
+        encode_frame(frame, &mut self.transfer_buffer);
+        // The browser copies the data when the transfers are queued.
+        let transfers = [&HEADER[..], &self.transfer_buffer].map(|data| {
+            self.device
+                .transfer_out_with_u8_slice(PUSH2_BULK_EP_OUT, data)
+        });
+        self.in_flight.set(true);
+        let in_flight = self.in_flight.clone();
+        spawn_local(async move {
+            for transfer in transfers {
+                let result = match transfer {
+                    Ok(promise) => promise.await.map(drop),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    warn!("WebUSB frame transfer failed: {}", js_message(&e));
+                    break;
+                }
+            }
+            in_flight.set(false);
+        });
+        Ok(())
+    }
+}
+
+/// The WebMIDI and WebUSB connections to a Push 2. See `Push2::with_backend`.
+pub struct WebBackend {
+    pub display: WebUsbDisplay,
+    pub midi_out: WebMidiOut,
+    midi_in: Receiver<Vec<u8>>,
+}
+
+impl WebBackend {
+    /// Opens the first MIDI input and output whose name contains `port`,
+    /// then asks the user for the display with `WebUsbDisplay::request`.
+    ///
+    /// Must be called from a user gesture, such as a click handler.
+    pub async fn request(port: &str) -> Result<Self, WebError> {
+        let window = web_sys::window().ok_or(WebError::NoWindow)?;
+        let options = MidiOptions::new();
+        options.set_sysex(true);
+        let midi_error = |e: JsValue| WebError::MidiDenied(js_message(&e));
+        let access: MidiAccess = window
+            .navigator()
+            .request_midi_access_with_options(&options)
+            .map_err(midi_error)?
+            .await
+            .map_err(midi_error)?
+            .unchecked_into();
+
+        let matches = |name: Option<String>| name.is_some_and(|n| n.contains(port));
+        let input = Array::from(access.inputs().values().as_ref())
+            .iter()
+            .map(JsCast::unchecked_into::<MidiInput>)
+            .find(|p| matches(p.name()))
+            .ok_or_else(|| WebError::PortNotFound(port.to_string()))?;
+        let output = Array::from(access.outputs().values().as_ref())
+            .iter()
+            .map(JsCast::unchecked_into::<MidiOutput>)
+            .find(|p| matches(p.name()))
+            .ok_or_else(|| WebError::PortNotFound(port.to_string()))?;
+
+        let (tx, rx) = mpsc::channel();
+        let on_message = Closure::<dyn FnMut(MidiMessageEvent)>::new(move |e: MidiMessageEvent| {
+            if let Ok(bytes) = e.data() {
+                let _ = tx.send(bytes);
+            }
+        });
+        input.set_onmidimessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let display = WebUsbDisplay::request().await?;
+        Ok(Self {
+            display,
+            midi_out: WebMidiOut {
+                output,
+                input,
+                _on_message: on_message,
+            },
+            midi_in: rx,
+        })
+    }
+}
+
+impl Backend for WebBackend {
+    fn connect(self) -> Result<BackendParts, Push2Error> {
+        Ok(BackendParts {
+            display: Box::new(self.display),
+            midi_out: Box::new(self.midi_out),
+            midi_in: self.midi_in,
+            device: Box::new(Push2Model),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+//! The WebSocket client loop shared by the remote server and the emulator.
+
+use std::io;
+use std::net::TcpStream;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// How long a client thread waits for input before sending queued messages.
+const CLIENT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Completes the WebSocket handshake on `stream`.
+pub(crate) fn accept(stream: TcpStream) -> Result<WebSocket<TcpStream>, tungstenite::Error> {
+    let socket = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(io::ErrorKind::WouldBlock.into())
+        }
+    })?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(CLIENT_POLL_INTERVAL))?;
+    Ok(socket)
+}
+
+/// Runs a client until it disconnects: sends it the messages queued on
+/// `message_rx` and hands each text message it sends to `on_text`, stopping
+/// when that returns false.
+pub(crate) fn run_client(
+    socket: &mut WebSocket<TcpStream>,
+    message_rx: &Receiver<Message>,
+    mut on_text: impl FnMut(&str) -> bool,
+) -> Result<(), tungstenite::Error> {
+    loop {
+        while let Ok(message) = message_rx.try_recv() {
+            socket.send(message)?;
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                if !on_text(&text) {
+                    return Ok(());
+                }
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}